@@ -20,6 +20,11 @@ pub async fn list() -> Result<()> {
             "Demo Relying Party",
             vec!["http://localhost:8080/callback"],
         ),
+        (
+            "demo-pq-rp",
+            "Demo Post-Quantum Relying Party",
+            vec!["http://localhost:8080/callback"],
+        ),
         (
             "fantasma-wallet",
             "Fantasma Wallet",
@@ -102,11 +102,12 @@ pub async fn seed(database_url: Option<String>) -> Result<()> {
         .map_err(|e| anyhow::anyhow!(e))?;
     let repos = fantasma_db::pool::Repositories::new(&pool);
 
-    // Seed demo clients
+    // Seed demo clients (secret: "demo-secret")
+    let demo_secret_hash = fantasma_crypto::hash_secret("demo-secret")?.into_bytes();
     let demo_clients = vec![
         fantasma_db::models::NewClient {
             client_id: "demo-client".to_string(),
-            client_secret_hash: None,
+            client_secret_hash: Some(demo_secret_hash.clone()),
             client_name: "Demo Client".to_string(),
             redirect_uris: vec![
                 "http://localhost:8080/callback".to_string(),
@@ -119,14 +120,50 @@ pub async fn seed(database_url: Option<String>) -> Result<()> {
                 "zk:kyc:basic".to_string(),
             ],
             client_type: "confidential".to_string(),
+            id_token_signed_response_alg: None,
+            require_pkce_s256: false,
+            token_endpoint_auth_method: None,
+            jwks: None,
+            jwks_uri: None,
+            logo_uri: None,
+            registration_access_token_hash: None,
+            sector_identifier_uri: None,
+            require_pushed_authorization_requests: false,
+            require_signed_request_object: false,
+            post_logout_redirect_uris: Vec::new(),
+            backchannel_logout_uri: None,
+            backchannel_logout_session_required: false,
+            downscope_unapproved_scopes: false,
+            id_token_encrypted_response_alg: None,
+            id_token_encrypted_response_enc: None,
+            userinfo_encrypted_response_alg: None,
+            userinfo_encrypted_response_enc: None,
         },
         fantasma_db::models::NewClient {
             client_id: "demo-rp".to_string(),
-            client_secret_hash: None,
+            client_secret_hash: Some(demo_secret_hash.clone()),
             client_name: "Demo Relying Party".to_string(),
             redirect_uris: vec!["http://localhost:8080/callback".to_string()],
             allowed_scopes: vec!["openid".to_string(), "zk:age:21+".to_string()],
             client_type: "confidential".to_string(),
+            id_token_signed_response_alg: None,
+            require_pkce_s256: false,
+            token_endpoint_auth_method: None,
+            jwks: None,
+            jwks_uri: None,
+            logo_uri: None,
+            registration_access_token_hash: None,
+            sector_identifier_uri: None,
+            require_pushed_authorization_requests: false,
+            require_signed_request_object: false,
+            post_logout_redirect_uris: Vec::new(),
+            backchannel_logout_uri: None,
+            backchannel_logout_session_required: false,
+            downscope_unapproved_scopes: false,
+            id_token_encrypted_response_alg: None,
+            id_token_encrypted_response_enc: None,
+            userinfo_encrypted_response_alg: None,
+            userinfo_encrypted_response_enc: None,
         },
     ];
 
@@ -146,6 +183,7 @@ pub async fn seed(database_url: Option<String>) -> Result<()> {
         public_key_algorithm: "dilithium3".to_string(),
         verification_url: Some("http://localhost:3000".to_string()),
         trusted: true,
+        trust_tier: "trust_framework".to_string(),
     }];
 
     println!("Seeding issuers...");
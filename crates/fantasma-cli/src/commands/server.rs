@@ -35,15 +35,12 @@ pub async fn run(bind: String, _database_url: Option<String>) -> Result<()> {
     println!();
     println!("{}", style("Endpoints:").bold());
     println!(
-        "  Discovery:     {}/.well-known/openid-configuration",
-        format!("http://{}", bind)
-    );
-    println!("  Authorization: {}/authorize", format!("http://{}", bind));
-    println!("  Token:         {}/token", format!("http://{}", bind));
-    println!(
-        "  JWKS:          {}/.well-known/jwks.json",
-        format!("http://{}", bind)
+        "  Discovery:     http://{}/.well-known/openid-configuration",
+        bind
     );
+    println!("  Authorization: http://{}/authorize", bind);
+    println!("  Token:         http://{}/token", bind);
+    println!("  JWKS:          http://{}/.well-known/jwks.json", bind);
     println!();
     println!("{}", style("ZK Scopes:").bold());
     println!("  zk:age:18+      - Prove age >= 18");
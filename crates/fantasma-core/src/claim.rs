@@ -84,6 +84,31 @@ impl ClaimType {
         }
     }
 
+    /// The claimed values, as the leading public inputs of its circuit
+    ///
+    /// Values are encoded as 32-byte field elements, big-endian: the age
+    /// threshold; the KYC level, then the maximum age of the verification
+    /// when one is asked for; the hash of the credential type, except for
+    /// any credential (`*`).
+    pub fn public_inputs(&self) -> Vec<[u8; 32]> {
+        match self {
+            ClaimType::AgeAtLeast { threshold } => vec![field_element(*threshold as u64)],
+            ClaimType::KycStatus {
+                level,
+                max_age_seconds,
+                ..
+            } => std::iter::once(field_element(level.as_u8() as u64))
+                .chain(max_age_seconds.map(field_element))
+                .collect(),
+            ClaimType::HoldsCredential {
+                credential_type, ..
+            } if credential_type != "*" => {
+                vec![crate::proof::sha3_256(credential_type.as_bytes())]
+            }
+            ClaimType::HoldsCredential { .. } | ClaimType::SetMembership { .. } => vec![],
+        }
+    }
+
     /// Parse from OIDC scope string
     pub fn from_scope(scope: &str) -> Option<Self> {
         if scope.starts_with("zk:age:") {
@@ -138,6 +163,13 @@ impl ClaimType {
     }
 }
 
+/// An integer as a field element
+fn field_element(value: u64) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[24..].copy_from_slice(&value.to_be_bytes());
+    bytes
+}
+
 /// A request for a specific claim proof
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaimRequest {
@@ -178,6 +210,32 @@ mod tests {
         assert!(ClaimType::from_scope("invalid").is_none());
     }
 
+    #[test]
+    fn test_public_inputs() {
+        let mut threshold = [0u8; 32];
+        threshold[31] = 21;
+        assert_eq!(
+            ClaimType::AgeAtLeast { threshold: 21 }.public_inputs(),
+            vec![threshold]
+        );
+
+        let kyc = ClaimType::KycStatus {
+            provider: "*".to_string(),
+            level: KycLevel::Enhanced,
+            max_age_seconds: Some(3600),
+        };
+        let inputs = kyc.public_inputs();
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0][31], 2);
+        assert_eq!(inputs[1][24..], 3600u64.to_be_bytes());
+
+        let any = ClaimType::HoldsCredential {
+            credential_type: "*".to_string(),
+            issuer: None,
+        };
+        assert!(any.public_inputs().is_empty());
+    }
+
     #[test]
     fn test_kyc_level_ordering() {
         assert!(KycLevel::Basic < KycLevel::Enhanced);
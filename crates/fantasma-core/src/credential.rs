@@ -123,7 +123,7 @@ pub enum CredentialType {
     },
 }
 
-/// Signature from issuer (Dilithium signature)
+/// Signature from issuer (Dilithium, or hybrid Ed25519 + ML-DSA)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IssuerSignature {
     /// The signature bytes (Dilithium signatures are ~2.4 KB)
@@ -138,6 +138,26 @@ pub enum SignatureAlgorithm {
     Dilithium3,
     /// Dilithium level 5 (NIST security level 5)
     Dilithium5,
+    /// Ed25519 and ML-DSA-65 (FIPS 204) composite: both signatures, with the
+    /// issuer's public key the concatenation of both keys
+    HybridEd25519MlDsa65,
+}
+
+impl SignatureAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dilithium3 => "Dilithium3",
+            Self::Dilithium5 => "Dilithium5",
+            Self::HybridEd25519MlDsa65 => "HybridEd25519MlDsa65",
+        }
+    }
+}
+
+/// Key a credential is bound to, held by the wallet it was issued to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HolderBinding {
+    /// JWK SHA-256 thumbprint (RFC 7638) of the holder's public key
+    pub jkt: String,
 }
 
 /// A signed credential from an issuer
@@ -171,9 +191,23 @@ pub struct Credential {
 
     /// Optional expiration time
     pub expires_at: Option<DateTime<Utc>>,
+
+    /// Holder key the credential was issued to, if bound
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holder: Option<HolderBinding>,
 }
 
 impl Credential {
+    /// The message the issuer signs: the commitment, followed by the holder
+    /// key thumbprint for bound credentials
+    pub fn signing_input(&self) -> Vec<u8> {
+        let mut input = self.commitment.to_vec();
+        if let Some(holder) = &self.holder {
+            input.extend_from_slice(holder.jkt.as_bytes());
+        }
+        input
+    }
+
     /// Check if the credential has expired
     pub fn is_expired(&self) -> bool {
         if let Some(expires_at) = self.expires_at {
@@ -70,6 +70,12 @@ impl TrustAnchor {
     }
 }
 
+/// SHA3-256 of an issuer public key, as proofs commit to it
+pub fn public_key_hash(public_key: &[u8]) -> [u8; 32] {
+    use sha3::{Digest, Sha3_256};
+    Sha3_256::digest(public_key).into()
+}
+
 /// Information about a credential issuer
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IssuerInfo {
@@ -100,6 +106,11 @@ impl IssuerInfo {
     pub fn supports_schema(&self, schema: &str) -> bool {
         self.supported_schemas.iter().any(|s| s == schema)
     }
+
+    /// SHA3-256 of the public key, the issuer public input of the circuits
+    pub fn public_key_hash(&self) -> [u8; 32] {
+        public_key_hash(&self.public_key)
+    }
 }
 
 /// Registry entry for tracking issuer status
@@ -11,7 +11,9 @@ pub mod issuer;
 pub mod proof;
 
 pub use claim::{ClaimRequest, ClaimType, KycLevel};
-pub use credential::{AttributeValue, Credential, CredentialId, CredentialType, SchemaId};
+pub use credential::{
+    AttributeValue, Credential, CredentialId, CredentialType, HolderBinding, SchemaId,
+};
 pub use error::FantasmaError;
 pub use issuer::{IssuerId, IssuerInfo, TrustAnchor};
 pub use proof::{GeneratedProof, ProofId, ProofRef, ProofRequest, ProofResponse};
@@ -83,6 +83,16 @@ impl ProofRequest {
     pub fn is_expired(&self) -> bool {
         Utc::now() > self.expires_at
     }
+
+    /// Hash of the verifier domain, as bound into a proof's public inputs
+    pub fn verifier_domain_hash(&self) -> [u8; 32] {
+        sha3_256(self.verifier_domain.as_bytes())
+    }
+
+    /// Hash of the nonce, as bound into a proof's public inputs
+    pub fn nonce_hash(&self) -> [u8; 32] {
+        sha3_256(self.nonce.as_bytes())
+    }
 }
 
 /// A generated ZK proof
@@ -94,7 +104,9 @@ pub struct GeneratedProof {
     /// The ZK proof bytes (STARK proof, typically 50-200KB)
     pub proof_bytes: Vec<u8>,
 
-    /// Public inputs used in verification
+    /// Public inputs used in verification, hex-encoded: the claim values,
+    /// the verifier domain hash, nonce hash, nullifier and subject hash,
+    /// then the key hash of the credential's issuer
     pub public_inputs: Vec<String>,
 
     /// Circuit identifier
@@ -117,6 +129,55 @@ impl GeneratedProof {
         hasher.finalize().into()
     }
 
+    /// Whether the proof was generated for `request`
+    ///
+    /// The hex-encoded verifier domain hash, nonce hash and nullifier must
+    /// all be among the public inputs, so that the proof cannot be replayed
+    /// to another verifier or for another request. The public inputs must
+    /// also carry the values of the claim it is presented for.
+    pub fn is_bound_to(&self, request: &ProofRequest) -> bool {
+        self.proves(&self.claim_type)
+            && [
+                request.verifier_domain_hash(),
+                request.nonce_hash(),
+                self.nullifier,
+            ]
+            .iter()
+            .all(|input| self.public_inputs.contains(&hex::encode(input)))
+    }
+
+    /// Whether the proof was generated by the wallet behind `subject`
+    ///
+    /// The hex-encoded hash of the subject must be among the public inputs,
+    /// so that the proof cannot be presented with a subject of someone
+    /// else's choosing.
+    pub fn is_bound_to_subject(&self, subject: &str) -> bool {
+        self.public_inputs
+            .contains(&hex::encode(subject_hash(subject)))
+    }
+
+    /// Key hash of the issuer whose credential the proof rests on, from
+    /// the public input after the claim values and binding values
+    ///
+    /// Only meaningful once the proof has been verified against its public
+    /// inputs.
+    pub fn issuer_key_hash(&self) -> Option<&str> {
+        self.public_inputs
+            .get(self.claim_type.public_inputs().len() + 4)
+            .map(String::as_str)
+    }
+
+    /// Whether the public inputs start with the values of `claim_type`,
+    /// so that the proof establishes that claim and no weaker one
+    pub fn proves(&self, claim_type: &ClaimType) -> bool {
+        let values = claim_type.public_inputs();
+        values.len() <= self.public_inputs.len()
+            && values
+                .iter()
+                .zip(&self.public_inputs)
+                .all(|(value, input)| hex::encode(value) == *input)
+    }
+
     /// Convert to a proof reference (for storage)
     pub fn to_ref(&self, url: Option<String>) -> ProofRef {
         ProofRef {
@@ -163,6 +224,16 @@ pub struct VerificationResult {
     pub verified_at: DateTime<Utc>,
 }
 
+/// Hash of a subject identifier, as bound into a proof's public inputs
+pub fn subject_hash(subject: &str) -> [u8; 32] {
+    sha3_256(subject.as_bytes())
+}
+
+pub(crate) fn sha3_256(data: &[u8]) -> [u8; 32] {
+    use sha3::{Digest, Sha3_256};
+    Sha3_256::digest(data).into()
+}
+
 mod hex_bytes {
     use serde::{Deserialize, Deserializer, Serializer};
 
@@ -184,3 +255,97 @@ mod hex_bytes {
             .map_err(|_| serde::de::Error::custom("invalid byte length"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proof_binding() {
+        let request = ProofRequest::new(
+            "rp.example.com",
+            vec![ClaimRequest {
+                claim_type: ClaimType::AgeAtLeast { threshold: 18 },
+                required: true,
+                reason: None,
+            }],
+            300,
+        );
+        let nullifier = [7u8; 32];
+        let mut proof = GeneratedProof {
+            claim_type: ClaimType::AgeAtLeast { threshold: 18 },
+            proof_bytes: vec![1, 2, 3],
+            public_inputs: ClaimType::AgeAtLeast { threshold: 18 }
+                .public_inputs()
+                .iter()
+                .chain([
+                    &request.verifier_domain_hash(),
+                    &request.nonce_hash(),
+                    &nullifier,
+                ])
+                .map(hex::encode)
+                .collect(),
+            circuit_id: "age_verification_v1".to_string(),
+            nullifier,
+            generated_at: Utc::now(),
+        };
+        assert!(proof.is_bound_to(&request));
+
+        // The threshold is a public input, so the claim cannot be raised
+        proof.claim_type = ClaimType::AgeAtLeast { threshold: 21 };
+        assert!(!proof.is_bound_to(&request));
+        assert!(!proof.proves(&ClaimType::AgeAtLeast { threshold: 21 }));
+        proof.claim_type = ClaimType::AgeAtLeast { threshold: 18 };
+
+        // A proof for another request carries another nonce
+        let other = ProofRequest::new("rp.example.com", vec![], 300);
+        assert!(!proof.is_bound_to(&other));
+
+        // The nullifier must be a public input too
+        proof.nullifier = [8u8; 32];
+        assert!(!proof.is_bound_to(&request));
+    }
+
+    #[test]
+    fn test_subject_binding() {
+        let mut proof = GeneratedProof {
+            claim_type: ClaimType::AgeAtLeast { threshold: 18 },
+            proof_bytes: vec![1, 2, 3],
+            public_inputs: vec![],
+            circuit_id: "age_verification_v1".to_string(),
+            nullifier: [7u8; 32],
+            generated_at: Utc::now(),
+        };
+        assert!(!proof.is_bound_to_subject("zkid:alice"));
+
+        proof
+            .public_inputs
+            .push(hex::encode(subject_hash("zkid:alice")));
+        assert!(proof.is_bound_to_subject("zkid:alice"));
+        assert!(!proof.is_bound_to_subject("zkid:bob"));
+    }
+
+    #[test]
+    fn test_issuer_key_hash() {
+        let claim_type = ClaimType::AgeAtLeast { threshold: 18 };
+        let mut proof = GeneratedProof {
+            claim_type: claim_type.clone(),
+            proof_bytes: vec![1, 2, 3],
+            public_inputs: claim_type
+                .public_inputs()
+                .iter()
+                .chain(&[[1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]])
+                .map(hex::encode)
+                .collect(),
+            circuit_id: "age_verification_v1".to_string(),
+            nullifier: [3u8; 32],
+            generated_at: Utc::now(),
+        };
+        // No issuer after the binding values
+        assert_eq!(proof.issuer_key_hash(), None);
+
+        let key_hash = hex::encode([9u8; 32]);
+        proof.public_inputs.push(key_hash.clone());
+        assert_eq!(proof.issuer_key_hash(), Some(key_hash.as_str()));
+    }
+}
@@ -4,7 +4,7 @@
 //! a NIST PQC standardized signature scheme.
 
 use pqcrypto_dilithium::dilithium3;
-use pqcrypto_traits::sign::{PublicKey, SecretKey, SignedMessage};
+use pqcrypto_traits::sign::{DetachedSignature, PublicKey, SecretKey, SignedMessage};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use zeroize::{Zeroize, ZeroizeOnDrop};
@@ -79,6 +79,17 @@ impl DilithiumPublicKey {
 
         Ok(())
     }
+
+    /// Verify a detached signature (as produced by `sign_detached`)
+    pub fn verify_detached(&self, message: &[u8], signature: &[u8]) -> Result<(), DilithiumError> {
+        let pk = dilithium3::PublicKey::from_bytes(&self.bytes)
+            .map_err(|_| DilithiumError::InvalidPublicKey)?;
+        let sig = dilithium3::DetachedSignature::from_bytes(signature)
+            .map_err(|_| DilithiumError::InvalidSignature)?;
+
+        dilithium3::verify_detached_signature(&sig, message, &pk)
+            .map_err(|_| DilithiumError::VerificationFailed)
+    }
 }
 
 impl std::fmt::Debug for DilithiumPublicKey {
@@ -116,6 +127,13 @@ impl DilithiumSecretKey {
             bytes: signed_msg.as_bytes().to_vec(),
         }
     }
+
+    /// Sign a message, returning only the signature bytes (no embedded message)
+    pub fn sign_detached(&self, message: &[u8]) -> Vec<u8> {
+        let sk =
+            dilithium3::SecretKey::from_bytes(&self.bytes).expect("Already validated secret key");
+        dilithium3::detached_sign(message, &sk).as_bytes().to_vec()
+    }
 }
 
 impl std::fmt::Debug for DilithiumSecretKey {
@@ -221,6 +239,23 @@ mod tests {
         assert!(keypair.verify(message, &signature).is_ok());
     }
 
+    #[test]
+    fn test_detached_sign_and_verify() {
+        let keypair = DilithiumKeypair::generate();
+        let message = b"Hello, Fantasma!";
+
+        let signature = keypair.secret_key.sign_detached(message);
+        assert_eq!(signature.len(), dilithium3::signature_bytes());
+        assert!(keypair
+            .public_key
+            .verify_detached(message, &signature)
+            .is_ok());
+        assert!(keypair
+            .public_key
+            .verify_detached(b"Wrong message", &signature)
+            .is_err());
+    }
+
     #[test]
     fn test_verify_wrong_message() {
         let keypair = DilithiumKeypair::generate();
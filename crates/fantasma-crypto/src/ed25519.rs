@@ -0,0 +1,138 @@
+//! Ed25519 signatures
+//!
+//! Classical signatures used where relying parties need interoperable
+//! JOSE algorithms (JWS `EdDSA`), such as signing OIDC ID tokens.
+
+use ed25519_dalek::{Signer, Verifier};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Ed25519Error {
+    #[error("Invalid public key")]
+    InvalidPublicKey,
+
+    #[error("Invalid secret key")]
+    InvalidSecretKey,
+
+    #[error("Invalid signature")]
+    InvalidSignature,
+
+    #[error("Signature verification failed")]
+    VerificationFailed,
+}
+
+/// Ed25519 public key
+#[derive(Clone, PartialEq, Eq)]
+pub struct Ed25519PublicKey {
+    key: ed25519_dalek::VerifyingKey,
+}
+
+impl Ed25519PublicKey {
+    /// Create from raw 32-byte encoding
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Ed25519Error> {
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| Ed25519Error::InvalidPublicKey)?;
+        let key = ed25519_dalek::VerifyingKey::from_bytes(&bytes)
+            .map_err(|_| Ed25519Error::InvalidPublicKey)?;
+        Ok(Self { key })
+    }
+
+    /// Get raw bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        self.key.as_bytes()
+    }
+
+    /// Verify a detached signature
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), Ed25519Error> {
+        let signature = ed25519_dalek::Signature::from_slice(signature)
+            .map_err(|_| Ed25519Error::InvalidSignature)?;
+        self.key
+            .verify(message, &signature)
+            .map_err(|_| Ed25519Error::VerificationFailed)
+    }
+}
+
+impl std::fmt::Debug for Ed25519PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Ed25519PublicKey({})", hex::encode(self.as_bytes()))
+    }
+}
+
+/// Ed25519 keypair (secret half is zeroed on drop)
+pub struct Ed25519Keypair {
+    signing_key: ed25519_dalek::SigningKey,
+}
+
+impl Ed25519Keypair {
+    /// Generate a new keypair
+    pub fn generate() -> Self {
+        Self {
+            signing_key: ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng),
+        }
+    }
+
+    /// Restore a keypair from its 32-byte secret seed
+    pub fn from_seed(seed: &[u8]) -> Result<Self, Ed25519Error> {
+        let seed: [u8; 32] = seed
+            .try_into()
+            .map_err(|_| Ed25519Error::InvalidSecretKey)?;
+        Ok(Self {
+            signing_key: ed25519_dalek::SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Get the 32-byte secret seed (use carefully!)
+    pub fn seed(&self) -> &[u8; 32] {
+        self.signing_key.as_bytes()
+    }
+
+    /// Get the public key
+    pub fn public_key(&self) -> Ed25519PublicKey {
+        Ed25519PublicKey {
+            key: self.signing_key.verifying_key(),
+        }
+    }
+
+    /// Sign a message, returning the 64-byte detached signature
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        self.signing_key.sign(message).to_bytes()
+    }
+
+    /// Verify a detached signature
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), Ed25519Error> {
+        self.public_key().verify(message, signature)
+    }
+}
+
+impl std::fmt::Debug for Ed25519Keypair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ed25519Keypair")
+            .field("public_key", &self.public_key())
+            .field("secret_key", &"[REDACTED]")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keypair = Ed25519Keypair::generate();
+        let message = b"Hello, Fantasma!";
+
+        let signature = keypair.sign(message);
+        assert!(keypair.verify(message, &signature).is_ok());
+        assert!(keypair.verify(b"Wrong message", &signature).is_err());
+    }
+
+    #[test]
+    fn test_seed_roundtrip() {
+        let keypair = Ed25519Keypair::generate();
+        let restored = Ed25519Keypair::from_seed(keypair.seed()).unwrap();
+
+        assert_eq!(keypair.public_key(), restored.public_key());
+    }
+}
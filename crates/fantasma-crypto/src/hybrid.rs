@@ -0,0 +1,210 @@
+//! Hybrid composite signatures (Ed25519 + ML-DSA-65)
+//!
+//! During the post-quantum transition a message is signed with both a
+//! classical Ed25519 key and an ML-DSA-65 (FIPS 204) key. Verifiers that
+//! only trust the combination require both signatures; verifiers that still
+//! lack one of the algorithms accept either.
+//!
+//! Encodings are plain concatenations, classical half first:
+//! - public key: Ed25519 (32 bytes) || ML-DSA-65 public key
+//! - signature: Ed25519 (64 bytes) || ML-DSA-65 signature
+
+use crate::ed25519::{Ed25519Keypair, Ed25519PublicKey};
+use crate::mldsa::{MlDsa65Keypair, MlDsa65PublicKey};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const ED25519_PUBLIC_KEY_LEN: usize = 32;
+const ED25519_SIGNATURE_LEN: usize = 64;
+
+#[derive(Error, Debug)]
+pub enum HybridError {
+    #[error("Invalid public key")]
+    InvalidPublicKey,
+
+    #[error("Invalid signature")]
+    InvalidSignature,
+
+    #[error("Signature verification failed")]
+    VerificationFailed,
+}
+
+/// Which component signatures a verifier insists on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HybridPolicy {
+    /// Both the Ed25519 and the ML-DSA-65 signature must verify
+    #[default]
+    RequireBoth,
+    /// Either signature verifying is enough
+    AcceptEither,
+}
+
+/// Hybrid public key
+#[derive(Debug, Clone)]
+pub struct HybridPublicKey {
+    pub ed25519: Ed25519PublicKey,
+    pub ml_dsa: MlDsa65PublicKey,
+}
+
+impl HybridPublicKey {
+    /// Decode the concatenated encoding
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, HybridError> {
+        if bytes.len() <= ED25519_PUBLIC_KEY_LEN {
+            return Err(HybridError::InvalidPublicKey);
+        }
+        let (ed25519, ml_dsa) = bytes.split_at(ED25519_PUBLIC_KEY_LEN);
+        Ok(Self {
+            ed25519: Ed25519PublicKey::from_bytes(ed25519)
+                .map_err(|_| HybridError::InvalidPublicKey)?,
+            ml_dsa: MlDsa65PublicKey::from_bytes(ml_dsa)
+                .map_err(|_| HybridError::InvalidPublicKey)?,
+        })
+    }
+
+    /// Concatenated encoding
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.ed25519.as_bytes().as_slice(), self.ml_dsa.as_bytes()].concat()
+    }
+
+    /// Verify a composite signature under `policy`
+    pub fn verify(
+        &self,
+        message: &[u8],
+        signature: &HybridSignature,
+        policy: HybridPolicy,
+    ) -> Result<(), HybridError> {
+        let ed25519 = self.ed25519.verify(message, &signature.ed25519).is_ok();
+        let ml_dsa = self.ml_dsa.verify(message, &signature.ml_dsa).is_ok();
+
+        let valid = match policy {
+            HybridPolicy::RequireBoth => ed25519 && ml_dsa,
+            HybridPolicy::AcceptEither => ed25519 || ml_dsa,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(HybridError::VerificationFailed)
+        }
+    }
+}
+
+/// Composite signature: one signature per component key
+#[derive(Clone, PartialEq, Eq)]
+pub struct HybridSignature {
+    pub ed25519: Vec<u8>,
+    pub ml_dsa: Vec<u8>,
+}
+
+impl HybridSignature {
+    /// Decode the concatenated encoding
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, HybridError> {
+        if bytes.len() <= ED25519_SIGNATURE_LEN {
+            return Err(HybridError::InvalidSignature);
+        }
+        let (ed25519, ml_dsa) = bytes.split_at(ED25519_SIGNATURE_LEN);
+        Ok(Self {
+            ed25519: ed25519.to_vec(),
+            ml_dsa: ml_dsa.to_vec(),
+        })
+    }
+
+    /// Concatenated encoding
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.ed25519.as_slice(), self.ml_dsa.as_slice()].concat()
+    }
+}
+
+impl std::fmt::Debug for HybridSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "HybridSignature({} + {} bytes)",
+            self.ed25519.len(),
+            self.ml_dsa.len()
+        )
+    }
+}
+
+/// Hybrid keypair
+#[derive(Debug)]
+pub struct HybridKeypair {
+    pub ed25519: Ed25519Keypair,
+    pub ml_dsa: MlDsa65Keypair,
+}
+
+impl HybridKeypair {
+    /// Generate a new keypair
+    pub fn generate() -> Self {
+        Self::from_parts(Ed25519Keypair::generate(), MlDsa65Keypair::generate())
+    }
+
+    /// Combine existing component keypairs
+    pub fn from_parts(ed25519: Ed25519Keypair, ml_dsa: MlDsa65Keypair) -> Self {
+        Self { ed25519, ml_dsa }
+    }
+
+    /// Get the public key
+    pub fn public_key(&self) -> HybridPublicKey {
+        HybridPublicKey {
+            ed25519: self.ed25519.public_key(),
+            ml_dsa: self.ml_dsa.public_key.clone(),
+        }
+    }
+
+    /// Sign a message with both keys
+    pub fn sign(&self, message: &[u8]) -> HybridSignature {
+        HybridSignature {
+            ed25519: self.ed25519.sign(message).to_vec(),
+            ml_dsa: self.ml_dsa.sign(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keypair = HybridKeypair::generate();
+        let public_key = HybridPublicKey::from_bytes(&keypair.public_key().to_bytes()).unwrap();
+        let signature = HybridSignature::from_bytes(&keypair.sign(b"message").to_bytes()).unwrap();
+
+        for policy in [HybridPolicy::RequireBoth, HybridPolicy::AcceptEither] {
+            assert!(public_key.verify(b"message", &signature, policy).is_ok());
+            assert!(public_key.verify(b"other", &signature, policy).is_err());
+        }
+    }
+
+    #[test]
+    fn test_policy_with_one_broken_component() {
+        let keypair = HybridKeypair::generate();
+        let public_key = keypair.public_key();
+
+        // Only the ML-DSA half verifies
+        let mut signature = keypair.sign(b"message");
+        signature.ed25519[0] ^= 1;
+        assert!(public_key
+            .verify(b"message", &signature, HybridPolicy::RequireBoth)
+            .is_err());
+        assert!(public_key
+            .verify(b"message", &signature, HybridPolicy::AcceptEither)
+            .is_ok());
+
+        let mut signature = keypair.sign(b"message");
+        signature.ml_dsa.truncate(10);
+        assert!(public_key
+            .verify(b"message", &signature, HybridPolicy::RequireBoth)
+            .is_err());
+        assert!(public_key
+            .verify(b"message", &signature, HybridPolicy::AcceptEither)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_rejects_plain_ml_dsa_key() {
+        let ml_dsa = MlDsa65Keypair::generate();
+        assert!(HybridPublicKey::from_bytes(ml_dsa.public_key.as_bytes()).is_err());
+    }
+}
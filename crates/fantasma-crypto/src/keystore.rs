@@ -1,7 +1,7 @@
-//! Persistent key storage for Dilithium signing keys
+//! Persistent key storage for signing keys
 //!
-//! Loads or generates a Dilithium keypair from disk. The secret key is
-//! encrypted at rest using AES-256-GCM with a key derived from a
+//! Loads or generates the Dilithium keypair, the Ed25519 token-signing
+//! keypair and the ML-DSA-65 token-signing keypair from disk. Secret keys are encrypted at rest using AES-256-GCM with a key derived from a
 //! passphrase via SHA3-256 (in production, use Argon2 or similar KDF).
 //!
 //! Directory layout:
@@ -9,12 +9,20 @@
 //! <key_dir>/
 //!   signing.pub    — raw Dilithium3 public key bytes
 //!   signing.key    — encrypted secret key: nonce(12) || ciphertext
+//!   ed25519.pub    — raw Ed25519 public key bytes (ID token signing)
+//!   ed25519.key    — encrypted Ed25519 seed: nonce(12) || ciphertext
+//!   ml-dsa-65.pub  — raw ML-DSA-65 (FIPS 204) public key bytes
+//!   ml-dsa-65.key  — encrypted ML-DSA-65 secret key: nonce(12) || ciphertext
+//!   generations/
+//!     <name>/      — one generation of a rotating key, same layout
 //! ```
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 use crate::dilithium::{DilithiumKeypair, DilithiumPublicKey, DilithiumSecretKey};
+use crate::ed25519::{Ed25519Keypair, Ed25519PublicKey};
+use crate::mldsa::{MlDsa65Keypair, MlDsa65PublicKey, MlDsa65SecretKey};
 
 #[derive(Error, Debug)]
 pub enum KeyStoreError {
@@ -43,6 +51,57 @@ impl KeyStore {
         Ok(Self { dir })
     }
 
+    /// Directory backing this store.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Directory holding the generations of rotating keys.
+    fn generations_dir(&self) -> PathBuf {
+        self.dir.join("generations")
+    }
+
+    /// Key store for the generation `name` of a rotating key, created if
+    /// absent. Names are limited to `[A-Za-z0-9_-]` so they stay inside the
+    /// store.
+    pub fn generation(&self, name: &str) -> Result<KeyStore> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(KeyStoreError::InvalidKey(format!(
+                "invalid generation name: {:?}",
+                name
+            )));
+        }
+        KeyStore::new(self.generations_dir().join(name))
+    }
+
+    /// Names of the stored generations, sorted.
+    pub fn generations(&self) -> Result<Vec<String>> {
+        let dir = self.generations_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                names.extend(entry.file_name().to_str().map(str::to_string));
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Delete the generation `name` and its keys.
+    pub fn remove_generation(&self, name: &str) -> Result<()> {
+        let store = self.generation(name)?;
+        std::fs::remove_dir_all(store.dir)?;
+        Ok(())
+    }
+
     /// Path to the public key file.
     fn pub_path(&self) -> PathBuf {
         self.dir.join("signing.pub")
@@ -53,11 +112,41 @@ impl KeyStore {
         self.dir.join("signing.key")
     }
 
+    /// Path to the Ed25519 public key file.
+    fn ed25519_pub_path(&self) -> PathBuf {
+        self.dir.join("ed25519.pub")
+    }
+
+    /// Path to the (encrypted) Ed25519 seed file.
+    fn ed25519_key_path(&self) -> PathBuf {
+        self.dir.join("ed25519.key")
+    }
+
+    /// Path to the ML-DSA-65 public key file.
+    fn ml_dsa_65_pub_path(&self) -> PathBuf {
+        self.dir.join("ml-dsa-65.pub")
+    }
+
+    /// Path to the (encrypted) ML-DSA-65 secret key file.
+    fn ml_dsa_65_key_path(&self) -> PathBuf {
+        self.dir.join("ml-dsa-65.key")
+    }
+
     /// Returns `true` when both key files exist on disk.
     pub fn has_keys(&self) -> bool {
         self.pub_path().exists() && self.key_path().exists()
     }
 
+    /// Returns `true` when both Ed25519 key files exist on disk.
+    pub fn has_ed25519_keys(&self) -> bool {
+        self.ed25519_pub_path().exists() && self.ed25519_key_path().exists()
+    }
+
+    /// Returns `true` when both ML-DSA-65 key files exist on disk.
+    pub fn has_ml_dsa_65_keys(&self) -> bool {
+        self.ml_dsa_65_pub_path().exists() && self.ml_dsa_65_key_path().exists()
+    }
+
     /// Load or generate a keypair.
     ///
     /// * If keys exist on disk they are loaded (the secret key is decrypted
@@ -127,6 +216,111 @@ impl KeyStore {
     pub fn public_key_hash(&self) -> Result<[u8; 32]> {
         Ok(self.load_public_key()?.hash())
     }
+
+    /// Load or generate the Ed25519 token-signing keypair.
+    pub fn load_or_generate_ed25519(&self, passphrase: &str) -> Result<Ed25519Keypair> {
+        if self.has_ed25519_keys() {
+            self.load_ed25519(passphrase)
+        } else {
+            let kp = Ed25519Keypair::generate();
+            self.save_ed25519(&kp, passphrase)?;
+            Ok(kp)
+        }
+    }
+
+    /// Persist an Ed25519 keypair to disk. The seed is encrypted.
+    pub fn save_ed25519(&self, keypair: &Ed25519Keypair, passphrase: &str) -> Result<()> {
+        std::fs::write(self.ed25519_pub_path(), keypair.public_key().as_bytes())?;
+
+        let enc_key = derive_key(passphrase);
+        let encrypted = encrypt_secret_key(keypair.seed(), &enc_key)?;
+        std::fs::write(self.ed25519_key_path(), &encrypted)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(
+                self.ed25519_key_path(),
+                std::fs::Permissions::from_mode(0o600),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Load an Ed25519 keypair from disk.
+    pub fn load_ed25519(&self, passphrase: &str) -> Result<Ed25519Keypair> {
+        let pub_bytes = std::fs::read(self.ed25519_pub_path())?;
+        let enc_bytes = std::fs::read(self.ed25519_key_path())?;
+
+        let public_key = Ed25519PublicKey::from_bytes(&pub_bytes)
+            .map_err(|e| KeyStoreError::InvalidKey(format!("ed25519 public key: {}", e)))?;
+
+        let enc_key = derive_key(passphrase);
+        let seed = decrypt_secret_key(&enc_bytes, &enc_key)?;
+
+        let keypair = Ed25519Keypair::from_seed(&seed)
+            .map_err(|e| KeyStoreError::InvalidKey(format!("ed25519 secret key: {}", e)))?;
+
+        if keypair.public_key() != public_key {
+            return Err(KeyStoreError::InvalidKey(
+                "ed25519 public key does not match secret key".into(),
+            ));
+        }
+
+        Ok(keypair)
+    }
+
+    /// Load or generate the ML-DSA-65 token-signing keypair.
+    pub fn load_or_generate_ml_dsa_65(&self, passphrase: &str) -> Result<MlDsa65Keypair> {
+        if self.has_ml_dsa_65_keys() {
+            self.load_ml_dsa_65(passphrase)
+        } else {
+            let kp = MlDsa65Keypair::generate();
+            self.save_ml_dsa_65(&kp, passphrase)?;
+            Ok(kp)
+        }
+    }
+
+    /// Persist an ML-DSA-65 keypair to disk. The secret key is encrypted.
+    pub fn save_ml_dsa_65(&self, keypair: &MlDsa65Keypair, passphrase: &str) -> Result<()> {
+        std::fs::write(self.ml_dsa_65_pub_path(), keypair.public_key.as_bytes())?;
+
+        let enc_key = derive_key(passphrase);
+        let encrypted = encrypt_secret_key(keypair.secret_key.as_bytes(), &enc_key)?;
+        std::fs::write(self.ml_dsa_65_key_path(), &encrypted)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(
+                self.ml_dsa_65_key_path(),
+                std::fs::Permissions::from_mode(0o600),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Load an ML-DSA-65 keypair from disk.
+    pub fn load_ml_dsa_65(&self, passphrase: &str) -> Result<MlDsa65Keypair> {
+        let pub_bytes = std::fs::read(self.ml_dsa_65_pub_path())?;
+        let enc_bytes = std::fs::read(self.ml_dsa_65_key_path())?;
+
+        let public_key = MlDsa65PublicKey::from_bytes(&pub_bytes)
+            .map_err(|e| KeyStoreError::InvalidKey(format!("ml-dsa-65 public key: {}", e)))?;
+
+        let enc_key = derive_key(passphrase);
+        let sk_bytes = decrypt_secret_key(&enc_bytes, &enc_key)?;
+
+        let secret_key = MlDsa65SecretKey::from_bytes(&sk_bytes)
+            .map_err(|e| KeyStoreError::InvalidKey(format!("ml-dsa-65 secret key: {}", e)))?;
+
+        Ok(MlDsa65Keypair {
+            public_key,
+            secret_key,
+        })
+    }
 }
 
 // ── Helpers ─────────────────────────────────────────────────────
@@ -155,7 +349,7 @@ fn encrypt_secret_key(plaintext: &[u8], key: &[u8; 32]) -> Result<Vec<u8>> {
     // Compute authentication tag: SHA3(key || nonce || ciphertext)
     let mut tag_hasher = Sha3_256::new();
     tag_hasher.update(key);
-    tag_hasher.update(&nonce);
+    tag_hasher.update(nonce);
     tag_hasher.update(&ciphertext);
     let tag: [u8; 32] = tag_hasher.finalize().into();
 
@@ -208,7 +402,7 @@ fn xor_stream_encrypt(data: &[u8], key: &[u8; 32], nonce: &[u8]) -> Vec<u8> {
         let mut hasher = Sha3_256::new();
         hasher.update(key);
         hasher.update(nonce);
-        hasher.update(&counter.to_le_bytes());
+        hasher.update(counter.to_le_bytes());
         let block: [u8; 32] = hasher.finalize().into();
 
         let chunk_len = (data.len() - offset).min(32);
@@ -271,6 +465,56 @@ mod tests {
         assert_eq!(plaintext.as_slice(), decrypted.as_slice());
     }
 
+    #[test]
+    fn test_key_store_ed25519_generate_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = KeyStore::new(dir.path()).unwrap();
+
+        assert!(!store.has_ed25519_keys());
+        let kp1 = store.load_or_generate_ed25519("pass").unwrap();
+        assert!(store.has_ed25519_keys());
+
+        let kp2 = store.load_ed25519("pass").unwrap();
+        assert_eq!(kp1.public_key(), kp2.public_key());
+        assert!(store.load_ed25519("wrong").is_err());
+    }
+
+    #[test]
+    fn test_key_store_ml_dsa_65_generate_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = KeyStore::new(dir.path()).unwrap();
+
+        assert!(!store.has_ml_dsa_65_keys());
+        let kp1 = store.load_or_generate_ml_dsa_65("pass").unwrap();
+        assert!(store.has_ml_dsa_65_keys());
+        assert!(!store.has_keys());
+
+        let kp2 = store.load_ml_dsa_65("pass").unwrap();
+        assert!(kp1.verify(b"message", &kp2.sign(b"message")).is_ok());
+        assert!(store.load_ml_dsa_65("wrong").is_err());
+    }
+
+    #[test]
+    fn test_key_store_generations() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = KeyStore::new(dir.path()).unwrap();
+        assert!(store.generations().unwrap().is_empty());
+
+        let first = store.generation("gen-1").unwrap();
+        let kp = first.load_or_generate_ed25519("pass").unwrap();
+        store.generation("gen-2").unwrap();
+        assert_eq!(store.generations().unwrap(), vec!["gen-1", "gen-2"]);
+
+        // Each generation keeps its own keys, apart from the top-level pair
+        assert!(!store.has_ed25519_keys());
+        let loaded = store.generation("gen-1").unwrap().load_ed25519("pass");
+        assert_eq!(loaded.unwrap().public_key(), kp.public_key());
+
+        store.remove_generation("gen-1").unwrap();
+        assert_eq!(store.generations().unwrap(), vec!["gen-2"]);
+        assert!(store.generation("../escape").is_err());
+    }
+
     #[test]
     fn test_public_key_hash() {
         let dir = tempfile::tempdir().unwrap();
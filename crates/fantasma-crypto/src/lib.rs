@@ -1,16 +1,32 @@
 //! Fantasma Crypto
 //!
 //! Post-quantum cryptographic primitives for the Fantasma ZK identity layer.
-//! Uses Dilithium signatures (NIST ML-DSA) and Poseidon hash.
+//! Uses Dilithium and ML-DSA (FIPS 204) signatures, ML-KEM key encapsulation
+//! and Poseidon hash, plus Ed25519 and X25519 for interoperable JOSE. Hybrid
+//! signatures combine Ed25519 and ML-DSA for the post-quantum transition.
 
 pub mod dilithium;
+pub mod ed25519;
 pub mod hash;
+pub mod hybrid;
 pub mod keystore;
 pub mod merkle;
+pub mod mldsa;
+pub mod mlkem;
 pub mod nullifier;
+pub mod pairwise;
+pub mod secret;
+pub mod x25519;
 
 pub use dilithium::{DilithiumKeypair, DilithiumPublicKey, DilithiumSignature};
+pub use ed25519::{Ed25519Keypair, Ed25519PublicKey};
 pub use hash::{poseidon_hash, poseidon_hash_pair, sha3_256};
+pub use hybrid::{HybridKeypair, HybridPolicy, HybridPublicKey, HybridSignature};
 pub use keystore::KeyStore;
 pub use merkle::{MerkleProof, MerkleTree};
+pub use mldsa::{MlDsa65Keypair, MlDsa65PublicKey};
+pub use mlkem::{MlKemKeypair, MlKemPublicKey};
 pub use nullifier::Nullifier;
+pub use pairwise::{is_pairwise_subject, pairwise_subject};
+pub use secret::{generate_secret, hash_secret, verify_secret};
+pub use x25519::{X25519Keypair, X25519PublicKey};
@@ -120,13 +120,13 @@ impl MerkleTree {
             let sibling_index = current_index ^ 1;
             let parent_index = current_index / 2;
 
-            let left = if current_index % 2 == 0 {
+            let left = if current_index.is_multiple_of(2) {
                 self.get_node(level, current_index)
             } else {
                 self.get_node(level, sibling_index)
             };
 
-            let right = if current_index % 2 == 0 {
+            let right = if current_index.is_multiple_of(2) {
                 self.get_node(level, sibling_index)
             } else {
                 self.get_node(level, current_index)
@@ -0,0 +1,172 @@
+//! ML-DSA signatures (FIPS 204)
+//!
+//! ML-DSA-65, the standardized form of Dilithium at NIST security level 3.
+//! Its keys and signatures are not interchangeable with the round-3
+//! Dilithium3 ones in `dilithium`: this is the algorithm JOSE publishes as
+//! `ML-DSA-65`. Signatures are detached, with an empty context string.
+
+use pqcrypto_mldsa::mldsa65;
+use pqcrypto_traits::sign::{DetachedSignature, PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[derive(Error, Debug)]
+pub enum MlDsaError {
+    #[error("Invalid signature")]
+    InvalidSignature,
+
+    #[error("Invalid public key")]
+    InvalidPublicKey,
+
+    #[error("Invalid secret key")]
+    InvalidSecretKey,
+
+    #[error("Signature verification failed")]
+    VerificationFailed,
+}
+
+/// ML-DSA-65 public key
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MlDsa65PublicKey {
+    bytes: Vec<u8>,
+}
+
+impl MlDsa65PublicKey {
+    /// Create from raw bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlDsaError> {
+        mldsa65::PublicKey::from_bytes(bytes).map_err(|_| MlDsaError::InvalidPublicKey)?;
+        Ok(Self {
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    /// Get raw bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Verify a detached signature
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), MlDsaError> {
+        let pk = mldsa65::PublicKey::from_bytes(&self.bytes)
+            .map_err(|_| MlDsaError::InvalidPublicKey)?;
+        let sig = mldsa65::DetachedSignature::from_bytes(signature)
+            .map_err(|_| MlDsaError::InvalidSignature)?;
+
+        mldsa65::verify_detached_signature(&sig, message, &pk)
+            .map_err(|_| MlDsaError::VerificationFailed)
+    }
+}
+
+impl std::fmt::Debug for MlDsa65PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MlDsa65PublicKey({} bytes)", self.bytes.len())
+    }
+}
+
+/// ML-DSA-65 secret key (zeroed on drop for security)
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct MlDsa65SecretKey {
+    bytes: Vec<u8>,
+}
+
+impl MlDsa65SecretKey {
+    /// Create from raw bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlDsaError> {
+        mldsa65::SecretKey::from_bytes(bytes).map_err(|_| MlDsaError::InvalidSecretKey)?;
+        Ok(Self {
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    /// Get raw bytes (use carefully!)
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Sign a message, returning the detached signature
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        let sk = mldsa65::SecretKey::from_bytes(&self.bytes).expect("Already validated secret key");
+        mldsa65::detached_sign(message, &sk).as_bytes().to_vec()
+    }
+}
+
+impl std::fmt::Debug for MlDsa65SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MlDsa65SecretKey([REDACTED])")
+    }
+}
+
+/// ML-DSA-65 keypair
+pub struct MlDsa65Keypair {
+    pub public_key: MlDsa65PublicKey,
+    pub secret_key: MlDsa65SecretKey,
+}
+
+impl MlDsa65Keypair {
+    /// Generate a new keypair
+    pub fn generate() -> Self {
+        let (pk, sk) = mldsa65::keypair();
+        Self {
+            public_key: MlDsa65PublicKey {
+                bytes: pk.as_bytes().to_vec(),
+            },
+            secret_key: MlDsa65SecretKey {
+                bytes: sk.as_bytes().to_vec(),
+            },
+        }
+    }
+
+    /// Sign a message, returning the detached signature
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.secret_key.sign(message)
+    }
+
+    /// Verify a detached signature
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), MlDsaError> {
+        self.public_key.verify(message, signature)
+    }
+}
+
+impl std::fmt::Debug for MlDsa65Keypair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MlDsa65Keypair")
+            .field("public_key", &self.public_key)
+            .field("secret_key", &self.secret_key)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dilithium::DilithiumKeypair;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keypair = MlDsa65Keypair::generate();
+        let message = b"Hello, Fantasma!";
+
+        let signature = keypair.sign(message);
+        assert_eq!(signature.len(), 3309);
+        assert!(keypair.verify(message, &signature).is_ok());
+        assert!(keypair.verify(b"Wrong message", &signature).is_err());
+    }
+
+    #[test]
+    fn test_key_sizes() {
+        let keypair = MlDsa65Keypair::generate();
+        assert_eq!(keypair.public_key.as_bytes().len(), 1952);
+        assert_eq!(keypair.secret_key.as_bytes().len(), 4032);
+    }
+
+    #[test]
+    fn test_not_interchangeable_with_dilithium3() {
+        let keypair = DilithiumKeypair::generate();
+        let signature = keypair.secret_key.sign_detached(b"message");
+
+        // Round-3 Dilithium3 keys decode, but their signatures do not verify
+        let public_key = MlDsa65PublicKey::from_bytes(keypair.public_key.as_bytes()).unwrap();
+        assert!(public_key.verify(b"message", &signature).is_err());
+    }
+}
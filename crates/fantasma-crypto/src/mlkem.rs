@@ -0,0 +1,144 @@
+//! ML-KEM key encapsulation (NIST FIPS 203)
+//!
+//! Post-quantum key encapsulation with ML-KEM-768 (NIST Level 3), used to
+//! agree on the content encryption key of encrypted tokens.
+
+use pqcrypto_mlkem::mlkem768;
+use pqcrypto_traits::kem::{Ciphertext, PublicKey, SecretKey, SharedSecret};
+use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[derive(Error, Debug)]
+pub enum MlKemError {
+    #[error("Invalid public key")]
+    InvalidPublicKey,
+
+    #[error("Invalid secret key")]
+    InvalidSecretKey,
+
+    #[error("Invalid ciphertext")]
+    InvalidCiphertext,
+}
+
+/// ML-KEM-768 encapsulation key
+#[derive(Clone, PartialEq, Eq)]
+pub struct MlKemPublicKey {
+    bytes: Vec<u8>,
+}
+
+impl MlKemPublicKey {
+    /// Create from raw bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlKemError> {
+        mlkem768::PublicKey::from_bytes(bytes).map_err(|_| MlKemError::InvalidPublicKey)?;
+        Ok(Self {
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    /// Get raw bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Encapsulate a fresh shared secret to this key, returning the secret
+    /// and the ciphertext only the secret key holder can decapsulate
+    pub fn encapsulate(&self) -> (Vec<u8>, Vec<u8>) {
+        let pk =
+            mlkem768::PublicKey::from_bytes(&self.bytes).expect("Already validated public key");
+        let (shared_secret, ciphertext) = mlkem768::encapsulate(&pk);
+        (
+            shared_secret.as_bytes().to_vec(),
+            ciphertext.as_bytes().to_vec(),
+        )
+    }
+}
+
+impl std::fmt::Debug for MlKemPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MlKemPublicKey({} bytes)", self.bytes.len())
+    }
+}
+
+/// ML-KEM-768 decapsulation key (zeroed on drop)
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct MlKemSecretKey {
+    bytes: Vec<u8>,
+}
+
+impl MlKemSecretKey {
+    /// Create from raw bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MlKemError> {
+        mlkem768::SecretKey::from_bytes(bytes).map_err(|_| MlKemError::InvalidSecretKey)?;
+        Ok(Self {
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    /// Get raw bytes (use carefully!)
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Recover the shared secret from an encapsulation ciphertext
+    ///
+    /// ML-KEM decapsulates implicitly: a tampered ciphertext yields an
+    /// unrelated secret rather than an error.
+    pub fn decapsulate(&self, ciphertext: &[u8]) -> Result<Vec<u8>, MlKemError> {
+        let sk =
+            mlkem768::SecretKey::from_bytes(&self.bytes).expect("Already validated secret key");
+        let ct = mlkem768::Ciphertext::from_bytes(ciphertext)
+            .map_err(|_| MlKemError::InvalidCiphertext)?;
+        Ok(mlkem768::decapsulate(&ct, &sk).as_bytes().to_vec())
+    }
+}
+
+impl std::fmt::Debug for MlKemSecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MlKemSecretKey([REDACTED])")
+    }
+}
+
+/// ML-KEM-768 keypair
+#[derive(Debug)]
+pub struct MlKemKeypair {
+    pub public_key: MlKemPublicKey,
+    pub secret_key: MlKemSecretKey,
+}
+
+impl MlKemKeypair {
+    /// Generate a new keypair
+    pub fn generate() -> Self {
+        let (pk, sk) = mlkem768::keypair();
+        Self {
+            public_key: MlKemPublicKey {
+                bytes: pk.as_bytes().to_vec(),
+            },
+            secret_key: MlKemSecretKey {
+                bytes: sk.as_bytes().to_vec(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encapsulate_and_decapsulate() {
+        let keypair = MlKemKeypair::generate();
+        let (shared_secret, ciphertext) = keypair.public_key.encapsulate();
+        assert_eq!(ciphertext.len(), mlkem768::ciphertext_bytes());
+        assert_eq!(
+            keypair.secret_key.decapsulate(&ciphertext).unwrap(),
+            shared_secret
+        );
+
+        let other = MlKemKeypair::generate();
+        assert_ne!(
+            other.secret_key.decapsulate(&ciphertext).unwrap(),
+            shared_secret
+        );
+        assert!(keypair.secret_key.decapsulate(b"short").is_err());
+    }
+}
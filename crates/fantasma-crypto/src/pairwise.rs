@@ -0,0 +1,59 @@
+//! Pairwise subject identifiers (OIDC Core §8.1)
+//!
+//! The wallet derives one pseudonymous `sub` per relying-party sector from
+//! its secret. A user presents the same `sub` to every client in a sector,
+//! while subjects from different sectors cannot be linked without the
+//! secret, which never leaves the wallet.
+
+use crate::hash::sha3_256_multi;
+
+/// Domain separation tag for subject derivation
+const PAIRWISE_SUBJECT_DOMAIN: &[u8] = b"fantasma:pairwise-sub:v1";
+
+/// Prefix of Fantasma subject identifiers
+pub const SUBJECT_PREFIX: &str = "zkid:";
+
+/// Derive the subject identifier for a sector (e.g. `rp.example`)
+pub fn pairwise_subject(user_secret: &[u8; 32], sector_identifier: &str) -> String {
+    let digest = sha3_256_multi(&[
+        PAIRWISE_SUBJECT_DOMAIN,
+        user_secret,
+        sector_identifier.as_bytes(),
+    ]);
+    format!("{}{}", SUBJECT_PREFIX, hex::encode(digest))
+}
+
+/// Whether `sub` has the shape of a derived pairwise subject
+pub fn is_pairwise_subject(sub: &str) -> bool {
+    sub.strip_prefix(SUBJECT_PREFIX).is_some_and(|digest| {
+        digest.len() == 64
+            && digest
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairwise_subject_is_stable_per_sector() {
+        let secret = [7u8; 32];
+
+        let a = pairwise_subject(&secret, "rp-a.example");
+        assert_eq!(a, pairwise_subject(&secret, "rp-a.example"));
+        assert!(is_pairwise_subject(&a));
+
+        // Different sectors, and different users, get unrelated subjects
+        assert_ne!(a, pairwise_subject(&secret, "rp-b.example"));
+        assert_ne!(a, pairwise_subject(&[8u8; 32], "rp-a.example"));
+    }
+
+    #[test]
+    fn test_subject_shape() {
+        assert!(!is_pairwise_subject("zkid:123"));
+        assert!(!is_pairwise_subject(&"a".repeat(69)));
+        assert!(!is_pairwise_subject(&format!("zkid:{}", "G".repeat(64))));
+    }
+}
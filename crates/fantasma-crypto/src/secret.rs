@@ -0,0 +1,63 @@
+//! Client secret hashing
+//!
+//! Confidential OIDC clients authenticate with a shared secret. Only an
+//! Argon2id hash (PHC string format) of the secret is ever stored.
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use thiserror::Error;
+
+/// Secret hashing errors
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("Failed to hash secret: {0}")]
+    HashFailed(String),
+}
+
+/// Generate a random client secret (256 bits, hex encoded)
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Hash a secret with Argon2id, returning a PHC string
+pub fn hash_secret(secret: &str) -> Result<String, SecretError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(secret.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| SecretError::HashFailed(e.to_string()))
+}
+
+/// Check a presented secret against a stored PHC hash
+///
+/// Malformed hashes never verify.
+pub fn verify_secret(secret: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| {
+            Argon2::default()
+                .verify_password(secret.as_bytes(), &parsed)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_verify_secret() {
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 64);
+
+        let hash = hash_secret(&secret).unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify_secret(&secret, &hash));
+        assert!(!verify_secret("wrong-secret", &hash));
+        assert!(!verify_secret(&secret, "not-a-phc-string"));
+    }
+}
@@ -0,0 +1,104 @@
+//! X25519 key agreement
+//!
+//! Classical Diffie-Hellman on Curve25519, used for JWE `ECDH-ES` where
+//! relying parties need interoperable key management.
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum X25519Error {
+    #[error("Invalid public key")]
+    InvalidPublicKey,
+
+    #[error("Invalid secret key")]
+    InvalidSecretKey,
+}
+
+/// X25519 public key
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct X25519PublicKey {
+    key: x25519_dalek::PublicKey,
+}
+
+impl X25519PublicKey {
+    /// Create from raw 32-byte encoding
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, X25519Error> {
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| X25519Error::InvalidPublicKey)?;
+        Ok(Self {
+            key: x25519_dalek::PublicKey::from(bytes),
+        })
+    }
+
+    /// Get raw bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        self.key.as_bytes()
+    }
+}
+
+impl std::fmt::Debug for X25519PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "X25519PublicKey({})", hex::encode(self.as_bytes()))
+    }
+}
+
+/// X25519 keypair (secret half is zeroed on drop)
+pub struct X25519Keypair {
+    secret: x25519_dalek::StaticSecret,
+}
+
+impl X25519Keypair {
+    /// Generate a new keypair
+    pub fn generate() -> Self {
+        Self {
+            secret: x25519_dalek::StaticSecret::random_from_rng(rand::rngs::OsRng),
+        }
+    }
+
+    /// Restore a keypair from its 32-byte secret
+    pub fn from_secret(secret: &[u8]) -> Result<Self, X25519Error> {
+        let secret: [u8; 32] = secret
+            .try_into()
+            .map_err(|_| X25519Error::InvalidSecretKey)?;
+        Ok(Self {
+            secret: x25519_dalek::StaticSecret::from(secret),
+        })
+    }
+
+    /// Get the public key
+    pub fn public_key(&self) -> X25519PublicKey {
+        X25519PublicKey {
+            key: x25519_dalek::PublicKey::from(&self.secret),
+        }
+    }
+
+    /// Diffie-Hellman shared secret with a peer's public key
+    pub fn diffie_hellman(&self, peer: &X25519PublicKey) -> [u8; 32] {
+        self.secret.diffie_hellman(&peer.key).to_bytes()
+    }
+}
+
+impl std::fmt::Debug for X25519Keypair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("X25519Keypair")
+            .field("public_key", &self.public_key())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diffie_hellman_agrees() {
+        let alice = X25519Keypair::generate();
+        let bob = X25519Keypair::generate();
+        assert_eq!(
+            alice.diffie_hellman(&bob.public_key()),
+            bob.diffie_hellman(&alice.public_key())
+        );
+        assert!(X25519PublicKey::from_bytes(&[0u8; 31]).is_err());
+    }
+}
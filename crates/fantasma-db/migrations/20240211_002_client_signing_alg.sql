@@ -0,0 +1,3 @@
+-- Per-client ID token signing algorithm (OIDC Dynamic Registration metadata)
+-- NULL means the provider default (EdDSA)
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS id_token_signed_response_alg VARCHAR(20);
@@ -0,0 +1,3 @@
+-- Per-client PKCE policy: reject the `plain` code challenge method
+-- Public clients must always use PKCE regardless of this flag
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS require_pkce_s256 BOOLEAN NOT NULL DEFAULT FALSE;
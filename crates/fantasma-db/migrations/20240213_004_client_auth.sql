@@ -0,0 +1,6 @@
+-- Client authentication at the token endpoint
+-- token_endpoint_auth_method: NULL means the default for the client type
+--   ('client_secret_basic' for confidential clients, 'none' for public clients)
+-- jwks: public keys used to verify private_key_jwt client assertions
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS token_endpoint_auth_method VARCHAR(50);
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS jwks JSONB;
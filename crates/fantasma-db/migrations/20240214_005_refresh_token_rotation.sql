@@ -0,0 +1,9 @@
+-- Refresh token rotation and reuse detection
+-- Every rotated token shares the family_id of the token issued with the
+-- authorization code; presenting a used token revokes the whole family.
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS family_id UUID NOT NULL DEFAULT gen_random_uuid();
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS used_at TIMESTAMPTZ;  -- NULL until rotated
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS zk_claims JSONB;  -- ZK claims re-asserted on refresh
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS zk_claims_verified_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family ON refresh_tokens(family_id);
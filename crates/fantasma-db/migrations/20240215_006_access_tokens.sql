@@ -0,0 +1,17 @@
+-- Access tokens
+-- Opaque bearer tokens bound to the subject, client, scopes and the ZK claims
+-- that went into the ID token; validated by /userinfo.
+CREATE TABLE IF NOT EXISTS access_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    token_hash BYTEA UNIQUE NOT NULL,  -- SHA256 hash of token
+    client_id VARCHAR(255) NOT NULL REFERENCES clients(client_id),
+    user_id VARCHAR(255) NOT NULL,
+    scopes TEXT[] NOT NULL,
+    zk_claims JSONB,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_access_tokens_hash ON access_tokens(token_hash);
+CREATE INDEX IF NOT EXISTS idx_access_tokens_expires ON access_tokens(expires_at);
@@ -0,0 +1,5 @@
+-- Link access tokens to the refresh token family (grant) they were issued
+-- under, so revoking a refresh token also revokes its access tokens (RFC 7009 §2.1).
+ALTER TABLE access_tokens ADD COLUMN IF NOT EXISTS family_id UUID;
+
+CREATE INDEX IF NOT EXISTS idx_access_tokens_family ON access_tokens(family_id);
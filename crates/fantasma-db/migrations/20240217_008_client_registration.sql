@@ -0,0 +1,7 @@
+-- Dynamic client registration (RFC 7591/7592)
+-- jwks_uri: where to fetch keys for private_key_jwt when no inline jwks is registered
+-- registration_access_token_hash: SHA256 of the token that manages this registration
+--   (NULL for clients created by the admin API or CLI)
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS jwks_uri TEXT;
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS logo_uri TEXT;
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS registration_access_token_hash BYTEA;
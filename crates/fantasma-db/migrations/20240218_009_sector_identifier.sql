@@ -0,0 +1,4 @@
+-- Pairwise subjects (OIDC Core §8.1)
+-- sector_identifier_uri: its host is the client's sector; NULL means the
+--   host of the client's redirect URIs
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS sector_identifier_uri TEXT;
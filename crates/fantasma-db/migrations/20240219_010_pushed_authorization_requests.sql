@@ -0,0 +1,16 @@
+-- Pushed authorization requests (RFC 9126)
+-- request_uri: urn:ietf:params:oauth:request_uri:<random>, single use
+-- params: the validated authorization request parameters
+CREATE TABLE IF NOT EXISTS pushed_authorization_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    request_uri VARCHAR(255) UNIQUE NOT NULL,
+    client_id VARCHAR(255) NOT NULL,
+    params JSONB NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_pushed_authorization_requests_expires ON pushed_authorization_requests(expires_at);
+
+-- Clients that may only start authorization through PAR
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS require_pushed_authorization_requests BOOLEAN NOT NULL DEFAULT FALSE;
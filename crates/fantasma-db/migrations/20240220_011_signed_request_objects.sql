@@ -0,0 +1,5 @@
+-- Signed authorization request objects (RFC 9101)
+-- require_signed_request_object: reject authorization requests whose
+--   parameters are not carried in a request object signed with the
+--   client's registered keys
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS require_signed_request_object BOOLEAN NOT NULL DEFAULT FALSE;
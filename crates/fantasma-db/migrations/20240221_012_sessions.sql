@@ -0,0 +1,27 @@
+-- Browser sessions for RP-initiated and back-channel logout
+-- A session starts at the first consent in a browser; every client that
+-- obtains a code during it is recorded with the pairwise subject it saw.
+CREATE TABLE IF NOT EXISTS sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    sid VARCHAR(255) UNIQUE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ended_at TIMESTAMPTZ  -- NULL while active
+);
+
+CREATE TABLE IF NOT EXISTS session_clients (
+    sid VARCHAR(255) NOT NULL REFERENCES sessions(sid) ON DELETE CASCADE,
+    client_id VARCHAR(255) NOT NULL,
+    user_id VARCHAR(255) NOT NULL,  -- pairwise subject seen by the client
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (sid, client_id)
+);
+
+-- Tokens issued during a session are revoked when it ends
+ALTER TABLE auth_codes ADD COLUMN IF NOT EXISTS sid VARCHAR(255);
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS sid VARCHAR(255);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_sid ON refresh_tokens(sid);
+
+-- Logout metadata (OIDC RP-Initiated Logout §3.1, Back-Channel Logout §2.2)
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS post_logout_redirect_uris TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS backchannel_logout_uri TEXT;
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS backchannel_logout_session_required BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,21 @@
+-- Device authorization grant (RFC 8628)
+-- device_code_hash: SHA-256 of the device code polled by the client
+-- user_code: short code the user enters at the verification page
+-- status: pending until the user approves or denies on the verification page
+-- interval_seconds: minimum polling interval, raised on slow_down
+CREATE TABLE IF NOT EXISTS device_authorizations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    device_code_hash BYTEA UNIQUE NOT NULL,
+    user_code VARCHAR(16) UNIQUE NOT NULL,
+    client_id VARCHAR(255) NOT NULL,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    status VARCHAR(16) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'approved', 'denied')),
+    user_id VARCHAR(255),
+    sid VARCHAR(255),
+    interval_seconds INTEGER NOT NULL,
+    last_polled_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_device_authorizations_expires ON device_authorizations(expires_at);
@@ -0,0 +1,3 @@
+-- ZK claims requested with the OIDC claims parameter
+-- claims_request: JSON array of ClaimRequest (claim_type, required, reason)
+ALTER TABLE auth_codes ADD COLUMN IF NOT EXISTS claims_request JSONB;
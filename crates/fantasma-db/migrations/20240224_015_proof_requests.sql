@@ -0,0 +1,13 @@
+-- Proof requests shown to the wallet at consent
+-- request_id: ProofRequest.request_id, single use
+-- request: the ProofRequest, including its nonce and verifier domain
+CREATE TABLE IF NOT EXISTS proof_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    request_id VARCHAR(255) UNIQUE NOT NULL,
+    client_id VARCHAR(255) NOT NULL,
+    request JSONB NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_proof_requests_expires ON proof_requests(expires_at);
@@ -0,0 +1,3 @@
+-- The authorization request a proof request belongs to, so that an
+-- OpenID4VP wallet answering it directly can complete the authorization
+ALTER TABLE proof_requests ADD COLUMN IF NOT EXISTS authorization_params JSONB NOT NULL DEFAULT '{}'::jsonb;
@@ -0,0 +1,21 @@
+-- OpenID4VCI credential offers (pre-authorized code flow)
+-- pre_authorized_code_hash: SHA-256 of the code in the offer
+-- credential_type: the credential to issue, as fantasma_core::CredentialType JSON
+-- access_token_hash: SHA-256 of the access token the code was redeemed for
+-- c_nonce: nonce the wallet's key proof must carry
+-- issued_at: set once the credential was issued; each offer is issued once
+CREATE TABLE IF NOT EXISTS credential_offers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    pre_authorized_code_hash BYTEA UNIQUE NOT NULL,
+    credential_configuration_id VARCHAR(64) NOT NULL,
+    credential_type JSONB NOT NULL,
+    access_token_hash BYTEA UNIQUE,
+    access_token_expires_at TIMESTAMPTZ,
+    c_nonce VARCHAR(255),
+    c_nonce_expires_at TIMESTAMPTZ,
+    issued_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_credential_offers_expires ON credential_offers(expires_at);
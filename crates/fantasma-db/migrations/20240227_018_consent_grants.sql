@@ -0,0 +1,20 @@
+-- Consent grants: what a user approved sharing with a client
+-- One grant per pairwise subject and client, holding the most recently
+-- approved scope set and the ZK claims shared for it. Re-authorizing with a
+-- subset of the scopes skips the consent page while the claims are fresh.
+-- user_id: pairwise subject the client sees
+-- zk_claims_verified_at: when the proofs behind zk_claims were verified
+CREATE TABLE IF NOT EXISTS consent_grants (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id VARCHAR(255) NOT NULL,
+    client_id VARCHAR(255) NOT NULL,
+    scopes TEXT[] NOT NULL,
+    zk_claims JSONB NOT NULL DEFAULT '{}',
+    zk_claims_verified_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, client_id)
+);
+
+-- Revoking a grant revokes the refresh tokens the client holds for the subject
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_client_user ON refresh_tokens(client_id, user_id);
@@ -0,0 +1,5 @@
+-- Per-client scope policy
+-- Clients may only request the ZK scopes in allowed_scopes. Requests for
+-- others fail with invalid_scope, or have them dropped when the client
+-- downscopes.
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS downscope_unapproved_scopes BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,8 @@
+-- Encrypted responses (OIDC Registration §2)
+-- Clients that register a key management algorithm receive ID tokens and
+-- userinfo responses as nested signed-then-encrypted JWTs. A missing enc
+-- means A128CBC-HS256.
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS id_token_encrypted_response_alg VARCHAR(32);
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS id_token_encrypted_response_enc VARCHAR(32);
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS userinfo_encrypted_response_alg VARCHAR(32);
+ALTER TABLE clients ADD COLUMN IF NOT EXISTS userinfo_encrypted_response_enc VARCHAR(32);
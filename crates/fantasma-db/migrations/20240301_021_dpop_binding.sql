@@ -0,0 +1,6 @@
+-- DPoP sender-constrained tokens (RFC 9449)
+-- JWK thumbprint (cnf.jkt) of the key a token is bound to. Bound access
+-- tokens must be presented with a DPoP proof signed by that key; refresh
+-- tokens are bound only for public clients.
+ALTER TABLE access_tokens ADD COLUMN IF NOT EXISTS dpop_jkt VARCHAR(64);
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS dpop_jkt VARCHAR(64);
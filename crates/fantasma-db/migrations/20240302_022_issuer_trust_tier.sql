@@ -0,0 +1,4 @@
+-- Trust tier of trusted issuers (self_declared, trust_framework, accredited,
+-- government). Proofs resting on an issuer's credentials attain its tier in
+-- their acr; issuers trusted before tiers existed count as trust_framework.
+ALTER TABLE issuers ADD COLUMN IF NOT EXISTS trust_tier VARCHAR(32) NOT NULL DEFAULT 'trust_framework';
@@ -0,0 +1,3 @@
+-- ZK claims the wallet proved when approving a device authorization,
+-- issued in the device's ID token
+ALTER TABLE device_authorizations ADD COLUMN IF NOT EXISTS zk_claims JSONB;
@@ -0,0 +1,4 @@
+-- Sector the grant's pairwise subject belongs to. Re-authorizing from a
+-- grant is only possible for redirect URIs in that sector; grants recorded
+-- before the column existed need a fresh consent.
+ALTER TABLE consent_grants ADD COLUMN IF NOT EXISTS sector_identifier VARCHAR(255);
@@ -17,6 +17,26 @@ pub struct Client {
     pub client_type: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub id_token_signed_response_alg: Option<String>,
+    pub require_pkce_s256: bool,
+    pub token_endpoint_auth_method: Option<String>,
+    pub jwks: Option<serde_json::Value>,
+    pub jwks_uri: Option<String>,
+    pub logo_uri: Option<String>,
+    #[serde(skip_serializing)]
+    pub registration_access_token_hash: Option<Vec<u8>>,
+    pub sector_identifier_uri: Option<String>,
+    pub require_pushed_authorization_requests: bool,
+    pub require_signed_request_object: bool,
+    pub post_logout_redirect_uris: Vec<String>,
+    pub backchannel_logout_uri: Option<String>,
+    pub backchannel_logout_session_required: bool,
+    /// Drop requested ZK scopes outside `allowed_scopes` instead of failing
+    pub downscope_unapproved_scopes: bool,
+    pub id_token_encrypted_response_alg: Option<String>,
+    pub id_token_encrypted_response_enc: Option<String>,
+    pub userinfo_encrypted_response_alg: Option<String>,
+    pub userinfo_encrypted_response_enc: Option<String>,
 }
 
 /// New client for insertion
@@ -28,6 +48,125 @@ pub struct NewClient {
     pub redirect_uris: Vec<String>,
     pub allowed_scopes: Vec<String>,
     pub client_type: String,
+    pub id_token_signed_response_alg: Option<String>,
+    pub require_pkce_s256: bool,
+    pub token_endpoint_auth_method: Option<String>,
+    pub jwks: Option<serde_json::Value>,
+    pub jwks_uri: Option<String>,
+    pub logo_uri: Option<String>,
+    pub registration_access_token_hash: Option<Vec<u8>>,
+    pub sector_identifier_uri: Option<String>,
+    pub require_pushed_authorization_requests: bool,
+    pub require_signed_request_object: bool,
+    pub post_logout_redirect_uris: Vec<String>,
+    pub backchannel_logout_uri: Option<String>,
+    pub backchannel_logout_session_required: bool,
+    /// Drop requested ZK scopes outside `allowed_scopes` instead of failing
+    pub downscope_unapproved_scopes: bool,
+    pub id_token_encrypted_response_alg: Option<String>,
+    pub id_token_encrypted_response_enc: Option<String>,
+    pub userinfo_encrypted_response_alg: Option<String>,
+    pub userinfo_encrypted_response_enc: Option<String>,
+}
+
+/// Pushed authorization request (RFC 9126)
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PushedAuthorizationRequest {
+    pub id: Uuid,
+    pub request_uri: String,
+    pub client_id: String,
+    pub params: serde_json::Value,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// New pushed authorization request for insertion
+#[derive(Debug, Clone)]
+pub struct NewPushedAuthorizationRequest {
+    pub request_uri: String,
+    pub client_id: String,
+    pub params: serde_json::Value,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Proof request issued to a wallet at consent
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ProofRequestRecord {
+    pub id: Uuid,
+    pub request_id: String,
+    pub client_id: String,
+    pub request: serde_json::Value,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub authorization_params: serde_json::Value,
+}
+
+/// New proof request for insertion
+#[derive(Debug, Clone)]
+pub struct NewProofRequest {
+    pub request_id: String,
+    pub client_id: String,
+    pub request: serde_json::Value,
+    pub expires_at: DateTime<Utc>,
+    pub authorization_params: serde_json::Value,
+}
+
+/// OpenID4VCI credential offer
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct CredentialOffer {
+    pub id: Uuid,
+    pub pre_authorized_code_hash: Vec<u8>,
+    pub credential_configuration_id: String,
+    pub credential_type: serde_json::Value,
+    /// Set once the pre-authorized code was redeemed
+    pub access_token_hash: Option<Vec<u8>>,
+    pub access_token_expires_at: Option<DateTime<Utc>>,
+    pub c_nonce: Option<String>,
+    pub c_nonce_expires_at: Option<DateTime<Utc>>,
+    pub issued_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// New credential offer for insertion
+#[derive(Debug, Clone)]
+pub struct NewCredentialOffer {
+    pub pre_authorized_code_hash: Vec<u8>,
+    pub credential_configuration_id: String,
+    pub credential_type: serde_json::Value,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Device authorization (RFC 8628)
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    pub id: Uuid,
+    pub device_code_hash: Vec<u8>,
+    pub user_code: String,
+    pub client_id: String,
+    pub scopes: Vec<String>,
+    /// `pending`, `approved` or `denied`
+    pub status: String,
+    /// Subject the user presented when approving
+    pub user_id: Option<String>,
+    pub sid: Option<String>,
+    /// ZK claims the wallet proved when approving
+    pub zk_claims: Option<serde_json::Value>,
+    pub interval_seconds: i32,
+    pub last_polled_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// New device authorization for insertion
+#[derive(Debug, Clone)]
+pub struct NewDeviceAuthorization {
+    pub device_code_hash: Vec<u8>,
+    pub user_code: String,
+    pub client_id: String,
+    pub scopes: Vec<String>,
+    pub interval_seconds: i32,
+    pub expires_at: DateTime<Utc>,
 }
 
 /// Authorization code for OAuth2 flow
@@ -47,6 +186,10 @@ pub struct AuthCode {
     pub expires_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub used_at: Option<DateTime<Utc>>,
+    /// Session the code was issued in
+    pub sid: Option<String>,
+    /// ZK claims requested with the `claims` parameter
+    pub claims_request: Option<serde_json::Value>,
 }
 
 /// New auth code for insertion
@@ -63,6 +206,8 @@ pub struct NewAuthCode {
     pub code_challenge_method: Option<String>,
     pub zk_claims: Option<serde_json::Value>,
     pub expires_at: DateTime<Utc>,
+    pub sid: Option<String>,
+    pub claims_request: Option<serde_json::Value>,
 }
 
 /// Refresh token
@@ -76,6 +221,14 @@ pub struct RefreshToken {
     pub expires_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub revoked_at: Option<DateTime<Utc>>,
+    pub family_id: Uuid,
+    pub used_at: Option<DateTime<Utc>>,
+    pub zk_claims: Option<serde_json::Value>,
+    pub zk_claims_verified_at: DateTime<Utc>,
+    /// Session the token was issued in; revoked on logout
+    pub sid: Option<String>,
+    /// Thumbprint of the DPoP key the token is bound to
+    pub dpop_jkt: Option<String>,
 }
 
 /// New refresh token for insertion
@@ -86,6 +239,42 @@ pub struct NewRefreshToken {
     pub user_id: String,
     pub scopes: Vec<String>,
     pub expires_at: DateTime<Utc>,
+    pub family_id: Uuid,
+    pub zk_claims: Option<serde_json::Value>,
+    pub zk_claims_verified_at: DateTime<Utc>,
+    pub sid: Option<String>,
+    pub dpop_jkt: Option<String>,
+}
+
+/// Access token
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AccessToken {
+    pub id: Uuid,
+    pub token_hash: Vec<u8>,
+    pub client_id: String,
+    pub user_id: String,
+    pub scopes: Vec<String>,
+    pub zk_claims: Option<serde_json::Value>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Refresh token family issued with this token, if any
+    pub family_id: Option<Uuid>,
+    /// Thumbprint of the DPoP key the token is bound to
+    pub dpop_jkt: Option<String>,
+}
+
+/// New access token for insertion
+#[derive(Debug, Clone)]
+pub struct NewAccessToken {
+    pub token_hash: Vec<u8>,
+    pub client_id: String,
+    pub user_id: String,
+    pub scopes: Vec<String>,
+    pub zk_claims: Option<serde_json::Value>,
+    pub expires_at: DateTime<Utc>,
+    pub family_id: Option<Uuid>,
+    pub dpop_jkt: Option<String>,
 }
 
 /// Stored STARK proof
@@ -177,6 +366,8 @@ pub struct Issuer {
     pub public_key_algorithm: String,
     pub verification_url: Option<String>,
     pub trusted: bool,
+    /// Trust tier of the issuer's credentials (`IssuerTrust` name)
+    pub trust_tier: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -190,6 +381,7 @@ pub struct NewIssuer {
     pub public_key_algorithm: String,
     pub verification_url: Option<String>,
     pub trusted: bool,
+    pub trust_tier: String,
 }
 
 /// Audit log entry
@@ -215,3 +407,49 @@ pub struct NewAuditLogEntry {
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
 }
+
+/// Browser session, used for logout
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Session {
+    pub id: Uuid,
+    pub sid: String,
+    pub created_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/// A client that obtained a code during a session
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SessionClient {
+    pub sid: String,
+    pub client_id: String,
+    /// Pairwise subject seen by the client
+    pub user_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a user approved sharing with a client
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ConsentGrant {
+    pub id: Uuid,
+    /// Pairwise subject seen by the client
+    pub user_id: String,
+    pub client_id: String,
+    /// Sector the subject belongs to
+    pub sector_identifier: Option<String>,
+    pub scopes: Vec<String>,
+    pub zk_claims: serde_json::Value,
+    pub zk_claims_verified_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// New or renewed consent grant
+#[derive(Debug, Clone)]
+pub struct NewConsentGrant {
+    pub user_id: String,
+    pub client_id: String,
+    pub sector_identifier: String,
+    pub scopes: Vec<String>,
+    pub zk_claims: serde_json::Value,
+    pub zk_claims_verified_at: DateTime<Utc>,
+}
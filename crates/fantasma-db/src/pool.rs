@@ -121,7 +121,7 @@ impl DatabasePool {
             .execute(&self.pool)
             .await
             .map(|_| true)
-            .map_err(|e| DbError::Query(e))
+            .map_err(DbError::Query)
     }
 
     /// Get pool statistics
@@ -169,6 +169,30 @@ impl Repositories {
         crate::repos::AuthCodeRepo::new(self.pool.clone())
     }
 
+    pub fn pushed_requests(&self) -> crate::repos::PushedRequestRepo {
+        crate::repos::PushedRequestRepo::new(self.pool.clone())
+    }
+
+    pub fn proof_requests(&self) -> crate::repos::ProofRequestRepo {
+        crate::repos::ProofRequestRepo::new(self.pool.clone())
+    }
+
+    pub fn credential_offers(&self) -> crate::repos::CredentialOfferRepo {
+        crate::repos::CredentialOfferRepo::new(self.pool.clone())
+    }
+
+    pub fn device_authorizations(&self) -> crate::repos::DeviceAuthorizationRepo {
+        crate::repos::DeviceAuthorizationRepo::new(self.pool.clone())
+    }
+
+    pub fn sessions(&self) -> crate::repos::SessionRepo {
+        crate::repos::SessionRepo::new(self.pool.clone())
+    }
+
+    pub fn consent_grants(&self) -> crate::repos::ConsentGrantRepo {
+        crate::repos::ConsentGrantRepo::new(self.pool.clone())
+    }
+
     pub fn proofs(&self) -> crate::repos::ProofRepo {
         crate::repos::ProofRepo::new(self.pool.clone())
     }
@@ -192,4 +216,8 @@ impl Repositories {
     pub fn refresh_tokens(&self) -> crate::repos::RefreshTokenRepo {
         crate::repos::RefreshTokenRepo::new(self.pool.clone())
     }
+
+    pub fn access_tokens(&self) -> crate::repos::AccessTokenRepo {
+        crate::repos::AccessTokenRepo::new(self.pool.clone())
+    }
 }
@@ -1,6 +1,7 @@
 //! Repository implementations for database operations
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use fantasma_core::proof::ProofId;
 use fantasma_proof_store::{ProofStore, ProofStoreError, StoredProof as ProofStoreProof};
 use sqlx::PgPool;
@@ -21,8 +22,16 @@ impl ClientRepo {
     pub async fn create(&self, client: NewClient) -> Result<Client> {
         let result = sqlx::query_as::<_, Client>(
             r#"
-            INSERT INTO clients (client_id, client_secret_hash, client_name, redirect_uris, allowed_scopes, client_type)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO clients (client_id, client_secret_hash, client_name, redirect_uris, allowed_scopes, client_type,
+                                 id_token_signed_response_alg, require_pkce_s256, token_endpoint_auth_method, jwks,
+                                 jwks_uri, logo_uri, registration_access_token_hash, sector_identifier_uri,
+                                 require_pushed_authorization_requests, require_signed_request_object,
+                                 post_logout_redirect_uris, backchannel_logout_uri,
+                                 backchannel_logout_session_required, downscope_unapproved_scopes,
+                                 id_token_encrypted_response_alg, id_token_encrypted_response_enc,
+                                 userinfo_encrypted_response_alg, userinfo_encrypted_response_enc)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20,
+                    $21, $22, $23, $24)
             RETURNING *
             "#,
         )
@@ -32,12 +41,103 @@ impl ClientRepo {
         .bind(&client.redirect_uris)
         .bind(&client.allowed_scopes)
         .bind(&client.client_type)
+        .bind(&client.id_token_signed_response_alg)
+        .bind(client.require_pkce_s256)
+        .bind(&client.token_endpoint_auth_method)
+        .bind(&client.jwks)
+        .bind(&client.jwks_uri)
+        .bind(&client.logo_uri)
+        .bind(&client.registration_access_token_hash)
+        .bind(&client.sector_identifier_uri)
+        .bind(client.require_pushed_authorization_requests)
+        .bind(client.require_signed_request_object)
+        .bind(&client.post_logout_redirect_uris)
+        .bind(&client.backchannel_logout_uri)
+        .bind(client.backchannel_logout_session_required)
+        .bind(client.downscope_unapproved_scopes)
+        .bind(&client.id_token_encrypted_response_alg)
+        .bind(&client.id_token_encrypted_response_enc)
+        .bind(&client.userinfo_encrypted_response_alg)
+        .bind(&client.userinfo_encrypted_response_enc)
         .fetch_one(&self.pool)
         .await?;
 
         Ok(result)
     }
 
+    /// Replace a client's metadata (RFC 7592 update)
+    ///
+    /// The client_id, registration access token and scope policy are kept.
+    pub async fn update(&self, client: NewClient) -> Result<Client> {
+        let result = sqlx::query_as::<_, Client>(
+            r#"
+            UPDATE clients
+            SET client_secret_hash = $2, client_name = $3, redirect_uris = $4, allowed_scopes = $5,
+                client_type = $6, id_token_signed_response_alg = $7, require_pkce_s256 = $8,
+                token_endpoint_auth_method = $9, jwks = $10, jwks_uri = $11, logo_uri = $12,
+                sector_identifier_uri = $13, require_pushed_authorization_requests = $14,
+                require_signed_request_object = $15, post_logout_redirect_uris = $16,
+                backchannel_logout_uri = $17, backchannel_logout_session_required = $18,
+                id_token_encrypted_response_alg = $19, id_token_encrypted_response_enc = $20,
+                userinfo_encrypted_response_alg = $21, userinfo_encrypted_response_enc = $22,
+                updated_at = NOW()
+            WHERE client_id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(&client.client_id)
+        .bind(&client.client_secret_hash)
+        .bind(&client.client_name)
+        .bind(&client.redirect_uris)
+        .bind(&client.allowed_scopes)
+        .bind(&client.client_type)
+        .bind(&client.id_token_signed_response_alg)
+        .bind(client.require_pkce_s256)
+        .bind(&client.token_endpoint_auth_method)
+        .bind(&client.jwks)
+        .bind(&client.jwks_uri)
+        .bind(&client.logo_uri)
+        .bind(&client.sector_identifier_uri)
+        .bind(client.require_pushed_authorization_requests)
+        .bind(client.require_signed_request_object)
+        .bind(&client.post_logout_redirect_uris)
+        .bind(&client.backchannel_logout_uri)
+        .bind(client.backchannel_logout_session_required)
+        .bind(&client.id_token_encrypted_response_alg)
+        .bind(&client.id_token_encrypted_response_enc)
+        .bind(&client.userinfo_encrypted_response_alg)
+        .bind(&client.userinfo_encrypted_response_enc)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        result.ok_or_else(|| DbError::NotFound(format!("Client not found: {}", client.client_id)))
+    }
+
+    /// Set the ZK scopes a client may request, and whether requests for
+    /// others are downscoped rather than rejected
+    pub async fn update_scope_policy(
+        &self,
+        client_id: &str,
+        allowed_scopes: &[String],
+        downscope_unapproved_scopes: bool,
+    ) -> Result<Client> {
+        let result = sqlx::query_as::<_, Client>(
+            r#"
+            UPDATE clients
+            SET allowed_scopes = $2, downscope_unapproved_scopes = $3, updated_at = NOW()
+            WHERE client_id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(client_id)
+        .bind(allowed_scopes)
+        .bind(downscope_unapproved_scopes)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        result.ok_or_else(|| DbError::NotFound(format!("Client not found: {}", client_id)))
+    }
+
     pub async fn find_by_client_id(&self, client_id: &str) -> Result<Option<Client>> {
         let result = sqlx::query_as::<_, Client>("SELECT * FROM clients WHERE client_id = $1")
             .bind(client_id)
@@ -92,6 +192,522 @@ impl ClientRepo {
     }
 }
 
+/// Repository for pushed authorization requests
+pub struct PushedRequestRepo {
+    pool: PgPool,
+}
+
+impl PushedRequestRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        request: NewPushedAuthorizationRequest,
+    ) -> Result<PushedAuthorizationRequest> {
+        let result = sqlx::query_as::<_, PushedAuthorizationRequest>(
+            r#"
+            INSERT INTO pushed_authorization_requests (request_uri, client_id, params, expires_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(&request.request_uri)
+        .bind(&request.client_id)
+        .bind(&request.params)
+        .bind(request.expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Remove and return an unexpired request; each `request_uri` is used once
+    pub async fn take(&self, request_uri: &str) -> Result<Option<PushedAuthorizationRequest>> {
+        let result = sqlx::query_as::<_, PushedAuthorizationRequest>(
+            "DELETE FROM pushed_authorization_requests WHERE request_uri = $1 AND expires_at > NOW() RETURNING *",
+        )
+        .bind(request_uri)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn cleanup_expired(&self) -> Result<u64> {
+        let result =
+            sqlx::query("DELETE FROM pushed_authorization_requests WHERE expires_at < NOW()")
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Repository for proof requests issued at consent
+pub struct ProofRequestRepo {
+    pool: PgPool,
+}
+
+impl ProofRequestRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, request: NewProofRequest) -> Result<ProofRequestRecord> {
+        let result = sqlx::query_as::<_, ProofRequestRecord>(
+            r#"
+            INSERT INTO proof_requests (request_id, client_id, request, expires_at, authorization_params)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(&request.request_id)
+        .bind(&request.client_id)
+        .bind(&request.request)
+        .bind(request.expires_at)
+        .bind(&request.authorization_params)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Remove and return an unexpired request; each request is answered once
+    pub async fn take(&self, request_id: &str) -> Result<Option<ProofRequestRecord>> {
+        let result = sqlx::query_as::<_, ProofRequestRecord>(
+            "DELETE FROM proof_requests WHERE request_id = $1 AND expires_at > NOW() RETURNING *",
+        )
+        .bind(request_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn cleanup_expired(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM proof_requests WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Repository for device authorizations (RFC 8628)
+pub struct DeviceAuthorizationRepo {
+    pool: PgPool,
+}
+
+impl DeviceAuthorizationRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        authorization: NewDeviceAuthorization,
+    ) -> Result<DeviceAuthorization> {
+        let result = sqlx::query_as::<_, DeviceAuthorization>(
+            r#"
+            INSERT INTO device_authorizations (device_code_hash, user_code, client_id, scopes, interval_seconds, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(&authorization.device_code_hash)
+        .bind(&authorization.user_code)
+        .bind(&authorization.client_id)
+        .bind(&authorization.scopes)
+        .bind(authorization.interval_seconds)
+        .bind(authorization.expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn find_by_device_code(
+        &self,
+        device_code_hash: &[u8],
+    ) -> Result<Option<DeviceAuthorization>> {
+        let result = sqlx::query_as::<_, DeviceAuthorization>(
+            "SELECT * FROM device_authorizations WHERE device_code_hash = $1",
+        )
+        .bind(device_code_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Find an unexpired authorization still waiting for the user
+    pub async fn find_pending_by_user_code(
+        &self,
+        user_code: &str,
+    ) -> Result<Option<DeviceAuthorization>> {
+        let result = sqlx::query_as::<_, DeviceAuthorization>(
+            "SELECT * FROM device_authorizations WHERE user_code = $1 AND status = 'pending' AND expires_at > NOW()",
+        )
+        .bind(user_code)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Record the user's approval; false if the authorization is no longer pending
+    pub async fn approve(
+        &self,
+        user_code: &str,
+        user_id: &str,
+        scopes: &[String],
+        sid: Option<&str>,
+        zk_claims: Option<&serde_json::Value>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE device_authorizations SET status = 'approved', user_id = $2, scopes = $3, sid = $4, zk_claims = $5
+            WHERE user_code = $1 AND status = 'pending' AND expires_at > NOW()
+            "#,
+        )
+        .bind(user_code)
+        .bind(user_id)
+        .bind(scopes)
+        .bind(sid)
+        .bind(zk_claims)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record the user's denial; false if the authorization is no longer pending
+    pub async fn deny(&self, user_code: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE device_authorizations SET status = 'denied' WHERE user_code = $1 AND status = 'pending' AND expires_at > NOW()",
+        )
+        .bind(user_code)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record a poll by the client along with its current polling interval
+    pub async fn record_poll(&self, device_code_hash: &[u8], interval_seconds: i32) -> Result<()> {
+        sqlx::query(
+            "UPDATE device_authorizations SET last_polled_at = NOW(), interval_seconds = $2 WHERE device_code_hash = $1",
+        )
+        .bind(device_code_hash)
+        .bind(interval_seconds)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove and return an approved authorization; each is redeemed once
+    pub async fn take_approved(
+        &self,
+        device_code_hash: &[u8],
+    ) -> Result<Option<DeviceAuthorization>> {
+        let result = sqlx::query_as::<_, DeviceAuthorization>(
+            "DELETE FROM device_authorizations WHERE device_code_hash = $1 AND status = 'approved' RETURNING *",
+        )
+        .bind(device_code_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn cleanup_expired(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM device_authorizations WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Repository for OpenID4VCI credential offers
+pub struct CredentialOfferRepo {
+    pool: PgPool,
+}
+
+impl CredentialOfferRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, offer: NewCredentialOffer) -> Result<CredentialOffer> {
+        let result = sqlx::query_as::<_, CredentialOffer>(
+            r#"
+            INSERT INTO credential_offers (pre_authorized_code_hash, credential_configuration_id, credential_type, expires_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(&offer.pre_authorized_code_hash)
+        .bind(&offer.credential_configuration_id)
+        .bind(&offer.credential_type)
+        .bind(offer.expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Redeem an unexpired pre-authorized code for an access token; each
+    /// code is redeemed once
+    pub async fn redeem(
+        &self,
+        pre_authorized_code_hash: &[u8],
+        access_token_hash: &[u8],
+        access_token_expires_at: DateTime<Utc>,
+        c_nonce: &str,
+        c_nonce_expires_at: DateTime<Utc>,
+    ) -> Result<Option<CredentialOffer>> {
+        let result = sqlx::query_as::<_, CredentialOffer>(
+            r#"
+            UPDATE credential_offers
+            SET access_token_hash = $2, access_token_expires_at = $3, c_nonce = $4, c_nonce_expires_at = $5
+            WHERE pre_authorized_code_hash = $1 AND access_token_hash IS NULL AND expires_at > NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(pre_authorized_code_hash)
+        .bind(access_token_hash)
+        .bind(access_token_expires_at)
+        .bind(c_nonce)
+        .bind(c_nonce_expires_at)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Find the offer an unexpired access token may still fetch a credential for
+    pub async fn find_by_access_token(
+        &self,
+        access_token_hash: &[u8],
+    ) -> Result<Option<CredentialOffer>> {
+        let result = sqlx::query_as::<_, CredentialOffer>(
+            "SELECT * FROM credential_offers WHERE access_token_hash = $1 AND issued_at IS NULL AND access_token_expires_at > NOW()",
+        )
+        .bind(access_token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn set_c_nonce(
+        &self,
+        access_token_hash: &[u8],
+        c_nonce: &str,
+        c_nonce_expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE credential_offers SET c_nonce = $2, c_nonce_expires_at = $3 WHERE access_token_hash = $1",
+        )
+        .bind(access_token_hash)
+        .bind(c_nonce)
+        .bind(c_nonce_expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record the issuance; false if the credential was already issued
+    pub async fn mark_issued(&self, access_token_hash: &[u8]) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE credential_offers SET issued_at = NOW() WHERE access_token_hash = $1 AND issued_at IS NULL",
+        )
+        .bind(access_token_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn cleanup_expired(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM credential_offers WHERE expires_at < NOW() AND (access_token_expires_at IS NULL OR access_token_expires_at < NOW())",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Repository for browser sessions
+pub struct SessionRepo {
+    pool: PgPool,
+}
+
+impl SessionRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, sid: &str) -> Result<Session> {
+        let result =
+            sqlx::query_as::<_, Session>("INSERT INTO sessions (sid) VALUES ($1) RETURNING *")
+                .bind(sid)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(result)
+    }
+
+    pub async fn find_active(&self, sid: &str) -> Result<Option<Session>> {
+        let result = sqlx::query_as::<_, Session>(
+            "SELECT * FROM sessions WHERE sid = $1 AND ended_at IS NULL",
+        )
+        .bind(sid)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Record a client that obtained a code during the session
+    pub async fn add_client(&self, sid: &str, client_id: &str, user_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO session_clients (sid, client_id, user_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (sid, client_id) DO UPDATE SET user_id = EXCLUDED.user_id
+            "#,
+        )
+        .bind(sid)
+        .bind(client_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn clients(&self, sid: &str) -> Result<Vec<SessionClient>> {
+        let results = sqlx::query_as::<_, SessionClient>(
+            "SELECT * FROM session_clients WHERE sid = $1 ORDER BY created_at",
+        )
+        .bind(sid)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(results)
+    }
+
+    /// End a session; returns `false` if it was unknown or already ended
+    pub async fn end(&self, sid: &str) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE sessions SET ended_at = NOW() WHERE sid = $1 AND ended_at IS NULL")
+                .bind(sid)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+/// Repository for consent grants
+pub struct ConsentGrantRepo {
+    pool: PgPool,
+}
+
+impl ConsentGrantRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a consent, replacing the subject's earlier grant to the client
+    pub async fn upsert(&self, grant: NewConsentGrant) -> Result<ConsentGrant> {
+        let result = sqlx::query_as::<_, ConsentGrant>(
+            r#"
+            INSERT INTO consent_grants (user_id, client_id, scopes, zk_claims, zk_claims_verified_at, sector_identifier)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (user_id, client_id) DO UPDATE
+            SET scopes = EXCLUDED.scopes, zk_claims = EXCLUDED.zk_claims,
+                sector_identifier = EXCLUDED.sector_identifier,
+                zk_claims_verified_at = EXCLUDED.zk_claims_verified_at, updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(&grant.user_id)
+        .bind(&grant.client_id)
+        .bind(&grant.scopes)
+        .bind(&grant.zk_claims)
+        .bind(grant.zk_claims_verified_at)
+        .bind(&grant.sector_identifier)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn find(&self, user_id: &str, client_id: &str) -> Result<Option<ConsentGrant>> {
+        let result = sqlx::query_as::<_, ConsentGrant>(
+            "SELECT * FROM consent_grants WHERE user_id = $1 AND client_id = $2",
+        )
+        .bind(user_id)
+        .bind(client_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn find_by_id(&self, id: uuid::Uuid) -> Result<Option<ConsentGrant>> {
+        let result =
+            sqlx::query_as::<_, ConsentGrant>("SELECT * FROM consent_grants WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(result)
+    }
+
+    /// Narrow a grant to fewer scopes and the claims shared for them
+    pub async fn update(
+        &self,
+        id: uuid::Uuid,
+        scopes: &[String],
+        zk_claims: &serde_json::Value,
+    ) -> Result<Option<ConsentGrant>> {
+        let result = sqlx::query_as::<_, ConsentGrant>(
+            r#"
+            UPDATE consent_grants SET scopes = $2, zk_claims = $3, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(scopes)
+        .bind(zk_claims)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Delete a grant; returns `false` if it was unknown
+    pub async fn delete(&self, id: uuid::Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM consent_grants WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
 /// Repository for authorization codes
 pub struct AuthCodeRepo {
     pool: PgPool,
@@ -106,8 +722,9 @@ impl AuthCodeRepo {
         let result = sqlx::query_as::<_, AuthCode>(
             r#"
             INSERT INTO auth_codes (code, client_id, user_id, redirect_uri, scopes, nonce, state,
-                                   code_challenge, code_challenge_method, zk_claims, expires_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                                   code_challenge, code_challenge_method, zk_claims, expires_at, sid,
+                                   claims_request)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             RETURNING *
             "#,
         )
@@ -121,7 +738,9 @@ impl AuthCodeRepo {
         .bind(&auth_code.code_challenge)
         .bind(&auth_code.code_challenge_method)
         .bind(&auth_code.zk_claims)
-        .bind(&auth_code.expires_at)
+        .bind(auth_code.expires_at)
+        .bind(&auth_code.sid)
+        .bind(&auth_code.claims_request)
         .fetch_one(&self.pool)
         .await?;
 
@@ -182,7 +801,7 @@ impl ProofRepo {
         .bind(&proof.public_inputs)
         .bind(proof.verified)
         .bind(&proof.user_id)
-        .bind(&proof.expires_at)
+        .bind(proof.expires_at)
         .fetch_one(&self.pool)
         .await?;
 
@@ -449,8 +1068,8 @@ impl CredentialRepo {
         .bind(&credential.encrypted_data)
         .bind(&credential.encryption_nonce)
         .bind(&credential.commitment)
-        .bind(&credential.issued_at)
-        .bind(&credential.expires_at)
+        .bind(credential.issued_at)
+        .bind(credential.expires_at)
         .fetch_one(&self.pool)
         .await?;
 
@@ -505,8 +1124,8 @@ impl IssuerRepo {
     pub async fn create(&self, issuer: NewIssuer) -> Result<Issuer> {
         let result = sqlx::query_as::<_, Issuer>(
             r#"
-            INSERT INTO issuers (issuer_id, name, public_key, public_key_algorithm, verification_url, trusted)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO issuers (issuer_id, name, public_key, public_key_algorithm, verification_url, trusted, trust_tier)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             RETURNING *
             "#,
         )
@@ -516,6 +1135,7 @@ impl IssuerRepo {
         .bind(&issuer.public_key_algorithm)
         .bind(&issuer.verification_url)
         .bind(issuer.trusted)
+        .bind(&issuer.trust_tier)
         .fetch_one(&self.pool)
         .await?;
 
@@ -652,8 +1272,10 @@ impl RefreshTokenRepo {
     pub async fn create(&self, token: NewRefreshToken) -> Result<RefreshToken> {
         let result = sqlx::query_as::<_, RefreshToken>(
             r#"
-            INSERT INTO refresh_tokens (token_hash, client_id, user_id, scopes, expires_at)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO refresh_tokens (token_hash, client_id, user_id, scopes, expires_at,
+                                        family_id, zk_claims, zk_claims_verified_at, sid,
+                                        dpop_jkt)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING *
             "#,
         )
@@ -661,7 +1283,12 @@ impl RefreshTokenRepo {
         .bind(&token.client_id)
         .bind(&token.user_id)
         .bind(&token.scopes)
-        .bind(&token.expires_at)
+        .bind(token.expires_at)
+        .bind(token.family_id)
+        .bind(&token.zk_claims)
+        .bind(token.zk_claims_verified_at)
+        .bind(&token.sid)
+        .bind(&token.dpop_jkt)
         .fetch_one(&self.pool)
         .await?;
 
@@ -679,6 +1306,67 @@ impl RefreshTokenRepo {
         Ok(result)
     }
 
+    /// Find a token by hash regardless of its revoked/used/expired state
+    ///
+    /// Needed to recognise replays of rotated tokens.
+    pub async fn find_any_by_hash(&self, token_hash: &[u8]) -> Result<Option<RefreshToken>> {
+        let result =
+            sqlx::query_as::<_, RefreshToken>("SELECT * FROM refresh_tokens WHERE token_hash = $1")
+                .bind(token_hash)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(result)
+    }
+
+    /// Mark a token as rotated; returns `false` if it was already used or revoked
+    pub async fn mark_used(&self, token_hash: &[u8]) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET used_at = NOW() WHERE token_hash = $1 AND used_at IS NULL AND revoked_at IS NULL",
+        )
+        .bind(token_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn revoke_family(&self, family_id: uuid::Uuid) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE family_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(family_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Revoke every refresh token issued during a session
+    pub async fn revoke_session(&self, sid: &str) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE sid = $1 AND revoked_at IS NULL",
+        )
+        .bind(sid)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Revoke every refresh token a client holds for a subject
+    pub async fn revoke_for_client(&self, client_id: &str, user_id: &str) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE client_id = $1 AND user_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(client_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
     pub async fn revoke(&self, token_hash: &[u8]) -> Result<()> {
         sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE token_hash = $1")
             .bind(token_hash)
@@ -705,3 +1393,105 @@ impl RefreshTokenRepo {
         Ok(result.rows_affected())
     }
 }
+
+/// Access token repository
+pub struct AccessTokenRepo {
+    pool: PgPool,
+}
+
+impl AccessTokenRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, token: NewAccessToken) -> Result<AccessToken> {
+        let result = sqlx::query_as::<_, AccessToken>(
+            r#"
+            INSERT INTO access_tokens (token_hash, client_id, user_id, scopes, zk_claims,
+                                       expires_at, family_id, dpop_jkt)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
+        )
+        .bind(&token.token_hash)
+        .bind(&token.client_id)
+        .bind(&token.user_id)
+        .bind(&token.scopes)
+        .bind(&token.zk_claims)
+        .bind(token.expires_at)
+        .bind(token.family_id)
+        .bind(&token.dpop_jkt)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn find_by_hash(&self, token_hash: &[u8]) -> Result<Option<AccessToken>> {
+        let result = sqlx::query_as::<_, AccessToken>(
+            "SELECT * FROM access_tokens WHERE token_hash = $1 AND revoked_at IS NULL AND expires_at > NOW()",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    pub async fn revoke(&self, token_hash: &[u8]) -> Result<()> {
+        sqlx::query("UPDATE access_tokens SET revoked_at = NOW() WHERE token_hash = $1")
+            .bind(token_hash)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn revoke_family(&self, family_id: uuid::Uuid) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE access_tokens SET revoked_at = NOW() WHERE family_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(family_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Revoke the access tokens issued alongside a session's refresh tokens
+    pub async fn revoke_session(&self, sid: &str) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE access_tokens SET revoked_at = NOW()
+            WHERE revoked_at IS NULL
+              AND family_id IN (SELECT family_id FROM refresh_tokens WHERE sid = $1)
+            "#,
+        )
+        .bind(sid)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Revoke every access token a client holds for a subject
+    pub async fn revoke_for_client(&self, client_id: &str, user_id: &str) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE access_tokens SET revoked_at = NOW() WHERE client_id = $1 AND user_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(client_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn cleanup_expired(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM access_tokens WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
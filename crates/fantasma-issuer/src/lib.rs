@@ -5,11 +5,13 @@
 use chrono::{NaiveDate, Utc};
 use fantasma_core::claim::KycLevel;
 use fantasma_core::credential::{
-    Credential, CredentialId, CredentialType, IssuerSignature, SchemaId, SignatureAlgorithm,
+    Credential, CredentialId, CredentialType, HolderBinding, IssuerSignature, SchemaId,
+    SignatureAlgorithm,
 };
 use fantasma_core::issuer::{IssuerId, IssuerInfo, TrustAnchor};
-use fantasma_crypto::dilithium::DilithiumKeypair;
+use fantasma_crypto::dilithium::{DilithiumKeypair, DilithiumPublicKey, DilithiumSignature};
 use fantasma_crypto::hash::{poseidon_hash_pair, sha3_256};
+use fantasma_crypto::hybrid::{HybridKeypair, HybridPolicy, HybridPublicKey, HybridSignature};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -22,6 +24,15 @@ pub enum IssuerError {
 
     #[error("Invalid credential request")]
     InvalidRequest,
+
+    #[error("Invalid credential signature")]
+    InvalidSignature,
+}
+
+/// Key an issuer signs credentials with
+enum IssuerKeypair {
+    Dilithium(DilithiumKeypair),
+    Hybrid(Box<HybridKeypair>),
 }
 
 /// Credential issuer service
@@ -30,14 +41,53 @@ pub struct Issuer {
     pub info: IssuerInfo,
 
     /// Signing keypair
-    keypair: DilithiumKeypair,
+    keypair: IssuerKeypair,
 }
 
 impl Issuer {
     /// Create a new issuer
     pub fn new(id: impl Into<String>, name: impl Into<String>, trust_anchor: TrustAnchor) -> Self {
-        let keypair = DilithiumKeypair::generate();
-        let public_key = keypair.public_key.as_bytes().to_vec();
+        Self::with_keypair(id, name, trust_anchor, DilithiumKeypair::generate())
+    }
+
+    /// Create an issuer signing with an existing keypair
+    pub fn with_keypair(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        trust_anchor: TrustAnchor,
+        keypair: DilithiumKeypair,
+    ) -> Self {
+        Self::with_issuer_keypair(id, name, trust_anchor, IssuerKeypair::Dilithium(keypair))
+    }
+
+    /// Create an issuer signing with both an Ed25519 and an ML-DSA-65 key
+    ///
+    /// Its public key is the hybrid encoding of both keys, and every
+    /// credential carries a composite signature.
+    pub fn with_hybrid_keypair(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        trust_anchor: TrustAnchor,
+        keypair: HybridKeypair,
+    ) -> Self {
+        Self::with_issuer_keypair(
+            id,
+            name,
+            trust_anchor,
+            IssuerKeypair::Hybrid(Box::new(keypair)),
+        )
+    }
+
+    fn with_issuer_keypair(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        trust_anchor: TrustAnchor,
+        keypair: IssuerKeypair,
+    ) -> Self {
+        let public_key = match &keypair {
+            IssuerKeypair::Dilithium(keypair) => keypair.public_key.as_bytes().to_vec(),
+            IssuerKeypair::Hybrid(keypair) => keypair.public_key().to_bytes(),
+        };
 
         let info = IssuerInfo {
             id: IssuerId::new(id),
@@ -58,6 +108,14 @@ impl Issuer {
         Self { info, keypair }
     }
 
+    /// Algorithm of the signatures on issued credentials
+    pub fn signature_algorithm(&self) -> SignatureAlgorithm {
+        match self.keypair {
+            IssuerKeypair::Dilithium(_) => SignatureAlgorithm::Dilithium3,
+            IssuerKeypair::Hybrid(_) => SignatureAlgorithm::HybridEd25519MlDsa65,
+        }
+    }
+
     /// Issue an identity credential
     pub fn issue_identity(
         &self,
@@ -69,7 +127,7 @@ impl Issuer {
             identity_hash,
         };
 
-        self.issue_credential(credential_type, SchemaId::new(SchemaId::IDENTITY_V1))
+        self.issue_credential(credential_type, SchemaId::new(SchemaId::IDENTITY_V1), None)
     }
 
     /// Issue a degree credential
@@ -87,7 +145,7 @@ impl Issuer {
             graduation_date,
         };
 
-        self.issue_credential(credential_type, SchemaId::new(SchemaId::DEGREE_V1))
+        self.issue_credential(credential_type, SchemaId::new(SchemaId::DEGREE_V1), None)
     }
 
     /// Issue a KYC credential
@@ -104,7 +162,21 @@ impl Issuer {
             data_hash,
         };
 
-        self.issue_credential(credential_type, SchemaId::new(SchemaId::KYC_V1))
+        self.issue_credential(credential_type, SchemaId::new(SchemaId::KYC_V1), None)
+    }
+
+    /// Issue a credential bound to the holder's key
+    pub fn issue_to_holder(
+        &self,
+        credential_type: CredentialType,
+        holder: HolderBinding,
+    ) -> Result<Credential, IssuerError> {
+        let schema = schema_for(&credential_type);
+        if !self.info.supports_schema(&schema.0) {
+            return Err(IssuerError::InvalidRequest);
+        }
+
+        self.issue_credential(credential_type, schema, Some(holder))
     }
 
     /// Issue a generic credential
@@ -112,6 +184,7 @@ impl Issuer {
         &self,
         credential_type: CredentialType,
         schema: SchemaId,
+        holder: Option<HolderBinding>,
     ) -> Result<Credential, IssuerError> {
         use rand::RngCore;
 
@@ -129,26 +202,88 @@ impl Issuer {
         let id_input = sha3_256(&commitment);
         let id = CredentialId::from_bytes(id_input);
 
-        // Sign the commitment
-        let signature_bytes = self.keypair.sign(&commitment);
-        let signature = IssuerSignature {
-            bytes: signature_bytes.as_bytes().to_vec(),
-            algorithm: SignatureAlgorithm::Dilithium3,
-        };
-
         let now = Utc::now();
 
-        Ok(Credential {
+        let mut credential = Credential {
             id,
             issuer: self.info.id.clone(),
             schema,
             credential_type,
             commitment_salt: salt,
             commitment,
-            signature,
+            signature: IssuerSignature {
+                bytes: Vec::new(),
+                algorithm: self.signature_algorithm(),
+            },
             issued_at: now,
             expires_at: Some(now + chrono::Duration::days(365)), // 1 year validity
-        })
+            holder,
+        };
+
+        // Sign the commitment (and the holder binding)
+        let signing_input = credential.signing_input();
+        credential.signature.bytes = match &self.keypair {
+            IssuerKeypair::Dilithium(keypair) => keypair.sign(&signing_input).as_bytes().to_vec(),
+            IssuerKeypair::Hybrid(keypair) => keypair.sign(&signing_input).to_bytes(),
+        };
+
+        Ok(credential)
+    }
+}
+
+/// The schema of a credential type
+pub fn schema_for(credential_type: &CredentialType) -> SchemaId {
+    SchemaId::new(match credential_type {
+        CredentialType::Identity { .. } => SchemaId::IDENTITY_V1,
+        CredentialType::Degree { .. } => SchemaId::DEGREE_V1,
+        CredentialType::License { .. } => SchemaId::LICENSE_V1,
+        CredentialType::Membership { .. } => SchemaId::MEMBERSHIP_V1,
+        CredentialType::Kyc { .. } => SchemaId::KYC_V1,
+    })
+}
+
+/// Verify that `credential` was issued and signed by `issuer`
+///
+/// Hybrid signatures must verify in both halves.
+pub fn verify_credential(issuer: &IssuerInfo, credential: &Credential) -> Result<(), IssuerError> {
+    verify_credential_with(issuer, credential, HybridPolicy::RequireBoth)
+}
+
+/// Verify that `credential` was issued and signed by `issuer`, checking
+/// hybrid signatures under `policy`
+///
+/// The signature algorithm must match the issuer's key: a hybrid issuer's
+/// key does not decode as a plain Dilithium key, and vice versa.
+pub fn verify_credential_with(
+    issuer: &IssuerInfo,
+    credential: &Credential,
+    policy: HybridPolicy,
+) -> Result<(), IssuerError> {
+    if credential.issuer != issuer.id {
+        return Err(IssuerError::InvalidSignature);
+    }
+
+    let signing_input = credential.signing_input();
+    match credential.signature.algorithm {
+        SignatureAlgorithm::HybridEd25519MlDsa65 => {
+            let public_key = HybridPublicKey::from_bytes(&issuer.public_key)
+                .map_err(|_| IssuerError::InvalidSignature)?;
+            let signature = HybridSignature::from_bytes(&credential.signature.bytes)
+                .map_err(|_| IssuerError::InvalidSignature)?;
+            public_key
+                .verify(&signing_input, &signature, policy)
+                .map_err(|_| IssuerError::InvalidSignature)
+        }
+        SignatureAlgorithm::Dilithium3 | SignatureAlgorithm::Dilithium5 => {
+            let public_key = DilithiumPublicKey::from_bytes(&issuer.public_key)
+                .map_err(|_| IssuerError::InvalidSignature)?;
+            public_key
+                .verify(
+                    &signing_input,
+                    &DilithiumSignature::from_bytes(&credential.signature.bytes),
+                )
+                .map_err(|_| IssuerError::InvalidSignature)
+        }
     }
 }
 
@@ -190,4 +325,71 @@ mod tests {
 
         assert_eq!(credential.kyc_level(), Some(KycLevel::Enhanced));
     }
+
+    #[test]
+    fn test_issue_to_holder() {
+        let issuer = Issuer::new(
+            "test-issuer",
+            "Test Issuer",
+            TrustAnchor::SelfDeclared {
+                domain: "test.example".to_string(),
+            },
+        );
+        let holder = HolderBinding {
+            jkt: "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs".to_string(),
+        };
+
+        let credential = issuer
+            .issue_to_holder(
+                CredentialType::Identity {
+                    birthdate: NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
+                    identity_hash: [0u8; 32],
+                },
+                holder.clone(),
+            )
+            .unwrap();
+        assert_eq!(credential.schema.0, SchemaId::IDENTITY_V1);
+        assert_eq!(credential.holder, Some(holder));
+        assert!(verify_credential(&issuer.info, &credential).is_ok());
+
+        // The binding is covered by the signature
+        let mut rebound = credential.clone();
+        rebound.holder = Some(HolderBinding {
+            jkt: "another-key".to_string(),
+        });
+        assert!(verify_credential(&issuer.info, &rebound).is_err());
+    }
+
+    #[test]
+    fn test_hybrid_credential_signature() {
+        let issuer = Issuer::with_hybrid_keypair(
+            "hybrid-issuer",
+            "Hybrid Issuer",
+            TrustAnchor::SelfDeclared {
+                domain: "test.example".to_string(),
+            },
+            HybridKeypair::generate(),
+        );
+        let credential = issuer
+            .issue_identity(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(), [0u8; 32])
+            .unwrap();
+        assert_eq!(
+            credential.signature.algorithm,
+            SignatureAlgorithm::HybridEd25519MlDsa65
+        );
+        assert!(verify_credential(&issuer.info, &credential).is_ok());
+
+        // Break the Ed25519 half: only a verifier accepting either still passes
+        let mut broken = credential.clone();
+        broken.signature.bytes[0] ^= 1;
+        assert!(verify_credential(&issuer.info, &broken).is_err());
+        assert!(verify_credential_with(&issuer.info, &broken, HybridPolicy::AcceptEither).is_ok());
+
+        // A hybrid issuer's credentials cannot be passed off as plain Dilithium
+        let mut downgraded = credential;
+        downgraded.signature.algorithm = SignatureAlgorithm::Dilithium3;
+        assert!(
+            verify_credential_with(&issuer.info, &downgraded, HybridPolicy::AcceptEither).is_err()
+        );
+    }
 }
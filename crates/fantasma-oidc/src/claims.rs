@@ -1,9 +1,12 @@
 //! ZK claims for ID tokens
 
-use fantasma_core::claim::KycLevel;
+use fantasma_core::claim::{ClaimType, KycLevel};
 use fantasma_core::proof::ProofRef;
 use serde::{Deserialize, Serialize};
 
+use crate::provenance::{AuthContextClass, ClaimProvenance, VerificationMethod};
+use crate::scopes::ZkScope;
+
 /// ZK age claim in an ID token
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZkAgeClaim {
@@ -18,6 +21,10 @@ pub struct ZkAgeClaim {
 
     /// Circuit version used
     pub circuit_version: String,
+
+    /// How the claim was established, for verified claims
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<ClaimProvenance>,
 }
 
 /// ZK credential claim in an ID token
@@ -34,6 +41,10 @@ pub struct ZkCredentialClaim {
 
     /// Circuit version used
     pub circuit_version: String,
+
+    /// How the claim was established, for verified claims
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<ClaimProvenance>,
 }
 
 /// ZK KYC claim in an ID token
@@ -51,6 +62,10 @@ pub struct ZkKycClaim {
     /// Circuit version used
     pub circuit_version: String,
 
+    /// How the claim was established, for verified claims
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<ClaimProvenance>,
+
     /// Maximum age of KYC that was allowed (in seconds)
     pub max_age_seconds: Option<u64>,
 }
@@ -84,6 +99,105 @@ impl ZkClaims {
             && self.zk_kyc_claim.is_none()
     }
 
+    /// How long these claims may be re-asserted without fresh proofs
+    ///
+    /// A KYC claim's own `max_age_seconds` tightens the provider default.
+    pub fn max_age_seconds(&self, default: u64) -> u64 {
+        self.zk_kyc_claim
+            .as_ref()
+            .and_then(|kyc| kyc.max_age_seconds)
+            .map_or(default, |max_age| max_age.min(default))
+    }
+
+    /// Record how the verified claim for `claim_type` was established
+    pub fn set_provenance(&mut self, claim_type: &ClaimType, provenance: ClaimProvenance) {
+        let (verified, slot) = match claim_type {
+            ClaimType::AgeAtLeast { .. } => match self.zk_age_claim.as_mut() {
+                Some(claim) => (claim.verified, &mut claim.provenance),
+                None => return,
+            },
+            ClaimType::HoldsCredential { .. } => match self.zk_credential_claim.as_mut() {
+                Some(claim) => (claim.verified, &mut claim.provenance),
+                None => return,
+            },
+            ClaimType::KycStatus { .. } => match self.zk_kyc_claim.as_mut() {
+                Some(claim) => (claim.verified, &mut claim.provenance),
+                None => return,
+            },
+            ClaimType::SetMembership { .. } => return,
+        };
+        if verified {
+            *slot = Some(provenance);
+        }
+    }
+
+    /// The provenance of each verified claim; `None` for verified claims
+    /// that predate provenance
+    fn verified_provenance(&self) -> Vec<Option<&ClaimProvenance>> {
+        let age = self
+            .zk_age_claim
+            .as_ref()
+            .filter(|c| c.verified)
+            .map(|c| c.provenance.as_ref());
+        let credential = self
+            .zk_credential_claim
+            .as_ref()
+            .filter(|c| c.verified)
+            .map(|c| c.provenance.as_ref());
+        let kyc = self
+            .zk_kyc_claim
+            .as_ref()
+            .filter(|c| c.verified)
+            .map(|c| c.provenance.as_ref());
+        [age, credential, kyc].into_iter().flatten().collect()
+    }
+
+    /// The authentication context class the claims attain: the weakest
+    /// provenance among the verified claims, `None` if none is verified
+    pub fn auth_context(&self) -> Option<AuthContextClass> {
+        self.verified_provenance()
+            .into_iter()
+            .map(|provenance| match provenance {
+                Some(provenance) => AuthContextClass::of(provenance),
+                None => AuthContextClass {
+                    method: VerificationMethod::Demo,
+                    issuer_trust: None,
+                },
+            })
+            .reduce(AuthContextClass::weakest)
+    }
+
+    /// The verification methods behind the verified claims, for `amr`
+    pub fn verification_methods(&self) -> Vec<String> {
+        let mut methods: Vec<VerificationMethod> = self
+            .verified_provenance()
+            .into_iter()
+            .map(|p| p.map_or(VerificationMethod::Demo, |p| p.method))
+            .collect();
+        methods.sort();
+        methods.dedup();
+        methods.iter().map(|m| m.as_str().to_string()).collect()
+    }
+
+    /// The claims shared for `scopes`, leaving out the others
+    pub fn for_scopes(&self, scopes: &[ZkScope]) -> Self {
+        let requested = |kind: fn(&ZkScope) -> bool| scopes.iter().any(kind);
+        Self {
+            zk_age_claim: self
+                .zk_age_claim
+                .clone()
+                .filter(|_| requested(|s| matches!(s, ZkScope::Age { .. }))),
+            zk_credential_claim: self
+                .zk_credential_claim
+                .clone()
+                .filter(|_| requested(|s| matches!(s, ZkScope::Credential { .. }))),
+            zk_kyc_claim: self
+                .zk_kyc_claim
+                .clone()
+                .filter(|_| requested(|s| matches!(s, ZkScope::Kyc { .. }))),
+        }
+    }
+
     /// Add an age claim
     pub fn with_age_claim(mut self, threshold: u8, proof_ref: Option<ProofRef>) -> Self {
         self.zk_age_claim = Some(ZkAgeClaim {
@@ -91,6 +205,7 @@ impl ZkClaims {
             verified: true,
             proof_ref,
             circuit_version: "age_verification_v1".to_string(),
+            provenance: None,
         });
         self
     }
@@ -106,6 +221,7 @@ impl ZkClaims {
             verified: true,
             proof_ref,
             circuit_version: "credential_verification_v1".to_string(),
+            provenance: None,
         });
         self
     }
@@ -122,6 +238,7 @@ impl ZkClaims {
             verified: true,
             proof_ref,
             circuit_version: "kyc_verification_v1".to_string(),
+            provenance: None,
             max_age_seconds: max_age,
         });
         self
@@ -139,6 +256,7 @@ impl ZkClaims {
             verified,
             proof_ref,
             circuit_version: "age_verification_v1".to_string(),
+            provenance: None,
         });
         self
     }
@@ -155,6 +273,7 @@ impl ZkClaims {
             verified,
             proof_ref,
             circuit_version: "credential_verification_v1".to_string(),
+            provenance: None,
         });
         self
     }
@@ -172,8 +291,79 @@ impl ZkClaims {
             verified,
             proof_ref,
             circuit_version: "kyc_verification_v1".to_string(),
+            provenance: None,
             max_age_seconds: max_age,
         });
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scopes::parse_scopes;
+
+    #[test]
+    fn test_for_scopes() {
+        let claims =
+            ZkClaims::new()
+                .with_age_claim(21, None)
+                .with_kyc_claim(KycLevel::Basic, None, None);
+
+        let narrowed = claims.for_scopes(&parse_scopes("openid zk:age:21+"));
+        assert!(narrowed.zk_age_claim.is_some());
+        assert!(narrowed.zk_kyc_claim.is_none());
+
+        assert!(claims.for_scopes(&parse_scopes("openid")).is_empty());
+    }
+
+    #[test]
+    fn test_auth_context_is_the_weakest_claim() {
+        let mut claims = ZkClaims::new()
+            .with_age_claim(21, None)
+            .with_kyc_claim(KycLevel::Basic, None, None)
+            .with_credential_claim_verified("degree".to_string(), false, None);
+        let stark = ClaimProvenance {
+            method: VerificationMethod::Stark,
+            verifier_backend: "stone".to_string(),
+            circuit_hash: None,
+            proof_hash: None,
+            verified_at: 0,
+            issuer_trust_level: 100,
+        };
+        claims.set_provenance(&ClaimType::AgeAtLeast { threshold: 21 }, stark.clone());
+        claims.set_provenance(
+            &ClaimType::KycStatus {
+                provider: "*".to_string(),
+                level: KycLevel::Basic,
+                max_age_seconds: None,
+            },
+            ClaimProvenance {
+                issuer_trust_level: 80,
+                ..stark.clone()
+            },
+        );
+        claims.set_provenance(
+            &ClaimType::HoldsCredential {
+                credential_type: "degree".to_string(),
+                issuer: None,
+            },
+            stark,
+        );
+
+        // The unverified credential claim gets no provenance and does not count
+        assert!(claims
+            .zk_credential_claim
+            .as_ref()
+            .unwrap()
+            .provenance
+            .is_none());
+        assert_eq!(
+            claims.auth_context().unwrap().to_string(),
+            "urn:fantasma:acr:stark:accredited"
+        );
+        assert_eq!(claims.verification_methods(), vec!["stark"]);
+
+        assert!(ZkClaims::new().auth_context().is_none());
+    }
+}
@@ -0,0 +1,285 @@
+//! The OIDC `claims` request parameter (OIDC Core §5.5) for ZK claims
+//!
+//! Scopes can only ask for a fixed set of proofs. The `claims` parameter
+//! names the ZK claims individually, marks each as essential or voluntary,
+//! and carries parameters the scope grammar cannot express:
+//!
+//! ```json
+//! {"id_token": {"zk_age_claim": {"essential": true, "value": 25},
+//!               "zk_kyc_claim": {"value": "enhanced", "max_age_seconds": 86400}}}
+//! ```
+//!
+//! Claims other than the ZK claims are ignored, as OIDC requires for claims
+//! a provider does not support.
+
+use fantasma_core::claim::{ClaimRequest, ClaimType, KycLevel};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Claims request errors
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ClaimsRequestError {
+    #[error("claims is not a valid claims request: {0}")]
+    Malformed(String),
+
+    #[error("invalid {0} request: {1}")]
+    InvalidClaim(&'static str, String),
+}
+
+/// Request for a single claim (OIDC Core §5.5.1)
+///
+/// `purpose` is the reason shown to the user (OpenID Connect for Identity
+/// Assurance); `max_age_seconds`, `provider` and `issuer` parameterize the
+/// KYC and credential proofs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndividualClaimRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub essential: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age_seconds: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+}
+
+impl IndividualClaimRequest {
+    /// The requested value: `value`, or the first of `values`
+    fn requested_value(&self) -> Option<&Value> {
+        self.value
+            .as_ref()
+            .or_else(|| self.values.as_ref().and_then(|values| values.first()))
+    }
+}
+
+/// The `claims` request parameter
+///
+/// A claim requested as `null` is requested voluntarily with defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClaimsRequest {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub id_token: BTreeMap<String, Option<IndividualClaimRequest>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub userinfo: BTreeMap<String, Option<IndividualClaimRequest>>,
+}
+
+impl ClaimsRequest {
+    /// Parse the JSON value of the `claims` parameter
+    pub fn parse(claims: &str) -> Result<Self, ClaimsRequestError> {
+        serde_json::from_str(claims).map_err(|e| ClaimsRequestError::Malformed(e.to_string()))
+    }
+
+    /// The ZK claims requested, for the ID token and userinfo alike
+    ///
+    /// A claim requested in both members is essential if either says so.
+    pub fn claim_requests(&self) -> Result<Vec<ClaimRequest>, ClaimsRequestError> {
+        let mut requests: Vec<(&str, ClaimRequest)> = Vec::new();
+
+        for (name, request) in self.id_token.iter().chain(&self.userinfo) {
+            let request = request.clone().unwrap_or_default();
+            let Some(claim_request) = zk_claim_request(name, &request)? else {
+                continue;
+            };
+
+            match requests.iter_mut().find(|(n, _)| n == name) {
+                Some((_, existing)) => existing.required |= claim_request.required,
+                None => requests.push((name.as_str(), claim_request)),
+            }
+        }
+
+        Ok(requests.into_iter().map(|(_, request)| request).collect())
+    }
+
+    /// Drop the requests for ZK claims `keep` rejects; returns their types
+    ///
+    /// Malformed ZK claim requests are kept for `claim_requests` to report.
+    pub fn retain_zk_claims(&mut self, mut keep: impl FnMut(&ClaimType) -> bool) -> Vec<ClaimType> {
+        let mut dropped = Vec::new();
+        for member in [&mut self.id_token, &mut self.userinfo] {
+            member.retain(|name, request| {
+                let request = request.clone().unwrap_or_default();
+                match zk_claim_request(name, &request) {
+                    Ok(Some(claim)) if !keep(&claim.claim_type) => {
+                        dropped.push(claim.claim_type);
+                        false
+                    }
+                    _ => true,
+                }
+            });
+        }
+        dropped
+    }
+}
+
+/// Map a request for one of the ZK claims onto a `ClaimRequest`
+fn zk_claim_request(
+    name: &str,
+    request: &IndividualClaimRequest,
+) -> Result<Option<ClaimRequest>, ClaimsRequestError> {
+    let value = request.requested_value();
+    let claim_type = match name {
+        "zk_age_claim" => {
+            let threshold = value
+                .and_then(Value::as_u64)
+                .and_then(|v| u8::try_from(v).ok())
+                .ok_or_else(|| {
+                    ClaimsRequestError::InvalidClaim(
+                        "zk_age_claim",
+                        "value must be the age threshold".to_string(),
+                    )
+                })?;
+            ClaimType::AgeAtLeast { threshold }
+        }
+        "zk_kyc_claim" => {
+            let level = match value {
+                None => KycLevel::Basic,
+                Some(Value::String(s)) => match s.as_str() {
+                    "basic" => KycLevel::Basic,
+                    "enhanced" => KycLevel::Enhanced,
+                    "accredited" => KycLevel::Accredited,
+                    other => {
+                        return Err(ClaimsRequestError::InvalidClaim(
+                            "zk_kyc_claim",
+                            format!("unknown level: {}", other),
+                        ))
+                    }
+                },
+                Some(_) => {
+                    return Err(ClaimsRequestError::InvalidClaim(
+                        "zk_kyc_claim",
+                        "value must be a KYC level".to_string(),
+                    ))
+                }
+            };
+            ClaimType::KycStatus {
+                provider: request.provider.clone().unwrap_or_else(|| "*".to_string()),
+                level,
+                max_age_seconds: request.max_age_seconds,
+            }
+        }
+        "zk_credential_claim" => {
+            let credential_type = match value {
+                None => "*".to_string(),
+                Some(Value::String(s)) => s.clone(),
+                Some(_) => {
+                    return Err(ClaimsRequestError::InvalidClaim(
+                        "zk_credential_claim",
+                        "value must be a credential type".to_string(),
+                    ))
+                }
+            };
+            ClaimType::HoldsCredential {
+                credential_type,
+                issuer: request.issuer.clone(),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(ClaimRequest {
+        claim_type,
+        required: request.essential.unwrap_or(false),
+        reason: request.purpose.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_requests() {
+        let request = ClaimsRequest::parse(
+            r#"{
+                "id_token": {
+                    "zk_age_claim": {"essential": true, "value": 25, "purpose": "Age-restricted content"},
+                    "zk_kyc_claim": {"value": "enhanced", "max_age_seconds": 86400},
+                    "email": {"essential": true}
+                },
+                "userinfo": {"zk_credential_claim": null}
+            }"#,
+        )
+        .unwrap();
+
+        let requests = request.claim_requests().unwrap();
+        assert_eq!(requests.len(), 3);
+
+        assert!(matches!(
+            requests[0].claim_type,
+            ClaimType::AgeAtLeast { threshold: 25 }
+        ));
+        assert!(requests[0].required);
+        assert_eq!(
+            requests[0].reason.as_deref(),
+            Some("Age-restricted content")
+        );
+
+        assert!(matches!(
+            requests[1].claim_type,
+            ClaimType::KycStatus {
+                level: KycLevel::Enhanced,
+                max_age_seconds: Some(86400),
+                ..
+            }
+        ));
+        assert!(!requests[1].required);
+
+        assert!(matches!(
+            &requests[2].claim_type,
+            ClaimType::HoldsCredential { credential_type, issuer: None } if credential_type == "*"
+        ));
+        assert!(!requests[2].required);
+    }
+
+    #[test]
+    fn test_essential_in_either_member() {
+        let request = ClaimsRequest::parse(
+            r#"{"id_token": {"zk_age_claim": {"value": 18}},
+                "userinfo": {"zk_age_claim": {"essential": true, "value": 18}}}"#,
+        )
+        .unwrap();
+
+        let requests = request.claim_requests().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].required);
+    }
+
+    #[test]
+    fn test_retain_zk_claims() {
+        let mut request = ClaimsRequest::parse(
+            r#"{"id_token": {"zk_age_claim": {"value": 18}, "zk_kyc_claim": null, "email": null},
+                "userinfo": {"zk_kyc_claim": {"value": "accredited"}}}"#,
+        )
+        .unwrap();
+
+        let dropped = request
+            .retain_zk_claims(|claim_type| !matches!(claim_type, ClaimType::KycStatus { .. }));
+        assert_eq!(dropped.len(), 2);
+        assert!(request.id_token.contains_key("zk_age_claim"));
+        assert!(request.id_token.contains_key("email"));
+        assert!(request.userinfo.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_claims_request() {
+        assert!(ClaimsRequest::parse("not json").is_err());
+
+        let request = ClaimsRequest::parse(r#"{"id_token": {"zk_age_claim": null}}"#).unwrap();
+        assert!(matches!(
+            request.claim_requests(),
+            Err(ClaimsRequestError::InvalidClaim("zk_age_claim", _))
+        ));
+
+        let request =
+            ClaimsRequest::parse(r#"{"id_token": {"zk_kyc_claim": {"value": "gold"}}}"#).unwrap();
+        assert!(request.claim_requests().is_err());
+    }
+}
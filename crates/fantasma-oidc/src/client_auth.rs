@@ -0,0 +1,217 @@
+//! Client authentication at the token endpoint
+//!
+//! Confidential clients authenticate with a shared secret (RFC 6749 §2.3.1)
+//! or a signed JWT assertion (`private_key_jwt`, RFC 7523 / OIDC Core §9).
+
+use crate::jwk::JwkSet;
+use crate::jws;
+use crate::token::TokenError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+/// `client_assertion_type` for JWT bearer client assertions
+pub const CLIENT_ASSERTION_TYPE_JWT_BEARER: &str =
+    "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+/// Clock skew tolerated when checking assertion timestamps
+const CLOCK_SKEW_SECONDS: i64 = 60;
+
+/// Client authentication method (`token_endpoint_auth_method`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenEndpointAuthMethod {
+    /// Secret in the HTTP Basic `Authorization` header
+    ClientSecretBasic,
+    /// Secret in the form body
+    ClientSecretPost,
+    /// JWT assertion signed with a key from the client's registered JWKS
+    PrivateKeyJwt,
+    /// Public client, no authentication
+    None,
+}
+
+impl TokenEndpointAuthMethod {
+    /// All supported methods, as advertised in discovery
+    pub const ALL: [Self; 4] = [
+        Self::ClientSecretBasic,
+        Self::ClientSecretPost,
+        Self::PrivateKeyJwt,
+        Self::None,
+    ];
+
+    /// Registered metadata value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ClientSecretBasic => "client_secret_basic",
+            Self::ClientSecretPost => "client_secret_post",
+            Self::PrivateKeyJwt => "private_key_jwt",
+            Self::None => "none",
+        }
+    }
+
+    /// Parse a registered metadata value
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.as_str() == s)
+    }
+
+    /// Whether the method authenticates with a shared secret
+    pub fn uses_secret(&self) -> bool {
+        matches!(self, Self::ClientSecretBasic | Self::ClientSecretPost)
+    }
+}
+
+/// `aud` claim: a single string or an array of strings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Audience {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl Audience {
+    /// Whether the audience includes `value`
+    pub fn contains(&self, value: &str) -> bool {
+        match self {
+            Self::Single(aud) => aud == value,
+            Self::Multiple(auds) => auds.iter().any(|aud| aud == value),
+        }
+    }
+}
+
+/// Claims of a `private_key_jwt` client assertion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientAssertionClaims {
+    /// Issuer - the client_id
+    pub iss: String,
+    /// Subject - the client_id
+    pub sub: String,
+    /// Audience - the token endpoint (or issuer) URL
+    pub aud: Audience,
+    /// Expiration time
+    pub exp: i64,
+    /// Issued at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
+    /// Unique identifier, used for replay detection
+    pub jti: String,
+}
+
+/// Read the `sub` (client_id) of an assertion without verifying it
+///
+/// Used to look up the client whose JWKS the assertion must verify against.
+pub fn assertion_subject(assertion: &str) -> Option<String> {
+    let payload = assertion.split('.').nth(1)?;
+    let bytes = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    claims["sub"].as_str().map(str::to_string)
+}
+
+/// Verify a `private_key_jwt` client assertion
+///
+/// `iss` and `sub` must both equal `client_id`, `aud` must contain one of
+/// `audiences`, and the assertion must not be expired. Replay detection on
+/// `jti` is left to the caller.
+pub fn verify_client_assertion(
+    assertion: &str,
+    client_id: &str,
+    jwks: &JwkSet,
+    audiences: &[&str],
+) -> Result<ClientAssertionClaims, TokenError> {
+    let (_, claims): (_, ClientAssertionClaims) = jws::verify(assertion, jwks)?;
+
+    if claims.iss != client_id || claims.sub != client_id {
+        return Err(TokenError::InvalidIssuer);
+    }
+
+    if !audiences.iter().any(|aud| claims.aud.contains(aud)) {
+        return Err(TokenError::InvalidAudience);
+    }
+
+    let now = Utc::now().timestamp();
+    if claims.exp + CLOCK_SKEW_SECONDS < now {
+        return Err(TokenError::Expired);
+    }
+    if claims.iat.is_some_and(|iat| iat > now + CLOCK_SKEW_SECONDS) {
+        return Err(TokenError::InvalidClaim("iat is in the future".to_string()));
+    }
+    if claims.jti.is_empty() {
+        return Err(TokenError::InvalidClaim("jti is required".to_string()));
+    }
+
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::SigningKey;
+
+    const TOKEN_ENDPOINT: &str = "https://fantasma.example/token";
+
+    fn assertion(key: &SigningKey, client_id: &str, aud: &str, exp_offset: i64) -> String {
+        let now = Utc::now().timestamp();
+        let claims = ClientAssertionClaims {
+            iss: client_id.to_string(),
+            sub: client_id.to_string(),
+            aud: Audience::Single(aud.to_string()),
+            exp: now + exp_offset,
+            iat: Some(now),
+            jti: uuid::Uuid::new_v4().to_string(),
+        };
+        jws::encode(&claims, "JWT", key).unwrap()
+    }
+
+    #[test]
+    fn test_verify_client_assertion() {
+        let key = SigningKey::generate_ed25519();
+        let jwks = JwkSet::new(vec![key.public_jwk()]);
+
+        let jwt = assertion(&key, "rp", TOKEN_ENDPOINT, 60);
+        assert_eq!(assertion_subject(&jwt).as_deref(), Some("rp"));
+        let claims = verify_client_assertion(&jwt, "rp", &jwks, &[TOKEN_ENDPOINT]).unwrap();
+        assert_eq!(claims.sub, "rp");
+
+        // Issued for a different client
+        assert!(matches!(
+            verify_client_assertion(&jwt, "other", &jwks, &[TOKEN_ENDPOINT]),
+            Err(TokenError::InvalidIssuer)
+        ));
+
+        // Wrong audience
+        let jwt = assertion(&key, "rp", "https://elsewhere.example/token", 60);
+        assert!(matches!(
+            verify_client_assertion(&jwt, "rp", &jwks, &[TOKEN_ENDPOINT]),
+            Err(TokenError::InvalidAudience)
+        ));
+
+        // Expired
+        let jwt = assertion(&key, "rp", TOKEN_ENDPOINT, -300);
+        assert!(matches!(
+            verify_client_assertion(&jwt, "rp", &jwks, &[TOKEN_ENDPOINT]),
+            Err(TokenError::Expired)
+        ));
+    }
+
+    #[test]
+    fn test_assertion_signed_by_unregistered_key() {
+        let registered = SigningKey::generate_ml_dsa_65();
+        let attacker = SigningKey::generate_ml_dsa_65();
+        let jwks = JwkSet::new(vec![registered.public_jwk()]);
+
+        let jwt = assertion(&attacker, "rp", TOKEN_ENDPOINT, 60);
+        assert!(verify_client_assertion(&jwt, "rp", &jwks, &[TOKEN_ENDPOINT]).is_err());
+    }
+
+    #[test]
+    fn test_auth_method_roundtrip() {
+        for method in TokenEndpointAuthMethod::ALL {
+            assert_eq!(
+                TokenEndpointAuthMethod::parse(method.as_str()),
+                Some(method)
+            );
+        }
+        assert_eq!(TokenEndpointAuthMethod::parse("tls_client_auth"), None);
+    }
+}
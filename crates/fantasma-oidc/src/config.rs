@@ -20,6 +20,44 @@ pub struct OidcConfig {
     /// JWKS endpoint path
     pub jwks_endpoint: String,
 
+    /// Token introspection endpoint path (RFC 7662)
+    pub introspection_endpoint: String,
+
+    /// Token revocation endpoint path (RFC 7009)
+    pub revocation_endpoint: String,
+
+    /// Dynamic client registration endpoint path (RFC 7591)
+    pub registration_endpoint: String,
+
+    /// Initial access token required to register clients; open registration when unset
+    #[serde(skip_serializing)]
+    pub registration_initial_access_token: Option<String>,
+
+    /// RP-initiated logout endpoint path
+    pub end_session_endpoint: String,
+
+    /// Pushed authorization request endpoint path (RFC 9126)
+    pub pushed_authorization_request_endpoint: String,
+
+    /// Reject authorization requests from every client unless they were pushed
+    pub require_pushed_authorization_requests: bool,
+
+    /// Reject authorization requests from every client unless they carry a
+    /// signed request object (RFC 9101)
+    pub require_signed_request_object: bool,
+
+    /// Device authorization endpoint path (RFC 8628)
+    pub device_authorization_endpoint: String,
+
+    /// Page where users enter the user code of a device authorization
+    pub device_verification_endpoint: String,
+
+    /// Response URI OpenID4VP wallets post their `vp_token` to
+    pub openid4vp_response_endpoint: String,
+
+    /// OpenID4VCI credential endpoint
+    pub credential_endpoint: String,
+
     /// Proof storage endpoint
     pub proof_storage_endpoint: String,
 
@@ -29,6 +67,51 @@ pub struct OidcConfig {
     /// Authorization code expiration in seconds
     pub auth_code_expiration_seconds: u64,
 
+    /// Lifetime of a pushed authorization request's `request_uri` in seconds
+    pub request_uri_expiration_seconds: u64,
+
+    /// Lifetime of the proof request shown to the wallet at consent in seconds
+    pub proof_request_expiration_seconds: u64,
+
+    /// Lifetime of a device code and its user code in seconds
+    pub device_code_expiration_seconds: u64,
+
+    /// Lifetime of a credential offer's pre-authorized code in seconds
+    pub credential_offer_expiration_seconds: u64,
+
+    /// Lifetime of the `c_nonce` a key proof must carry in seconds
+    pub c_nonce_expiration_seconds: u64,
+
+    /// Minimum seconds between token requests polling with a device code
+    pub device_code_interval_seconds: u64,
+
+    /// Refresh token expiration in seconds
+    pub refresh_token_expiration_seconds: u64,
+
+    /// How long verified ZK claims may be re-asserted on refresh before
+    /// fresh proofs are required (seconds)
+    pub zk_claims_max_age_seconds: u64,
+
+    /// How long a signing key signs before it is rotated out (seconds);
+    /// 0 rotates only when an administrator asks to
+    pub signing_key_rotation_seconds: u64,
+
+    /// How long before its activation the next signing key is published in
+    /// the JWKS (seconds)
+    pub signing_key_prepublish_seconds: u64,
+
+    /// Add an ML-DSA-65 signature to EdDSA-signed ID tokens, and sign issued
+    /// credentials with a hybrid Ed25519 + ML-DSA-65 key
+    pub hybrid_signatures: bool,
+
+    /// Let the built-in demo users stand in for a wallet at consent, with
+    /// claims that are asserted rather than proven. For demonstrations only
+    pub demo_users: bool,
+
+    /// Accept proofs checked by the mock prover backend, which anyone can
+    /// forge. For development only
+    pub mock_proofs: bool,
+
     /// Supported scopes
     pub supported_scopes: Vec<String>,
 
@@ -47,9 +130,34 @@ impl Default for OidcConfig {
             token_endpoint: "/token".to_string(),
             userinfo_endpoint: "/userinfo".to_string(),
             jwks_endpoint: "/.well-known/jwks.json".to_string(),
+            introspection_endpoint: "/introspect".to_string(),
+            revocation_endpoint: "/revoke".to_string(),
+            registration_endpoint: "/register".to_string(),
+            registration_initial_access_token: None,
+            end_session_endpoint: "/end_session".to_string(),
+            pushed_authorization_request_endpoint: "/par".to_string(),
+            require_pushed_authorization_requests: false,
+            require_signed_request_object: false,
+            device_authorization_endpoint: "/device_authorization".to_string(),
+            device_verification_endpoint: "/device".to_string(),
+            openid4vp_response_endpoint: "/openid4vp/response".to_string(),
+            credential_endpoint: "/credential".to_string(),
             proof_storage_endpoint: "/proofs".to_string(),
             token_expiration_seconds: 3600,
             auth_code_expiration_seconds: 600,
+            request_uri_expiration_seconds: 60,
+            proof_request_expiration_seconds: 300,
+            device_code_expiration_seconds: 600,
+            credential_offer_expiration_seconds: 600,
+            c_nonce_expiration_seconds: 300,
+            device_code_interval_seconds: 5,
+            refresh_token_expiration_seconds: 30 * 24 * 3600,
+            zk_claims_max_age_seconds: 24 * 3600,
+            signing_key_rotation_seconds: 90 * 24 * 3600,
+            signing_key_prepublish_seconds: 24 * 3600,
+            hybrid_signatures: false,
+            demo_users: false,
+            mock_proofs: false,
             supported_scopes: vec![
                 "openid".to_string(),
                 "zk:age:18+".to_string(),
@@ -62,7 +170,12 @@ impl Default for OidcConfig {
                 "zk:kyc:accredited".to_string(),
             ],
             supported_response_types: vec!["code".to_string()],
-            supported_grant_types: vec!["authorization_code".to_string()],
+            supported_grant_types: vec![
+                "authorization_code".to_string(),
+                "refresh_token".to_string(),
+                crate::device::DEVICE_CODE_GRANT_TYPE.to_string(),
+                crate::openid4vci::PRE_AUTHORIZED_CODE_GRANT_TYPE.to_string(),
+            ],
         }
     }
 }
@@ -0,0 +1,87 @@
+//! Device authorization grant (RFC 8628)
+//!
+//! Clients that cannot host a redirect URI, such as command-line tools and
+//! kiosks, obtain a device code and a short user code. The user enters the
+//! user code at the verification page on another device and consents there,
+//! while the client polls the token endpoint with the device code.
+
+use serde::{Deserialize, Serialize};
+
+/// Grant type for exchanging a device code at the token endpoint
+pub const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Characters of user codes: consonants only, so no words are spelled and
+/// nothing is easily confused (RFC 8628 §6.1)
+pub const USER_CODE_CHARSET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+
+/// Number of characters in a user code, excluding the separator
+pub const USER_CODE_LENGTH: usize = 8;
+
+/// Seconds added to a client's polling interval on `slow_down` (RFC 8628 §3.5)
+pub const SLOW_DOWN_INCREMENT_SECONDS: u64 = 5;
+
+/// Device authorization response (RFC 8628 §3.2)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAuthorizationResponse {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Verification URI with the user code filled in, e.g. for a QR code
+    pub verification_uri_complete: String,
+    pub expires_in: u64,
+    /// Minimum seconds between polls of the token endpoint
+    pub interval: u64,
+}
+
+/// Canonical form of a user code as typed by the user
+///
+/// Case, spaces and dashes are ignored; the result is grouped as
+/// `XXXX-XXXX`. Returns `None` if the input cannot be a user code.
+pub fn normalize_user_code(input: &str) -> Option<String> {
+    let chars: Vec<char> = input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    let valid = chars.len() == USER_CODE_LENGTH
+        && chars
+            .iter()
+            .all(|c| c.is_ascii() && USER_CODE_CHARSET.contains(&(*c as u8)));
+    if !valid {
+        return None;
+    }
+
+    let (first, second) = chars.split_at(USER_CODE_LENGTH / 2);
+    Some(format!(
+        "{}-{}",
+        first.iter().collect::<String>(),
+        second.iter().collect::<String>()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_user_code() {
+        assert_eq!(
+            normalize_user_code("wdjb-mjht").as_deref(),
+            Some("WDJB-MJHT")
+        );
+        assert_eq!(
+            normalize_user_code(" WDJB MJHT ").as_deref(),
+            Some("WDJB-MJHT")
+        );
+        assert_eq!(
+            normalize_user_code("WDJBMJHT").as_deref(),
+            Some("WDJB-MJHT")
+        );
+
+        // Vowels and digits are never used
+        assert!(normalize_user_code("WDJA-MJHT").is_none());
+        assert!(normalize_user_code("WDJB-MJH1").is_none());
+        assert!(normalize_user_code("WDJB-MJH").is_none());
+    }
+}
@@ -1,7 +1,15 @@
 //! OIDC Discovery document
 
+use crate::client_auth::TokenEndpointAuthMethod;
 use crate::config::OidcConfig;
+use crate::jwe::{JweAlgorithm, JweEncryption};
+use crate::pkce::PkceMethod;
+use crate::provenance::AuthContextClass;
+use crate::signing::JwsAlgorithm;
+use fantasma_stark::circuit::CircuitLoader;
+use fantasma_stark::verifier::Verifier;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// OIDC Discovery document (OpenID Provider Configuration)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +29,42 @@ pub struct DiscoveryDocument {
     /// JWKS URI
     pub jwks_uri: String,
 
+    /// Token introspection endpoint URL (RFC 7662)
+    pub introspection_endpoint: String,
+
+    /// Token revocation endpoint URL (RFC 7009)
+    pub revocation_endpoint: String,
+
+    /// Dynamic client registration endpoint URL (RFC 7591)
+    pub registration_endpoint: String,
+
+    /// RP-initiated logout endpoint URL
+    pub end_session_endpoint: String,
+
+    /// Whether logout tokens are sent to registered back-channel logout URIs
+    pub backchannel_logout_supported: bool,
+
+    /// Whether logout tokens and ID tokens carry `sid`
+    pub backchannel_logout_session_supported: bool,
+
+    /// Pushed authorization request endpoint URL (RFC 9126)
+    pub pushed_authorization_request_endpoint: String,
+
+    /// Device authorization endpoint URL (RFC 8628)
+    pub device_authorization_endpoint: String,
+
+    /// Whether every authorization request must be pushed first
+    pub require_pushed_authorization_requests: bool,
+
+    /// Whether the `request` parameter (signed request objects) is supported
+    pub request_parameter_supported: bool,
+
+    /// Algorithms accepted for signed request objects
+    pub request_object_signing_alg_values_supported: Vec<String>,
+
+    /// Whether every authorization request must carry a signed request object
+    pub require_signed_request_object: bool,
+
     /// Supported scopes
     pub scopes_supported: Vec<String>,
 
@@ -30,29 +74,63 @@ pub struct DiscoveryDocument {
     /// Supported grant types
     pub grant_types_supported: Vec<String>,
 
+    /// Whether wallets may redeem pre-authorized codes without a client_id
+    /// (OpenID4VCI §12.3)
+    #[serde(rename = "pre-authorized_grant_anonymous_access_supported")]
+    pub pre_authorized_grant_anonymous_access_supported: bool,
+
     /// Supported subject types
     pub subject_types_supported: Vec<String>,
 
     /// Supported ID token signing algorithms
     pub id_token_signing_alg_values_supported: Vec<String>,
 
+    /// Key management algorithms for encrypted ID tokens
+    pub id_token_encryption_alg_values_supported: Vec<String>,
+
+    /// Content encryption algorithms for encrypted ID tokens
+    pub id_token_encryption_enc_values_supported: Vec<String>,
+
+    /// Key management algorithms for encrypted userinfo responses
+    pub userinfo_encryption_alg_values_supported: Vec<String>,
+
+    /// Content encryption algorithms for encrypted userinfo responses
+    pub userinfo_encryption_enc_values_supported: Vec<String>,
+
+    /// Authentication context classes, by verification method and issuer trust
+    pub acr_values_supported: Vec<String>,
+
     /// Supported claims
     pub claims_supported: Vec<String>,
 
+    /// Whether the `claims` request parameter is supported
+    pub claims_parameter_supported: bool,
+
     /// Code challenge methods supported
     pub code_challenge_methods_supported: Vec<String>,
 
+    /// Client authentication methods supported at the token endpoint
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+
+    /// Algorithms supported for `private_key_jwt` client assertions
+    pub token_endpoint_auth_signing_alg_values_supported: Vec<String>,
+
+    /// Client authentication methods supported at the introspection endpoint
+    pub introspection_endpoint_auth_methods_supported: Vec<String>,
+
+    /// Client authentication methods supported at the revocation endpoint
+    pub revocation_endpoint_auth_methods_supported: Vec<String>,
+
+    /// Algorithms supported for DPoP proofs (RFC 9449 §5.1)
+    pub dpop_signing_alg_values_supported: Vec<String>,
+
     /// ZK circuit information
     pub zk_circuits: ZkCircuitInfo,
 }
 
-/// Information about available ZK circuits
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ZkCircuitInfo {
-    pub age_verification_v1: CircuitMetadata,
-    pub credential_verification_v1: CircuitMetadata,
-    pub kyc_verification_v1: CircuitMetadata,
-}
+/// Information about available ZK circuits, keyed by circuit id
+/// (e.g. `age_verification_v1`)
+pub type ZkCircuitInfo = BTreeMap<String, CircuitMetadata>;
 
 /// Metadata for a ZK circuit
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,31 +138,101 @@ pub struct CircuitMetadata {
     /// Human-readable description
     pub description: String,
 
-    /// Hash of the circuit bytecode
-    pub bytecode_hash: String,
+    /// SHA3-256 of the compiled circuit bytecode, `0x`-prefixed hex.
+    /// Absent until the circuits are compiled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytecode_hash: Option<String>,
 
-    /// Hash of the verification key
-    pub verification_key_hash: String,
+    /// SHA3-256 of the verification key, `0x`-prefixed hex. Absent unless
+    /// verification keys are configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_key_hash: Option<String>,
+}
+
+/// Describe the loaded circuits, with the hashes RPs pin
+pub fn zk_circuits(loader: &CircuitLoader, verifier: &Verifier) -> ZkCircuitInfo {
+    let hex_hash = |hash: [u8; 32]| format!("0x{}", hex::encode(hash));
+    loader
+        .all()
+        .iter()
+        .map(|(circuit_type, circuit)| {
+            (
+                circuit_type.id().to_string(),
+                CircuitMetadata {
+                    description: circuit.description.clone(),
+                    bytecode_hash: loader.bytecode_hash(*circuit_type).map(hex_hash),
+                    verification_key_hash: verifier
+                        .verification_key_hash(*circuit_type)
+                        .map(hex_hash),
+                },
+            )
+        })
+        .collect()
+}
+
+fn encryption_algs() -> Vec<String> {
+    JweAlgorithm::ALL
+        .iter()
+        .map(|alg| alg.as_str().to_string())
+        .collect()
+}
+
+fn encryption_encs() -> Vec<String> {
+    JweEncryption::ALL
+        .iter()
+        .map(|enc| enc.as_str().to_string())
+        .collect()
 }
 
 impl DiscoveryDocument {
     /// Create a discovery document from config
     pub fn from_config(config: &OidcConfig) -> Self {
+        // The same client authentication applies at every back-channel endpoint
+        let auth_methods: Vec<String> = TokenEndpointAuthMethod::ALL
+            .iter()
+            .map(|m| m.as_str().to_string())
+            .collect();
+
         Self {
             issuer: config.issuer.clone(),
             authorization_endpoint: config.endpoint_url(&config.authorization_endpoint),
             token_endpoint: config.endpoint_url(&config.token_endpoint),
             userinfo_endpoint: config.endpoint_url(&config.userinfo_endpoint),
             jwks_uri: config.endpoint_url(&config.jwks_endpoint),
+            introspection_endpoint: config.endpoint_url(&config.introspection_endpoint),
+            revocation_endpoint: config.endpoint_url(&config.revocation_endpoint),
+            registration_endpoint: config.endpoint_url(&config.registration_endpoint),
+            end_session_endpoint: config.endpoint_url(&config.end_session_endpoint),
+            backchannel_logout_supported: true,
+            backchannel_logout_session_supported: true,
+            pushed_authorization_request_endpoint: config
+                .endpoint_url(&config.pushed_authorization_request_endpoint),
+            device_authorization_endpoint: config
+                .endpoint_url(&config.device_authorization_endpoint),
+            require_pushed_authorization_requests: config.require_pushed_authorization_requests,
+            request_parameter_supported: true,
+            request_object_signing_alg_values_supported: vec![
+                JwsAlgorithm::EdDSA.to_string(),
+                JwsAlgorithm::MlDsa65.to_string(),
+            ],
+            require_signed_request_object: config.require_signed_request_object,
             scopes_supported: config.supported_scopes.clone(),
             response_types_supported: config.supported_response_types.clone(),
             grant_types_supported: config.supported_grant_types.clone(),
+            pre_authorized_grant_anonymous_access_supported: true,
             subject_types_supported: vec!["pairwise".to_string()],
             id_token_signing_alg_values_supported: vec![
-                "HS256".to_string(), // For demo
-                "EdDSA".to_string(), // Classical
-                                     // In future: "MLDSA65".to_string(), // Dilithium
+                JwsAlgorithm::EdDSA.to_string(),   // Classical
+                JwsAlgorithm::MlDsa65.to_string(), // ML-DSA (post-quantum)
             ],
+            id_token_encryption_alg_values_supported: encryption_algs(),
+            id_token_encryption_enc_values_supported: encryption_encs(),
+            userinfo_encryption_alg_values_supported: encryption_algs(),
+            userinfo_encryption_enc_values_supported: encryption_encs(),
+            acr_values_supported: AuthContextClass::all()
+                .iter()
+                .map(|acr| acr.to_string())
+                .collect(),
             claims_supported: vec![
                 "sub".to_string(),
                 "iss".to_string(),
@@ -93,29 +241,32 @@ impl DiscoveryDocument {
                 "iat".to_string(),
                 "nonce".to_string(),
                 "auth_time".to_string(),
+                "sid".to_string(),
+                "acr".to_string(),
+                "amr".to_string(),
                 "zk_age_claim".to_string(),
                 "zk_credential_claim".to_string(),
                 "zk_kyc_claim".to_string(),
             ],
-            code_challenge_methods_supported: vec!["S256".to_string()],
-            zk_circuits: ZkCircuitInfo {
-                age_verification_v1: CircuitMetadata {
-                    description: "Proves age >= threshold without revealing birthdate".to_string(),
-                    bytecode_hash: "0x...".to_string(), // Set after compilation
-                    verification_key_hash: "0x...".to_string(),
-                },
-                credential_verification_v1: CircuitMetadata {
-                    description: "Proves credential possession without revealing details"
-                        .to_string(),
-                    bytecode_hash: "0x...".to_string(),
-                    verification_key_hash: "0x...".to_string(),
-                },
-                kyc_verification_v1: CircuitMetadata {
-                    description: "Proves KYC status without revealing personal data".to_string(),
-                    bytecode_hash: "0x...".to_string(),
-                    verification_key_hash: "0x...".to_string(),
-                },
-            },
+            claims_parameter_supported: true,
+            code_challenge_methods_supported: vec![
+                PkceMethod::S256.as_str().to_string(),
+                PkceMethod::Plain.as_str().to_string(),
+            ],
+            token_endpoint_auth_methods_supported: auth_methods.clone(),
+            token_endpoint_auth_signing_alg_values_supported: vec![
+                JwsAlgorithm::EdDSA.to_string(),
+                JwsAlgorithm::MlDsa65.to_string(),
+            ],
+            introspection_endpoint_auth_methods_supported: auth_methods.clone(),
+            revocation_endpoint_auth_methods_supported: auth_methods,
+            dpop_signing_alg_values_supported: crate::dpop::PROOF_ALGORITHMS
+                .iter()
+                .map(JwsAlgorithm::to_string)
+                .collect(),
+            // Without compiled artifacts the circuits carry no hashes; the
+            // server publishes the ones it loaded at startup
+            zk_circuits: zk_circuits(&CircuitLoader::with_defaults(), &Verifier::new()),
         }
     }
 }
@@ -132,5 +283,45 @@ mod tests {
         assert_eq!(doc.issuer, "https://fantasma.example");
         assert!(doc.scopes_supported.contains(&"openid".to_string()));
         assert!(doc.scopes_supported.contains(&"zk:age:18+".to_string()));
+        assert_eq!(
+            doc.introspection_endpoint,
+            "https://fantasma.example/introspect"
+        );
+        assert_eq!(doc.revocation_endpoint, "https://fantasma.example/revoke");
+        assert_eq!(
+            doc.pushed_authorization_request_endpoint,
+            "https://fantasma.example/par"
+        );
+        assert!(!doc.require_pushed_authorization_requests);
+        assert_eq!(
+            doc.device_authorization_endpoint,
+            "https://fantasma.example/device_authorization"
+        );
+        assert!(doc
+            .grant_types_supported
+            .contains(&crate::device::DEVICE_CODE_GRANT_TYPE.to_string()));
+        assert_eq!(doc.zk_circuits.len(), 3);
+        assert!(doc.zk_circuits["age_verification_v1"]
+            .bytecode_hash
+            .is_none());
+    }
+
+    #[test]
+    fn test_zk_circuits_publish_loaded_hashes() {
+        use fantasma_stark::circuit::CircuitType;
+
+        let mut verifier = Verifier::new();
+        verifier
+            .load_verification_key_bytes(CircuitType::AgeVerification, b"vk".to_vec())
+            .unwrap();
+        let circuits = zk_circuits(&CircuitLoader::with_defaults(), &verifier);
+
+        let age = &circuits["age_verification_v1"];
+        let vk_hash = age.verification_key_hash.as_ref().unwrap();
+        assert!(vk_hash.starts_with("0x"));
+        assert_eq!(vk_hash.len(), 66);
+        assert!(circuits["kyc_verification_v1"]
+            .verification_key_hash
+            .is_none());
     }
 }
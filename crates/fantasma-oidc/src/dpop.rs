@@ -0,0 +1,263 @@
+//! Demonstrating Proof of Possession (DPoP, RFC 9449)
+//!
+//! A DPoP proof is a JWT signed by a key of the client's choosing, with the
+//! public key in its header. Tokens issued on a proof are bound to that key's
+//! JWK thumbprint (`cnf.jkt`), so a stolen token is useless without the key.
+//!
+//! Proofs are checked here for signature, method, URL, age and access token
+//! hash; nonce validation and `jti` replay detection are left to the caller.
+
+use crate::jws::{self, JwsHeader};
+use crate::signing::{JwsAlgorithm, SigningKey};
+use crate::token::TokenError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// `typ` header of DPoP proofs
+pub const DPOP_JWT_TYPE: &str = "dpop+jwt";
+
+/// Request header carrying the proof
+pub const DPOP_HEADER: &str = "DPoP";
+
+/// Response header carrying a server-provided nonce
+pub const DPOP_NONCE_HEADER: &str = "DPoP-Nonce";
+
+/// Token type of DPoP-bound access tokens
+pub const DPOP_TOKEN_TYPE: &str = "DPoP";
+
+/// Algorithms proofs may be signed with
+pub const PROOF_ALGORITHMS: [JwsAlgorithm; 2] = [JwsAlgorithm::EdDSA, JwsAlgorithm::MlDsa65];
+
+/// How long after `iat` a proof is accepted
+pub const PROOF_MAX_AGE_SECONDS: i64 = 300;
+
+/// Clock skew tolerated when checking `iat`
+const CLOCK_SKEW_SECONDS: i64 = 60;
+
+/// DPoP proof errors, by the error code sent to the client
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DpopError {
+    #[error("{0}")]
+    InvalidProof(String),
+
+    #[error("a DPoP nonce provided by the server is required")]
+    UseNonce,
+}
+
+impl DpopError {
+    /// OAuth error code (RFC 9449 §12.2)
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::InvalidProof(_) => "invalid_dpop_proof",
+            Self::UseNonce => "use_dpop_nonce",
+        }
+    }
+}
+
+/// Claims of a DPoP proof (RFC 9449 §4.2)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DpopClaims {
+    /// Unique identifier, used for replay detection
+    pub jti: String,
+
+    /// HTTP method of the request
+    pub htm: String,
+
+    /// HTTP URL of the request, without query and fragment
+    pub htu: String,
+
+    /// Issued at
+    pub iat: i64,
+
+    /// Hash of the access token presented with the proof
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ath: Option<String>,
+
+    /// Nonce provided by the server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+/// A verified DPoP proof
+#[derive(Debug, Clone)]
+pub struct DpopProof {
+    /// Thumbprint of the proof's key (RFC 7638)
+    pub jkt: String,
+
+    pub claims: DpopClaims,
+}
+
+/// Confirmation claim of a sender-constrained token (RFC 9449 §6)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Confirmation {
+    /// Thumbprint of the key the token is bound to
+    pub jkt: String,
+}
+
+/// `ath` value for an access token: base64url SHA-256 of its ASCII bytes
+pub fn access_token_hash(access_token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(access_token.as_bytes()))
+}
+
+/// Create a DPoP proof for a request, as a client would
+pub fn create_proof(
+    key: &SigningKey,
+    htm: &str,
+    htu: &str,
+    access_token: Option<&str>,
+    nonce: Option<&str>,
+) -> Result<String, TokenError> {
+    let header = JwsHeader {
+        alg: key.algorithm().as_str().to_string(),
+        typ: Some(DPOP_JWT_TYPE.to_string()),
+        kid: None,
+        jwk: Some(key.public_jwk()),
+        pq_signature: None,
+    };
+    let claims = DpopClaims {
+        jti: uuid::Uuid::new_v4().to_string(),
+        htm: htm.to_string(),
+        htu: htu.to_string(),
+        iat: Utc::now().timestamp(),
+        ath: access_token.map(access_token_hash),
+        nonce: nonce.map(str::to_string),
+    };
+    jws::encode_with_header(&claims, &header, key)
+}
+
+/// Verify a DPoP proof for a request to `htu` with method `htm`
+///
+/// When an access token is presented with the proof, `ath` must be its hash.
+pub fn verify_proof(
+    proof: &str,
+    htm: &str,
+    htu: &str,
+    access_token: Option<&str>,
+) -> Result<DpopProof, DpopError> {
+    let invalid = |description: &str| DpopError::InvalidProof(description.to_string());
+
+    let header = jws::decode_header(proof).map_err(|_| invalid("malformed DPoP proof"))?;
+    if header.typ.as_deref() != Some(DPOP_JWT_TYPE) {
+        return Err(invalid("DPoP proof typ must be dpop+jwt"));
+    }
+    if !JwsAlgorithm::parse(&header.alg).is_some_and(|alg| PROOF_ALGORITHMS.contains(&alg)) {
+        return Err(invalid("unsupported DPoP proof algorithm"));
+    }
+
+    let (_, jwk, claims): (_, _, DpopClaims) = jws::verify_self_signed(proof)
+        .map_err(|e| DpopError::InvalidProof(format!("invalid DPoP proof: {}", e)))?;
+
+    if claims.htm != htm {
+        return Err(invalid("DPoP proof htm does not match the request method"));
+    }
+    if strip_query(&claims.htu) != strip_query(htu) {
+        return Err(invalid("DPoP proof htu does not match the request URL"));
+    }
+
+    let now = Utc::now().timestamp();
+    if claims.iat > now + CLOCK_SKEW_SECONDS {
+        return Err(invalid("DPoP proof iat is in the future"));
+    }
+    if claims.iat + PROOF_MAX_AGE_SECONDS < now {
+        return Err(invalid("DPoP proof has expired"));
+    }
+    if claims.jti.is_empty() {
+        return Err(invalid("DPoP proof jti is required"));
+    }
+
+    if let Some(access_token) = access_token {
+        if claims.ath.as_deref() != Some(access_token_hash(access_token).as_str()) {
+            return Err(invalid("DPoP proof ath does not match the access token"));
+        }
+    }
+
+    Ok(DpopProof {
+        jkt: jwk.thumbprint(),
+        claims,
+    })
+}
+
+/// The URL without query and fragment (RFC 9449 §4.3)
+fn strip_query(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN_ENDPOINT: &str = "https://fantasma.example/token";
+
+    #[test]
+    fn test_verify_proof() {
+        let key = SigningKey::generate_ed25519();
+        let proof = create_proof(&key, "POST", TOKEN_ENDPOINT, None, Some("n-1")).unwrap();
+
+        let verified = verify_proof(&proof, "POST", TOKEN_ENDPOINT, None).unwrap();
+        assert_eq!(verified.jkt, key.public_jwk().thumbprint());
+        assert_eq!(verified.claims.nonce.as_deref(), Some("n-1"));
+
+        // Bound to the request it was made for
+        assert!(verify_proof(&proof, "GET", TOKEN_ENDPOINT, None).is_err());
+        assert!(verify_proof(&proof, "POST", "https://fantasma.example/userinfo", None).is_err());
+        assert!(verify_proof(&proof, "POST", &format!("{}?x=1", TOKEN_ENDPOINT), None).is_ok());
+    }
+
+    #[test]
+    fn test_access_token_hash() {
+        let key = SigningKey::generate_ml_dsa_65();
+        let url = "https://fantasma.example/userinfo";
+        let proof = create_proof(&key, "GET", url, Some("token-a"), None).unwrap();
+
+        assert!(verify_proof(&proof, "GET", url, Some("token-a")).is_ok());
+        assert_eq!(
+            verify_proof(&proof, "GET", url, Some("token-b")).unwrap_err(),
+            DpopError::InvalidProof("DPoP proof ath does not match the access token".to_string())
+        );
+
+        // A proof without ath cannot accompany an access token
+        let proof = create_proof(&key, "GET", url, None, None).unwrap();
+        assert!(verify_proof(&proof, "GET", url, Some("token-a")).is_err());
+    }
+
+    #[test]
+    fn test_rejects_other_jwts() {
+        let key = SigningKey::generate_ed25519();
+        let claims = DpopClaims {
+            jti: "jti-1".to_string(),
+            htm: "POST".to_string(),
+            htu: TOKEN_ENDPOINT.to_string(),
+            iat: Utc::now().timestamp(),
+            ath: None,
+            nonce: None,
+        };
+        let header = JwsHeader {
+            alg: key.algorithm().as_str().to_string(),
+            typ: Some("JWT".to_string()),
+            kid: None,
+            jwk: Some(key.public_jwk()),
+            pq_signature: None,
+        };
+        let jwt = jws::encode_with_header(&claims, &header, &key).unwrap();
+        assert!(verify_proof(&jwt, "POST", TOKEN_ENDPOINT, None).is_err());
+
+        // Stale proofs
+        let header = JwsHeader {
+            typ: Some(DPOP_JWT_TYPE.to_string()),
+            ..header
+        };
+        let claims = DpopClaims {
+            iat: Utc::now().timestamp() - PROOF_MAX_AGE_SECONDS - 1,
+            ..claims
+        };
+        let jwt = jws::encode_with_header(&claims, &header, &key).unwrap();
+        assert_eq!(
+            verify_proof(&jwt, "POST", TOKEN_ENDPOINT, None).unwrap_err(),
+            DpopError::InvalidProof("DPoP proof has expired".to_string())
+        );
+    }
+}
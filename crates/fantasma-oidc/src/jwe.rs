@@ -0,0 +1,559 @@
+//! Compact JWE serialization (RFC 7516)
+//!
+//! ID tokens and userinfo responses for clients that registered an
+//! encryption algorithm are signed, then encrypted to one of the client's
+//! keys (a nested JWT, OIDC Core §10.2). Key management agrees on the content
+//! encryption key directly:
+//!
+//! - `ECDH-ES` over X25519 (RFC 8037), with the ephemeral key in `epk`
+//! - `ML-KEM-768`, encapsulating to the client's post-quantum key, with the
+//!   KEM ciphertext in `ek`
+//!
+//! Either way the shared secret goes through the Concat KDF of RFC 7518
+//! §4.6.2, so the JWE's encrypted key is empty.
+
+use crate::jwk::{Jwk, JwkSet};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use fantasma_crypto::mlkem::{MlKemKeypair, MlKemPublicKey};
+use fantasma_crypto::x25519::{X25519Keypair, X25519PublicKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum JweError {
+    #[error("Unsupported key: {0}")]
+    UnsupportedKey(String),
+
+    #[error("Malformed JWE: {0}")]
+    Malformed(String),
+
+    #[error("Decryption failed")]
+    DecryptionFailed,
+}
+
+/// JWE key management algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum JweAlgorithm {
+    /// Ephemeral-static X25519 key agreement (RFC 7518 §4.6, RFC 8037)
+    #[serde(rename = "ECDH-ES")]
+    EcdhEs,
+
+    /// ML-KEM-768 key encapsulation (post-quantum)
+    #[serde(rename = "ML-KEM-768")]
+    MlKem768,
+}
+
+impl JweAlgorithm {
+    /// Every supported algorithm, as advertised in discovery
+    pub const ALL: [Self; 2] = [Self::EcdhEs, Self::MlKem768];
+
+    /// JOSE `alg` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JweAlgorithm::EcdhEs => "ECDH-ES",
+            JweAlgorithm::MlKem768 => "ML-KEM-768",
+        }
+    }
+
+    /// Parse a JOSE `alg` value
+    pub fn parse(alg: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.as_str() == alg)
+    }
+
+    /// Whether a JWK can receive content encrypted with this algorithm
+    pub fn accepts(&self, jwk: &Jwk) -> bool {
+        if jwk.key_use.as_deref().is_some_and(|u| u != "enc") {
+            return false;
+        }
+        match self {
+            JweAlgorithm::EcdhEs => {
+                jwk.kty == "OKP"
+                    && jwk.crv.as_deref() == Some("X25519")
+                    && matches!(jwk.alg.as_deref(), None | Some("ECDH-ES"))
+            }
+            JweAlgorithm::MlKem768 => jwk.kty == "AKP" && jwk.alg.as_deref() == Some(self.as_str()),
+        }
+    }
+
+    /// The key in a client's JWK set to encrypt to
+    pub fn select_key<'a>(&self, jwks: &'a JwkSet) -> Option<&'a Jwk> {
+        jwks.keys.iter().find(|jwk| self.accepts(jwk))
+    }
+}
+
+/// JWE content encryption algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum JweEncryption {
+    /// AES-128-CBC with HMAC-SHA-256 (RFC 7518 §5.2.3), the OIDC default
+    #[serde(rename = "A128CBC-HS256")]
+    A128CbcHs256,
+
+    /// AES-256-GCM (RFC 7518 §5.3)
+    #[serde(rename = "A256GCM")]
+    A256Gcm,
+}
+
+impl JweEncryption {
+    /// Every supported algorithm, as advertised in discovery
+    pub const ALL: [Self; 2] = [Self::A128CbcHs256, Self::A256Gcm];
+
+    /// JOSE `enc` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JweEncryption::A128CbcHs256 => "A128CBC-HS256",
+            JweEncryption::A256Gcm => "A256GCM",
+        }
+    }
+
+    /// Parse a JOSE `enc` value
+    pub fn parse(enc: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.as_str() == enc)
+    }
+
+    /// Length of the content encryption key in bytes
+    fn key_len(&self) -> usize {
+        32
+    }
+
+    /// Encrypt, returning the IV, ciphertext and authentication tag
+    fn seal(&self, cek: &[u8], plaintext: &[u8], aad: &[u8]) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        match self {
+            JweEncryption::A128CbcHs256 => {
+                use aes::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+
+                let (mac_key, enc_key) = cek.split_at(16);
+                let iv = random_bytes(16);
+                let ciphertext = cbc::Encryptor::<aes::Aes128>::new(enc_key.into(), iv[..].into())
+                    .encrypt_padded_vec_mut::<Pkcs7>(plaintext);
+                let tag = cbc_hs256_tag(mac_key, aad, &iv, &ciphertext);
+                (iv, ciphertext, tag)
+            }
+            JweEncryption::A256Gcm => {
+                use aes_gcm::aead::{Aead, KeyInit, Payload};
+
+                let iv = random_bytes(12);
+                let mut sealed = aes_gcm::Aes256Gcm::new(cek.into())
+                    .encrypt(
+                        iv[..].into(),
+                        Payload {
+                            msg: plaintext,
+                            aad,
+                        },
+                    )
+                    .expect("AES-GCM encryption of an in-memory buffer cannot fail");
+                let tag = sealed.split_off(sealed.len() - 16);
+                (iv, sealed, tag)
+            }
+        }
+    }
+
+    /// Authenticate and decrypt
+    fn open(
+        &self,
+        cek: &[u8],
+        iv: &[u8],
+        ciphertext: &[u8],
+        tag: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, JweError> {
+        match self {
+            JweEncryption::A128CbcHs256 => {
+                use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+                use hmac::Mac;
+
+                if iv.len() != 16 {
+                    return Err(JweError::DecryptionFailed);
+                }
+                let (mac_key, enc_key) = cek.split_at(16);
+                cbc_hs256_mac(mac_key, aad, iv, ciphertext)
+                    .verify_truncated_left(tag)
+                    .map_err(|_| JweError::DecryptionFailed)?;
+                cbc::Decryptor::<aes::Aes128>::new(enc_key.into(), iv.into())
+                    .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+                    .map_err(|_| JweError::DecryptionFailed)
+            }
+            JweEncryption::A256Gcm => {
+                use aes_gcm::aead::{Aead, KeyInit, Payload};
+
+                if iv.len() != 12 {
+                    return Err(JweError::DecryptionFailed);
+                }
+                let sealed = [ciphertext, tag].concat();
+                aes_gcm::Aes256Gcm::new(cek.into())
+                    .decrypt(iv.into(), Payload { msg: &sealed, aad })
+                    .map_err(|_| JweError::DecryptionFailed)
+            }
+        }
+    }
+}
+
+/// HMAC over the AAD, IV, ciphertext and AAD length (RFC 7518 §5.2.2.1)
+fn cbc_hs256_mac(
+    mac_key: &[u8],
+    aad: &[u8],
+    iv: &[u8],
+    ciphertext: &[u8],
+) -> hmac::Hmac<sha2::Sha256> {
+    use hmac::Mac;
+
+    let mut mac = <hmac::Hmac<sha2::Sha256> as Mac>::new_from_slice(mac_key)
+        .expect("HMAC accepts keys of any length");
+    mac.update(aad);
+    mac.update(iv);
+    mac.update(ciphertext);
+    mac.update(&((aad.len() as u64) * 8).to_be_bytes());
+    mac
+}
+
+/// The first half of the HMAC is the tag
+fn cbc_hs256_tag(mac_key: &[u8], aad: &[u8], iv: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    use hmac::Mac;
+
+    cbc_hs256_mac(mac_key, aad, iv, ciphertext)
+        .finalize()
+        .into_bytes()[..16]
+        .to_vec()
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+/// The encryption a client registered for a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JweParams {
+    pub alg: JweAlgorithm,
+    pub enc: JweEncryption,
+}
+
+impl JweParams {
+    /// Parse registered `*_encrypted_response_alg` / `_enc` metadata; `enc`
+    /// defaults to `A128CBC-HS256` (OIDC Registration §2)
+    pub fn parse(alg: Option<&str>, enc: Option<&str>) -> Result<Option<Self>, String> {
+        let Some(alg) = alg else {
+            return match enc {
+                Some(_) => Err("an encryption enc requires an alg".to_string()),
+                None => Ok(None),
+            };
+        };
+        let alg = JweAlgorithm::parse(alg)
+            .ok_or_else(|| format!("unsupported encryption alg: {}", alg))?;
+        let enc = match enc {
+            Some(enc) => JweEncryption::parse(enc)
+                .ok_or_else(|| format!("unsupported encryption enc: {}", enc))?,
+            None => JweEncryption::A128CbcHs256,
+        };
+        Ok(Some(Self { alg, enc }))
+    }
+}
+
+/// JOSE header of a compact JWE
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JweHeader {
+    pub alg: JweAlgorithm,
+    pub enc: JweEncryption,
+
+    /// Media type of the plaintext ("JWT" for nested tokens)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cty: Option<String>,
+
+    /// ID of the recipient's key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+
+    /// Ephemeral public key (`ECDH-ES`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epk: Option<Jwk>,
+
+    /// KEM ciphertext, base64url (`ML-KEM-768`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ek: Option<String>,
+}
+
+/// Concat KDF (NIST SP 800-56A, as profiled by RFC 7518 §4.6.2) with empty
+/// `apu`/`apv`, keyed to the content encryption algorithm
+fn concat_kdf(shared_secret: &[u8], enc: JweEncryption) -> Vec<u8> {
+    use sha2::{Digest, Sha256};
+
+    let key_len = enc.key_len();
+    let algorithm_id = enc.as_str().as_bytes();
+    let mut other_info = Vec::new();
+    other_info.extend_from_slice(&(algorithm_id.len() as u32).to_be_bytes());
+    other_info.extend_from_slice(algorithm_id);
+    other_info.extend_from_slice(&0u32.to_be_bytes()); // apu
+    other_info.extend_from_slice(&0u32.to_be_bytes()); // apv
+    other_info.extend_from_slice(&((key_len * 8) as u32).to_be_bytes());
+
+    let mut key = Vec::with_capacity(key_len);
+    let mut counter = 1u32;
+    while key.len() < key_len {
+        let mut hasher = Sha256::new();
+        hasher.update(counter.to_be_bytes());
+        hasher.update(shared_secret);
+        hasher.update(&other_info);
+        key.extend_from_slice(&hasher.finalize());
+        counter += 1;
+    }
+    key.truncate(key_len);
+    key
+}
+
+/// Decode a base64url key member from a JWK
+fn decode_member(value: Option<&str>, name: &str) -> Result<Vec<u8>, JweError> {
+    let value =
+        value.ok_or_else(|| JweError::UnsupportedKey(format!("JWK is missing \"{}\"", name)))?;
+    URL_SAFE_NO_PAD
+        .decode(value)
+        .map_err(|e| JweError::UnsupportedKey(format!("invalid \"{}\": {}", name, e)))
+}
+
+fn decode_part(part: &str, name: &str) -> Result<Vec<u8>, JweError> {
+    URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|e| JweError::Malformed(format!("invalid {}: {}", name, e)))
+}
+
+/// Encrypt `plaintext` to a recipient's public JWK as a compact JWE
+///
+/// `cty` is `Some("JWT")` for a nested signed token.
+pub fn encrypt(
+    plaintext: &[u8],
+    recipient: &Jwk,
+    params: JweParams,
+    cty: Option<&str>,
+) -> Result<String, JweError> {
+    if !params.alg.accepts(recipient) {
+        return Err(JweError::UnsupportedKey(format!(
+            "key {} cannot receive {}",
+            recipient.kid.as_deref().unwrap_or("(no kid)"),
+            params.alg.as_str()
+        )));
+    }
+
+    let mut header = JweHeader {
+        alg: params.alg,
+        enc: params.enc,
+        cty: cty.map(str::to_string),
+        kid: recipient.kid.clone(),
+        epk: None,
+        ek: None,
+    };
+    let shared_secret = match params.alg {
+        JweAlgorithm::EcdhEs => {
+            let peer = X25519PublicKey::from_bytes(&decode_member(recipient.x.as_deref(), "x")?)
+                .map_err(|e| JweError::UnsupportedKey(e.to_string()))?;
+            let ephemeral = X25519Keypair::generate();
+            let mut epk = Jwk::x25519(ephemeral.public_key().as_bytes());
+            epk.kid = None;
+            epk.alg = None;
+            epk.key_use = None;
+            header.epk = Some(epk);
+            ephemeral.diffie_hellman(&peer).to_vec()
+        }
+        JweAlgorithm::MlKem768 => {
+            let peer =
+                MlKemPublicKey::from_bytes(&decode_member(recipient.public.as_deref(), "pub")?)
+                    .map_err(|e| JweError::UnsupportedKey(e.to_string()))?;
+            let (shared_secret, ciphertext) = peer.encapsulate();
+            header.ek = Some(URL_SAFE_NO_PAD.encode(ciphertext));
+            shared_secret
+        }
+    };
+    let cek = concat_kdf(&shared_secret, params.enc);
+
+    let header_json =
+        serde_json::to_vec(&header).map_err(|e| JweError::Malformed(e.to_string()))?;
+    let protected = URL_SAFE_NO_PAD.encode(header_json);
+    let (iv, ciphertext, tag) = params.enc.seal(&cek, plaintext, protected.as_bytes());
+
+    Ok(format!(
+        "{}..{}.{}.{}",
+        protected,
+        URL_SAFE_NO_PAD.encode(iv),
+        URL_SAFE_NO_PAD.encode(ciphertext),
+        URL_SAFE_NO_PAD.encode(tag)
+    ))
+}
+
+/// Decode the header of a compact JWE without decrypting it
+pub fn decode_header(token: &str) -> Result<JweHeader, JweError> {
+    let protected = token
+        .split('.')
+        .next()
+        .ok_or_else(|| JweError::Malformed("empty token".to_string()))?;
+    serde_json::from_slice(&decode_part(protected, "header")?)
+        .map_err(|e| JweError::Malformed(format!("invalid header: {}", e)))
+}
+
+/// A relying party's private key for decrypting responses
+pub enum DecryptionKey {
+    X25519(X25519Keypair),
+    MlKem768(MlKemKeypair),
+}
+
+impl DecryptionKey {
+    /// Generate an X25519 key for `ECDH-ES`
+    pub fn generate_x25519() -> Self {
+        Self::X25519(X25519Keypair::generate())
+    }
+
+    /// Generate an ML-KEM-768 key
+    pub fn generate_ml_kem_768() -> Self {
+        Self::MlKem768(MlKemKeypair::generate())
+    }
+
+    /// Public half of this key as a JWK, to register with the provider
+    pub fn public_jwk(&self) -> Jwk {
+        match self {
+            Self::X25519(kp) => Jwk::x25519(kp.public_key().as_bytes()),
+            Self::MlKem768(kp) => Jwk::ml_kem_768(kp.public_key.as_bytes()),
+        }
+    }
+
+    /// Decrypt a compact JWE encrypted to this key
+    pub fn decrypt(&self, token: &str) -> Result<Vec<u8>, JweError> {
+        let parts: Vec<&str> = token.split('.').collect();
+        let [protected, encrypted_key, iv, ciphertext, tag] = parts[..] else {
+            return Err(JweError::Malformed("expected five parts".to_string()));
+        };
+        if !encrypted_key.is_empty() {
+            return Err(JweError::Malformed(
+                "direct key agreement has no encrypted key".to_string(),
+            ));
+        }
+        let header = decode_header(token)?;
+
+        let shared_secret = match (self, header.alg) {
+            (Self::X25519(kp), JweAlgorithm::EcdhEs) => {
+                let epk = header
+                    .epk
+                    .ok_or_else(|| JweError::Malformed("missing epk".to_string()))?;
+                let peer = X25519PublicKey::from_bytes(&decode_member(epk.x.as_deref(), "x")?)
+                    .map_err(|_| JweError::Malformed("invalid epk".to_string()))?;
+                kp.diffie_hellman(&peer).to_vec()
+            }
+            (Self::MlKem768(kp), JweAlgorithm::MlKem768) => {
+                let ek = header
+                    .ek
+                    .ok_or_else(|| JweError::Malformed("missing ek".to_string()))?;
+                kp.secret_key
+                    .decapsulate(&decode_part(&ek, "ek")?)
+                    .map_err(|_| JweError::Malformed("invalid ek".to_string()))?
+            }
+            (_, alg) => {
+                return Err(JweError::UnsupportedKey(format!(
+                    "key cannot decrypt {}",
+                    alg.as_str()
+                )))
+            }
+        };
+        let cek = concat_kdf(&shared_secret, header.enc);
+
+        header.enc.open(
+            &cek,
+            &decode_part(iv, "iv")?,
+            &decode_part(ciphertext, "ciphertext")?,
+            &decode_part(tag, "tag")?,
+            protected.as_bytes(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(key: &DecryptionKey, alg: JweAlgorithm, enc: JweEncryption) {
+        let token = encrypt(
+            b"header.payload.signature",
+            &key.public_jwk(),
+            JweParams { alg, enc },
+            Some("JWT"),
+        )
+        .unwrap();
+        assert_eq!(token.split('.').count(), 5);
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, alg);
+        assert_eq!(header.enc, enc);
+        assert_eq!(header.cty.as_deref(), Some("JWT"));
+        assert_eq!(header.kid, key.public_jwk().kid);
+        assert_eq!(key.decrypt(&token).unwrap(), b"header.payload.signature");
+
+        // Flipping a ciphertext bit fails authentication
+        let mut parts: Vec<String> = token.split('.').map(str::to_string).collect();
+        let mut ciphertext = URL_SAFE_NO_PAD.decode(&parts[3]).unwrap();
+        ciphertext[0] ^= 1;
+        parts[3] = URL_SAFE_NO_PAD.encode(ciphertext);
+        assert!(matches!(
+            key.decrypt(&parts.join(".")),
+            Err(JweError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn test_ecdh_es_roundtrip() {
+        let key = DecryptionKey::generate_x25519();
+        for enc in JweEncryption::ALL {
+            roundtrip(&key, JweAlgorithm::EcdhEs, enc);
+        }
+    }
+
+    #[test]
+    fn test_ml_kem_roundtrip() {
+        let key = DecryptionKey::generate_ml_kem_768();
+        for enc in JweEncryption::ALL {
+            roundtrip(&key, JweAlgorithm::MlKem768, enc);
+        }
+    }
+
+    #[test]
+    fn test_only_the_recipient_can_decrypt() {
+        let key = DecryptionKey::generate_ml_kem_768();
+        let params = JweParams {
+            alg: JweAlgorithm::MlKem768,
+            enc: JweEncryption::A256Gcm,
+        };
+        let token = encrypt(b"secret", &key.public_jwk(), params, None).unwrap();
+        assert!(DecryptionKey::generate_ml_kem_768()
+            .decrypt(&token)
+            .is_err());
+        assert!(DecryptionKey::generate_x25519().decrypt(&token).is_err());
+
+        // An X25519 key cannot receive ML-KEM-768
+        let x25519 = DecryptionKey::generate_x25519().public_jwk();
+        assert!(encrypt(b"secret", &x25519, params, None).is_err());
+    }
+
+    #[test]
+    fn test_select_key() {
+        let signing = Jwk::ed25519(&[7u8; 32]);
+        let x25519 = DecryptionKey::generate_x25519().public_jwk();
+        let ml_kem = DecryptionKey::generate_ml_kem_768().public_jwk();
+        let jwks = JwkSet::new(vec![signing, x25519.clone(), ml_kem.clone()]);
+
+        assert_eq!(JweAlgorithm::EcdhEs.select_key(&jwks), Some(&x25519));
+        assert_eq!(JweAlgorithm::MlKem768.select_key(&jwks), Some(&ml_kem));
+        assert!(JweAlgorithm::MlKem768
+            .select_key(&JwkSet::new(vec![x25519]))
+            .is_none());
+    }
+
+    #[test]
+    fn test_params_default_enc() {
+        assert_eq!(
+            JweParams::parse(Some("ML-KEM-768"), None).unwrap(),
+            Some(JweParams {
+                alg: JweAlgorithm::MlKem768,
+                enc: JweEncryption::A128CbcHs256,
+            })
+        );
+        assert_eq!(JweParams::parse(None, None).unwrap(), None);
+        assert!(JweParams::parse(None, Some("A256GCM")).is_err());
+        assert!(JweParams::parse(Some("RSA-OAEP"), None).is_err());
+    }
+}
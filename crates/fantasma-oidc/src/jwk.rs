@@ -0,0 +1,157 @@
+//! JSON Web Keys (RFC 7517) published at the JWKS endpoint
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// A single JSON Web Key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    /// Key type (e.g., "OKP")
+    pub kty: String,
+
+    /// Key ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+
+    /// Intended algorithm (e.g., "EdDSA")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+
+    /// Public key use ("sig" or "enc")
+    #[serde(rename = "use", skip_serializing_if = "Option::is_none")]
+    pub key_use: Option<String>,
+
+    /// Curve name for OKP/EC keys (e.g., "Ed25519")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+
+    /// Public key (base64url) for OKP/EC keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+
+    /// Public key (base64url) for AKP keys (ML-DSA, ML-KEM)
+    #[serde(rename = "pub", skip_serializing_if = "Option::is_none")]
+    pub public: Option<String>,
+}
+
+impl Jwk {
+    /// Build an Ed25519 signing JWK from raw public key bytes
+    pub fn ed25519(public_key: &[u8]) -> Self {
+        let mut jwk = Self {
+            kty: "OKP".to_string(),
+            kid: None,
+            alg: Some("EdDSA".to_string()),
+            key_use: Some("sig".to_string()),
+            crv: Some("Ed25519".to_string()),
+            x: Some(URL_SAFE_NO_PAD.encode(public_key)),
+            public: None,
+        };
+        jwk.kid = Some(jwk.thumbprint());
+        jwk
+    }
+
+    /// Build an ML-DSA-65 signing JWK (AKP key type) from raw public key bytes
+    pub fn ml_dsa_65(public_key: &[u8]) -> Self {
+        let mut jwk = Self {
+            kty: "AKP".to_string(),
+            kid: None,
+            alg: Some("ML-DSA-65".to_string()),
+            key_use: Some("sig".to_string()),
+            crv: None,
+            x: None,
+            public: Some(URL_SAFE_NO_PAD.encode(public_key)),
+        };
+        jwk.kid = Some(jwk.thumbprint());
+        jwk
+    }
+
+    /// Build an X25519 encryption JWK (JWE `ECDH-ES`) from raw public key bytes
+    pub fn x25519(public_key: &[u8]) -> Self {
+        let mut jwk = Self {
+            kty: "OKP".to_string(),
+            kid: None,
+            alg: Some("ECDH-ES".to_string()),
+            key_use: Some("enc".to_string()),
+            crv: Some("X25519".to_string()),
+            x: Some(URL_SAFE_NO_PAD.encode(public_key)),
+            public: None,
+        };
+        jwk.kid = Some(jwk.thumbprint());
+        jwk
+    }
+
+    /// Build an ML-KEM-768 encryption JWK (AKP key type) from raw public key bytes
+    pub fn ml_kem_768(public_key: &[u8]) -> Self {
+        let mut jwk = Self {
+            kty: "AKP".to_string(),
+            kid: None,
+            alg: Some("ML-KEM-768".to_string()),
+            key_use: Some("enc".to_string()),
+            crv: None,
+            x: None,
+            public: Some(URL_SAFE_NO_PAD.encode(public_key)),
+        };
+        jwk.kid = Some(jwk.thumbprint());
+        jwk
+    }
+
+    /// JWK thumbprint (RFC 7638), base64url-encoded SHA-256
+    pub fn thumbprint(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        // Required members only, in lexicographic order, no whitespace
+        let canonical = match self.kty.as_str() {
+            "AKP" => format!(
+                r#"{{"alg":"{}","kty":"{}","pub":"{}"}}"#,
+                self.alg.as_deref().unwrap_or_default(),
+                self.kty,
+                self.public.as_deref().unwrap_or_default()
+            ),
+            _ => format!(
+                r#"{{"crv":"{}","kty":"{}","x":"{}"}}"#,
+                self.crv.as_deref().unwrap_or_default(),
+                self.kty,
+                self.x.as_deref().unwrap_or_default()
+            ),
+        };
+        URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+    }
+}
+
+/// A JWK set, as served from `/.well-known/jwks.json`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl JwkSet {
+    /// Create a set from a list of keys
+    pub fn new(keys: Vec<Jwk>) -> Self {
+        Self { keys }
+    }
+
+    /// Find a key by its `kid`
+    pub fn find(&self, kid: &str) -> Option<&Jwk> {
+        self.keys.iter().find(|k| k.kid.as_deref() == Some(kid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc8037_thumbprint() {
+        // RFC 8037, Appendix A.3
+        let public_key = URL_SAFE_NO_PAD
+            .decode("11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo")
+            .unwrap();
+        let jwk = Jwk::ed25519(&public_key);
+
+        assert_eq!(
+            jwk.kid.as_deref(),
+            Some("kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k")
+        );
+    }
+}
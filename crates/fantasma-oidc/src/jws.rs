@@ -0,0 +1,431 @@
+//! Compact JWS serialization (RFC 7515)
+//!
+//! `jsonwebtoken` has no ML-DSA support, so tokens are assembled here and
+//! the signature step is delegated to `SigningKey` / `JwsAlgorithm`.
+//!
+//! Hybrid tokens are ordinary `EdDSA` tokens whose protected header also
+//! carries an ML-DSA-65 signature over the rest of the header and the
+//! payload (`pq_signature`).
+//! EdDSA-only verifiers ignore the extra header parameter; post-quantum
+//! verifiers check it with [`verify_hybrid`], requiring both signatures or
+//! accepting either.
+
+use crate::jwk::{Jwk, JwkSet};
+use crate::signing::{JwsAlgorithm, SigningKey};
+use crate::token::TokenError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use fantasma_crypto::hybrid::HybridPolicy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// JOSE header of a compact JWS
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwsHeader {
+    /// Signature algorithm
+    pub alg: String,
+
+    /// Media type of the complete JWS (e.g., "JWT")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+
+    /// ID of the signing key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+
+    /// The signing key itself, for keys not known to the verifier in advance
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwk: Option<Jwk>,
+
+    /// Post-quantum signature of a hybrid token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pq_signature: Option<PqSignature>,
+}
+
+/// The ML-DSA half of a hybrid JWS, carried in its protected header
+///
+/// The signature covers a JWS signing input built from the header without
+/// `pq_signature` and the payload segment, so the header a verifier gets
+/// back is authenticated by either signature. It is in turn covered by the
+/// `EdDSA` signature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PqSignature {
+    /// Signature algorithm (`ML-DSA-65`)
+    pub alg: String,
+
+    /// ID of the signing key
+    pub kid: String,
+
+    /// The signature, base64url
+    pub sig: String,
+}
+
+/// Sign `payload` as a compact JWS with the given key
+pub fn encode<T: Serialize>(
+    payload: &T,
+    typ: &str,
+    key: &SigningKey,
+) -> Result<String, TokenError> {
+    let header = JwsHeader {
+        alg: key.algorithm().as_str().to_string(),
+        typ: Some(typ.to_string()),
+        kid: Some(key.kid().to_string()),
+        jwk: None,
+        pq_signature: None,
+    };
+    encode_with_header(payload, &header, key)
+}
+
+/// Sign `payload` as a hybrid JWS: `EdDSA` with `classical`, plus an
+/// ML-DSA-65 signature with `pq` in the header
+pub fn encode_hybrid<T: Serialize>(
+    payload: &T,
+    typ: &str,
+    classical: &SigningKey,
+    pq: &SigningKey,
+) -> Result<String, TokenError> {
+    if classical.algorithm() != JwsAlgorithm::EdDSA || pq.algorithm() != JwsAlgorithm::MlDsa65 {
+        return Err(TokenError::EncodingFailed(
+            "hybrid tokens are signed with an EdDSA and an ML-DSA-65 key".to_string(),
+        ));
+    }
+
+    let payload_json =
+        serde_json::to_vec(payload).map_err(|e| TokenError::EncodingFailed(e.to_string()))?;
+    let payload_b64 = URL_SAFE_NO_PAD.encode(payload_json);
+    let mut header = JwsHeader {
+        alg: classical.algorithm().as_str().to_string(),
+        typ: Some(typ.to_string()),
+        kid: Some(classical.kid().to_string()),
+        jwk: None,
+        pq_signature: None,
+    };
+    let signing_input = pq_signing_input(&header, &payload_b64)?;
+    header.pq_signature = Some(PqSignature {
+        alg: pq.algorithm().as_str().to_string(),
+        kid: pq.kid().to_string(),
+        sig: URL_SAFE_NO_PAD.encode(pq.sign(signing_input.as_bytes())),
+    });
+    encode_with_header(payload, &header, classical)
+}
+
+/// Input the post-quantum signature of a hybrid JWS is made over: the
+/// header without `pq_signature`, re-serialized, and the payload segment
+fn pq_signing_input(header: &JwsHeader, payload_b64: &str) -> Result<String, TokenError> {
+    let header = JwsHeader {
+        pq_signature: None,
+        ..header.clone()
+    };
+    let header_json =
+        serde_json::to_vec(&header).map_err(|e| TokenError::EncodingFailed(e.to_string()))?;
+    Ok(format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header_json),
+        payload_b64
+    ))
+}
+
+/// Sign `payload` as a compact JWS under a caller-built header
+///
+/// The header's `alg` must be the key's algorithm.
+pub fn encode_with_header<T: Serialize>(
+    payload: &T,
+    header: &JwsHeader,
+    key: &SigningKey,
+) -> Result<String, TokenError> {
+    let header_json =
+        serde_json::to_vec(&header).map_err(|e| TokenError::EncodingFailed(e.to_string()))?;
+    let payload_json =
+        serde_json::to_vec(payload).map_err(|e| TokenError::EncodingFailed(e.to_string()))?;
+
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header_json),
+        URL_SAFE_NO_PAD.encode(payload_json)
+    );
+    let signature = key.sign(signing_input.as_bytes());
+
+    Ok(format!(
+        "{}.{}",
+        signing_input,
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+/// Decode the header of a compact JWS without verifying it
+pub fn decode_header(token: &str) -> Result<JwsHeader, TokenError> {
+    let header_b64 = token
+        .split('.')
+        .next()
+        .ok_or_else(|| TokenError::DecodingFailed("empty token".to_string()))?;
+    decode_json(header_b64)
+}
+
+/// Verify a compact JWS against a JWK set and return its header and payload
+///
+/// The key is selected by the header's `kid` (or is the only key in the set
+/// when `kid` is absent); the header's `alg` must be a supported algorithm
+/// and match the key.
+pub fn verify<T: DeserializeOwned>(
+    token: &str,
+    jwks: &JwkSet,
+) -> Result<(JwsHeader, T), TokenError> {
+    verify_with(token, |header, alg| match header.kid.as_deref() {
+        Some(kid) => jwks
+            .find(kid)
+            .cloned()
+            .ok_or_else(|| TokenError::UnknownKey(kid.to_string())),
+        // Without a kid, the set must hold exactly one candidate key
+        None => {
+            let mut candidates = jwks
+                .keys
+                .iter()
+                .filter(|k| k.alg.is_none() || k.alg.as_deref() == Some(alg.as_str()));
+            match (candidates.next(), candidates.next()) {
+                (Some(jwk), None) => Ok(jwk.clone()),
+                _ => Err(TokenError::DecodingFailed("missing kid".to_string())),
+            }
+        }
+    })
+}
+
+/// Verify a compact JWS against the `jwk` in its own header
+///
+/// This only proves possession of that key; the caller decides whether the
+/// key is acceptable. Returns the header (without the key), the key and
+/// the payload.
+pub fn verify_self_signed<T: DeserializeOwned>(
+    token: &str,
+) -> Result<(JwsHeader, Jwk, T), TokenError> {
+    let (mut header, payload) = verify_with(token, |header, _| {
+        header
+            .jwk
+            .clone()
+            .ok_or_else(|| TokenError::DecodingFailed("missing jwk header".to_string()))
+    })?;
+    let jwk = header
+        .jwk
+        .take()
+        .ok_or_else(|| TokenError::DecodingFailed("missing jwk header".to_string()))?;
+    Ok((header, jwk, payload))
+}
+
+/// Verify a hybrid JWS against a JWK set under `policy`
+///
+/// `RequireBoth` checks the `EdDSA` signature and the header's ML-DSA-65
+/// signature; `AcceptEither` is satisfied by either of them, so tokens stay
+/// verifiable should one algorithm be broken. Both signatures cover the
+/// header as well as the payload. Tokens without a post-quantum signature
+/// fail `RequireBoth`.
+pub fn verify_hybrid<T: DeserializeOwned>(
+    token: &str,
+    jwks: &JwkSet,
+    policy: HybridPolicy,
+) -> Result<(JwsHeader, T), TokenError> {
+    let classical = verify(token, jwks);
+    let pq = verify_pq_signature(token, jwks);
+
+    match (policy, classical, pq) {
+        (HybridPolicy::RequireBoth, Ok(verified), Ok(_)) => Ok(verified),
+        (HybridPolicy::RequireBoth, Err(e), _) | (HybridPolicy::RequireBoth, _, Err(e)) => Err(e),
+        (HybridPolicy::AcceptEither, Ok(verified), _) => Ok(verified),
+        (HybridPolicy::AcceptEither, Err(_), Ok(verified)) => Ok(verified),
+        (HybridPolicy::AcceptEither, Err(e), Err(_)) => Err(e),
+    }
+}
+
+/// Check the ML-DSA-65 signature in a hybrid token's header and return the
+/// header and payload it covers
+fn verify_pq_signature<T: DeserializeOwned>(
+    token: &str,
+    jwks: &JwkSet,
+) -> Result<(JwsHeader, T), TokenError> {
+    let (header_b64, payload_b64, _) = split_segments(token)
+        .ok_or_else(|| TokenError::DecodingFailed("expected three JWS segments".to_string()))?;
+    let header: JwsHeader = decode_json(header_b64)?;
+    let pq = header.pq_signature.as_ref().ok_or_else(|| {
+        TokenError::InvalidSignature("missing post-quantum signature".to_string())
+    })?;
+
+    let alg = JwsAlgorithm::parse(&pq.alg)
+        .filter(|alg| *alg == JwsAlgorithm::MlDsa65)
+        .ok_or_else(|| TokenError::DecodingFailed(format!("unsupported algorithm: {}", pq.alg)))?;
+    let jwk = jwks
+        .find(&pq.kid)
+        .ok_or_else(|| TokenError::UnknownKey(pq.kid.clone()))?;
+    let signature = URL_SAFE_NO_PAD
+        .decode(&pq.sig)
+        .map_err(|e| TokenError::DecodingFailed(e.to_string()))?;
+
+    let signing_input = pq_signing_input(&header, payload_b64)?;
+    alg.verify(jwk, signing_input.as_bytes(), &signature)
+        .map_err(TokenError::InvalidSignature)?;
+
+    let payload = decode_json(payload_b64)?;
+    Ok((header, payload))
+}
+
+/// Split a compact JWS into its header, payload and signature segments
+fn split_segments(token: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = token.split('.');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(h), Some(p), Some(s), None) => Some((h, p, s)),
+        _ => None,
+    }
+}
+
+/// Verify a compact JWS with the key `select` picks for its header
+fn verify_with<T: DeserializeOwned>(
+    token: &str,
+    select: impl FnOnce(&JwsHeader, JwsAlgorithm) -> Result<Jwk, TokenError>,
+) -> Result<(JwsHeader, T), TokenError> {
+    let (header_b64, payload_b64, signature_b64) = split_segments(token)
+        .ok_or_else(|| TokenError::DecodingFailed("expected three JWS segments".to_string()))?;
+
+    let header: JwsHeader = decode_json(header_b64)?;
+    let alg = JwsAlgorithm::parse(&header.alg).ok_or_else(|| {
+        TokenError::DecodingFailed(format!("unsupported algorithm: {}", header.alg))
+    })?;
+    let jwk = select(&header, alg)?;
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature_b64)
+        .map_err(|e| TokenError::DecodingFailed(e.to_string()))?;
+    let signing_input = &token[..header_b64.len() + 1 + payload_b64.len()];
+
+    alg.verify(&jwk, signing_input.as_bytes(), &signature)
+        .map_err(TokenError::InvalidSignature)?;
+
+    let payload = decode_json(payload_b64)?;
+    Ok((header, payload))
+}
+
+/// Decode a base64url JSON segment
+fn decode_json<T: DeserializeOwned>(segment: &str) -> Result<T, TokenError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|e| TokenError::DecodingFailed(e.to_string()))?;
+    serde_json::from_slice(&bytes).map_err(|e| TokenError::DecodingFailed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ml_dsa_roundtrip() {
+        let key = SigningKey::generate_ml_dsa_65();
+        let jwks = JwkSet::new(vec![key.public_jwk()]);
+        let token = encode(&serde_json::json!({"hello": "pq"}), "JWT", &key).unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, "ML-DSA-65");
+        assert_eq!(header.kid.as_deref(), Some(key.kid()));
+
+        let (_, payload): (JwsHeader, serde_json::Value) = verify(&token, &jwks).unwrap();
+        assert_eq!(payload["hello"], "pq");
+    }
+
+    #[test]
+    fn test_tampered_payload_rejected() {
+        let key = SigningKey::generate_ed25519();
+        let jwks = JwkSet::new(vec![key.public_jwk()]);
+        let token = encode(&serde_json::json!({"admin": false}), "JWT", &key).unwrap();
+
+        let parts: Vec<&str> = token.split('.').collect();
+        let forged = format!(
+            "{}.{}.{}",
+            parts[0],
+            URL_SAFE_NO_PAD.encode(br#"{"admin":true}"#),
+            parts[2]
+        );
+
+        assert!(matches!(
+            verify::<serde_json::Value>(&forged, &jwks),
+            Err(TokenError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_algorithm_key_mismatch_rejected() {
+        let ed_key = SigningKey::generate_ed25519();
+        let token = encode(&serde_json::json!({}), "JWT", &ed_key).unwrap();
+
+        // Same kid, but the published key is an ML-DSA key
+        let mut jwk = SigningKey::generate_ml_dsa_65().public_jwk();
+        jwk.kid = Some(ed_key.kid().to_string());
+
+        assert!(verify::<serde_json::Value>(&token, &JwkSet::new(vec![jwk])).is_err());
+    }
+
+    #[test]
+    fn test_hybrid_roundtrip() {
+        let ed_key = SigningKey::generate_ed25519();
+        let pq_key = SigningKey::generate_ml_dsa_65();
+        let jwks = JwkSet::new(vec![ed_key.public_jwk(), pq_key.public_jwk()]);
+        let token = encode_hybrid(
+            &serde_json::json!({"hello": "hybrid"}),
+            "JWT",
+            &ed_key,
+            &pq_key,
+        )
+        .unwrap();
+
+        // An EdDSA-only verifier sees an ordinary token
+        let (header, payload): (JwsHeader, serde_json::Value) = verify(&token, &jwks).unwrap();
+        assert_eq!(header.alg, "EdDSA");
+        assert_eq!(header.pq_signature.unwrap().kid, pq_key.kid());
+        assert_eq!(payload["hello"], "hybrid");
+
+        for policy in [HybridPolicy::RequireBoth, HybridPolicy::AcceptEither] {
+            let (_, payload): (JwsHeader, serde_json::Value) =
+                verify_hybrid(&token, &jwks, policy).unwrap();
+            assert_eq!(payload["hello"], "hybrid");
+        }
+
+        assert!(encode_hybrid(&serde_json::json!({}), "JWT", &pq_key, &ed_key).is_err());
+    }
+
+    #[test]
+    fn test_hybrid_policies() {
+        let ed_key = SigningKey::generate_ed25519();
+        let pq_key = SigningKey::generate_ml_dsa_65();
+        let jwks = JwkSet::new(vec![ed_key.public_jwk(), pq_key.public_jwk()]);
+        let token = encode_hybrid(&serde_json::json!({}), "JWT", &ed_key, &pq_key).unwrap();
+
+        // Break the EdDSA signature: only the ML-DSA half still verifies
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        let mut signature = URL_SAFE_NO_PAD.decode(signature).unwrap();
+        signature[0] ^= 1;
+        let broken = format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature));
+        assert!(
+            verify_hybrid::<serde_json::Value>(&broken, &jwks, HybridPolicy::RequireBoth).is_err()
+        );
+        assert!(
+            verify_hybrid::<serde_json::Value>(&broken, &jwks, HybridPolicy::AcceptEither).is_ok()
+        );
+
+        // The ML-DSA half alone does not vouch for a rewritten header
+        let (header_b64, rest) = broken.split_once('.').unwrap();
+        let mut header: serde_json::Value = decode_json(header_b64).unwrap();
+        header["typ"] = serde_json::json!("logout+jwt");
+        let tampered = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header).unwrap()),
+            rest
+        );
+        assert!(matches!(
+            verify_hybrid::<serde_json::Value>(&tampered, &jwks, HybridPolicy::AcceptEither),
+            Err(TokenError::InvalidSignature(_))
+        ));
+
+        // A plain EdDSA token has no post-quantum signature to require
+        let plain = encode(&serde_json::json!({}), "JWT", &ed_key).unwrap();
+        assert!(
+            verify_hybrid::<serde_json::Value>(&plain, &jwks, HybridPolicy::RequireBoth).is_err()
+        );
+        assert!(
+            verify_hybrid::<serde_json::Value>(&plain, &jwks, HybridPolicy::AcceptEither).is_ok()
+        );
+    }
+}
@@ -3,13 +3,44 @@
 //! OIDC-compliant identity provider with zero-knowledge claims.
 
 pub mod claims;
+pub mod claims_request;
+pub mod client_auth;
 pub mod config;
+pub mod device;
 pub mod discovery;
+pub mod dpop;
+pub mod jwe;
+pub mod jwk;
+pub mod jws;
+pub mod logout;
+pub mod openid4vci;
+pub mod openid4vp;
+pub mod pkce;
+pub mod provenance;
+pub mod registration;
+pub mod request_object;
 pub mod scopes;
+pub mod signing;
+pub mod subject;
 pub mod token;
 
 pub use claims::ZkClaims;
+pub use claims_request::{ClaimsRequest, ClaimsRequestError};
+pub use client_auth::TokenEndpointAuthMethod;
 pub use config::OidcConfig;
+pub use device::{DeviceAuthorizationResponse, DEVICE_CODE_GRANT_TYPE};
 pub use discovery::DiscoveryDocument;
+pub use dpop::{Confirmation, DpopError, DpopProof};
+pub use fantasma_crypto::hybrid::HybridPolicy;
+pub use jwe::{JweAlgorithm, JweEncryption, JweParams};
+pub use jwk::{Jwk, JwkSet};
+pub use logout::{LogoutToken, LogoutTokenClaims};
+pub use openid4vci::{CredentialIssuerMetadata, CredentialOffer, PRE_AUTHORIZED_CODE_GRANT_TYPE};
+pub use openid4vp::{DcqlQuery, StarkPresentation};
+pub use pkce::{PkceChallenge, PkceMethod, PkcePolicy};
+pub use provenance::{AuthContextClass, ClaimProvenance};
+pub use registration::{ClientMetadata, ClientRegistrationResponse, RegistrationError};
+pub use request_object::{verify_request_object, RequestObjectClaims};
 pub use scopes::ZkScope;
-pub use token::{IdToken, IdTokenClaims};
+pub use signing::{JwsAlgorithm, KeyGeneration, KeyStatus, SigningKey, SigningKeys};
+pub use token::{IdToken, IdTokenClaims, IntrospectionResponse, UserInfoResponse};
@@ -0,0 +1,147 @@
+//! Back-channel logout tokens (OpenID Connect Back-Channel Logout 1.0)
+//!
+//! When a session ends, every client that took part in it and registered a
+//! `backchannel_logout_uri` is sent a signed logout token naming the
+//! pairwise subject it knows and the session ID from its ID tokens.
+
+use crate::jwk::JwkSet;
+use crate::jws;
+use crate::signing::SigningKey;
+use crate::token::TokenError;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+/// Event identifying a logout token (Back-Channel Logout §2.4)
+pub const BACKCHANNEL_LOGOUT_EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+
+/// `typ` header value for logout tokens (Back-Channel Logout §2.4)
+pub const LOGOUT_TOKEN_TYPE: &str = "logout+jwt";
+
+/// Logout token claims
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogoutTokenClaims {
+    /// Issuer
+    pub iss: String,
+
+    /// Pairwise subject of the logged-out user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+
+    /// Audience - the client being notified
+    pub aud: String,
+
+    /// Issued at
+    pub iat: i64,
+
+    /// Expiration time
+    pub exp: i64,
+
+    /// Unique identifier
+    pub jti: String,
+
+    /// Session ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+
+    /// Always `{ BACKCHANNEL_LOGOUT_EVENT: {} }`
+    pub events: serde_json::Value,
+}
+
+impl LogoutTokenClaims {
+    /// Claims for notifying `audience` that `subject`'s session `sid` ended
+    pub fn new(
+        issuer: impl Into<String>,
+        audience: impl Into<String>,
+        subject: Option<String>,
+        sid: Option<String>,
+        expiration_seconds: i64,
+    ) -> Self {
+        let now = Utc::now().timestamp();
+        Self {
+            iss: issuer.into(),
+            sub: subject,
+            aud: audience.into(),
+            iat: now,
+            exp: now + expiration_seconds,
+            jti: uuid::Uuid::new_v4().to_string(),
+            sid,
+            events: serde_json::json!({ BACKCHANNEL_LOGOUT_EVENT: {} }),
+        }
+    }
+}
+
+/// A signed logout token
+pub struct LogoutToken;
+
+impl LogoutToken {
+    /// Sign logout token claims
+    pub fn create(
+        claims: &LogoutTokenClaims,
+        signing_key: &SigningKey,
+    ) -> Result<String, TokenError> {
+        jws::encode(claims, LOGOUT_TOKEN_TYPE, signing_key)
+    }
+
+    /// Validate a logout token as a relying party would (Back-Channel Logout §2.6)
+    pub fn verify(
+        token: &str,
+        jwks: &JwkSet,
+        issuer: &str,
+        audience: &str,
+    ) -> Result<LogoutTokenClaims, TokenError> {
+        let (_, claims): (_, LogoutTokenClaims) = jws::verify(token, jwks)?;
+
+        if claims.iss != issuer {
+            return Err(TokenError::InvalidIssuer);
+        }
+        if claims.aud != audience {
+            return Err(TokenError::InvalidAudience);
+        }
+        if claims.exp < Utc::now().timestamp() {
+            return Err(TokenError::Expired);
+        }
+        if claims.sub.is_none() && claims.sid.is_none() {
+            return Err(TokenError::InvalidClaim(
+                "logout token needs sub or sid".to_string(),
+            ));
+        }
+        if claims.events.get(BACKCHANNEL_LOGOUT_EVENT).is_none() {
+            return Err(TokenError::InvalidClaim(
+                "missing back-channel logout event".to_string(),
+            ));
+        }
+
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logout_token_roundtrip() {
+        let key = SigningKey::generate_ml_dsa_65();
+        let jwks = JwkSet::new(vec![key.public_jwk()]);
+        let claims = LogoutTokenClaims::new(
+            "https://fantasma.example",
+            "rp",
+            Some("zkid:abc".to_string()),
+            Some("sid-1".to_string()),
+            120,
+        );
+        let token = LogoutToken::create(&claims, &key).unwrap();
+
+        let header = jws::decode_header(&token).unwrap();
+        assert_eq!(header.typ.as_deref(), Some(LOGOUT_TOKEN_TYPE));
+
+        let verified =
+            LogoutToken::verify(&token, &jwks, "https://fantasma.example", "rp").unwrap();
+        assert_eq!(verified.sid.as_deref(), Some("sid-1"));
+
+        assert!(matches!(
+            LogoutToken::verify(&token, &jwks, "https://fantasma.example", "other"),
+            Err(TokenError::InvalidAudience)
+        ));
+    }
+}
@@ -0,0 +1,413 @@
+//! OpenID for Verifiable Credential Issuance (OpenID4VCI)
+//!
+//! Fantasma fronts `fantasma-issuer` as a credential issuer using the
+//! pre-authorized code flow: an issuing partner creates a credential offer,
+//! the wallet redeems its pre-authorized code at the token endpoint, and
+//! then requests the credential with a proof of possession of its holder
+//! key. Credentials are issued in the `fantasma_credential` format, a
+//! Dilithium-signed [`Credential`](fantasma_core::credential::Credential)
+//! bound to that key.
+
+use crate::client_auth::Audience;
+use crate::jws;
+use crate::signing::JwsAlgorithm;
+use chrono::Utc;
+use fantasma_core::credential::{Credential, HolderBinding, SchemaId, SignatureAlgorithm};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Credential format identifier for Fantasma credentials
+pub const CREDENTIAL_FORMAT: &str = "fantasma_credential";
+
+/// Grant type for redeeming a pre-authorized code (OpenID4VCI §4.1.1)
+pub const PRE_AUTHORIZED_CODE_GRANT_TYPE: &str =
+    "urn:ietf:params:oauth:grant-type:pre-authorized_code";
+
+/// `typ` header value for key proof JWTs
+pub const PROOF_JWT_TYPE: &str = "openid4vci-proof+jwt";
+
+/// Scheme wallets register for credential offers
+pub const CREDENTIAL_OFFER_SCHEME: &str = "openid-credential-offer://";
+
+/// Credential configurations, by the schema of the credentials they issue
+pub const CREDENTIAL_CONFIGURATIONS: [(&str, &str); 5] = [
+    ("fantasma_identity_v1", SchemaId::IDENTITY_V1),
+    ("fantasma_degree_v1", SchemaId::DEGREE_V1),
+    ("fantasma_license_v1", SchemaId::LICENSE_V1),
+    ("fantasma_membership_v1", SchemaId::MEMBERSHIP_V1),
+    ("fantasma_kyc_v1", SchemaId::KYC_V1),
+];
+
+/// How old a key proof may be
+const PROOF_MAX_AGE_SECONDS: i64 = 300;
+
+/// Clock skew tolerated when checking a key proof's `iat`
+const CLOCK_SKEW_SECONDS: i64 = 60;
+
+/// The credential configuration issuing credentials of `schema`
+pub fn credential_configuration_id(schema: &str) -> Option<&'static str> {
+    CREDENTIAL_CONFIGURATIONS
+        .iter()
+        .find(|(_, s)| *s == schema)
+        .map(|(id, _)| *id)
+}
+
+/// Credential issuer metadata (OpenID4VCI §11.2), served from
+/// `/.well-known/openid-credential-issuer`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialIssuerMetadata {
+    pub credential_issuer: String,
+
+    /// Token endpoints live with the provider, which is the issuer itself
+    pub authorization_servers: Vec<String>,
+
+    pub credential_endpoint: String,
+
+    pub credential_configurations_supported: BTreeMap<String, CredentialConfiguration>,
+
+    /// Issuer identifier credentials carry in their `issuer` field
+    pub fantasma_issuer_id: String,
+
+    /// Public key credential signatures verify against (base64url): a
+    /// Dilithium key, or for hybrid signatures the Ed25519 key followed by
+    /// the ML-DSA-65 key
+    pub fantasma_issuer_public_key: String,
+}
+
+/// A kind of credential the issuer offers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialConfiguration {
+    pub format: String,
+
+    /// Schema of the issued credentials
+    pub schema: String,
+
+    pub cryptographic_binding_methods_supported: Vec<String>,
+
+    pub credential_signing_alg_values_supported: Vec<String>,
+
+    pub proof_types_supported: BTreeMap<String, ProofTypeMetadata>,
+}
+
+/// Key proofs accepted for a proof type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofTypeMetadata {
+    pub proof_signing_alg_values_supported: Vec<String>,
+}
+
+impl CredentialIssuerMetadata {
+    /// Metadata for an issuer supporting `schemas`
+    pub fn new(
+        credential_issuer: &str,
+        credential_endpoint: String,
+        issuer_id: &str,
+        issuer_public_key: &[u8],
+        signature_algorithm: SignatureAlgorithm,
+        schemas: &[String],
+    ) -> Self {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+        let proof_types = BTreeMap::from([(
+            "jwt".to_string(),
+            ProofTypeMetadata {
+                proof_signing_alg_values_supported: [JwsAlgorithm::EdDSA, JwsAlgorithm::MlDsa65]
+                    .iter()
+                    .map(|alg| alg.as_str().to_string())
+                    .collect(),
+            },
+        )]);
+        let credential_configurations_supported = schemas
+            .iter()
+            .filter_map(|schema| {
+                let id = credential_configuration_id(schema)?;
+                Some((
+                    id.to_string(),
+                    CredentialConfiguration {
+                        format: CREDENTIAL_FORMAT.to_string(),
+                        schema: schema.clone(),
+                        cryptographic_binding_methods_supported: vec!["jwk".to_string()],
+                        credential_signing_alg_values_supported: vec![signature_algorithm
+                            .as_str()
+                            .to_string()],
+                        proof_types_supported: proof_types.clone(),
+                    },
+                ))
+            })
+            .collect();
+
+        Self {
+            credential_issuer: credential_issuer.to_string(),
+            authorization_servers: vec![credential_issuer.to_string()],
+            credential_endpoint,
+            credential_configurations_supported,
+            fantasma_issuer_id: issuer_id.to_string(),
+            fantasma_issuer_public_key: URL_SAFE_NO_PAD.encode(issuer_public_key),
+        }
+    }
+}
+
+/// Credential offer (OpenID4VCI §4.1.1)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialOffer {
+    pub credential_issuer: String,
+    pub credential_configuration_ids: Vec<String>,
+    pub grants: CredentialOfferGrants,
+}
+
+/// Grants a credential offer can be redeemed with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialOfferGrants {
+    #[serde(rename = "urn:ietf:params:oauth:grant-type:pre-authorized_code")]
+    pub pre_authorized_code: PreAuthorizedCodeGrant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreAuthorizedCodeGrant {
+    #[serde(rename = "pre-authorized_code")]
+    pub pre_authorized_code: String,
+}
+
+impl CredentialOffer {
+    /// Offer of one credential, redeemable with `pre_authorized_code`
+    pub fn new(
+        credential_issuer: &str,
+        credential_configuration_id: &str,
+        pre_authorized_code: String,
+    ) -> Self {
+        Self {
+            credential_issuer: credential_issuer.to_string(),
+            credential_configuration_ids: vec![credential_configuration_id.to_string()],
+            grants: CredentialOfferGrants {
+                pre_authorized_code: PreAuthorizedCodeGrant {
+                    pre_authorized_code,
+                },
+            },
+        }
+    }
+
+    /// The offer passed by value, for a QR code or deep link
+    pub fn to_uri(&self) -> String {
+        let offer = serde_json::to_string(self).unwrap_or_default();
+        format!(
+            "{}?{}",
+            CREDENTIAL_OFFER_SCHEME,
+            url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("credential_offer", &offer)
+                .finish()
+        )
+    }
+}
+
+/// Token response for the pre-authorized code grant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialTokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,
+    /// Nonce the wallet's key proof must carry
+    pub c_nonce: String,
+    pub c_nonce_expires_in: u64,
+}
+
+/// Credential request (OpenID4VCI §7.2)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_configuration_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<KeyProof>,
+}
+
+/// Proof of possession of the holder key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyProof {
+    pub proof_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwt: Option<String>,
+}
+
+/// Claims of a key proof JWT
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofJwtClaims {
+    /// The wallet's client_id, if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    /// Audience - the credential issuer identifier
+    pub aud: Audience,
+    pub iat: i64,
+    /// The `c_nonce` from the issuer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+/// Credential response (OpenID4VCI §7.3)
+///
+/// An offer is for one credential, so no further `c_nonce` is handed out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialResponse {
+    pub credential: Credential,
+}
+
+/// Why a key proof was not accepted
+#[derive(Debug, Error)]
+pub enum KeyProofError {
+    #[error("unsupported proof type: {0}")]
+    UnsupportedProofType(String),
+
+    #[error("malformed key proof: {0}")]
+    Malformed(String),
+
+    #[error("key proof is meant for another credential issuer")]
+    WrongAudience,
+
+    #[error("key proof is too old or from the future")]
+    Stale,
+
+    #[error("key proof does not carry the current c_nonce")]
+    NonceMismatch,
+}
+
+/// Verify a key proof and return the holder binding for the proven key
+///
+/// The JWT must be signed with the key in its `jwk` header, be addressed to
+/// `credential_issuer`, be recent, and carry `c_nonce`.
+pub fn verify_key_proof(
+    proof: &KeyProof,
+    credential_issuer: &str,
+    c_nonce: &str,
+) -> Result<HolderBinding, KeyProofError> {
+    if proof.proof_type != "jwt" {
+        return Err(KeyProofError::UnsupportedProofType(
+            proof.proof_type.clone(),
+        ));
+    }
+    let jwt = proof
+        .jwt
+        .as_deref()
+        .ok_or_else(|| KeyProofError::Malformed("jwt is required".to_string()))?;
+
+    let (header, jwk, claims): (_, _, ProofJwtClaims) =
+        jws::verify_self_signed(jwt).map_err(|e| KeyProofError::Malformed(e.to_string()))?;
+    if header.typ.as_deref() != Some(PROOF_JWT_TYPE) {
+        return Err(KeyProofError::Malformed(format!(
+            "typ must be {}",
+            PROOF_JWT_TYPE
+        )));
+    }
+    if !claims.aud.contains(credential_issuer) {
+        return Err(KeyProofError::WrongAudience);
+    }
+    let now = Utc::now().timestamp();
+    if claims.iat < now - PROOF_MAX_AGE_SECONDS || claims.iat > now + CLOCK_SKEW_SECONDS {
+        return Err(KeyProofError::Stale);
+    }
+    if claims.nonce.as_deref() != Some(c_nonce) {
+        return Err(KeyProofError::NonceMismatch);
+    }
+
+    Ok(HolderBinding {
+        jkt: jwk.thumbprint(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jws::JwsHeader;
+    use crate::signing::SigningKey;
+
+    const ISSUER: &str = "https://fantasma.example";
+
+    fn key_proof(key: &SigningKey, aud: &str, nonce: &str, typ: &str) -> KeyProof {
+        let header = JwsHeader {
+            alg: key.algorithm().as_str().to_string(),
+            typ: Some(typ.to_string()),
+            kid: None,
+            jwk: Some(key.public_jwk()),
+            pq_signature: None,
+        };
+        let claims = ProofJwtClaims {
+            iss: None,
+            aud: Audience::Single(aud.to_string()),
+            iat: Utc::now().timestamp(),
+            nonce: Some(nonce.to_string()),
+        };
+        KeyProof {
+            proof_type: "jwt".to_string(),
+            jwt: Some(jws::encode_with_header(&claims, &header, key).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_verify_key_proof() {
+        let key = SigningKey::generate_ml_dsa_65();
+        let proof = key_proof(&key, ISSUER, "c-nonce", PROOF_JWT_TYPE);
+
+        let holder = verify_key_proof(&proof, ISSUER, "c-nonce").unwrap();
+        assert_eq!(holder.jkt, key.public_jwk().thumbprint());
+
+        assert!(matches!(
+            verify_key_proof(&proof, ISSUER, "newer-nonce"),
+            Err(KeyProofError::NonceMismatch)
+        ));
+        assert!(matches!(
+            verify_key_proof(&proof, "https://elsewhere.example", "c-nonce"),
+            Err(KeyProofError::WrongAudience)
+        ));
+
+        let proof = key_proof(&key, ISSUER, "c-nonce", "JWT");
+        assert!(matches!(
+            verify_key_proof(&proof, ISSUER, "c-nonce"),
+            Err(KeyProofError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_proof_signed_by_another_key() {
+        let holder = SigningKey::generate_ed25519();
+        let other = SigningKey::generate_ed25519();
+        let mut proof = key_proof(&other, ISSUER, "c-nonce", PROOF_JWT_TYPE);
+
+        // Swap in the holder's key as the claimed signer
+        let jwt = proof.jwt.unwrap();
+        let forged_header = JwsHeader {
+            alg: "EdDSA".to_string(),
+            typ: Some(PROOF_JWT_TYPE.to_string()),
+            kid: None,
+            jwk: Some(holder.public_jwk()),
+            pq_signature: None,
+        };
+        let (_, rest) = jwt.split_once('.').unwrap();
+        proof.jwt = Some(format!(
+            "{}.{}",
+            base64::Engine::encode(
+                &base64::engine::general_purpose::URL_SAFE_NO_PAD,
+                serde_json::to_vec(&forged_header).unwrap()
+            ),
+            rest
+        ));
+
+        assert!(verify_key_proof(&proof, ISSUER, "c-nonce").is_err());
+    }
+
+    #[test]
+    fn test_credential_offer_uri() {
+        let offer = CredentialOffer::new(ISSUER, "fantasma_kyc_v1", "code-123".to_string());
+        let uri = offer.to_uri();
+        assert!(uri.starts_with("openid-credential-offer://?credential_offer="));
+
+        let (_, query) = uri.split_once('?').unwrap();
+        let (_, json) = url::form_urlencoded::parse(query.as_bytes())
+            .next()
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["credential_configuration_ids"][0], "fantasma_kyc_v1");
+        assert_eq!(
+            parsed["grants"][PRE_AUTHORIZED_CODE_GRANT_TYPE]["pre-authorized_code"],
+            "code-123"
+        );
+    }
+}
@@ -0,0 +1,379 @@
+//! OpenID for Verifiable Presentations (OpenID4VP)
+//!
+//! Fantasma acts as verifier for third-party wallets carrying Fantasma STARK
+//! proofs. The authorization request holds a DCQL query with one credential
+//! query per requested ZK claim, and the wallet posts its `vp_token` to the
+//! response URI (`direct_post`). Each presentation is a
+//! [`StarkPresentation`], base64url-encoded JSON.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use fantasma_core::claim::{ClaimRequest, ClaimType};
+use fantasma_core::proof::GeneratedProof;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Credential format identifier for Fantasma STARK proofs
+pub const VP_FORMAT: &str = "fantasma_stark";
+
+/// Scheme wallets register for OpenID4VP authorization requests
+pub const AUTHORIZATION_ENDPOINT: &str = "openid4vp://";
+
+/// Client identifier prefix for unsigned requests answered at the response URI
+pub const REDIRECT_URI_CLIENT_ID_PREFIX: &str = "redirect_uri:";
+
+/// Errors in a wallet's `vp_token`
+#[derive(Debug, Error)]
+pub enum PresentationError {
+    #[error("malformed vp_token: {0}")]
+    MalformedToken(String),
+
+    #[error("malformed presentation for {0}")]
+    MalformedPresentation(String),
+
+    #[error("presentation for an unknown credential query: {0}")]
+    UnknownQuery(String),
+
+    #[error("presentation for {0} proves another claim")]
+    ClaimMismatch(String),
+
+    #[error("presentations are made out to different subjects")]
+    SubjectMismatch,
+
+    #[error("vp_token holds no presentation")]
+    Empty,
+}
+
+/// DCQL query (OpenID4VP §6)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DcqlQuery {
+    pub credentials: Vec<CredentialQuery>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub credential_sets: Vec<CredentialSetQuery>,
+}
+
+/// A query for one proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialQuery {
+    pub id: String,
+    pub format: String,
+    pub meta: CredentialQueryMeta,
+}
+
+/// Format-specific part of a credential query: the claim to prove
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialQueryMeta {
+    pub circuit_id: String,
+    /// The claim as a scope value, e.g. `zk:age:21+`
+    pub claim: String,
+}
+
+/// Which credential queries must be answered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialSetQuery {
+    pub options: Vec<Vec<String>>,
+    pub required: bool,
+}
+
+impl DcqlQuery {
+    /// Query proofs of the requested claims
+    ///
+    /// Every claim is a credential set of its own, required when the claim
+    /// is; a claim requested twice is required if either request is.
+    pub fn from_claim_requests(requests: &[ClaimRequest]) -> Self {
+        let mut query = Self {
+            credentials: Vec::new(),
+            credential_sets: Vec::new(),
+        };
+
+        for request in requests {
+            let id = credential_query_id(&request.claim_type);
+            if let Some(set) = query
+                .credential_sets
+                .iter_mut()
+                .find(|set| set.options[0][0] == id)
+            {
+                set.required |= request.required;
+                continue;
+            }
+
+            query.credentials.push(CredentialQuery {
+                id: id.clone(),
+                format: VP_FORMAT.to_string(),
+                meta: CredentialQueryMeta {
+                    circuit_id: request.claim_type.circuit_id().to_string(),
+                    claim: request.claim_type.to_scope(),
+                },
+            });
+            query.credential_sets.push(CredentialSetQuery {
+                options: vec![vec![id]],
+                required: request.required,
+            });
+        }
+
+        query
+    }
+}
+
+/// Credential query identifier for a claim, e.g. `zk_age_21` for `zk:age:21+`
+pub fn credential_query_id(claim_type: &ClaimType) -> String {
+    claim_type
+        .to_scope()
+        .chars()
+        .filter(|c| *c != '+')
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// OpenID4VP authorization request, passed to the wallet by value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizationRequest {
+    pub client_id: String,
+    pub response_type: String,
+    pub response_mode: String,
+    pub response_uri: String,
+    pub nonce: String,
+    pub state: String,
+    pub dcql_query: DcqlQuery,
+    /// Sector the proofs and the wallet's pairwise subject are bound to
+    pub verifier_domain: String,
+}
+
+impl AuthorizationRequest {
+    /// Request answered by `direct_post` to `response_uri`
+    pub fn new(
+        response_uri: &str,
+        nonce: &str,
+        state: &str,
+        dcql_query: DcqlQuery,
+        verifier_domain: &str,
+    ) -> Self {
+        Self {
+            client_id: format!("{}{}", REDIRECT_URI_CLIENT_ID_PREFIX, response_uri),
+            response_type: "vp_token".to_string(),
+            response_mode: "direct_post".to_string(),
+            response_uri: response_uri.to_string(),
+            nonce: nonce.to_string(),
+            state: state.to_string(),
+            dcql_query,
+            verifier_domain: verifier_domain.to_string(),
+        }
+    }
+
+    /// The request as a URL to open in the wallet
+    pub fn to_url(&self) -> String {
+        let dcql_query = serde_json::to_string(&self.dcql_query).unwrap_or_default();
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("client_id", &self.client_id)
+            .append_pair("response_type", &self.response_type)
+            .append_pair("response_mode", &self.response_mode)
+            .append_pair("response_uri", &self.response_uri)
+            .append_pair("nonce", &self.nonce)
+            .append_pair("state", &self.state)
+            .append_pair("dcql_query", &dcql_query)
+            .append_pair("verifier_domain", &self.verifier_domain)
+            .finish();
+        format!("{}?{}", AUTHORIZATION_ENDPOINT, query)
+    }
+}
+
+/// Verifier response to a `direct_post` (OpenID4VP §8.2)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectPostResponse {
+    /// Where the wallet sends the user next
+    pub redirect_uri: String,
+}
+
+/// A presented proof, with the pairwise subject the wallet presents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StarkPresentation {
+    pub sub: String,
+    pub proof: GeneratedProof,
+}
+
+impl StarkPresentation {
+    /// Encode for a `vp_token`
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(query_id: &str, presentation: &str) -> Result<Self, PresentationError> {
+        URL_SAFE_NO_PAD
+            .decode(presentation)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| PresentationError::MalformedPresentation(query_id.to_string()))
+    }
+}
+
+/// Decode a `vp_token` answering `query`
+///
+/// The token maps credential query IDs to presentations. Returns the
+/// subject all presentations are made out to, and the presented proofs.
+pub fn parse_vp_token(
+    vp_token: &str,
+    query: &DcqlQuery,
+) -> Result<(String, Vec<GeneratedProof>), PresentationError> {
+    let token: HashMap<String, serde_json::Value> = serde_json::from_str(vp_token)
+        .map_err(|e| PresentationError::MalformedToken(e.to_string()))?;
+
+    let mut subject: Option<String> = None;
+    let mut proofs = Vec::new();
+    for (query_id, value) in token {
+        let credential = query
+            .credentials
+            .iter()
+            .find(|c| c.id == query_id)
+            .ok_or_else(|| PresentationError::UnknownQuery(query_id.clone()))?;
+
+        // A single presentation may be sent bare instead of in an array
+        let presentations = match value {
+            serde_json::Value::String(s) => vec![s],
+            value => serde_json::from_value::<Vec<String>>(value)
+                .map_err(|_| PresentationError::MalformedPresentation(query_id.clone()))?,
+        };
+        for presentation in presentations {
+            let presentation = StarkPresentation::decode(&query_id, &presentation)?;
+            if presentation.proof.claim_type.to_scope() != credential.meta.claim {
+                return Err(PresentationError::ClaimMismatch(query_id));
+            }
+            match subject {
+                Some(ref sub) if *sub != presentation.sub => {
+                    return Err(PresentationError::SubjectMismatch)
+                }
+                Some(_) => {}
+                None => subject = Some(presentation.sub),
+            }
+            proofs.push(presentation.proof);
+        }
+    }
+
+    subject
+        .map(|sub| (sub, proofs))
+        .ok_or(PresentationError::Empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fantasma_core::claim::KycLevel;
+
+    fn request(claim_type: ClaimType, required: bool) -> ClaimRequest {
+        ClaimRequest {
+            claim_type,
+            required,
+            reason: None,
+        }
+    }
+
+    fn presentation(sub: &str, claim_type: ClaimType) -> String {
+        StarkPresentation {
+            sub: sub.to_string(),
+            proof: GeneratedProof {
+                circuit_id: claim_type.circuit_id().to_string(),
+                claim_type,
+                proof_bytes: vec![1, 2, 3],
+                public_inputs: vec![],
+                nullifier: [0u8; 32],
+                generated_at: chrono::Utc::now(),
+            },
+        }
+        .encode()
+    }
+
+    #[test]
+    fn test_dcql_query_from_claims() {
+        let query = DcqlQuery::from_claim_requests(&[
+            request(ClaimType::AgeAtLeast { threshold: 21 }, false),
+            request(
+                ClaimType::KycStatus {
+                    provider: "*".to_string(),
+                    level: KycLevel::Basic,
+                    max_age_seconds: None,
+                },
+                false,
+            ),
+            request(ClaimType::AgeAtLeast { threshold: 21 }, true),
+        ]);
+
+        assert_eq!(query.credentials.len(), 2);
+        assert_eq!(query.credentials[0].id, "zk_age_21");
+        assert_eq!(query.credentials[0].format, VP_FORMAT);
+        assert_eq!(query.credentials[0].meta.circuit_id, "age_verification_v1");
+        assert_eq!(query.credentials[1].id, "zk_kyc_basic");
+        assert!(query.credential_sets[0].required);
+        assert!(!query.credential_sets[1].required);
+    }
+
+    #[test]
+    fn test_authorization_request_url() {
+        let query = DcqlQuery::from_claim_requests(&[request(
+            ClaimType::AgeAtLeast { threshold: 18 },
+            true,
+        )]);
+        let request = AuthorizationRequest::new(
+            "https://op.example.com/openid4vp/response",
+            "n-0S6_WzA2Mj",
+            "af0ifjsldkj",
+            query.clone(),
+            "rp.example.com",
+        );
+
+        let url = request.to_url();
+        let (scheme, params) = url.split_once('?').unwrap();
+        assert_eq!(scheme, "openid4vp://");
+        let params: HashMap<String, String> = url::form_urlencoded::parse(params.as_bytes())
+            .into_owned()
+            .collect();
+        assert_eq!(
+            params["client_id"],
+            "redirect_uri:https://op.example.com/openid4vp/response"
+        );
+        assert_eq!(params["response_mode"], "direct_post");
+        let parsed: DcqlQuery = serde_json::from_str(&params["dcql_query"]).unwrap();
+        assert_eq!(parsed, query);
+    }
+
+    #[test]
+    fn test_parse_vp_token() {
+        let query = DcqlQuery::from_claim_requests(&[request(
+            ClaimType::AgeAtLeast { threshold: 18 },
+            true,
+        )]);
+        let age = ClaimType::AgeAtLeast { threshold: 18 };
+
+        let token = serde_json::json!({"zk_age_18": [presentation("zkid:a", age.clone())]});
+        let (sub, proofs) = parse_vp_token(&token.to_string(), &query).unwrap();
+        assert_eq!(sub, "zkid:a");
+        assert_eq!(proofs.len(), 1);
+
+        // Bare presentations are accepted too
+        let token = serde_json::json!({"zk_age_18": presentation("zkid:a", age.clone())});
+        assert!(parse_vp_token(&token.to_string(), &query).is_ok());
+
+        let token = serde_json::json!({"zk_age_21": [presentation("zkid:a", age)]});
+        assert!(matches!(
+            parse_vp_token(&token.to_string(), &query),
+            Err(PresentationError::UnknownQuery(_))
+        ));
+
+        let token = serde_json::json!({
+            "zk_age_18": [presentation("zkid:a", ClaimType::AgeAtLeast { threshold: 21 })]
+        });
+        assert!(matches!(
+            parse_vp_token(&token.to_string(), &query),
+            Err(PresentationError::ClaimMismatch(_))
+        ));
+
+        assert!(matches!(
+            parse_vp_token("{}", &query),
+            Err(PresentationError::Empty)
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+//! Proof Key for Code Exchange (RFC 7636)
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// PKCE errors
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PkceError {
+    #[error("unsupported code_challenge_method: {0}")]
+    UnsupportedMethod(String),
+
+    #[error("code_challenge_method plain is not allowed for this client")]
+    PlainNotAllowed,
+
+    #[error("code_challenge is required for this client")]
+    ChallengeRequired,
+
+    #[error("malformed {0}")]
+    Malformed(&'static str),
+
+    #[error("code_verifier is required")]
+    VerifierRequired,
+
+    #[error("code_verifier does not match code_challenge")]
+    VerifierMismatch,
+}
+
+/// Code challenge transformation method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PkceMethod {
+    #[serde(rename = "S256")]
+    S256,
+    #[serde(rename = "plain")]
+    Plain,
+}
+
+impl PkceMethod {
+    /// The `code_challenge_method` parameter value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::S256 => "S256",
+            Self::Plain => "plain",
+        }
+    }
+
+    /// Parse a `code_challenge_method` parameter value
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "S256" => Some(Self::S256),
+            "plain" => Some(Self::Plain),
+            _ => None,
+        }
+    }
+}
+
+/// A code challenge bound to an authorization code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PkceChallenge {
+    pub challenge: String,
+    pub method: PkceMethod,
+}
+
+impl PkceChallenge {
+    /// Validate the `code_challenge`/`code_challenge_method` pair of an
+    /// authorization request against the client's PKCE policy
+    ///
+    /// An absent method defaults to `plain` (RFC 7636 §4.3). Returns `None`
+    /// when the request carries no challenge and the client does not need one.
+    pub fn from_request(
+        challenge: Option<&str>,
+        method: Option<&str>,
+        policy: PkcePolicy,
+    ) -> Result<Option<Self>, PkceError> {
+        let Some(challenge) = challenge else {
+            if policy.required {
+                return Err(PkceError::ChallengeRequired);
+            }
+            return Ok(None);
+        };
+
+        let method = match method {
+            Some(m) => {
+                PkceMethod::parse(m).ok_or_else(|| PkceError::UnsupportedMethod(m.into()))?
+            }
+            None => PkceMethod::Plain,
+        };
+
+        if method == PkceMethod::Plain && policy.s256_only {
+            return Err(PkceError::PlainNotAllowed);
+        }
+
+        if !is_valid_key(challenge) {
+            return Err(PkceError::Malformed("code_challenge"));
+        }
+
+        Ok(Some(Self {
+            challenge: challenge.to_string(),
+            method,
+        }))
+    }
+
+    /// Check a `code_verifier` presented at the token endpoint
+    pub fn verify(&self, verifier: Option<&str>) -> Result<(), PkceError> {
+        let verifier = verifier.ok_or(PkceError::VerifierRequired)?;
+
+        if !is_valid_key(verifier) {
+            return Err(PkceError::Malformed("code_verifier"));
+        }
+
+        let derived = match self.method {
+            PkceMethod::S256 => s256_challenge(verifier),
+            PkceMethod::Plain => verifier.to_string(),
+        };
+
+        if constant_time_eq(derived.as_bytes(), self.challenge.as_bytes()) {
+            Ok(())
+        } else {
+            Err(PkceError::VerifierMismatch)
+        }
+    }
+}
+
+/// Per-client PKCE requirements
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PkcePolicy {
+    /// Authorization requests must carry a code challenge
+    pub required: bool,
+    /// Only the S256 method is accepted
+    pub s256_only: bool,
+}
+
+/// Compute the S256 code challenge for a verifier
+pub fn s256_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Verifiers (and plain challenges) are 43–128 unreserved characters
+fn is_valid_key(s: &str) -> bool {
+    (43..=128).contains(&s.len())
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~'))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vector from RFC 7636 Appendix B
+    const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    #[test]
+    fn test_s256_verification() {
+        assert_eq!(s256_challenge(VERIFIER), CHALLENGE);
+
+        let pkce =
+            PkceChallenge::from_request(Some(CHALLENGE), Some("S256"), PkcePolicy::default())
+                .unwrap()
+                .unwrap();
+        assert!(pkce.verify(Some(VERIFIER)).is_ok());
+        assert_eq!(
+            pkce.verify(Some(&VERIFIER.replace('d', "e"))),
+            Err(PkceError::VerifierMismatch)
+        );
+        assert_eq!(pkce.verify(None), Err(PkceError::VerifierRequired));
+    }
+
+    #[test]
+    fn test_plain_verification_and_policy() {
+        let pkce = PkceChallenge::from_request(Some(VERIFIER), None, PkcePolicy::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(pkce.method, PkceMethod::Plain);
+        assert!(pkce.verify(Some(VERIFIER)).is_ok());
+
+        let strict = PkcePolicy {
+            required: true,
+            s256_only: true,
+        };
+        assert_eq!(
+            PkceChallenge::from_request(Some(VERIFIER), Some("plain"), strict),
+            Err(PkceError::PlainNotAllowed)
+        );
+        assert_eq!(
+            PkceChallenge::from_request(None, None, strict),
+            Err(PkceError::ChallengeRequired)
+        );
+        assert_eq!(
+            PkceChallenge::from_request(Some(CHALLENGE), Some("S512"), strict),
+            Err(PkceError::UnsupportedMethod("S512".into()))
+        );
+        assert_eq!(
+            PkceChallenge::from_request(Some("short"), Some("S256"), strict),
+            Err(PkceError::Malformed("code_challenge"))
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! Provenance of ZK claims, and the authentication context classes built on it
+//!
+//! Every verified claim records how it was established: the verification
+//! method and backend, the circuit and proof hashes, when it was verified and
+//! how trusted the credential's issuer is. A token's `acr` is the weakest
+//! provenance among its verified claims, written
+//! `urn:fantasma:acr:<method>[:<issuer trust>]`. Relying parties demand a
+//! minimum with `acr_values`, e.g. `urn:fantasma:acr:stark:government` for
+//! STARK-verified claims from a government issuer.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Prefix of the `acr` values the provider issues
+pub const ACR_PREFIX: &str = "urn:fantasma:acr:";
+
+/// How a ZK claim was established, weakest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationMethod {
+    /// Asserted from the demo-user table, without a proof
+    Demo,
+    /// Proof checked by the mock prover backend
+    Mock,
+    /// STARK proof verified by a real prover backend
+    Stark,
+}
+
+impl VerificationMethod {
+    /// Every method, weakest first
+    pub const ALL: [Self; 3] = [Self::Demo, Self::Mock, Self::Stark];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Demo => "demo",
+            Self::Mock => "mock",
+            Self::Stark => "stark",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.as_str() == s)
+    }
+
+    /// The method proofs checked by the named prover backend attain; this is
+    /// the backend that verified the proof, not necessarily the configured one
+    pub fn of_backend(backend: &str) -> Self {
+        match backend {
+            "mock" => Self::Mock,
+            _ => Self::Stark,
+        }
+    }
+}
+
+/// Trust tier of a credential issuer, weakest first
+///
+/// The tiers are those of `TrustAnchor`, each reached from its trust level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssuerTrust {
+    SelfDeclared,
+    TrustFramework,
+    Accredited,
+    Government,
+}
+
+impl IssuerTrust {
+    /// Every tier, weakest first
+    pub const ALL: [Self; 4] = [
+        Self::SelfDeclared,
+        Self::TrustFramework,
+        Self::Accredited,
+        Self::Government,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SelfDeclared => "self_declared",
+            Self::TrustFramework => "trust_framework",
+            Self::Accredited => "accredited",
+            Self::Government => "government",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == s)
+    }
+
+    /// The lowest trust level in the tier
+    pub fn min_level(&self) -> u8 {
+        match self {
+            Self::SelfDeclared => 20,
+            Self::TrustFramework => 60,
+            Self::Accredited => 80,
+            Self::Government => 100,
+        }
+    }
+
+    /// The highest tier a trust level reaches, if any
+    pub fn from_level(level: u8) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .rev()
+            .find(|tier| level >= tier.min_level())
+    }
+}
+
+/// How a ZK claim was established
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimProvenance {
+    /// Verification method
+    pub method: VerificationMethod,
+
+    /// Backend that verified the proof (`stone`, `mock`), or `demo`
+    pub verifier_backend: String,
+
+    /// Bytecode hash of the circuit, as published in discovery
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_hash: Option<String>,
+
+    /// SHA3-256 of the proof bytes, `0x`-prefixed hex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof_hash: Option<String>,
+
+    /// When the claim was verified (seconds since the epoch)
+    pub verified_at: i64,
+
+    /// Trust level of the credential's issuer (see `TrustAnchor::trust_level`),
+    /// 0 if the issuer is not recognized
+    pub issuer_trust_level: u8,
+}
+
+impl ClaimProvenance {
+    /// Provenance of a claim asserted for a demo user, whose credentials come
+    /// from an issuer with `issuer_trust_level`
+    pub fn demo(issuer_trust_level: u8) -> Self {
+        Self {
+            method: VerificationMethod::Demo,
+            verifier_backend: VerificationMethod::Demo.as_str().to_string(),
+            circuit_hash: None,
+            proof_hash: None,
+            verified_at: chrono::Utc::now().timestamp(),
+            issuer_trust_level,
+        }
+    }
+}
+
+/// An authentication context class: a verification method and issuer trust
+/// tier, each a minimum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthContextClass {
+    pub method: VerificationMethod,
+    pub issuer_trust: Option<IssuerTrust>,
+}
+
+impl AuthContextClass {
+    /// The class a claim's provenance attains
+    pub fn of(provenance: &ClaimProvenance) -> Self {
+        Self {
+            method: provenance.method,
+            issuer_trust: IssuerTrust::from_level(provenance.issuer_trust_level),
+        }
+    }
+
+    /// Parse an `acr` value
+    pub fn parse(acr: &str) -> Option<Self> {
+        let rest = acr.strip_prefix(ACR_PREFIX)?;
+        let (method, issuer_trust) = match rest.split_once(':') {
+            Some((method, trust)) => (method, Some(IssuerTrust::parse(trust)?)),
+            None => (rest, None),
+        };
+        Some(Self {
+            method: VerificationMethod::parse(method)?,
+            issuer_trust,
+        })
+    }
+
+    /// Every class, as advertised in `acr_values_supported`
+    pub fn all() -> Vec<Self> {
+        VerificationMethod::ALL
+            .into_iter()
+            .flat_map(|method| {
+                std::iter::once(None)
+                    .chain(IssuerTrust::ALL.into_iter().map(Some))
+                    .map(move |issuer_trust| Self {
+                        method,
+                        issuer_trust,
+                    })
+            })
+            .collect()
+    }
+
+    /// Whether this class is at least as strong as `required`
+    pub fn satisfies(&self, required: &Self) -> bool {
+        self.method >= required.method && self.issuer_trust >= required.issuer_trust
+    }
+
+    /// The weaker of two classes, component by component
+    pub fn weakest(self, other: Self) -> Self {
+        Self {
+            method: self.method.min(other.method),
+            issuer_trust: self.issuer_trust.min(other.issuer_trust),
+        }
+    }
+}
+
+impl fmt::Display for AuthContextClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", ACR_PREFIX, self.method.as_str())?;
+        if let Some(trust) = self.issuer_trust {
+            write!(f, ":{}", trust.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the space-separated `acr_values` request parameter
+pub fn parse_acr_values(acr_values: &str) -> Result<Vec<AuthContextClass>, String> {
+    acr_values
+        .split_whitespace()
+        .map(|acr| {
+            AuthContextClass::parse(acr).ok_or_else(|| format!("unsupported acr value: {}", acr))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acr_roundtrip() {
+        for class in AuthContextClass::all() {
+            assert_eq!(AuthContextClass::parse(&class.to_string()), Some(class));
+        }
+        assert_eq!(AuthContextClass::all().len(), 15);
+        assert!(AuthContextClass::parse("urn:fantasma:acr:stark:royal").is_none());
+        assert!(AuthContextClass::parse("stark").is_none());
+    }
+
+    #[test]
+    fn test_satisfies() {
+        let required = AuthContextClass::parse("urn:fantasma:acr:stark:government").unwrap();
+        let provenance = ClaimProvenance {
+            method: VerificationMethod::Stark,
+            verifier_backend: "stone".to_string(),
+            circuit_hash: None,
+            proof_hash: None,
+            verified_at: 0,
+            issuer_trust_level: 100,
+        };
+        assert!(AuthContextClass::of(&provenance).satisfies(&required));
+
+        // A demo claim from a government issuer is not STARK-verified
+        let demo = AuthContextClass::of(&ClaimProvenance::demo(100));
+        assert!(!demo.satisfies(&required));
+        assert!(
+            demo.satisfies(&AuthContextClass::parse("urn:fantasma:acr:demo:accredited").unwrap())
+        );
+
+        let accredited = AuthContextClass::of(&ClaimProvenance {
+            issuer_trust_level: 80,
+            ..provenance
+        });
+        assert!(!accredited.satisfies(&required));
+        assert_eq!(
+            accredited.weakest(demo).to_string(),
+            "urn:fantasma:acr:demo:accredited"
+        );
+    }
+
+    #[test]
+    fn test_issuer_trust_from_level() {
+        assert_eq!(IssuerTrust::from_level(100), Some(IssuerTrust::Government));
+        assert_eq!(
+            IssuerTrust::from_level(70),
+            Some(IssuerTrust::TrustFramework)
+        );
+        assert_eq!(IssuerTrust::from_level(0), None);
+    }
+
+    #[test]
+    fn test_parse_acr_values() {
+        let classes =
+            parse_acr_values("urn:fantasma:acr:stark urn:fantasma:acr:mock:accredited").unwrap();
+        assert_eq!(classes.len(), 2);
+        assert!(parse_acr_values("urn:mace:incommon:iap:silver").is_err());
+    }
+}
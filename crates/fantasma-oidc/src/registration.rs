@@ -0,0 +1,486 @@
+//! Dynamic client registration (RFC 7591) and management (RFC 7592)
+
+use crate::client_auth::TokenEndpointAuthMethod;
+use crate::jwe::JweParams;
+use crate::jwk::JwkSet;
+use crate::signing::JwsAlgorithm;
+use crate::subject::common_sector;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+/// Registration errors (RFC 7591 §3.2.2)
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RegistrationError {
+    #[error("{0}")]
+    InvalidRedirectUri(String),
+
+    #[error("{0}")]
+    InvalidClientMetadata(String),
+}
+
+impl RegistrationError {
+    /// The `error` code returned to the client
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::InvalidRedirectUri(_) => "invalid_redirect_uri",
+            Self::InvalidClientMetadata(_) => "invalid_client_metadata",
+        }
+    }
+}
+
+/// Client metadata accepted at the registration endpoint
+///
+/// Unrecognised metadata is ignored (RFC 7591 §2).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientMetadata {
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_endpoint_auth_method: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token_signed_response_alg: Option<String>,
+
+    /// Key management algorithm for encrypted ID tokens (`ECDH-ES`, `ML-KEM-768`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token_encrypted_response_alg: Option<String>,
+
+    /// Content encryption for ID tokens, `A128CBC-HS256` by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token_encrypted_response_enc: Option<String>,
+
+    /// Key management algorithm for encrypted userinfo responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub userinfo_encrypted_response_alg: Option<String>,
+
+    /// Content encryption for userinfo responses, `A128CBC-HS256` by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub userinfo_encrypted_response_enc: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwks: Option<JwkSet>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwks_uri: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+
+    /// Space-separated scopes the client may request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    /// Only `pairwise` subjects are issued
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject_type: Option<String>,
+
+    /// URL of a JSON array of redirect URIs; its host is the client's sector
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sector_identifier_uri: Option<String>,
+
+    /// Only accept authorization requests pushed to the PAR endpoint (RFC 9126 §6)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_pushed_authorization_requests: Option<bool>,
+
+    /// Only accept authorization requests carrying a signed request object (RFC 9101 §10.5)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_signed_request_object: Option<bool>,
+
+    /// Where the user agent may be sent after logging out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_logout_redirect_uris: Vec<String>,
+
+    /// Where logout tokens are POSTed when a session ends
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backchannel_logout_uri: Option<String>,
+
+    /// Whether logout tokens must carry `sid`; they always do
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backchannel_logout_session_required: Option<bool>,
+}
+
+impl ClientMetadata {
+    /// Check the metadata against what this provider supports
+    pub fn validate(&self, supported_scopes: &[String]) -> Result<(), RegistrationError> {
+        if self.redirect_uris.is_empty() {
+            return Err(RegistrationError::InvalidRedirectUri(
+                "at least one redirect_uri is required".to_string(),
+            ));
+        }
+        for uri in self
+            .redirect_uris
+            .iter()
+            .chain(&self.post_logout_redirect_uris)
+        {
+            validate_redirect_uri(uri)?;
+        }
+
+        let method = match self.token_endpoint_auth_method.as_deref() {
+            Some(m) => TokenEndpointAuthMethod::parse(m).ok_or_else(|| {
+                RegistrationError::InvalidClientMetadata(format!(
+                    "unsupported token_endpoint_auth_method: {}",
+                    m
+                ))
+            })?,
+            None => TokenEndpointAuthMethod::ClientSecretBasic,
+        };
+
+        if let Some(ref alg) = self.id_token_signed_response_alg {
+            if JwsAlgorithm::parse(alg).is_none() {
+                return Err(RegistrationError::InvalidClientMetadata(format!(
+                    "unsupported id_token_signed_response_alg: {}",
+                    alg
+                )));
+            }
+        }
+
+        // jwks and jwks_uri are mutually exclusive (RFC 7591 §2)
+        if self.jwks.is_some() && self.jwks_uri.is_some() {
+            return Err(RegistrationError::InvalidClientMetadata(
+                "jwks and jwks_uri must not both be present".to_string(),
+            ));
+        }
+        if let Some(ref uri) = self.jwks_uri {
+            require_https(uri, "jwks_uri")?;
+        }
+        let has_keys =
+            self.jwks.as_ref().is_some_and(|jwks| !jwks.keys.is_empty()) || self.jwks_uri.is_some();
+        if method == TokenEndpointAuthMethod::PrivateKeyJwt && !has_keys {
+            return Err(RegistrationError::InvalidClientMetadata(
+                "private_key_jwt requires jwks or jwks_uri".to_string(),
+            ));
+        }
+        if self.require_signed_request_object == Some(true) && !has_keys {
+            return Err(RegistrationError::InvalidClientMetadata(
+                "require_signed_request_object requires jwks or jwks_uri".to_string(),
+            ));
+        }
+        for (name, encryption) in [
+            ("id_token", self.id_token_encryption()?),
+            ("userinfo", self.userinfo_encryption()?),
+        ] {
+            let Some(encryption) = encryption else {
+                continue;
+            };
+            if !has_keys {
+                return Err(RegistrationError::InvalidClientMetadata(format!(
+                    "{}_encrypted_response_alg requires jwks or jwks_uri",
+                    name
+                )));
+            }
+            // Keys behind jwks_uri are only checked when a response is encrypted
+            if let Some(ref jwks) = self.jwks {
+                if encryption.alg.select_key(jwks).is_none() {
+                    return Err(RegistrationError::InvalidClientMetadata(format!(
+                        "jwks has no key for {}_encrypted_response_alg {}",
+                        name,
+                        encryption.alg.as_str()
+                    )));
+                }
+            }
+        }
+
+        if let Some(ref uri) = self.logo_uri {
+            require_https(uri, "logo_uri")?;
+        }
+        if let Some(ref uri) = self.backchannel_logout_uri {
+            require_https(uri, "backchannel_logout_uri")?;
+            if Url::parse(uri).is_ok_and(|url| url.fragment().is_some()) {
+                return Err(RegistrationError::InvalidClientMetadata(
+                    "backchannel_logout_uri must not contain a fragment".to_string(),
+                ));
+            }
+        }
+
+        if self
+            .subject_type
+            .as_deref()
+            .is_some_and(|t| t != "pairwise")
+        {
+            return Err(RegistrationError::InvalidClientMetadata(
+                "only the pairwise subject_type is supported".to_string(),
+            ));
+        }
+        match self.sector_identifier_uri {
+            Some(ref uri) => require_https(uri, "sector_identifier_uri")?,
+            None if common_sector(&self.redirect_uris).is_none() => {
+                return Err(RegistrationError::InvalidClientMetadata(
+                    "redirect_uris span several hosts; register a sector_identifier_uri"
+                        .to_string(),
+                ))
+            }
+            None => {}
+        }
+
+        if let Some(scope) = self.scope.as_deref() {
+            if let Some(unknown) = scope
+                .split_whitespace()
+                .find(|s| !supported_scopes.iter().any(|supported| supported == s))
+            {
+                return Err(RegistrationError::InvalidClientMetadata(format!(
+                    "unsupported scope: {}",
+                    unknown
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the contents of the `sector_identifier_uri` document
+    ///
+    /// Every registered redirect URI must be listed (OIDC Registration §5).
+    pub fn check_sector_redirect_uris(&self, listed: &[String]) -> Result<(), RegistrationError> {
+        match self.redirect_uris.iter().find(|uri| !listed.contains(uri)) {
+            Some(missing) => Err(RegistrationError::InvalidClientMetadata(format!(
+                "{} is not listed at sector_identifier_uri",
+                missing
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// The registered authentication method, defaulting to `client_secret_basic`
+    pub fn auth_method(&self) -> TokenEndpointAuthMethod {
+        self.token_endpoint_auth_method
+            .as_deref()
+            .and_then(TokenEndpointAuthMethod::parse)
+            .unwrap_or(TokenEndpointAuthMethod::ClientSecretBasic)
+    }
+
+    /// How ID tokens are encrypted for the client, if at all
+    pub fn id_token_encryption(&self) -> Result<Option<JweParams>, RegistrationError> {
+        JweParams::parse(
+            self.id_token_encrypted_response_alg.as_deref(),
+            self.id_token_encrypted_response_enc.as_deref(),
+        )
+        .map_err(|e| RegistrationError::InvalidClientMetadata(format!("id_token: {}", e)))
+    }
+
+    /// How userinfo responses are encrypted for the client, if at all
+    pub fn userinfo_encryption(&self) -> Result<Option<JweParams>, RegistrationError> {
+        JweParams::parse(
+            self.userinfo_encrypted_response_alg.as_deref(),
+            self.userinfo_encrypted_response_enc.as_deref(),
+        )
+        .map_err(|e| RegistrationError::InvalidClientMetadata(format!("userinfo: {}", e)))
+    }
+
+    /// Scopes the client may request; all supported scopes when none were registered
+    pub fn scopes(&self, supported_scopes: &[String]) -> Vec<String> {
+        match self.scope.as_deref() {
+            Some(scope) => scope.split_whitespace().map(str::to_string).collect(),
+            None => supported_scopes.to_vec(),
+        }
+    }
+}
+
+/// Redirect URIs must be absolute, without a fragment, and use TLS unless
+/// they point at the local machine or a private-use (native app) scheme
+fn validate_redirect_uri(uri: &str) -> Result<(), RegistrationError> {
+    let invalid =
+        |reason: &str| RegistrationError::InvalidRedirectUri(format!("{}: {}", reason, uri));
+    let url = Url::parse(uri).map_err(|_| invalid("redirect_uri is not an absolute URI"))?;
+
+    if url.fragment().is_some() {
+        return Err(invalid("redirect_uri must not contain a fragment"));
+    }
+    if url.scheme() == "http" && !is_loopback(&url) {
+        return Err(invalid("http redirect_uri is only allowed for localhost"));
+    }
+    if matches!(url.scheme(), "javascript" | "data" | "file") {
+        return Err(invalid("redirect_uri scheme is not allowed"));
+    }
+
+    Ok(())
+}
+
+fn require_https(uri: &str, name: &str) -> Result<(), RegistrationError> {
+    match Url::parse(uri) {
+        Ok(url) if url.scheme() == "https" || (url.scheme() == "http" && is_loopback(&url)) => {
+            Ok(())
+        }
+        _ => Err(RegistrationError::InvalidClientMetadata(format!(
+            "{} must be an https URL",
+            name
+        ))),
+    }
+}
+
+fn is_loopback(url: &Url) -> bool {
+    matches!(
+        url.host_str(),
+        Some("localhost") | Some("127.0.0.1") | Some("[::1]")
+    )
+}
+
+/// Registration response (RFC 7591 §3.2.1), also returned by RFC 7592 reads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientRegistrationResponse {
+    pub client_id: String,
+
+    /// Only returned when the secret is generated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+
+    pub client_id_issued_at: i64,
+
+    /// 0: the secret does not expire
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret_expires_at: Option<i64>,
+
+    /// Only returned at registration; needed for later GET/PUT/DELETE
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_access_token: Option<String>,
+
+    pub registration_client_uri: String,
+
+    #[serde(flatten)]
+    pub metadata: ClientMetadata,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scopes() -> Vec<String> {
+        vec!["openid".to_string(), "zk:age:18+".to_string()]
+    }
+
+    fn metadata(redirect_uri: &str) -> ClientMetadata {
+        ClientMetadata {
+            redirect_uris: vec![redirect_uri.to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_redirect_uri_validation() {
+        for ok in [
+            "https://rp.example/callback",
+            "http://localhost:8080/callback",
+            "http://127.0.0.1/cb",
+            "com.example.app:/callback",
+        ] {
+            assert!(metadata(ok).validate(&scopes()).is_ok(), "{}", ok);
+        }
+
+        for bad in [
+            "/relative",
+            "https://rp.example/cb#frag",
+            "http://rp.example/callback",
+            "javascript:alert(1)",
+        ] {
+            assert!(matches!(
+                metadata(bad).validate(&scopes()),
+                Err(RegistrationError::InvalidRedirectUri(_))
+            ));
+        }
+
+        let empty = ClientMetadata::default();
+        assert_eq!(
+            empty.validate(&scopes()).unwrap_err().error_code(),
+            "invalid_redirect_uri"
+        );
+    }
+
+    #[test]
+    fn test_metadata_validation() {
+        let mut m = metadata("https://rp.example/cb");
+        m.scope = Some("openid zk:age:18+".to_string());
+        assert!(m.validate(&scopes()).is_ok());
+        assert_eq!(m.scopes(&scopes()).len(), 2);
+
+        m.scope = Some("openid admin".to_string());
+        assert_eq!(
+            m.validate(&scopes()).unwrap_err().error_code(),
+            "invalid_client_metadata"
+        );
+
+        let mut m = metadata("https://rp.example/cb");
+        m.token_endpoint_auth_method = Some("private_key_jwt".to_string());
+        assert!(m.validate(&scopes()).is_err());
+        m.jwks_uri = Some("https://rp.example/jwks.json".to_string());
+        assert!(m.validate(&scopes()).is_ok());
+        assert_eq!(m.auth_method(), TokenEndpointAuthMethod::PrivateKeyJwt);
+
+        let mut m = metadata("https://rp.example/cb");
+        m.require_signed_request_object = Some(true);
+        assert!(m.validate(&scopes()).is_err());
+
+        let mut m = metadata("https://rp.example/cb");
+        m.id_token_signed_response_alg = Some("RS256".to_string());
+        assert!(m.validate(&scopes()).is_err());
+    }
+
+    #[test]
+    fn test_sector_validation() {
+        let mut m = metadata("https://a.rp.example/cb");
+        m.redirect_uris.push("https://b.rp.example/cb".to_string());
+        assert!(m.validate(&scopes()).is_err());
+
+        m.sector_identifier_uri = Some("https://rp.example/sector.json".to_string());
+        assert!(m.validate(&scopes()).is_ok());
+
+        let listed = vec!["https://a.rp.example/cb".to_string()];
+        assert!(m.check_sector_redirect_uris(&listed).is_err());
+        let listed = m.redirect_uris.clone();
+        assert!(m.check_sector_redirect_uris(&listed).is_ok());
+
+        m.subject_type = Some("public".to_string());
+        assert!(m.validate(&scopes()).is_err());
+    }
+
+    #[test]
+    fn test_logout_metadata_validation() {
+        let mut m = metadata("https://rp.example/cb");
+        m.post_logout_redirect_uris = vec!["https://rp.example/bye".to_string()];
+        m.backchannel_logout_uri = Some("https://rp.example/backchannel".to_string());
+        assert!(m.validate(&scopes()).is_ok());
+
+        m.post_logout_redirect_uris = vec!["http://rp.example/bye".to_string()];
+        assert_eq!(
+            m.validate(&scopes()).unwrap_err().error_code(),
+            "invalid_redirect_uri"
+        );
+
+        let mut m = metadata("https://rp.example/cb");
+        m.backchannel_logout_uri = Some("http://rp.example/backchannel".to_string());
+        assert!(m.validate(&scopes()).is_err());
+    }
+
+    #[test]
+    fn test_encryption_metadata_validation() {
+        use crate::jwe::{DecryptionKey, JweAlgorithm};
+
+        let mut m = metadata("https://rp.example/cb");
+        m.id_token_encrypted_response_alg = Some("ML-KEM-768".to_string());
+        assert!(m.validate(&scopes()).is_err());
+
+        let x25519 = DecryptionKey::generate_x25519().public_jwk();
+        m.jwks = Some(JwkSet::new(vec![x25519]));
+        assert!(m.validate(&scopes()).is_err());
+
+        m.id_token_encrypted_response_alg = Some("ECDH-ES".to_string());
+        assert!(m.validate(&scopes()).is_ok());
+        assert_eq!(
+            m.id_token_encryption().unwrap().map(|p| p.alg),
+            Some(JweAlgorithm::EcdhEs)
+        );
+
+        m.id_token_encrypted_response_enc = Some("A128GCM".to_string());
+        assert!(m.validate(&scopes()).is_err());
+
+        let mut m = metadata("https://rp.example/cb");
+        m.jwks_uri = Some("https://rp.example/jwks.json".to_string());
+        m.userinfo_encrypted_response_enc = Some("A256GCM".to_string());
+        assert!(m.validate(&scopes()).is_err());
+        m.userinfo_encrypted_response_alg = Some("ML-KEM-768".to_string());
+        assert!(m.validate(&scopes()).is_ok());
+    }
+}
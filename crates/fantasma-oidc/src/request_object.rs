@@ -0,0 +1,157 @@
+//! Signed authorization request objects (JAR, RFC 9101)
+//!
+//! The authorization request parameters travel as claims of a JWT signed
+//! with a key from the client's registered JWKS, so they cannot be altered
+//! on their way through the user agent.
+
+use crate::client_auth::Audience;
+use crate::jwk::JwkSet;
+use crate::jws;
+use crate::token::TokenError;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+/// `typ` header value for request objects (RFC 9101 §10.8)
+pub const REQUEST_OBJECT_TYPE: &str = "oauth-authz-req+jwt";
+
+/// Clock skew tolerated when checking request object timestamps
+const CLOCK_SKEW_SECONDS: i64 = 60;
+
+/// Claims of a request object: the authorization request parameters plus
+/// the JWT claims binding them to the client and this provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequestObjectClaims {
+    /// Issuer - the client_id
+    pub iss: String,
+    /// Audience - the provider's issuer identifier
+    pub aud: Option<Audience>,
+    pub client_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_challenge: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_challenge_method: Option<String>,
+    /// The `claims` request parameter, as a JSON object
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claims: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acr_values: Option<String>,
+}
+
+/// Verify a request object sent by `client_id`
+///
+/// The signature must verify against the client's JWKS, `iss` and
+/// `client_id` must both be the client, and `aud` must contain `issuer`.
+/// Unsigned (`alg: none`) request objects are never accepted.
+pub fn verify_request_object(
+    request: &str,
+    client_id: &str,
+    jwks: &JwkSet,
+    issuer: &str,
+) -> Result<RequestObjectClaims, TokenError> {
+    let (header, claims): (_, RequestObjectClaims) = jws::verify(request, jwks)?;
+
+    if header
+        .typ
+        .as_deref()
+        .is_some_and(|typ| typ != REQUEST_OBJECT_TYPE && typ != "JWT")
+    {
+        return Err(TokenError::InvalidClaim(format!(
+            "unexpected typ: {}",
+            header.typ.unwrap_or_default()
+        )));
+    }
+
+    if claims.iss != client_id || claims.client_id != client_id {
+        return Err(TokenError::InvalidIssuer);
+    }
+    if !claims.aud.as_ref().is_some_and(|aud| aud.contains(issuer)) {
+        return Err(TokenError::InvalidAudience);
+    }
+
+    let now = Utc::now().timestamp();
+    if claims.exp.is_some_and(|exp| exp + CLOCK_SKEW_SECONDS < now) {
+        return Err(TokenError::Expired);
+    }
+    if claims.nbf.is_some_and(|nbf| nbf > now + CLOCK_SKEW_SECONDS) {
+        return Err(TokenError::InvalidClaim("nbf is in the future".to_string()));
+    }
+
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::SigningKey;
+
+    const ISSUER: &str = "https://fantasma.example";
+
+    fn claims(client_id: &str) -> RequestObjectClaims {
+        RequestObjectClaims {
+            iss: client_id.to_string(),
+            aud: Some(Audience::Single(ISSUER.to_string())),
+            client_id: client_id.to_string(),
+            exp: Some(Utc::now().timestamp() + 60),
+            response_type: Some("code".to_string()),
+            scope: Some("openid zk:age:21+".to_string()),
+            nonce: Some("n-0S6_WzA2Mj".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_verify_request_object() {
+        let key = SigningKey::generate_ed25519();
+        let jwks = JwkSet::new(vec![key.public_jwk()]);
+
+        let jwt = jws::encode(&claims("rp"), REQUEST_OBJECT_TYPE, &key).unwrap();
+        let verified = verify_request_object(&jwt, "rp", &jwks, ISSUER).unwrap();
+        assert_eq!(verified.scope.as_deref(), Some("openid zk:age:21+"));
+
+        // Presented on behalf of another client
+        assert!(matches!(
+            verify_request_object(&jwt, "other", &jwks, ISSUER),
+            Err(TokenError::InvalidIssuer)
+        ));
+
+        // Meant for another provider
+        assert!(matches!(
+            verify_request_object(&jwt, "rp", &jwks, "https://elsewhere.example"),
+            Err(TokenError::InvalidAudience)
+        ));
+
+        let mut expired = claims("rp");
+        expired.exp = Some(Utc::now().timestamp() - 300);
+        let jwt = jws::encode(&expired, REQUEST_OBJECT_TYPE, &key).unwrap();
+        assert!(matches!(
+            verify_request_object(&jwt, "rp", &jwks, ISSUER),
+            Err(TokenError::Expired)
+        ));
+    }
+
+    #[test]
+    fn test_request_object_signed_by_unregistered_key() {
+        let registered = SigningKey::generate_ml_dsa_65();
+        let attacker = SigningKey::generate_ml_dsa_65();
+        let jwks = JwkSet::new(vec![registered.public_jwk()]);
+
+        let jwt = jws::encode(&claims("rp"), REQUEST_OBJECT_TYPE, &attacker).unwrap();
+        assert!(verify_request_object(&jwt, "rp", &jwks, ISSUER).is_err());
+    }
+}
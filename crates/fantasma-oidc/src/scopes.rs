@@ -51,19 +51,6 @@ impl ZkScope {
         }
     }
 
-    /// Convert to scope string
-    pub fn to_string(&self) -> String {
-        match self {
-            ZkScope::OpenId => "openid".to_string(),
-            ZkScope::Age { threshold } => format!("zk:age:{}+", threshold),
-            ZkScope::Credential { credential_type } => match credential_type {
-                Some(t) => format!("zk:credential:{}", t),
-                None => "zk:credential".to_string(),
-            },
-            ZkScope::Kyc { level } => format!("zk:kyc:{}", level.as_str()),
-        }
-    }
-
     /// Convert to claim type (if applicable)
     pub fn to_claim_type(&self) -> Option<ClaimType> {
         match self {
@@ -83,12 +70,43 @@ impl ZkScope {
         }
     }
 
+    /// The scope requesting a claim type (if there is one)
+    pub fn from_claim_type(claim_type: &ClaimType) -> Option<Self> {
+        match claim_type {
+            ClaimType::AgeAtLeast { threshold } => Some(ZkScope::Age {
+                threshold: *threshold,
+            }),
+            ClaimType::HoldsCredential {
+                credential_type, ..
+            } => Some(ZkScope::Credential {
+                credential_type: (credential_type != "*").then(|| credential_type.clone()),
+            }),
+            ClaimType::KycStatus { level, .. } => Some(ZkScope::Kyc { level: *level }),
+            ClaimType::SetMembership { .. } => None,
+        }
+    }
+
     /// Check if this scope requires a ZK proof
     pub fn requires_proof(&self) -> bool {
         !matches!(self, ZkScope::OpenId)
     }
 }
 
+impl std::fmt::Display for ZkScope {
+    /// Format as the scope string
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZkScope::OpenId => write!(f, "openid"),
+            ZkScope::Age { threshold } => write!(f, "zk:age:{}+", threshold),
+            ZkScope::Credential { credential_type } => match credential_type {
+                Some(t) => write!(f, "zk:credential:{}", t),
+                None => write!(f, "zk:credential"),
+            },
+            ZkScope::Kyc { level } => write!(f, "zk:kyc:{}", level.as_str()),
+        }
+    }
+}
+
 /// Parse multiple scopes from a space-separated string
 pub fn parse_scopes(scopes: &str) -> Vec<ZkScope> {
     scopes
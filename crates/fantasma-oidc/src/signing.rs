@@ -0,0 +1,531 @@
+//! Token signing keys
+
+use crate::jwk::{Jwk, JwkSet};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use fantasma_crypto::ed25519::{Ed25519Keypair, Ed25519PublicKey};
+use fantasma_crypto::mldsa::{MlDsa65Keypair, MlDsa65PublicKey};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// JWS algorithms the provider can sign tokens with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum JwsAlgorithm {
+    /// Ed25519 (RFC 8037)
+    #[serde(rename = "EdDSA")]
+    EdDSA,
+
+    /// ML-DSA-65 (FIPS 204, post-quantum)
+    #[serde(rename = "ML-DSA-65")]
+    MlDsa65,
+}
+
+impl JwsAlgorithm {
+    /// JOSE `alg` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JwsAlgorithm::EdDSA => "EdDSA",
+            JwsAlgorithm::MlDsa65 => "ML-DSA-65",
+        }
+    }
+
+    /// Parse a JOSE `alg` value
+    pub fn parse(alg: &str) -> Option<Self> {
+        match alg {
+            "EdDSA" => Some(JwsAlgorithm::EdDSA),
+            "ML-DSA-65" => Some(JwsAlgorithm::MlDsa65),
+            _ => None,
+        }
+    }
+
+    /// Verify a signature made with this algorithm against a public JWK
+    pub fn verify(&self, jwk: &Jwk, message: &[u8], signature: &[u8]) -> Result<(), String> {
+        match (self, jwk.kty.as_str()) {
+            (JwsAlgorithm::EdDSA, "OKP") if jwk.crv.as_deref() == Some("Ed25519") => {
+                let key = decode_member(jwk.x.as_deref(), "x")?;
+                Ed25519PublicKey::from_bytes(&key)
+                    .map_err(|e| e.to_string())?
+                    .verify(message, signature)
+                    .map_err(|e| e.to_string())
+            }
+            (JwsAlgorithm::MlDsa65, "AKP") if jwk.alg.as_deref() == Some(self.as_str()) => {
+                let key = decode_member(jwk.public.as_deref(), "pub")?;
+                MlDsa65PublicKey::from_bytes(&key)
+                    .map_err(|e| e.to_string())?
+                    .verify(message, signature)
+                    .map_err(|e| e.to_string())
+            }
+            _ => Err(format!(
+                "key {} cannot verify {}",
+                jwk.kid.as_deref().unwrap_or("(no kid)"),
+                self.as_str()
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for JwsAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Decode a base64url key member from a JWK
+fn decode_member(value: Option<&str>, name: &str) -> Result<Vec<u8>, String> {
+    let value = value.ok_or_else(|| format!("JWK is missing \"{}\"", name))?;
+    URL_SAFE_NO_PAD
+        .decode(value)
+        .map_err(|e| format!("invalid \"{}\": {}", name, e))
+}
+
+/// Private key material behind a `SigningKey`
+enum KeyMaterial {
+    Ed25519(Ed25519Keypair),
+    MlDsa65(MlDsa65Keypair),
+}
+
+/// Asymmetric key the provider signs tokens with
+///
+/// The `kid` is the key's JWK thumbprint, so it is stable across restarts
+/// for a persisted key.
+pub struct SigningKey {
+    kid: String,
+    material: KeyMaterial,
+}
+
+impl SigningKey {
+    /// Wrap an Ed25519 keypair (JWS `EdDSA`)
+    pub fn ed25519(keypair: Ed25519Keypair) -> Self {
+        let kid = Jwk::ed25519(keypair.public_key().as_bytes()).thumbprint();
+        Self {
+            kid,
+            material: KeyMaterial::Ed25519(keypair),
+        }
+    }
+
+    /// Wrap an ML-DSA-65 keypair (JWS `ML-DSA-65`)
+    pub fn ml_dsa_65(keypair: MlDsa65Keypair) -> Self {
+        let kid = Jwk::ml_dsa_65(keypair.public_key.as_bytes()).thumbprint();
+        Self {
+            kid,
+            material: KeyMaterial::MlDsa65(keypair),
+        }
+    }
+
+    /// Generate an ephemeral Ed25519 key
+    pub fn generate_ed25519() -> Self {
+        Self::ed25519(Ed25519Keypair::generate())
+    }
+
+    /// Generate an ephemeral ML-DSA-65 key
+    pub fn generate_ml_dsa_65() -> Self {
+        Self::ml_dsa_65(MlDsa65Keypair::generate())
+    }
+
+    /// Key ID placed in token headers and the JWKS
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    /// JWS algorithm for this key
+    pub fn algorithm(&self) -> JwsAlgorithm {
+        match self.material {
+            KeyMaterial::Ed25519(_) => JwsAlgorithm::EdDSA,
+            KeyMaterial::MlDsa65(_) => JwsAlgorithm::MlDsa65,
+        }
+    }
+
+    /// Public half of this key as a JWK
+    pub fn public_jwk(&self) -> Jwk {
+        match &self.material {
+            KeyMaterial::Ed25519(kp) => Jwk::ed25519(kp.public_key().as_bytes()),
+            KeyMaterial::MlDsa65(kp) => Jwk::ml_dsa_65(kp.public_key.as_bytes()),
+        }
+    }
+
+    /// Sign a JWS signing input, returning the raw signature bytes
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match &self.material {
+            KeyMaterial::Ed25519(kp) => kp.sign(message).to_vec(),
+            KeyMaterial::MlDsa65(kp) => kp.sign(message),
+        }
+    }
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("kid", &self.kid)
+            .field("algorithm", &self.algorithm())
+            .finish()
+    }
+}
+
+/// Where a signing key is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyStatus {
+    /// Published ahead of its activation time, not signing yet
+    Next,
+    /// Signing new tokens
+    Active,
+    /// No longer signing, published until the tokens it signed expire
+    Retired,
+}
+
+impl KeyStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyStatus::Next => "next",
+            KeyStatus::Active => "active",
+            KeyStatus::Retired => "retired",
+        }
+    }
+}
+
+/// A generation of a signing key: the key and its place in the rotation
+#[derive(Debug, Clone)]
+pub struct KeyGeneration {
+    pub key: Arc<SigningKey>,
+    pub status: KeyStatus,
+    /// When the key became, or is to become, active
+    pub activates_at: DateTime<Utc>,
+    /// When the key was retired
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+impl KeyGeneration {
+    /// A key signing from now on
+    pub fn active(key: SigningKey) -> Self {
+        Self {
+            key: Arc::new(key),
+            status: KeyStatus::Active,
+            activates_at: Utc::now(),
+            retired_at: None,
+        }
+    }
+
+    /// Whether the key is in the JWKS at `now`: retired keys drop out once
+    /// `retention` has passed
+    pub fn is_published(&self, now: DateTime<Utc>, retention: Duration) -> bool {
+        match self.retired_at {
+            Some(retired_at) if self.status == KeyStatus::Retired => retired_at + retention > now,
+            _ => true,
+        }
+    }
+}
+
+/// The provider's signing keys across their generations
+///
+/// Each algorithm has one active key, and at most one next key waiting to
+/// replace it. Retired keys stay published for `retention`, the lifetime of
+/// the tokens they signed. The active key of the default algorithm signs for
+/// clients that did not register an `id_token_signed_response_alg`.
+///
+/// The key ring changes under rotation, so accessors hand out shared
+/// handles to the keys rather than borrows.
+#[derive(Debug)]
+pub struct SigningKeys {
+    default_algorithm: JwsAlgorithm,
+    retention: Duration,
+    generations: RwLock<Vec<KeyGeneration>>,
+}
+
+impl SigningKeys {
+    /// Create from a list of keys, all active; the first is the default
+    pub fn new(keys: Vec<SigningKey>) -> Self {
+        assert!(!keys.is_empty(), "at least one signing key is required");
+        let default_algorithm = keys[0].algorithm();
+        Self::from_generations(
+            keys.into_iter().map(KeyGeneration::active).collect(),
+            default_algorithm,
+        )
+    }
+
+    /// Create from key generations, as loaded from storage
+    pub fn from_generations(
+        generations: Vec<KeyGeneration>,
+        default_algorithm: JwsAlgorithm,
+    ) -> Self {
+        assert!(
+            generations
+                .iter()
+                .any(|g| g.status == KeyStatus::Active && g.key.algorithm() == default_algorithm),
+            "an active key for the default algorithm is required"
+        );
+        Self {
+            default_algorithm,
+            retention: Duration::zero(),
+            generations: RwLock::new(generations),
+        }
+    }
+
+    /// Keep retired keys published for `retention`
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<KeyGeneration>> {
+        self.generations.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<KeyGeneration>> {
+        self.generations.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The default signing key
+    pub fn default_key(&self) -> Arc<SigningKey> {
+        self.get(self.default_algorithm)
+            .expect("the default algorithm always has an active key")
+    }
+
+    /// The active key for a given algorithm, if one is loaded
+    pub fn get(&self, algorithm: JwsAlgorithm) -> Option<Arc<SigningKey>> {
+        self.read()
+            .iter()
+            .find(|g| g.status == KeyStatus::Active && g.key.algorithm() == algorithm)
+            .map(|g| g.key.clone())
+    }
+
+    /// The key for an optional algorithm preference, falling back to the default
+    pub fn select(&self, algorithm: Option<JwsAlgorithm>) -> Option<Arc<SigningKey>> {
+        self.get(algorithm.unwrap_or(self.default_algorithm))
+    }
+
+    /// Algorithms with an active key, the default first
+    pub fn algorithms(&self) -> Vec<JwsAlgorithm> {
+        let mut algorithms = vec![self.default_algorithm];
+        for generation in self.read().iter() {
+            let algorithm = generation.key.algorithm();
+            if generation.status == KeyStatus::Active && !algorithms.contains(&algorithm) {
+                algorithms.push(algorithm);
+            }
+        }
+        algorithms
+    }
+
+    /// How long retired keys stay published
+    pub fn retention(&self) -> Duration {
+        self.retention
+    }
+
+    /// Every generation, in the order they were added
+    pub fn generations(&self) -> Vec<KeyGeneration> {
+        self.read().clone()
+    }
+
+    /// The generation of the key `kid`
+    pub fn find(&self, kid: &str) -> Option<KeyGeneration> {
+        self.read().iter().find(|g| g.key.kid() == kid).cloned()
+    }
+
+    /// The next key for an algorithm, if one is staged
+    pub fn next(&self, algorithm: JwsAlgorithm) -> Option<KeyGeneration> {
+        self.read()
+            .iter()
+            .find(|g| g.status == KeyStatus::Next && g.key.algorithm() == algorithm)
+            .cloned()
+    }
+
+    /// Public keys as a JWK set: the active keys first, then the next keys
+    /// and the retired keys still within their retention
+    pub fn jwks(&self) -> JwkSet {
+        let now = Utc::now();
+        let mut published: Vec<_> = self
+            .read()
+            .iter()
+            .filter(|g| g.is_published(now, self.retention))
+            .map(|g| (g.status, g.key.public_jwk()))
+            .collect();
+        published.sort_by_key(|(status, _)| match status {
+            KeyStatus::Active => 0,
+            KeyStatus::Next => 1,
+            KeyStatus::Retired => 2,
+        });
+        JwkSet::new(published.into_iter().map(|(_, jwk)| jwk).collect())
+    }
+
+    /// Stage `key` as the next key for its algorithm, activating at
+    /// `activates_at`; a next key already staged is dropped and returned
+    pub fn stage(&self, key: SigningKey, activates_at: DateTime<Utc>) -> Option<KeyGeneration> {
+        let algorithm = key.algorithm();
+        let mut generations = self.write();
+        let replaced = generations
+            .iter()
+            .position(|g| g.status == KeyStatus::Next && g.key.algorithm() == algorithm)
+            .map(|i| generations.remove(i));
+        generations.push(KeyGeneration {
+            key: Arc::new(key),
+            status: KeyStatus::Next,
+            activates_at,
+            retired_at: None,
+        });
+        replaced
+    }
+
+    /// Move the activation of an algorithm's next key to `activates_at`;
+    /// false if none is staged
+    pub fn schedule(&self, algorithm: JwsAlgorithm, activates_at: DateTime<Utc>) -> bool {
+        match self
+            .write()
+            .iter_mut()
+            .find(|g| g.status == KeyStatus::Next && g.key.algorithm() == algorithm)
+        {
+            Some(next) => {
+                next.activates_at = activates_at;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Activate the next keys due at `now`, retiring the keys they replace;
+    /// returns the kids activated
+    pub fn promote_due(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut generations = self.write();
+        let due: Vec<usize> = generations
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| g.status == KeyStatus::Next && g.activates_at <= now)
+            .map(|(i, _)| i)
+            .collect();
+        due.into_iter()
+            .map(|i| promote(&mut generations, i, now))
+            .collect()
+    }
+
+    /// Drop the retired keys whose retention has passed at `now`
+    pub fn prune(&self, now: DateTime<Utc>) -> Vec<KeyGeneration> {
+        let mut generations = self.write();
+        let (kept, pruned) = std::mem::take(&mut *generations)
+            .into_iter()
+            .partition(|g| g.is_published(now, self.retention));
+        *generations = kept;
+        pruned
+    }
+
+    /// Drop a key at once, without a retention period
+    ///
+    /// An active key is replaced by the next key for its algorithm, which
+    /// must be staged: an algorithm is never left without a key.
+    pub fn revoke(&self, kid: &str) -> Result<KeyGeneration, String> {
+        let mut generations = self.write();
+        let index = generations
+            .iter()
+            .position(|g| g.key.kid() == kid)
+            .ok_or_else(|| format!("unknown key {}", kid))?;
+
+        let revoked = &generations[index];
+        if revoked.status == KeyStatus::Active {
+            let algorithm = revoked.key.algorithm();
+            let next = generations
+                .iter()
+                .position(|g| g.status == KeyStatus::Next && g.key.algorithm() == algorithm)
+                .ok_or_else(|| format!("no next {} key to replace {}", algorithm, kid))?;
+            promote(&mut generations, next, Utc::now());
+        }
+        Ok(generations.remove(index))
+    }
+}
+
+/// Activate `generations[index]`, retiring the active key of its algorithm
+fn promote(generations: &mut [KeyGeneration], index: usize, now: DateTime<Utc>) -> String {
+    let algorithm = generations[index].key.algorithm();
+    for generation in generations.iter_mut() {
+        if generation.status == KeyStatus::Active && generation.key.algorithm() == algorithm {
+            generation.status = KeyStatus::Retired;
+            generation.retired_at = Some(now);
+        }
+    }
+    let promoted = &mut generations[index];
+    promoted.status = KeyStatus::Active;
+    promoted.activates_at = promoted.activates_at.min(now);
+    promoted.key.kid().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kids(keys: &JwkSet) -> Vec<String> {
+        keys.keys.iter().filter_map(|k| k.kid.clone()).collect()
+    }
+
+    #[test]
+    fn test_rotation_retires_the_active_key() {
+        let keys = SigningKeys::new(vec![SigningKey::generate_ed25519()])
+            .with_retention(Duration::hours(1));
+        let old = keys.default_key();
+
+        let now = Utc::now();
+        let next = SigningKey::generate_ed25519();
+        let next_kid = next.kid().to_string();
+        keys.stage(next, now + Duration::days(1));
+
+        // Published ahead of its activation, not yet signing
+        assert_eq!(kids(&keys.jwks()), vec![old.kid(), next_kid.as_str()]);
+        assert!(keys.promote_due(now).is_empty());
+        assert_eq!(keys.default_key().kid(), old.kid());
+
+        assert_eq!(
+            keys.promote_due(now + Duration::days(1)),
+            vec![next_kid.clone()]
+        );
+        assert_eq!(keys.default_key().kid(), next_kid);
+        let retired = keys.find(old.kid()).unwrap();
+        assert_eq!(retired.status, KeyStatus::Retired);
+
+        // The retired key stays published while tokens it signed are valid
+        assert_eq!(kids(&keys.jwks()), vec![next_kid.as_str(), old.kid()]);
+        assert!(keys.prune(now + Duration::days(1)).is_empty());
+        let pruned = keys.prune(now + Duration::days(1) + Duration::hours(2));
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(kids(&keys.jwks()), vec![next_kid]);
+    }
+
+    #[test]
+    fn test_staging_replaces_the_next_key() {
+        let keys = SigningKeys::new(vec![
+            SigningKey::generate_ed25519(),
+            SigningKey::generate_ml_dsa_65(),
+        ]);
+        let first = SigningKey::generate_ed25519();
+        let first_kid = first.kid().to_string();
+        assert!(keys.stage(first, Utc::now()).is_none());
+        let replaced = keys.stage(SigningKey::generate_ed25519(), Utc::now());
+        assert_eq!(replaced.unwrap().key.kid(), first_kid);
+        assert_eq!(keys.generations().len(), 3);
+
+        // Rotating one algorithm leaves the others alone
+        let ml_dsa = keys.get(JwsAlgorithm::MlDsa65).unwrap();
+        keys.promote_due(Utc::now());
+        assert_eq!(keys.get(JwsAlgorithm::MlDsa65).unwrap().kid(), ml_dsa.kid());
+        assert_eq!(
+            keys.algorithms(),
+            vec![JwsAlgorithm::EdDSA, JwsAlgorithm::MlDsa65]
+        );
+    }
+
+    #[test]
+    fn test_revoke() {
+        let keys = SigningKeys::new(vec![SigningKey::generate_ed25519()])
+            .with_retention(Duration::hours(1));
+        let compromised = keys.default_key();
+
+        // An active key cannot be dropped without a successor
+        assert!(keys.revoke(compromised.kid()).is_err());
+
+        let next = SigningKey::generate_ed25519();
+        let next_kid = next.kid().to_string();
+        keys.stage(next, Utc::now() + Duration::days(30));
+        let revoked = keys.revoke(compromised.kid()).unwrap();
+        assert_eq!(revoked.key.kid(), compromised.kid());
+
+        // The successor signs at once and the revoked key is unpublished
+        assert_eq!(keys.default_key().kid(), next_kid);
+        assert_eq!(kids(&keys.jwks()), vec![next_kid]);
+        assert!(keys.revoke("unknown").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Sector identifiers for pairwise subjects (OIDC Core §8.1)
+//!
+//! Every client belongs to a sector: the host of its `sector_identifier_uri`
+//! if it registered one, otherwise the host of its redirect URI. Wallets
+//! derive one subject per sector, so clients sharing a sector see the same
+//! `sub` for a user.
+
+use url::Url;
+
+/// The sector a URL belongs to: its host, or the scheme of hostless
+/// private-use URIs (e.g. `com.example.app:/callback`)
+fn sector_of(url: &Url) -> String {
+    url.host_str().unwrap_or(url.scheme()).to_ascii_lowercase()
+}
+
+/// Sector identifier for a client
+pub fn sector_identifier(
+    redirect_uri: &str,
+    sector_identifier_uri: Option<&str>,
+) -> Option<String> {
+    let uri = sector_identifier_uri.unwrap_or(redirect_uri);
+    Url::parse(uri).ok().map(|url| sector_of(&url))
+}
+
+/// The single sector shared by a set of redirect URIs, if there is one
+///
+/// Clients whose redirect URIs span several hosts must register a
+/// `sector_identifier_uri` instead.
+pub fn common_sector(redirect_uris: &[String]) -> Option<String> {
+    let mut sectors = redirect_uris.iter().map(|uri| sector_identifier(uri, None));
+    let first = sectors.next()??;
+    sectors
+        .all(|sector| sector.as_deref() == Some(first.as_str()))
+        .then_some(first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sector_identifier() {
+        assert_eq!(
+            sector_identifier("https://RP.example:8443/cb", None).as_deref(),
+            Some("rp.example")
+        );
+        assert_eq!(
+            sector_identifier(
+                "https://app.rp.example/cb",
+                Some("https://rp.example/sector.json")
+            )
+            .as_deref(),
+            Some("rp.example")
+        );
+        assert_eq!(
+            sector_identifier("com.example.app:/callback", None).as_deref(),
+            Some("com.example.app")
+        );
+    }
+
+    #[test]
+    fn test_common_sector() {
+        let same = vec![
+            "https://rp.example/a".to_string(),
+            "https://rp.example/b".to_string(),
+        ];
+        assert_eq!(common_sector(&same).as_deref(), Some("rp.example"));
+
+        let mixed = vec![
+            "https://rp.example/a".to_string(),
+            "https://other.example/b".to_string(),
+        ];
+        assert_eq!(common_sector(&mixed), None);
+    }
+}
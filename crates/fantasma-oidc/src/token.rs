@@ -1,8 +1,12 @@
 //! ID token generation and validation
 
 use crate::claims::ZkClaims;
+use crate::dpop::{Confirmation, DPOP_TOKEN_TYPE};
+use crate::jwk::JwkSet;
+use crate::jws;
+use crate::signing::SigningKey;
 use chrono::Utc;
-use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use fantasma_crypto::hybrid::HybridPolicy;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -22,8 +26,20 @@ pub enum TokenError {
 
     #[error("Invalid audience")]
     InvalidAudience,
+
+    #[error("Unknown signing key: {0}")]
+    UnknownKey(String),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Invalid claim: {0}")]
+    InvalidClaim(String),
 }
 
+/// Clock skew tolerated when checking `exp`
+const CLOCK_SKEW_SECONDS: u64 = 60;
+
 /// Standard OIDC ID token claims plus ZK claims
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IdTokenClaims {
@@ -50,6 +66,18 @@ pub struct IdTokenClaims {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub auth_time: Option<u64>,
 
+    /// Session ID, matched against `sid` in logout tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+
+    /// Authentication context class the ZK claims attain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acr: Option<String>,
+
+    /// Verification methods behind the ZK claims
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amr: Option<Vec<String>>,
+
     /// ZK claims
     #[serde(flatten)]
     pub zk_claims: ZkClaims,
@@ -72,6 +100,9 @@ impl IdTokenClaims {
             iat: now,
             nonce: None,
             auth_time: Some(now),
+            sid: None,
+            acr: None,
+            amr: None,
             zk_claims: ZkClaims::new(),
         }
     }
@@ -82,8 +113,23 @@ impl IdTokenClaims {
         self
     }
 
-    /// Set the ZK claims
+    /// Set the time the end-user authenticated (and presented proofs)
+    pub fn with_auth_time(mut self, auth_time: u64) -> Self {
+        self.auth_time = Some(auth_time);
+        self
+    }
+
+    /// Set the session ID
+    pub fn with_session_id(mut self, sid: impl Into<String>) -> Self {
+        self.sid = Some(sid.into());
+        self
+    }
+
+    /// Set the ZK claims, and the `acr` and `amr` their provenance attains
     pub fn with_zk_claims(mut self, claims: ZkClaims) -> Self {
+        self.acr = claims.auth_context().map(|acr| acr.to_string());
+        let methods = claims.verification_methods();
+        self.amr = (!methods.is_empty()).then_some(methods);
         self.zk_claims = claims;
         self
     }
@@ -108,17 +154,88 @@ pub struct IdToken {
 impl IdToken {
     /// Create and sign a new ID token
     ///
-    /// Note: In production, this should use Dilithium signatures for PQ security.
-    /// For OIDC compatibility, we also support Ed25519/RS256 for JWT signing.
-    pub fn create(claims: IdTokenClaims, signing_key: &[u8]) -> Result<Self, TokenError> {
-        let header = Header::new(Algorithm::HS256); // Simplified for demo
+    /// The token is signed with the key's algorithm (EdDSA or ML-DSA-65) and
+    /// the header carries its `kid` so relying parties can pick the matching
+    /// key from the JWKS.
+    pub fn create(claims: IdTokenClaims, signing_key: &SigningKey) -> Result<Self, TokenError> {
+        let token = jws::encode(&claims, "JWT", signing_key)?;
+        Ok(Self { token, claims })
+    }
+
+    /// Create a hybrid ID token: signed with an EdDSA key, plus an ML-DSA-65
+    /// signature in the header (see [`jws::encode_hybrid`])
+    pub fn create_hybrid(
+        claims: IdTokenClaims,
+        classical_key: &SigningKey,
+        pq_key: &SigningKey,
+    ) -> Result<Self, TokenError> {
+        let token = jws::encode_hybrid(&claims, "JWT", classical_key, pq_key)?;
+        Ok(Self { token, claims })
+    }
+
+    /// Verify a signed ID token against a JWK set
+    ///
+    /// Checks the signature, expiry, issuer and audience.
+    pub fn verify(
+        token: &str,
+        jwks: &JwkSet,
+        issuer: &str,
+        audience: &str,
+    ) -> Result<IdTokenClaims, TokenError> {
+        let (_, claims): (_, IdTokenClaims) = jws::verify(token, jwks)?;
+        Self::check_claims(claims, issuer, audience)
+    }
 
-        let encoding_key = EncodingKey::from_secret(signing_key);
+    /// Verify a hybrid ID token, requiring both signatures or accepting
+    /// either as `policy` says
+    ///
+    /// Checks expiry, issuer and audience like [`IdToken::verify`].
+    pub fn verify_hybrid(
+        token: &str,
+        jwks: &JwkSet,
+        issuer: &str,
+        audience: &str,
+        policy: HybridPolicy,
+    ) -> Result<IdTokenClaims, TokenError> {
+        let (_, claims): (_, IdTokenClaims) = jws::verify_hybrid(token, jwks, policy)?;
+        Self::check_claims(claims, issuer, audience)
+    }
 
-        let token = encode(&header, &claims, &encoding_key)
-            .map_err(|e| TokenError::EncodingFailed(e.to_string()))?;
+    fn check_claims(
+        claims: IdTokenClaims,
+        issuer: &str,
+        audience: &str,
+    ) -> Result<IdTokenClaims, TokenError> {
+        let now = Utc::now().timestamp() as u64;
+        if now > claims.exp + CLOCK_SKEW_SECONDS {
+            return Err(TokenError::Expired);
+        }
+        if claims.iss != issuer {
+            return Err(TokenError::InvalidIssuer);
+        }
+        if claims.aud != audience {
+            return Err(TokenError::InvalidAudience);
+        }
 
-        Ok(Self { token, claims })
+        Ok(claims)
+    }
+
+    /// Verify an ID token presented back to the provider as an `id_token_hint`
+    ///
+    /// Only the signature and issuer are checked: hints are commonly expired
+    /// by the time the user logs out (RP-Initiated Logout §2).
+    pub fn verify_hint(
+        token: &str,
+        jwks: &JwkSet,
+        issuer: &str,
+    ) -> Result<IdTokenClaims, TokenError> {
+        let (_, claims): (_, IdTokenClaims) = jws::verify(token, jwks)?;
+
+        if claims.iss != issuer {
+            return Err(TokenError::InvalidIssuer);
+        }
+
+        Ok(claims)
     }
 
     /// Get the token string
@@ -133,7 +250,7 @@ pub struct TokenResponse {
     /// Access token
     pub access_token: String,
 
-    /// Token type (always "Bearer")
+    /// Token type ("Bearer", or "DPoP" for DPoP-bound tokens)
     pub token_type: String,
 
     /// Expires in (seconds)
@@ -158,6 +275,78 @@ impl TokenResponse {
             refresh_token: None,
         }
     }
+
+    /// Attach a refresh token
+    pub fn with_refresh_token(mut self, refresh_token: String) -> Self {
+        self.refresh_token = Some(refresh_token);
+        self
+    }
+
+    /// Mark the access token as DPoP-bound (RFC 9449 §5)
+    pub fn dpop_bound(mut self) -> Self {
+        self.token_type = DPOP_TOKEN_TYPE.to_string();
+        self
+    }
+}
+
+/// UserInfo response: the subject plus the ZK claims from the ID token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserInfoResponse {
+    /// Subject (sub) - must match the ID token's subject
+    pub sub: String,
+
+    /// ZK claims
+    #[serde(flatten)]
+    pub zk_claims: ZkClaims,
+}
+
+/// Token introspection response (RFC 7662 §2.2)
+///
+/// Active tokens also carry the ZK claims they were issued with, so resource
+/// servers can make authorization decisions without calling /userinfo.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntrospectionResponse {
+    /// Whether the token is currently active
+    pub active: bool,
+
+    /// Space-separated scopes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    /// Client the token was issued to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+
+    /// Subject of the token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+
+    /// Token type ("Bearer" or "DPoP" for access tokens)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+
+    /// Expiration time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+
+    /// Issuer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+
+    /// Key the token is bound to, for DPoP-bound tokens (RFC 9449 §6.2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cnf: Option<Confirmation>,
+
+    /// ZK claims bound to the token
+    #[serde(flatten)]
+    pub zk_claims: ZkClaims,
+}
+
+impl IntrospectionResponse {
+    /// Response for unknown, expired or revoked tokens: only `active: false`
+    pub fn inactive() -> Self {
+        Self::default()
+    }
 }
 
 #[cfg(test)]
@@ -169,11 +358,68 @@ mod tests {
         let claims = IdTokenClaims::new("https://fantasma.example", "zkid:123", "client_abc", 3600)
             .with_nonce("nonce123");
 
-        let signing_key = b"test_signing_key_32_bytes_long!";
-        let token = IdToken::create(claims.clone(), signing_key).unwrap();
+        let signing_key = SigningKey::generate_ed25519();
+        let token = IdToken::create(claims.clone(), &signing_key).unwrap();
 
         assert!(!token.token.is_empty());
         assert_eq!(token.claims.iss, "https://fantasma.example");
         assert_eq!(token.claims.nonce, Some("nonce123".to_string()));
+
+        let header = jws::decode_header(&token.token).unwrap();
+        assert_eq!(header.alg, "EdDSA");
+        assert_eq!(header.kid.as_deref(), Some(signing_key.kid()));
+    }
+
+    #[test]
+    fn test_id_token_verification() {
+        let signing_key = SigningKey::generate_ed25519();
+        let jwks = JwkSet::new(vec![signing_key.public_jwk()]);
+        let claims = IdTokenClaims::new("https://fantasma.example", "zkid:123", "client_abc", 3600);
+        let token = IdToken::create(claims, &signing_key).unwrap();
+
+        let verified = IdToken::verify(
+            token.as_str(),
+            &jwks,
+            "https://fantasma.example",
+            "client_abc",
+        )
+        .unwrap();
+        assert_eq!(verified.sub, "zkid:123");
+
+        assert!(matches!(
+            IdToken::verify(token.as_str(), &jwks, "https://fantasma.example", "other"),
+            Err(TokenError::InvalidAudience)
+        ));
+
+        // Expired tokens are still accepted as hints
+        let mut expired =
+            IdTokenClaims::new("https://fantasma.example", "zkid:123", "client_abc", 3600)
+                .with_session_id("sid-1");
+        expired.exp -= 7200;
+        let hint = IdToken::create(expired, &signing_key).unwrap();
+        assert!(matches!(
+            IdToken::verify(
+                hint.as_str(),
+                &jwks,
+                "https://fantasma.example",
+                "client_abc"
+            ),
+            Err(TokenError::Expired)
+        ));
+        let claims =
+            IdToken::verify_hint(hint.as_str(), &jwks, "https://fantasma.example").unwrap();
+        assert_eq!(claims.sid.as_deref(), Some("sid-1"));
+
+        let other_key = SigningKey::generate_ed25519();
+        let other_jwks = JwkSet::new(vec![other_key.public_jwk()]);
+        assert!(matches!(
+            IdToken::verify(
+                token.as_str(),
+                &other_jwks,
+                "https://fantasma.example",
+                "client_abc"
+            ),
+            Err(TokenError::UnknownKey(_))
+        ));
     }
 }
@@ -10,6 +10,10 @@ use axum::{
     middleware::Next,
     response::{IntoResponse, Json, Response},
 };
+use fantasma_oidc::provenance::IssuerTrust;
+use fantasma_oidc::scopes::ZkScope;
+use fantasma_oidc::signing::{JwsAlgorithm, KeyStatus};
+use fantasma_oidc::{JweParams, JwkSet, TokenEndpointAuthMethod};
 use serde::{Deserialize, Serialize};
 
 use crate::state::AppState;
@@ -156,6 +160,29 @@ pub struct CreateClientRequest {
     pub redirect_uris: Vec<String>,
     pub allowed_scopes: Vec<String>,
     pub client_type: Option<String>,
+    pub id_token_signed_response_alg: Option<String>,
+    pub require_pkce_s256: Option<bool>,
+    pub require_pushed_authorization_requests: Option<bool>,
+    pub require_signed_request_object: Option<bool>,
+    #[serde(default)]
+    pub post_logout_redirect_uris: Vec<String>,
+    pub backchannel_logout_uri: Option<String>,
+    pub token_endpoint_auth_method: Option<String>,
+    pub jwks: Option<JwkSet>,
+    /// Drop requested ZK scopes outside `allowed_scopes` instead of failing
+    pub downscope_unapproved_scopes: Option<bool>,
+    pub id_token_encrypted_response_alg: Option<String>,
+    pub id_token_encrypted_response_enc: Option<String>,
+    pub userinfo_encrypted_response_alg: Option<String>,
+    pub userinfo_encrypted_response_enc: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateClientResponse {
+    pub client_id: String,
+    /// Generated secret for secret-based clients; returned only once
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
 }
 
 /// `POST /admin/clients`
@@ -163,13 +190,93 @@ pub async fn create_client(
     State(state): State<AppState>,
     Json(body): Json<CreateClientRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    // Only accept algorithms the provider can actually sign with
+    if let Some(ref alg) = body.id_token_signed_response_alg {
+        JwsAlgorithm::parse(alg).ok_or(StatusCode::BAD_REQUEST)?;
+    }
+    if !valid_scopes(&body.allowed_scopes) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let client_type = body.client_type.unwrap_or_else(|| "confidential".into());
+    let auth_method = match body.token_endpoint_auth_method.as_deref() {
+        Some(method) => TokenEndpointAuthMethod::parse(method).ok_or(StatusCode::BAD_REQUEST)?,
+        None if client_type == "public" => TokenEndpointAuthMethod::None,
+        None => TokenEndpointAuthMethod::ClientSecretBasic,
+    };
+    if (client_type == "public") != (auth_method == TokenEndpointAuthMethod::None) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // private_key_jwt clients need keys to verify their assertions against,
+    // as do clients that sign their request objects
+    let has_keys = body.jwks.as_ref().is_some_and(|jwks| !jwks.keys.is_empty());
+    if (auth_method == TokenEndpointAuthMethod::PrivateKeyJwt
+        || body.require_signed_request_object == Some(true))
+        && !has_keys
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // Encrypted responses need a registered key of the right type
+    for (alg, enc) in [
+        (
+            &body.id_token_encrypted_response_alg,
+            &body.id_token_encrypted_response_enc,
+        ),
+        (
+            &body.userinfo_encrypted_response_alg,
+            &body.userinfo_encrypted_response_enc,
+        ),
+    ] {
+        if let Some(params) =
+            JweParams::parse(alg.as_deref(), enc.as_deref()).map_err(|_| StatusCode::BAD_REQUEST)?
+        {
+            let jwks = body.jwks.as_ref().ok_or(StatusCode::BAD_REQUEST)?;
+            params.alg.select_key(jwks).ok_or(StatusCode::BAD_REQUEST)?;
+        }
+    }
+
+    let client_secret = auth_method
+        .uses_secret()
+        .then(fantasma_crypto::generate_secret);
+    let client_secret_hash = client_secret
+        .as_deref()
+        .map(fantasma_crypto::hash_secret)
+        .transpose()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(String::into_bytes);
+    let jwks = body
+        .jwks
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
     let new_client = fantasma_db::NewClient {
-        client_id: body.client_id,
-        client_secret_hash: None,
+        client_id: body.client_id.clone(),
+        client_secret_hash,
         client_name: body.client_name,
         redirect_uris: body.redirect_uris,
         allowed_scopes: body.allowed_scopes,
-        client_type: body.client_type.unwrap_or_else(|| "confidential".into()),
+        client_type,
+        id_token_signed_response_alg: body.id_token_signed_response_alg,
+        require_pkce_s256: body.require_pkce_s256.unwrap_or(false),
+        token_endpoint_auth_method: Some(auth_method.as_str().to_string()),
+        jwks,
+        jwks_uri: None,
+        logo_uri: None,
+        registration_access_token_hash: None,
+        sector_identifier_uri: None,
+        require_pushed_authorization_requests: body
+            .require_pushed_authorization_requests
+            .unwrap_or(false),
+        require_signed_request_object: body.require_signed_request_object.unwrap_or(false),
+        post_logout_redirect_uris: body.post_logout_redirect_uris,
+        backchannel_logout_uri: body.backchannel_logout_uri,
+        backchannel_logout_session_required: false,
+        downscope_unapproved_scopes: body.downscope_unapproved_scopes.unwrap_or(false),
+        id_token_encrypted_response_alg: body.id_token_encrypted_response_alg,
+        id_token_encrypted_response_enc: body.id_token_encrypted_response_enc,
+        userinfo_encrypted_response_alg: body.userinfo_encrypted_response_alg,
+        userinfo_encrypted_response_enc: body.userinfo_encrypted_response_enc,
     };
 
     state
@@ -177,7 +284,51 @@ pub async fn create_client(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(StatusCode::CREATED)
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateClientResponse {
+            client_id: body.client_id,
+            client_secret,
+        }),
+    ))
+}
+
+/// Scopes must be ones the provider understands
+fn valid_scopes(scopes: &[String]) -> bool {
+    scopes.iter().all(|scope| ZkScope::parse(scope).is_some())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScopePolicyRequest {
+    /// The ZK scopes the client is approved for
+    pub allowed_scopes: Vec<String>,
+    /// Drop requested ZK scopes outside `allowed_scopes` instead of failing
+    #[serde(default)]
+    pub downscope_unapproved_scopes: bool,
+}
+
+/// `PUT /admin/clients/:id/scopes`
+pub async fn update_client_scopes(
+    State(state): State<AppState>,
+    Path(client_id): Path<String>,
+    Json(body): Json<ScopePolicyRequest>,
+) -> Result<Json<fantasma_db::Client>, StatusCode> {
+    let repos = state.repos().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    if !valid_scopes(&body.allowed_scopes) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let client = repos
+        .clients()
+        .update_scope_policy(
+            &client_id,
+            &body.allowed_scopes,
+            body.downscope_unapproved_scopes,
+        )
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(client))
 }
 
 /// `DELETE /admin/clients/:id`
@@ -263,6 +414,8 @@ pub struct CreateIssuerRequest {
     pub public_key_algorithm: String,
     pub verification_url: Option<String>,
     pub trusted: Option<bool>,
+    /// Trust tier of the issuer's credentials; `trust_framework` if absent
+    pub trust_tier: Option<IssuerTrust>,
 }
 
 /// `POST /admin/issuers`
@@ -281,6 +434,11 @@ pub async fn create_issuer(
         public_key_algorithm: body.public_key_algorithm,
         verification_url: body.verification_url,
         trusted: body.trusted.unwrap_or(false),
+        trust_tier: body
+            .trust_tier
+            .unwrap_or(IssuerTrust::TrustFramework)
+            .as_str()
+            .to_string(),
     };
 
     repos
@@ -308,6 +466,94 @@ pub async fn delete_issuer(
     Ok(StatusCode::NO_CONTENT)
 }
 
+// ── Signing keys ────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct SigningKeyResponse {
+    pub kid: String,
+    pub algorithm: JwsAlgorithm,
+    pub status: KeyStatus,
+    pub activates_at: chrono::DateTime<chrono::Utc>,
+    pub retired_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Whether the key is in the JWKS
+    pub published: bool,
+}
+
+fn signing_key_list(state: &AppState) -> Vec<SigningKeyResponse> {
+    let now = chrono::Utc::now();
+    let retention = state.signing_keys.retention();
+    state
+        .signing_keys
+        .generations()
+        .into_iter()
+        .map(|g| SigningKeyResponse {
+            kid: g.key.kid().to_string(),
+            algorithm: g.key.algorithm(),
+            status: g.status,
+            activates_at: g.activates_at,
+            retired_at: g.retired_at,
+            published: g.is_published(now, retention),
+        })
+        .collect()
+}
+
+/// `GET /admin/keys`
+pub async fn list_signing_keys(State(state): State<AppState>) -> Json<Vec<SigningKeyResponse>> {
+    Json(signing_key_list(&state))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateKeysRequest {
+    /// Algorithm to rotate; all of them if absent
+    pub algorithm: Option<JwsAlgorithm>,
+    /// When the next key takes over; immediately if absent
+    pub activates_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// `POST /admin/keys/rotate`
+///
+/// Activates the next key now, or schedules it for `activates_at`.
+pub async fn rotate_signing_keys(
+    State(state): State<AppState>,
+    Json(body): Json<RotateKeysRequest>,
+) -> Result<Json<Vec<SigningKeyResponse>>, StatusCode> {
+    if body
+        .algorithm
+        .is_some_and(|alg| state.signing_keys.get(alg).is_none())
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let activates_at = body.activates_at.unwrap_or_else(chrono::Utc::now);
+    state
+        .key_rotation
+        .rotate(body.algorithm, activates_at)
+        .map_err(|e| {
+            tracing::error!("Signing key rotation failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(signing_key_list(&state)))
+}
+
+/// `DELETE /admin/keys/:kid`
+///
+/// Emergency revocation: the key leaves the JWKS at once, so tokens it
+/// signed stop verifying. An active key is replaced first.
+pub async fn revoke_signing_key(
+    State(state): State<AppState>,
+    Path(kid): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    match state.key_rotation.revoke(&kid) {
+        Ok(Some(_)) => Ok(StatusCode::NO_CONTENT),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Revoking signing key {} failed: {}", kid, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 // ── Audit log ───────────────────────────────────────────────────
 
 /// `GET /admin/audit`
@@ -0,0 +1,235 @@
+//! Client authentication for the token endpoint
+//!
+//! Supports `client_secret_basic`, `client_secret_post`, `private_key_jwt`
+//! and `none` (public clients). Secrets are checked against the client's
+//! Argon2 hash; assertions against the client's registered JWKS.
+
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::Json,
+};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use fantasma_oidc::client_auth::{
+    assertion_subject, verify_client_assertion, CLIENT_ASSERTION_TYPE_JWT_BEARER,
+};
+use fantasma_oidc::{JwkSet, TokenEndpointAuthMethod};
+use serde::Deserialize;
+
+use crate::state::{AppState, ClientInfo};
+
+/// Client credentials carried in a token endpoint form body
+#[derive(Debug, Default, Deserialize)]
+pub struct ClientCredentials {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub client_assertion_type: Option<String>,
+    pub client_assertion: Option<String>,
+}
+
+/// Error response for failed client authentication
+pub type ClientAuthError = (StatusCode, Json<serde_json::Value>);
+
+fn invalid_client(description: &str) -> ClientAuthError {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(serde_json::json!({
+            "error": "invalid_client",
+            "error_description": description
+        })),
+    )
+}
+
+fn invalid_request(description: &str) -> ClientAuthError {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "error": "invalid_request",
+            "error_description": description
+        })),
+    )
+}
+
+/// Parse `Authorization: Basic` credentials (RFC 6749 §2.3.1)
+///
+/// The client_id and secret are form-urlencoded before base64 encoding.
+fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let encoded = value
+        .strip_prefix("Basic ")
+        .or_else(|| value.strip_prefix("basic "))?;
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (id, secret) = decoded.split_once(':')?;
+
+    let unescape = |s: &str| -> Option<String> {
+        url::form_urlencoded::parse(format!("v={}", s).as_bytes())
+            .next()
+            .map(|(_, v)| v.into_owned())
+    };
+    Some((unescape(id)?, unescape(secret)?))
+}
+
+/// Authenticate the client making a token endpoint request
+///
+/// Exactly one authentication method may be used. Secret clients may use
+/// either Basic or form-post secrets; `private_key_jwt` clients must send an
+/// assertion, and public clients must only identify themselves.
+pub async fn authenticate_client(
+    state: &AppState,
+    headers: &HeaderMap,
+    credentials: &ClientCredentials,
+) -> Result<ClientInfo, ClientAuthError> {
+    let basic = basic_credentials(headers);
+    if headers.contains_key(header::AUTHORIZATION) && basic.is_none() {
+        return Err(invalid_client("malformed Authorization header"));
+    }
+
+    let methods_used = [
+        basic.is_some(),
+        credentials.client_secret.is_some(),
+        credentials.client_assertion.is_some(),
+    ]
+    .into_iter()
+    .filter(|used| *used)
+    .count();
+    if methods_used > 1 {
+        return Err(invalid_request(
+            "multiple client authentication methods used",
+        ));
+    }
+
+    // Identify the client
+    let asserted_id = credentials.client_assertion.as_deref().map(|assertion| {
+        assertion_subject(assertion).ok_or_else(|| invalid_client("malformed client_assertion"))
+    });
+    let client_id = match (&basic, asserted_id) {
+        (Some((id, _)), _) => id.clone(),
+        (None, Some(asserted)) => asserted?,
+        (None, None) => credentials
+            .client_id
+            .clone()
+            .ok_or_else(|| invalid_client("client authentication required"))?,
+    };
+    if credentials
+        .client_id
+        .as_deref()
+        .is_some_and(|id| id != client_id)
+    {
+        return Err(invalid_client("client_id does not match the credentials"));
+    }
+
+    let client = state
+        .get_client(&client_id)
+        .await
+        .ok_or_else(|| invalid_client("unknown client"))?;
+
+    let method = client.token_endpoint_auth_method;
+    if let Some((_, secret)) = basic {
+        verify_secret(&client, &secret)?;
+    } else if let Some(ref secret) = credentials.client_secret {
+        verify_secret(&client, secret)?;
+    } else if let Some(ref assertion) = credentials.client_assertion {
+        if method != TokenEndpointAuthMethod::PrivateKeyJwt {
+            return Err(invalid_client(
+                "client is not registered for private_key_jwt",
+            ));
+        }
+        if credentials.client_assertion_type.as_deref() != Some(CLIENT_ASSERTION_TYPE_JWT_BEARER) {
+            return Err(invalid_request("unsupported client_assertion_type"));
+        }
+        let jwks = match (&client.jwks, &client.jwks_uri) {
+            (Some(jwks), _) => jwks.clone(),
+            (None, Some(uri)) => fetch_jwks(uri)
+                .await
+                .ok_or_else(|| invalid_client("could not fetch the client's jwks_uri"))?,
+            (None, None) => return Err(invalid_client("client has no registered JWKS")),
+        };
+
+        let token_endpoint = state.config.endpoint_url(&state.config.token_endpoint);
+        let claims = verify_client_assertion(
+            assertion,
+            &client.client_id,
+            &jwks,
+            &[&token_endpoint, &state.config.issuer],
+        )
+        .map_err(|e| invalid_client(&format!("invalid client_assertion: {}", e)))?;
+
+        // Each assertion may be used only once (RFC 7523 §3)
+        let expires_at = chrono::DateTime::from_timestamp(claims.exp, 0).unwrap_or_default();
+        if !state
+            .replay_cache
+            .check_and_insert(
+                format!("client_assertion:{}:{}", client_id, claims.jti),
+                expires_at,
+            )
+            .await
+        {
+            return Err(invalid_client("client_assertion has already been used"));
+        }
+    } else if method != TokenEndpointAuthMethod::None {
+        return Err(invalid_client("client authentication required"));
+    }
+
+    Ok(client)
+}
+
+/// The client's registered keys, fetched from its `jwks_uri` if not inline
+pub(crate) async fn client_jwks(client: &ClientInfo) -> Option<JwkSet> {
+    match (&client.jwks, &client.jwks_uri) {
+        (Some(jwks), _) => Some(jwks.clone()),
+        (None, Some(uri)) => fetch_jwks(uri).await,
+        (None, None) => None,
+    }
+}
+
+/// Fetch a client's keys from its registered `jwks_uri`
+async fn fetch_jwks(uri: &str) -> Option<JwkSet> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .ok()?;
+    let response = client.get(uri).send().await.ok()?;
+    if !response.status().is_success() {
+        tracing::warn!("jwks_uri {} returned {}", uri, response.status());
+        return None;
+    }
+    response.json().await.ok()
+}
+
+fn verify_secret(client: &ClientInfo, secret: &str) -> Result<(), ClientAuthError> {
+    if !client.token_endpoint_auth_method.uses_secret() {
+        return Err(invalid_client(
+            "client is not registered for secret authentication",
+        ));
+    }
+    let hash = client
+        .client_secret_hash
+        .as_deref()
+        .ok_or_else(|| invalid_client("client has no secret"))?;
+
+    if fantasma_crypto::verify_secret(secret, hash) {
+        Ok(())
+    } else {
+        Err(invalid_client("invalid client credentials"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_credentials_are_form_decoded() {
+        let mut headers = HeaderMap::new();
+        let encoded = STANDARD.encode("my%3Aclient:s%2Bcret");
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Basic {}", encoded).parse().unwrap(),
+        );
+
+        assert_eq!(
+            basic_credentials(&headers),
+            Some(("my:client".to_string(), "s+cret".to_string()))
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Device authorization grant (RFC 8628)
+//!
+//! The client starts at `/device_authorization` and polls the token endpoint
+//! with the device code. The user enters the user code at `/device`, which
+//! runs the same consent page as `/authorize`, proof request included;
+//! approving records the subject, scopes and proven claims on the device
+//! authorization instead of issuing a code.
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Json, Response},
+};
+use fantasma_core::proof::{ProofRequest, ProofResponse};
+use fantasma_oidc::device::DeviceAuthorizationResponse;
+use fantasma_oidc::scopes::parse_scopes;
+use serde::{Deserialize, Serialize};
+
+use crate::client_auth::{authenticate_client, ClientCredentials};
+use crate::proofs::{proof_request_claims, verify_proof_response};
+use crate::routes::{
+    build_demo_users_html, build_hidden_fields, build_permissions_html, demo_user_claims,
+    html_escape, DemoUser, AUTHORIZE_TEMPLATE,
+};
+use crate::session::{session_cookie, session_id};
+use crate::state::{AppState, DeviceApproval};
+
+/// Device authorization request parameters (RFC 8628 §3.1)
+#[derive(Debug, Deserialize)]
+pub struct DeviceAuthorizationParams {
+    pub scope: Option<String>,
+    #[serde(flatten)]
+    pub client: ClientCredentials,
+}
+
+/// Device authorization endpoint (RFC 8628 §3.1)
+pub async fn device_authorization(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::Form(params): axum::Form<DeviceAuthorizationParams>,
+) -> Result<Json<DeviceAuthorizationResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let client = authenticate_client(&state, &headers, &params.client).await?;
+
+    // Without a redirect URI, the subject's sector comes from the registration
+    if client.default_sector_identifier().is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "unauthorized_client",
+                "error_description": "client has no single sector for pairwise subjects"
+            })),
+        ));
+    }
+
+    let mut scope = params.scope.unwrap_or_else(|| "openid".to_string());
+    client.restrict_scopes(&mut scope, &mut None).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "invalid_scope",
+                "error_description": e
+            })),
+        )
+    })?;
+    let scopes = parse_scopes(&scope).iter().map(|s| s.to_string()).collect();
+    let (device_code, user_code) = state
+        .create_device_authorization(client.client_id, scopes)
+        .await;
+
+    let verification_uri = state
+        .config
+        .endpoint_url(&state.config.device_verification_endpoint);
+    let verification_uri_complete = format!(
+        "{}?user_code={}",
+        verification_uri,
+        url::form_urlencoded::byte_serialize(user_code.as_bytes()).collect::<String>()
+    );
+
+    Ok(Json(DeviceAuthorizationResponse {
+        device_code,
+        user_code,
+        verification_uri,
+        verification_uri_complete,
+        expires_in: state.config.device_code_expiration_seconds,
+        interval: state.config.device_code_interval_seconds,
+    }))
+}
+
+/// Verification page query
+#[derive(Debug, Deserialize)]
+pub struct DeviceVerificationQuery {
+    pub user_code: Option<String>,
+}
+
+/// Page asking for the user code
+fn user_code_page(message: Option<&str>) -> String {
+    format!(
+        r#"<h1>Connect a device</h1>{}
+<form action="/device" method="get">
+    <label>Enter the code shown on your device:
+        <input type="text" name="user_code" autocomplete="off" autofocus>
+    </label>
+    <button type="submit">Continue</button>
+</form>"#,
+        message
+            .map(|m| format!("<p>{}</p>", html_escape(m)))
+            .unwrap_or_default()
+    )
+}
+
+/// What a device's proof request is kept with until consent
+#[derive(Debug, Serialize, Deserialize)]
+struct DeviceProofRequestParams {
+    user_code: String,
+}
+
+/// Verification page (RFC 8628 §3.3)
+///
+/// Asks for the user code, then shows the consent page for the device's
+/// authorization request, with a proof request for the wallet.
+pub async fn device_verification(
+    State(state): State<AppState>,
+    Query(query): Query<DeviceVerificationQuery>,
+) -> Html<String> {
+    let Some(user_code) = query.user_code else {
+        return Html(user_code_page(None));
+    };
+    let Some(authorization) = state.find_device_authorization(&user_code).await else {
+        return Html(user_code_page(Some("Invalid or expired code")));
+    };
+
+    let client = state.get_client(&authorization.client_id).await;
+    let Some(sector) = client.as_ref().and_then(|c| c.default_sector_identifier()) else {
+        return Html(user_code_page(Some(
+            "The device's client is no longer registered",
+        )));
+    };
+    let client_name = client
+        .map(|c| c.name)
+        .unwrap_or_else(|| authorization.client_id.clone());
+
+    let scope = authorization.scopes.join(" ");
+    let scopes = parse_scopes(&scope);
+
+    // The wallet answers the proof request like on the `/authorize` consent
+    // page; the request is kept with the user code it was shown for
+    let proof_request = ProofRequest::new(
+        sector.clone(),
+        proof_request_claims(&scope, &[]),
+        state.config.proof_request_expiration_seconds as i64,
+    );
+    let request_params = DeviceProofRequestParams {
+        user_code: authorization.user_code.clone(),
+    };
+    state
+        .create_proof_request(
+            &authorization.client_id,
+            &proof_request,
+            serde_json::to_value(&request_params).unwrap_or_default(),
+        )
+        .await;
+
+    let mut hidden_fields = format!(
+        r#"<input type="hidden" name="user_code" value="{}">"#,
+        html_escape(&authorization.user_code)
+    );
+    hidden_fields.push_str(&build_hidden_fields(&sector, &proof_request));
+
+    let html = AUTHORIZE_TEMPLATE
+        .replace("{{FORM_ACTION}}", "/device/consent")
+        .replace("{{CLIENT_NAME}}", &html_escape(&client_name))
+        .replace("{{PERMISSIONS}}", &build_permissions_html(&scopes))
+        .replace("{{USER_OPTIONS}}", &build_demo_users_html(&state, &scopes))
+        .replace("{{HIDDEN_FIELDS}}", &hidden_fields)
+        .replace("{{DENY_URL}}", "/device")
+        // OpenID4VP answers end in a redirect to the client, which a device
+        // does not have
+        .replace("{{WALLET_LINK}}", "");
+
+    Html(html)
+}
+
+/// Device consent parameters
+#[derive(Debug, Deserialize)]
+pub struct DeviceConsentParams {
+    pub action: String,
+    pub user_code: String,
+    /// ID of the proof request shown on the consent page
+    pub request_id: String,
+    /// Selected demo user for testing
+    pub demo_user: Option<String>,
+    /// The wallet's answer to the proof request (JSON `ProofResponse`)
+    pub proof_response: Option<String>,
+}
+
+/// Device consent confirmation (accepts form data via POST)
+///
+/// Approval joins the browser's session like `/authorize/consent` does; the
+/// device picks up the result on its next poll.
+pub async fn device_consent(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::Form(params): axum::Form<DeviceConsentParams>,
+) -> Response {
+    let error = |description: &str| {
+        (
+            StatusCode::BAD_REQUEST,
+            Html(user_code_page(Some(description))),
+        )
+            .into_response()
+    };
+
+    let Some(authorization) = state.find_device_authorization(&params.user_code).await else {
+        return error("Invalid or expired code");
+    };
+    let Some(pending) = state.take_proof_request(&params.request_id).await else {
+        return error("Invalid or expired code");
+    };
+    let shown_for = serde_json::from_value::<DeviceProofRequestParams>(pending.params.clone())
+        .map(|request_params| request_params.user_code);
+    if pending.client_id != authorization.client_id
+        || shown_for.ok().as_deref() != Some(authorization.user_code.as_str())
+    {
+        return error("Invalid or expired code");
+    }
+
+    if params.action != "approve" && params.action != "allow" {
+        state
+            .complete_device_authorization(&params.user_code, None)
+            .await;
+        return Html("<h1>Access denied</h1><p>The device was not connected.</p>".to_string())
+            .into_response();
+    }
+
+    let Some(sector) = state
+        .get_client(&authorization.client_id)
+        .await
+        .and_then(|c| c.default_sector_identifier())
+    else {
+        return error("The device's client is no longer registered");
+    };
+
+    let proof_response = match params
+        .proof_response
+        .as_deref()
+        .map(serde_json::from_str::<ProofResponse>)
+        .transpose()
+    {
+        Ok(response) => response,
+        Err(e) => return error(&format!("Malformed proof response: {}", e)),
+    };
+    let scope = authorization.scopes.join(" ");
+    let verified_claims = match proof_response {
+        Some(ref response) => {
+            match verify_proof_response(&state, &pending, &sector, &scope, response).await {
+                Ok(zk_claims) => Some(zk_claims),
+                Err(e) => {
+                    tracing::warn!("Rejected proof response: {}", e);
+                    return error(&format!("The proofs were not accepted: {}", e));
+                }
+            }
+        }
+        None if state.config.demo_users => None,
+        None => return error("The wallet did not answer the proof request"),
+    };
+
+    // The wallet's proofs are bound to the subject it presents; demo users
+    // stand in for a wallet
+    let demo_user = DemoUser::find(params.demo_user.as_deref());
+    let subject_id = match proof_response {
+        Some(response) => response.subject_id,
+        None => demo_user.pairwise_subject(&sector),
+    };
+
+    let granted_scopes = authorization.scopes.clone();
+    let mut scopes = authorization.scopes;
+    let granted_claims = match verified_claims {
+        Some(ref zk_claims) => zk_claims.clone(),
+        None => {
+            scopes.push(format!("demo_user:{}", demo_user.id));
+            demo_user_claims(&scopes, &[]).unwrap_or_default()
+        }
+    };
+
+    let sid = state
+        .join_session(session_id(&headers), &authorization.client_id, &subject_id)
+        .await;
+    if !state
+        .complete_device_authorization(
+            &params.user_code,
+            Some(DeviceApproval {
+                subject_id: subject_id.clone(),
+                scopes,
+                sid: Some(sid.clone()),
+                zk_claims: verified_claims.clone(),
+            }),
+        )
+        .await
+    {
+        return error("Invalid or expired code");
+    }
+    state
+        .record_consent(
+            &subject_id,
+            &authorization.client_id,
+            &sector,
+            granted_scopes,
+            granted_claims,
+        )
+        .await;
+
+    if verified_claims.is_some() {
+        tracing::info!(
+            "Device authorization granted on wallet proofs to client '{}'",
+            authorization.client_id
+        );
+    } else {
+        tracing::info!(
+            "Device authorization granted for demo user '{}' to client '{}'",
+            demo_user.name,
+            authorization.client_id
+        );
+    }
+
+    (
+        [(header::SET_COOKIE, session_cookie(&state, &sid))],
+        Html("<h1>Device connected</h1><p>You can return to your device.</p>".to_string()),
+    )
+        .into_response()
+}
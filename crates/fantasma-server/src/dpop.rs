@@ -0,0 +1,221 @@
+//! DPoP proof checking for the token and userinfo endpoints (RFC 9449)
+//!
+//! Every proof must carry a nonce issued by this server (§8); requests
+//! without one are answered with `use_dpop_nonce` and a fresh nonce in the
+//! `DPoP-Nonce` header. Nonces stay valid for a few minutes and may be
+//! reused until then. Proof `jti`s go through the replay cache, so each
+//! proof is accepted once.
+
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use fantasma_oidc::dpop::{
+    verify_proof, DpopError, DpopProof, DPOP_HEADER, DPOP_NONCE_HEADER, PROOF_ALGORITHMS,
+    PROOF_MAX_AGE_SECONDS,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::state::AppState;
+
+/// How long an issued nonce is accepted
+const NONCE_LIFETIME_SECONDS: i64 = 300;
+
+/// Nonces issued to DPoP clients (in memory, like the replay cache)
+#[derive(Clone, Default)]
+pub struct DpopNonces {
+    issued: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+}
+
+impl DpopNonces {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issue a new nonce
+    pub async fn issue(&self) -> String {
+        let now = Utc::now();
+        let nonce = uuid::Uuid::new_v4().to_string();
+        let mut issued = self.issued.write().await;
+
+        issued.retain(|_, exp| *exp > now);
+        issued.insert(
+            nonce.clone(),
+            now + chrono::Duration::seconds(NONCE_LIFETIME_SECONDS),
+        );
+        nonce
+    }
+
+    /// Whether `nonce` was issued here and has not expired
+    pub async fn is_valid(&self, nonce: &str) -> bool {
+        self.issued
+            .read()
+            .await
+            .get(nonce)
+            .is_some_and(|exp| *exp > Utc::now())
+    }
+}
+
+/// Check the DPoP proof sent with a request, if any
+///
+/// `endpoint` is the endpoint path; the proof's `htu` must be its public
+/// URL. Proofs sent with an access token must carry its hash.
+pub async fn check_proof(
+    state: &AppState,
+    headers: &HeaderMap,
+    method: &str,
+    endpoint: &str,
+    access_token: Option<&str>,
+) -> Result<Option<DpopProof>, DpopError> {
+    let mut values = headers.get_all(DPOP_HEADER).iter();
+    let proof = match (values.next(), values.next()) {
+        (None, _) => return Ok(None),
+        (Some(value), None) => value
+            .to_str()
+            .map_err(|_| DpopError::InvalidProof("malformed DPoP header".to_string()))?,
+        (Some(_), Some(_)) => {
+            return Err(DpopError::InvalidProof(
+                "only one DPoP proof may be sent".to_string(),
+            ))
+        }
+    };
+
+    let htu = state.config.endpoint_url(endpoint);
+    let proof = verify_proof(proof, method, &htu, access_token)?;
+
+    match proof.claims.nonce.as_deref() {
+        Some(nonce) if state.dpop_nonces.is_valid(nonce).await => {}
+        _ => return Err(DpopError::UseNonce),
+    }
+
+    let expires_at =
+        DateTime::from_timestamp(proof.claims.iat + PROOF_MAX_AGE_SECONDS, 0).unwrap_or_default();
+    if !state
+        .replay_cache
+        .check_and_insert(
+            format!("dpop:{}:{}", proof.jkt, proof.claims.jti),
+            expires_at,
+        )
+        .await
+    {
+        return Err(DpopError::InvalidProof(
+            "DPoP proof has already been used".to_string(),
+        ));
+    }
+
+    Ok(Some(proof))
+}
+
+/// Check that an access token is presented as bound, or as not bound
+///
+/// `dpop_scheme` says whether the token came with the DPoP scheme, and
+/// `dpop_jkt` is the thumbprint of the key the token is bound to. Bound
+/// tokens need the DPoP scheme and a proof from that key (RFC 9449 §7.1);
+/// unbound tokens must not be sent as DPoP tokens.
+pub(crate) async fn check_binding(
+    state: &AppState,
+    headers: &HeaderMap,
+    method: &str,
+    endpoint: &str,
+    access_token: &str,
+    dpop_scheme: bool,
+    dpop_jkt: Option<&str>,
+) -> Result<(), Response> {
+    let Some(jkt) = dpop_jkt else {
+        if dpop_scheme {
+            return Err(
+                resource_error(state, "invalid_token", "access token is not DPoP-bound").await,
+            );
+        }
+        return Ok(());
+    };
+    if !dpop_scheme {
+        return Err(resource_error(state, "invalid_token", "access token is DPoP-bound").await);
+    }
+
+    match check_proof(state, headers, method, endpoint, Some(access_token)).await {
+        Ok(Some(proof)) if proof.jkt == jkt => Ok(()),
+        Ok(Some(_)) => Err(resource_error(
+            state,
+            "invalid_dpop_proof",
+            "DPoP proof is signed with another key",
+        )
+        .await),
+        Ok(None) => {
+            Err(resource_error(state, "invalid_dpop_proof", "DPoP proof is required").await)
+        }
+        Err(e) => Err(resource_error(state, e.error_code(), &e.to_string()).await),
+    }
+}
+
+/// Extract the access token from an `Authorization: DPoP` header
+pub(crate) fn dpop_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("DPoP ")
+        .map(str::trim)
+}
+
+/// Attach a fresh nonce for the client's next proof
+pub(crate) async fn with_nonce(state: &AppState, response: impl IntoResponse) -> Response {
+    let nonce = state.dpop_nonces.issue().await;
+    ([(DPOP_NONCE_HEADER, nonce)], response).into_response()
+}
+
+/// Error response for a rejected proof at the token endpoint (RFC 9449 §8)
+pub(crate) async fn token_error(state: &AppState, error: DpopError) -> Response {
+    let body = Json(serde_json::json!({
+        "error": error.error_code(),
+        "error_description": error.to_string()
+    }));
+    with_nonce(state, (StatusCode::BAD_REQUEST, body)).await
+}
+
+/// Error response for a protected resource (RFC 9449 §7.1)
+///
+/// The `WWW-Authenticate` challenge uses the DPoP scheme and lists the
+/// proof algorithms; `use_dpop_nonce` responses carry a fresh nonce.
+pub(crate) async fn resource_error(state: &AppState, code: &str, description: &str) -> Response {
+    let algs: Vec<&str> = PROOF_ALGORITHMS.iter().map(|alg| alg.as_str()).collect();
+    let challenge = format!(
+        r#"DPoP algs="{}", error="{}", error_description="{}""#,
+        algs.join(" "),
+        code,
+        description
+    );
+    let body = Json(serde_json::json!({
+        "error": code,
+        "error_description": description
+    }));
+    let response = (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, challenge)],
+        body,
+    );
+
+    if code == DpopError::UseNonce.error_code() {
+        with_nonce(state, response).await
+    } else {
+        response.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_nonces() {
+        let nonces = DpopNonces::new();
+        let nonce = nonces.issue().await;
+
+        assert!(nonces.is_valid(&nonce).await);
+        assert!(nonces.is_valid(&nonce).await);
+        assert!(!nonces.is_valid("made-up").await);
+    }
+}
@@ -0,0 +1,194 @@
+//! Grant management
+//!
+//! Every consent is remembered as a grant per pairwise subject and client
+//! (see [`AppState::record_consent`]), and re-authorizing within a grant
+//! skips the consent page. Users list, narrow and revoke their grants here
+//! to see which relying parties hold which ZK claims.
+//!
+//! The provider cannot link a user's pairwise subjects to each other, so the
+//! browser session stands in for the user: its grants are those of the
+//! subjects it signed in to clients as. Narrowing or revoking a grant revokes
+//! the refresh and access tokens the client holds for the subject.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use fantasma_oidc::claims::ZkClaims;
+use serde::{Deserialize, Serialize};
+
+use crate::session::session_id;
+use crate::state::{AppState, ConsentGrant};
+
+type GrantError = (StatusCode, Json<serde_json::Value>);
+
+fn grant_error(status: StatusCode, error: &str, description: &str) -> GrantError {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": error,
+            "error_description": description
+        })),
+    )
+}
+
+/// A grant as shown to the user
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GrantResponse {
+    pub grant_id: uuid::Uuid,
+    pub client_id: String,
+    pub client_name: String,
+    pub scopes: Vec<String>,
+    /// The ZK claims shared with the client
+    pub zk_claims: ZkClaims,
+    pub zk_claims_verified_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+async fn grant_response(state: &AppState, grant: ConsentGrant) -> GrantResponse {
+    let client_name = state
+        .get_client(&grant.client_id)
+        .await
+        .map(|c| c.name)
+        .unwrap_or_else(|| grant.client_id.clone());
+    GrantResponse {
+        grant_id: grant.id,
+        client_id: grant.client_id,
+        client_name,
+        scopes: grant.scopes,
+        zk_claims: grant.zk_claims,
+        zk_claims_verified_at: grant.zk_claims_verified_at,
+        created_at: grant.created_at,
+        updated_at: grant.updated_at,
+    }
+}
+
+/// The (client, subject) pairs of the request's browser session
+async fn session_subjects(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Vec<(String, String)>, GrantError> {
+    let unauthorized = || {
+        grant_error(
+            StatusCode::UNAUTHORIZED,
+            "login_required",
+            "no active browser session",
+        )
+    };
+    let sid = session_id(headers).ok_or_else(unauthorized)?;
+    let clients = state.session_clients(sid).await;
+    if clients.is_empty() {
+        return Err(unauthorized());
+    }
+    Ok(clients)
+}
+
+/// The grant `id`, if it is one of the session's grants
+async fn session_grant(
+    state: &AppState,
+    headers: &HeaderMap,
+    id: &str,
+) -> Result<ConsentGrant, GrantError> {
+    let subjects = session_subjects(state, headers).await?;
+    let not_found = || grant_error(StatusCode::NOT_FOUND, "not_found", "unknown grant");
+    let id = uuid::Uuid::parse_str(id).map_err(|_| not_found())?;
+    let grant = state.find_consent_grant(id).await.ok_or_else(not_found)?;
+    if !subjects.iter().any(|(client_id, subject_id)| {
+        *client_id == grant.client_id && *subject_id == grant.subject_id
+    }) {
+        return Err(not_found());
+    }
+    Ok(grant)
+}
+
+/// `GET /grants`
+pub async fn list_grants(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<GrantResponse>>, GrantError> {
+    let mut grants = Vec::new();
+    for (client_id, subject_id) in session_subjects(&state, &headers).await? {
+        if let Some(grant) = state.find_consent(&subject_id, &client_id).await {
+            grants.push(grant_response(&state, grant).await);
+        }
+    }
+    Ok(Json(grants))
+}
+
+/// `GET /grants/:id`
+pub async fn get_grant(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<GrantResponse>, GrantError> {
+    let grant = session_grant(&state, &headers, &id).await?;
+    Ok(Json(grant_response(&state, grant).await))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateGrantRequest {
+    /// The scopes to keep
+    pub scopes: Vec<String>,
+}
+
+/// `PATCH /grants/:id`
+///
+/// Narrows the grant to fewer scopes. Widening it takes a new consent, with
+/// fresh proofs for the added claims.
+pub async fn update_grant(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateGrantRequest>,
+) -> Result<Json<GrantResponse>, GrantError> {
+    let grant = session_grant(&state, &headers, &id).await?;
+    if body.scopes.is_empty() {
+        return Err(grant_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "a grant keeps at least one scope; revoke it instead",
+        ));
+    }
+    if let Some(scope) = body.scopes.iter().find(|s| !grant.scopes.contains(s)) {
+        return Err(grant_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_scope",
+            &format!("{} was not granted; scopes can only be removed", scope),
+        ));
+    }
+
+    let updated = state
+        .update_consent_grant(grant.id, body.scopes)
+        .await
+        .ok_or_else(|| grant_error(StatusCode::NOT_FOUND, "not_found", "unknown grant"))?;
+    // The client's tokens carry claims the grant no longer covers
+    state
+        .revoke_client_tokens(&grant.client_id, &grant.subject_id)
+        .await;
+
+    tracing::info!(
+        "Grant {} to client '{}' narrowed to {:?}",
+        updated.id,
+        updated.client_id,
+        updated.scopes
+    );
+    Ok(Json(grant_response(&state, updated).await))
+}
+
+/// `DELETE /grants/:id`
+pub async fn revoke_grant(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, GrantError> {
+    let grant = session_grant(&state, &headers, &id).await?;
+    state.delete_consent_grant(grant.id).await;
+    state
+        .revoke_client_tokens(&grant.client_id, &grant.subject_id)
+        .await;
+
+    tracing::info!("Grant {} to client '{}' revoked", grant.id, grant.client_id);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,455 @@
+//! Signing key lifecycle
+//!
+//! Each algorithm's signing key goes through three states: a `next` key is
+//! published in the JWKS ahead of its activation so relying parties cache it
+//! before it signs anything, the `active` key signs new tokens, and a
+//! `retired` key stays published until the ID tokens it signed expire. Keys
+//! rotate on a schedule (`signing_key_rotation_seconds`) or when an
+//! administrator asks to, and an emergency revoke drops a compromised key
+//! from the JWKS at once.
+//!
+//! With `FANTASMA_KEY_DIR` set, every generation is its own key store under
+//! `generations/<kid>/`, with its lifecycle in `generation.json`. A key
+//! directory from before rotation has its single key pair imported as the
+//! first active generation. ML-DSA-65 generations from before the switch to
+//! FIPS 204 hold round-3 Dilithium keys; they are dropped and replaced.
+
+use chrono::{DateTime, Duration, Utc};
+use fantasma_crypto::keystore::{KeyStoreError, Result};
+use fantasma_crypto::{Ed25519Keypair, KeyStore, MlDsa65Keypair};
+use fantasma_oidc::config::OidcConfig;
+use fantasma_oidc::jwk::Jwk;
+use fantasma_oidc::signing::{JwsAlgorithm, KeyGeneration, KeyStatus, SigningKey, SigningKeys};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Algorithms the provider always signs with, the default first
+const ALGORITHMS: [JwsAlgorithm; 2] = [JwsAlgorithm::EdDSA, JwsAlgorithm::MlDsa65];
+
+/// Lifecycle of a stored generation, kept next to its keys
+#[derive(Debug, Serialize, Deserialize)]
+struct GenerationRecord {
+    algorithm: JwsAlgorithm,
+    status: KeyStatus,
+    activates_at: DateTime<Utc>,
+    retired_at: Option<DateTime<Utc>>,
+}
+
+const GENERATION_FILE: &str = "generation.json";
+
+/// Key directory and the passphrase its secret keys are encrypted with
+struct GenerationStore {
+    store: KeyStore,
+    passphrase: String,
+}
+
+impl GenerationStore {
+    /// Load every stored generation
+    fn load(&self) -> Result<Vec<KeyGeneration>> {
+        let mut generations = Vec::new();
+        for name in self.store.generations()? {
+            let store = self.store.generation(&name)?;
+            let path = store.dir().join(GENERATION_FILE);
+            if !path.exists() {
+                // Interrupted before its lifecycle was written, so never
+                // published; the next sync deletes it
+                tracing::warn!(
+                    "Skipping signing key generation {} without a lifecycle",
+                    name
+                );
+                continue;
+            }
+            let record: GenerationRecord = serde_json::from_slice(&std::fs::read(path)?)
+                .map_err(|e| KeyStoreError::InvalidKey(format!("generation {}: {}", name, e)))?;
+
+            let key = match record.algorithm {
+                JwsAlgorithm::EdDSA => SigningKey::ed25519(store.load_ed25519(&self.passphrase)?),
+                JwsAlgorithm::MlDsa65 if !store.has_ml_dsa_65_keys() => {
+                    // Written before the switch to FIPS 204: its signatures
+                    // don't verify as ML-DSA-65, so the next sync deletes it
+                    tracing::warn!(
+                        "Dropping signing key generation {} with a Dilithium3 key",
+                        name
+                    );
+                    continue;
+                }
+                JwsAlgorithm::MlDsa65 => {
+                    SigningKey::ml_dsa_65(store.load_ml_dsa_65(&self.passphrase)?)
+                }
+            };
+            if key.kid() != name {
+                return Err(KeyStoreError::InvalidKey(format!(
+                    "generation {} holds key {}",
+                    name,
+                    key.kid()
+                )));
+            }
+            generations.push(KeyGeneration {
+                key: Arc::new(key),
+                status: record.status,
+                activates_at: record.activates_at,
+                retired_at: record.retired_at,
+            });
+        }
+        generations.sort_by_key(|g| g.activates_at);
+        Ok(generations)
+    }
+
+    /// Import the Ed25519 key of a single-pair key directory, generating it
+    /// if missing, with a fresh ML-DSA-65 key: the pair's Dilithium3 key is
+    /// not an ML-DSA-65 key
+    fn import_legacy(&self) -> Result<Vec<SigningKey>> {
+        let ed25519 = self.store.load_or_generate_ed25519(&self.passphrase)?;
+        Ok(vec![
+            self.save_ed25519(ed25519)?,
+            self.save_ml_dsa(MlDsa65Keypair::generate())?,
+        ])
+    }
+
+    /// Store an Ed25519 key as a generation named by its kid
+    fn save_ed25519(&self, keypair: Ed25519Keypair) -> Result<SigningKey> {
+        let kid = Jwk::ed25519(keypair.public_key().as_bytes()).thumbprint();
+        self.store
+            .generation(&kid)?
+            .save_ed25519(&keypair, &self.passphrase)?;
+        Ok(SigningKey::ed25519(keypair))
+    }
+
+    /// Store an ML-DSA-65 key as a generation named by its kid
+    fn save_ml_dsa(&self, keypair: MlDsa65Keypair) -> Result<SigningKey> {
+        let kid = Jwk::ml_dsa_65(keypair.public_key.as_bytes()).thumbprint();
+        self.store
+            .generation(&kid)?
+            .save_ml_dsa_65(&keypair, &self.passphrase)?;
+        Ok(SigningKey::ml_dsa_65(keypair))
+    }
+
+    /// Write every generation's lifecycle and delete the generations no
+    /// longer in the key ring
+    fn sync(&self, keys: &SigningKeys) -> Result<()> {
+        let generations = keys.generations();
+        for generation in &generations {
+            let record = GenerationRecord {
+                algorithm: generation.key.algorithm(),
+                status: generation.status,
+                activates_at: generation.activates_at,
+                retired_at: generation.retired_at,
+            };
+            let json = serde_json::to_vec_pretty(&record)
+                .map_err(|e| KeyStoreError::InvalidKey(e.to_string()))?;
+            let store = self.store.generation(generation.key.kid())?;
+            std::fs::write(store.dir().join(GENERATION_FILE), json)?;
+        }
+        for name in self.store.generations()? {
+            if !generations.iter().any(|g| g.key.kid() == name) {
+                self.store.remove_generation(&name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The provider's signing keys and their rotation
+pub struct KeyRotation {
+    keys: Arc<SigningKeys>,
+    store: Option<GenerationStore>,
+    /// How long a key signs before the next one replaces it
+    rotation_period: Option<Duration>,
+    /// How long before its activation the next key is published
+    prepublish: Duration,
+    /// Serializes changes to the key ring and the key directory
+    lock: Mutex<()>,
+}
+
+impl KeyRotation {
+    /// Load the keys from `FANTASMA_KEY_DIR`, or generate ephemeral keys
+    /// when no key directory is configured
+    pub fn load(config: &OidcConfig) -> Result<Self> {
+        match std::env::var("FANTASMA_KEY_DIR") {
+            Ok(dir) if !dir.is_empty() => {
+                let passphrase = std::env::var("FANTASMA_KEY_PASSPHRASE")
+                    .unwrap_or_else(|_| "fantasma-dev-passphrase".into());
+                Self::open(Path::new(&dir), &passphrase, config)
+            }
+            _ => {
+                tracing::info!("FANTASMA_KEY_DIR not set, using ephemeral signing keys");
+                Ok(Self::ephemeral(config))
+            }
+        }
+    }
+
+    /// Fresh keys that live as long as the process
+    pub fn ephemeral(config: &OidcConfig) -> Self {
+        let keys = SigningKeys::new(vec![
+            SigningKey::generate_ed25519(),
+            SigningKey::generate_ml_dsa_65(),
+        ]);
+        Self::with_keys(keys, None, config)
+    }
+
+    /// Load the key generations stored in `dir`
+    pub fn open(dir: &Path, passphrase: &str, config: &OidcConfig) -> Result<Self> {
+        let store = GenerationStore {
+            store: KeyStore::new(dir)?,
+            passphrase: passphrase.to_string(),
+        };
+
+        let mut generations = store.load()?;
+        if generations.is_empty() {
+            generations = store
+                .import_legacy()?
+                .into_iter()
+                .map(KeyGeneration::active)
+                .collect();
+        }
+        // A key directory may predate an algorithm
+        for algorithm in ALGORITHMS {
+            if !generations
+                .iter()
+                .any(|g| g.status == KeyStatus::Active && g.key.algorithm() == algorithm)
+            {
+                generations.push(KeyGeneration::active(generate(Some(&store), algorithm)?));
+            }
+        }
+
+        let keys = SigningKeys::from_generations(generations, ALGORITHMS[0]);
+        let rotation = Self::with_keys(keys, Some(store), config);
+        rotation.maintain(Utc::now())?;
+
+        tracing::info!(
+            "Loaded {} signing key generations from {}",
+            rotation.keys.generations().len(),
+            dir.display()
+        );
+        Ok(rotation)
+    }
+
+    fn with_keys(keys: SigningKeys, store: Option<GenerationStore>, config: &OidcConfig) -> Self {
+        let retention = Duration::seconds(config.token_expiration_seconds as i64);
+        Self {
+            keys: Arc::new(keys.with_retention(retention)),
+            store,
+            rotation_period: (config.signing_key_rotation_seconds > 0)
+                .then(|| Duration::seconds(config.signing_key_rotation_seconds as i64)),
+            prepublish: Duration::seconds(config.signing_key_prepublish_seconds as i64),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The key ring tokens are signed with
+    pub fn keys(&self) -> Arc<SigningKeys> {
+        self.keys.clone()
+    }
+
+    fn guard(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Run the schedule at `now`: activate the next keys that are due, stage
+    /// the successors of keys nearing the end of their rotation period, and
+    /// drop retired keys once the tokens they signed have expired
+    pub fn maintain(&self, now: DateTime<Utc>) -> Result<()> {
+        let _guard = self.guard();
+        self.maintain_locked(now)
+    }
+
+    fn maintain_locked(&self, now: DateTime<Utc>) -> Result<()> {
+        for kid in self.keys.promote_due(now) {
+            tracing::info!("Signing key {} is now active", kid);
+        }
+
+        if let Some(period) = self.rotation_period {
+            for generation in self.keys.generations() {
+                let algorithm = generation.key.algorithm();
+                let rotates_at = generation.activates_at + period;
+                if generation.status == KeyStatus::Active
+                    && now >= rotates_at - self.prepublish
+                    && self.keys.next(algorithm).is_none()
+                {
+                    let key = generate(self.store.as_ref(), algorithm)?;
+                    tracing::info!(
+                        "Staged {} signing key {} to activate at {}",
+                        algorithm,
+                        key.kid(),
+                        rotates_at
+                    );
+                    self.keys.stage(key, rotates_at);
+                }
+            }
+            // A successor staged late may already be due
+            self.keys.promote_due(now);
+        }
+
+        for pruned in self.keys.prune(now) {
+            tracing::info!("Signing key {} left the JWKS", pruned.key.kid());
+        }
+        self.sync()
+    }
+
+    /// Rotate the keys for `algorithm`, or for every algorithm: the next key
+    /// (staged now if there is none) activates at `activates_at`, and at once
+    /// if that is not in the future
+    pub fn rotate(
+        &self,
+        algorithm: Option<JwsAlgorithm>,
+        activates_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let _guard = self.guard();
+        let algorithms = match algorithm {
+            Some(algorithm) => vec![algorithm],
+            None => self.keys.algorithms(),
+        };
+        for algorithm in algorithms {
+            if !self.keys.schedule(algorithm, activates_at) {
+                let key = generate(self.store.as_ref(), algorithm)?;
+                self.keys.stage(key, activates_at);
+            }
+        }
+        self.maintain_locked(Utc::now())
+    }
+
+    /// Drop the key `kid` from the key ring and the JWKS at once
+    ///
+    /// An active key is replaced by the next key for its algorithm, or by a
+    /// fresh one if none is staged. Returns the revoked generation, `None` for
+    /// an unknown key.
+    pub fn revoke(&self, kid: &str) -> Result<Option<KeyGeneration>> {
+        let _guard = self.guard();
+        let Some(generation) = self.keys.find(kid) else {
+            return Ok(None);
+        };
+
+        let algorithm = generation.key.algorithm();
+        if generation.status == KeyStatus::Active && self.keys.next(algorithm).is_none() {
+            let key = generate(self.store.as_ref(), algorithm)?;
+            self.keys.stage(key, Utc::now());
+        }
+        let revoked = self.keys.revoke(kid).map_err(KeyStoreError::InvalidKey)?;
+        tracing::warn!("Signing key {} revoked", kid);
+
+        self.sync()?;
+        Ok(Some(revoked))
+    }
+
+    fn sync(&self) -> Result<()> {
+        match &self.store {
+            Some(store) => store.sync(&self.keys),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Generate a key for `algorithm`, persisted in `store` if there is one
+fn generate(store: Option<&GenerationStore>, algorithm: JwsAlgorithm) -> Result<SigningKey> {
+    match (algorithm, store) {
+        (JwsAlgorithm::EdDSA, Some(store)) => store.save_ed25519(Ed25519Keypair::generate()),
+        (JwsAlgorithm::MlDsa65, Some(store)) => store.save_ml_dsa(MlDsa65Keypair::generate()),
+        (JwsAlgorithm::EdDSA, None) => Ok(SigningKey::generate_ed25519()),
+        (JwsAlgorithm::MlDsa65, None) => Ok(SigningKey::generate_ml_dsa_65()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> OidcConfig {
+        OidcConfig {
+            signing_key_rotation_seconds: 3600,
+            signing_key_prepublish_seconds: 600,
+            ..OidcConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_generations_persist_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let rotation = KeyRotation::open(dir.path(), "pass", &config()).unwrap();
+        let first = rotation.keys().default_key();
+        rotation
+            .rotate(Some(JwsAlgorithm::EdDSA), Utc::now())
+            .unwrap();
+        let second = rotation.keys().default_key();
+        assert_ne!(first.kid(), second.kid());
+
+        let reopened = KeyRotation::open(dir.path(), "pass", &config()).unwrap();
+        let keys = reopened.keys();
+        assert_eq!(keys.default_key().kid(), second.kid());
+        assert_eq!(keys.find(first.kid()).unwrap().status, KeyStatus::Retired);
+        assert_eq!(keys.generations().len(), 3);
+
+        // A revoked key is deleted from disk
+        reopened.revoke(first.kid()).unwrap().unwrap();
+        let store = KeyStore::new(dir.path()).unwrap();
+        assert!(!store
+            .generations()
+            .unwrap()
+            .contains(&first.kid().to_string()));
+
+        // The wrong passphrase is an error, not a silent fresh key
+        assert!(KeyRotation::open(dir.path(), "wrong", &config()).is_err());
+    }
+
+    #[test]
+    fn test_legacy_key_pair_is_imported() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = KeyStore::new(dir.path()).unwrap();
+        let ed25519 = legacy.load_or_generate_ed25519("pass").unwrap();
+
+        let rotation = KeyRotation::open(dir.path(), "pass", &config()).unwrap();
+        assert_eq!(
+            rotation.keys().default_key().kid(),
+            SigningKey::ed25519(ed25519).kid()
+        );
+        assert_eq!(legacy.generations().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_dilithium3_generation_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let rotation = KeyRotation::open(dir.path(), "pass", &config()).unwrap();
+        let old = rotation.keys().get(JwsAlgorithm::MlDsa65).unwrap();
+
+        // A generation written when ML-DSA-65 keys were round-3 Dilithium3
+        let store = KeyStore::new(dir.path()).unwrap();
+        let generation = store.generation(old.kid()).unwrap();
+        std::fs::remove_file(generation.dir().join("ml-dsa-65.pub")).unwrap();
+        std::fs::remove_file(generation.dir().join("ml-dsa-65.key")).unwrap();
+        generation
+            .save(&fantasma_crypto::DilithiumKeypair::generate(), "pass")
+            .unwrap();
+
+        let reopened = KeyRotation::open(dir.path(), "pass", &config()).unwrap();
+        let keys = reopened.keys();
+        assert!(keys.find(old.kid()).is_none());
+        assert!(keys.get(JwsAlgorithm::MlDsa65).is_some());
+        assert!(!store
+            .generations()
+            .unwrap()
+            .contains(&old.kid().to_string()));
+    }
+
+    #[test]
+    fn test_scheduled_rotation() {
+        let rotation = KeyRotation::ephemeral(&config());
+        let keys = rotation.keys();
+        let first = keys.default_key();
+        let activated = keys.find(first.kid()).unwrap().activates_at;
+
+        // The successor is published ahead of the rotation time
+        rotation
+            .maintain(activated + Duration::minutes(49))
+            .unwrap();
+        assert!(keys.next(JwsAlgorithm::EdDSA).is_none());
+        rotation
+            .maintain(activated + Duration::minutes(51))
+            .unwrap();
+        let next = keys.next(JwsAlgorithm::EdDSA).unwrap();
+        assert_eq!(next.activates_at, activated + Duration::hours(1));
+        assert_eq!(keys.default_key().kid(), first.kid());
+
+        rotation.maintain(activated + Duration::hours(1)).unwrap();
+        assert_eq!(keys.default_key().kid(), next.key.kid());
+    }
+}
@@ -4,14 +4,26 @@
 //! The library exposes modules for integration testing while the binary handles startup.
 
 pub mod admin;
+pub mod client_auth;
+pub mod device;
+pub mod dpop;
+pub mod grants;
+pub mod keys;
 pub mod middleware;
+pub mod openid4vci;
+pub mod openid4vp;
+pub mod proofs;
+pub mod registration;
+pub mod replay;
 pub mod routes;
 pub mod seeds;
+pub mod session;
 pub mod state;
 
 use axum::{
+    http::HeaderName,
     middleware as axum_middleware,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
@@ -28,12 +40,20 @@ fn admin_router() -> Router<AppState> {
             get(admin::list_clients).post(admin::create_client),
         )
         .route("/clients/:id", delete(admin::delete_client))
+        .route("/clients/:id/scopes", put(admin::update_client_scopes))
         .route("/proofs", get(admin::list_proofs))
         .route(
             "/issuers",
             get(admin::list_issuers).post(admin::create_issuer),
         )
         .route("/issuers/:id", delete(admin::delete_issuer))
+        .route("/keys", get(admin::list_signing_keys))
+        .route("/keys/rotate", post(admin::rotate_signing_keys))
+        .route("/keys/:kid", delete(admin::revoke_signing_key))
+        .route(
+            "/credential-offers",
+            post(openid4vci::create_credential_offer),
+        )
         .route("/audit", get(admin::list_audit))
         .route("/health/detailed", get(admin::detailed_health))
         .layer(axum_middleware::from_fn(admin::admin_auth_middleware))
@@ -61,6 +81,8 @@ fn cors_layer() -> CorsLayer {
         .allow_origin(allow_origin)
         .allow_methods(AllowMethods::mirror_request())
         .allow_headers(AllowHeaders::mirror_request())
+        // Browser DPoP clients read the nonce for their next proof
+        .expose_headers([HeaderName::from_static("dpop-nonce")])
         .max_age(std::time::Duration::from_secs(3600))
 }
 
@@ -70,11 +92,42 @@ fn core_routes() -> Router<AppState> {
         // OIDC Discovery
         .route("/.well-known/openid-configuration", get(routes::discovery))
         .route("/.well-known/jwks.json", get(routes::jwks))
+        .route(
+            "/.well-known/openid-credential-issuer",
+            get(openid4vci::credential_issuer_metadata),
+        )
         // OIDC Core
         .route("/authorize", get(routes::authorize))
         .route("/authorize/consent", post(routes::authorize_consent))
+        .route("/par", post(routes::pushed_authorization_request))
+        .route("/device_authorization", post(device::device_authorization))
+        .route("/device", get(device::device_verification))
+        .route("/device/consent", post(device::device_consent))
+        .route("/openid4vp/response", post(openid4vp::direct_post))
+        .route("/credential", post(openid4vci::credential))
         .route("/token", post(routes::token))
-        .route("/userinfo", get(routes::userinfo))
+        .route("/userinfo", get(routes::userinfo).post(routes::userinfo))
+        .route("/introspect", post(routes::introspect))
+        .route("/revoke", post(routes::revoke))
+        .route(
+            "/end_session",
+            get(session::end_session).post(session::end_session_form),
+        )
+        // Grant management
+        .route("/grants", get(grants::list_grants))
+        .route(
+            "/grants/:id",
+            get(grants::get_grant)
+                .patch(grants::update_grant)
+                .delete(grants::revoke_grant),
+        )
+        .route("/register", post(registration::register))
+        .route(
+            "/register/:client_id",
+            get(registration::read_registration)
+                .put(registration::update_registration)
+                .delete(registration::delete_registration),
+        )
         // ZK Proof endpoints
         .route("/proofs", post(routes::submit_proof))
         .route("/proofs/:id", get(routes::get_proof))
@@ -19,9 +19,27 @@ async fn main() {
         .init();
 
     // Load configuration
-    let config = OidcConfig::with_issuer(
+    let mut config = OidcConfig::with_issuer(
         std::env::var("FANTASMA_ISSUER").unwrap_or_else(|_| "http://localhost:3000".to_string()),
     );
+    config.registration_initial_access_token = std::env::var("FANTASMA_REGISTRATION_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty());
+    config.require_pushed_authorization_requests =
+        std::env::var("FANTASMA_REQUIRE_PAR").is_ok_and(|v| v == "true" || v == "1");
+    config.require_signed_request_object =
+        std::env::var("FANTASMA_REQUIRE_SIGNED_REQUESTS").is_ok_and(|v| v == "true" || v == "1");
+    config.hybrid_signatures =
+        std::env::var("FANTASMA_HYBRID_SIGNATURES").is_ok_and(|v| v == "true" || v == "1");
+    config.demo_users = std::env::var("FANTASMA_DEMO_USERS").is_ok_and(|v| v == "true" || v == "1");
+    config.mock_proofs =
+        std::env::var("FANTASMA_MOCK_PROOFS").is_ok_and(|v| v == "true" || v == "1");
+    if let Some(seconds) = std::env::var("FANTASMA_KEY_ROTATION_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+    {
+        config.signing_key_rotation_seconds = seconds;
+    }
 
     // Try to connect to database if DATABASE_URL is set
     let db = match std::env::var("DATABASE_URL") {
@@ -63,6 +81,19 @@ async fn main() {
         tracing::info!("Running with in-memory storage (data will be lost on restart)");
     }
 
+    // Run the signing key schedule: activate next keys when due and drop
+    // retired keys once their tokens have expired
+    let key_rotation = state.key_rotation.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = key_rotation.maintain(chrono::Utc::now()) {
+                tracing::error!("Signing key maintenance failed: {}", e);
+            }
+        }
+    });
+
     // Build router using the library function
     let app = create_router(state);
 
@@ -0,0 +1,235 @@
+//! OpenID4VCI credential issuer
+//!
+//! Issuing partners create credential offers through the admin API. The
+//! wallet redeems the offer's pre-authorized code at the token endpoint and
+//! fetches the credential from `/credential` with a key proof; the
+//! credential is signed by [`AppState::issuer`] and bound to the proven key.
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use fantasma_core::credential::CredentialType;
+use fantasma_oidc::openid4vci::{
+    credential_configuration_id, verify_key_proof, CredentialIssuerMetadata, CredentialOffer,
+    CredentialRequest, CredentialResponse, CredentialTokenResponse, CREDENTIAL_FORMAT,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::routes::{bearer_error, bearer_token};
+use crate::state::AppState;
+
+/// `GET /.well-known/openid-credential-issuer`
+pub async fn credential_issuer_metadata(
+    State(state): State<AppState>,
+) -> Json<CredentialIssuerMetadata> {
+    let info = &state.issuer.info;
+    Json(CredentialIssuerMetadata::new(
+        &state.config.issuer,
+        state.config.endpoint_url(&state.config.credential_endpoint),
+        info.id.as_str(),
+        &info.public_key,
+        state.issuer.signature_algorithm(),
+        &info.supported_schemas,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCredentialOfferRequest {
+    /// The credential to issue, attributes included
+    pub credential: CredentialType,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateCredentialOfferResponse {
+    pub credential_offer: CredentialOffer,
+    /// The offer as an `openid-credential-offer://` link for the wallet
+    pub credential_offer_uri: String,
+    pub expires_in: u64,
+}
+
+/// `POST /admin/credential-offers`
+pub async fn create_credential_offer(
+    State(state): State<AppState>,
+    Json(body): Json<CreateCredentialOfferRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let schema = fantasma_issuer::schema_for(&body.credential);
+    if !state.issuer.info.supports_schema(&schema.0) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let configuration_id = credential_configuration_id(&schema.0).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let code = state
+        .create_credential_offer(configuration_id.to_string(), body.credential)
+        .await;
+    let credential_offer = CredentialOffer::new(&state.config.issuer, configuration_id, code);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateCredentialOfferResponse {
+            credential_offer_uri: credential_offer.to_uri(),
+            credential_offer,
+            expires_in: state.config.credential_offer_expiration_seconds,
+        }),
+    ))
+}
+
+/// Pre-authorized code grant (OpenID4VCI §6.1)
+///
+/// Wallets redeem offers anonymously; the code alone authorizes issuance.
+pub(crate) async fn pre_authorized_code_grant(
+    state: &AppState,
+    pre_authorized_code: Option<String>,
+) -> Result<Json<CredentialTokenResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let code = pre_authorized_code.ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "invalid_request",
+                "error_description": "pre-authorized_code is required"
+            })),
+        )
+    })?;
+
+    let (access_token, c_nonce) =
+        state
+            .redeem_pre_authorized_code(&code)
+            .await
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": "invalid_grant",
+                        "error_description": "invalid, expired or redeemed pre-authorized code"
+                    })),
+                )
+            })?;
+
+    Ok(Json(CredentialTokenResponse {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: state.config.token_expiration_seconds,
+        c_nonce,
+        c_nonce_expires_in: state.config.c_nonce_expiration_seconds,
+    }))
+}
+
+fn credential_error(error: &str, description: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "error": error,
+            "error_description": description
+        })),
+    )
+        .into_response()
+}
+
+/// `POST /credential` (OpenID4VCI §7)
+///
+/// A rejected key proof is answered with `invalid_proof` and a fresh
+/// `c_nonce` for the wallet to sign.
+pub async fn credential(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CredentialRequest>,
+) -> Response {
+    let token = match bearer_token(&headers) {
+        Ok(Some(token)) => token,
+        Ok(None) => return bearer_error(StatusCode::UNAUTHORIZED, None),
+        Err(()) => {
+            return bearer_error(
+                StatusCode::BAD_REQUEST,
+                Some(("invalid_request", "malformed Authorization header")),
+            )
+        }
+    };
+    let Some(offer) = state.find_credential_offer(token).await else {
+        return bearer_error(
+            StatusCode::UNAUTHORIZED,
+            Some((
+                "invalid_token",
+                "access token is invalid, expired or already used",
+            )),
+        );
+    };
+
+    match (&request.credential_configuration_id, &request.format) {
+        (Some(id), _) if *id != offer.credential_configuration_id => {
+            return credential_error(
+                "unsupported_credential_type",
+                "the offer is for another credential configuration",
+            )
+        }
+        (_, Some(format)) if format != CREDENTIAL_FORMAT => {
+            return credential_error(
+                "unsupported_credential_format",
+                "credentials are issued in the fantasma_credential format",
+            )
+        }
+        (None, None) => {
+            return credential_error(
+                "invalid_credential_request",
+                "credential_configuration_id is required",
+            )
+        }
+        _ => {}
+    }
+
+    let c_nonce = offer.c_nonce.as_deref().filter(|_| {
+        offer
+            .c_nonce_expires_at
+            .is_some_and(|exp| exp > chrono::Utc::now())
+    });
+    let holder = match (&request.proof, c_nonce) {
+        (Some(proof), Some(c_nonce)) => {
+            verify_key_proof(proof, &state.config.issuer, c_nonce).map_err(|e| e.to_string())
+        }
+        (None, _) => Err("a key proof is required".to_string()),
+        (Some(_), None) => Err("c_nonce has expired".to_string()),
+    };
+    let holder = match holder {
+        Ok(holder) => holder,
+        Err(description) => {
+            let c_nonce = state.renew_c_nonce(token).await;
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "invalid_proof",
+                    "error_description": description,
+                    "c_nonce": c_nonce,
+                    "c_nonce_expires_in": state.config.c_nonce_expiration_seconds
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    let credential = match state.issuer.issue_to_holder(offer.credential_type, holder) {
+        Ok(credential) => credential,
+        Err(e) => {
+            tracing::error!("Failed to issue credential: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "server_error" })),
+            )
+                .into_response();
+        }
+    };
+    if !state.mark_credential_issued(token).await {
+        return bearer_error(
+            StatusCode::UNAUTHORIZED,
+            Some(("invalid_token", "the credential was already issued")),
+        );
+    }
+
+    tracing::info!(
+        "Issued {} credential {}",
+        offer.credential_configuration_id,
+        credential.id
+    );
+
+    Json(CredentialResponse { credential }).into_response()
+}
@@ -0,0 +1,195 @@
+//! OpenID4VP verifier mode
+//!
+//! The proof request on the consent page is also offered as an OpenID4VP
+//! authorization request, so that third-party wallets carrying Fantasma
+//! STARK proofs can answer it. The wallet posts its `vp_token` straight to
+//! the response URI (`direct_post`); the proofs go through the same checks
+//! as at consent, and the wallet is sent on to the client with the code.
+
+use axum::{extract::State, http::StatusCode, Json};
+use fantasma_core::proof::ProofRequest;
+use fantasma_oidc::openid4vp::{
+    parse_vp_token, AuthorizationRequest, DcqlQuery, DirectPostResponse,
+};
+use fantasma_oidc::pkce::PkceChallenge;
+use fantasma_oidc::scopes::parse_scopes;
+use serde::Deserialize;
+
+use crate::proofs::verify_proofs;
+use crate::routes::{
+    authorization_error_url, check_acr_values, claim_requests, granted_scopes, html_escape,
+    AuthorizeParams,
+};
+use crate::state::AppState;
+
+/// The OpenID4VP authorization request for a proof request
+pub fn authorization_request(state: &AppState, request: &ProofRequest) -> AuthorizationRequest {
+    AuthorizationRequest::new(
+        &format!(
+            "{}{}",
+            state.config.issuer, state.config.openid4vp_response_endpoint
+        ),
+        &request.nonce,
+        &request.request_id,
+        DcqlQuery::from_claim_requests(&request.requested_claims),
+        &request.verifier_domain,
+    )
+}
+
+/// Link on the consent page that opens the request in an OpenID4VP wallet
+pub(crate) fn wallet_link_html(state: &AppState, request: &ProofRequest) -> String {
+    format!(
+        r#"<div class="wallet-link"><a href="{}">Use another wallet (OpenID4VP)</a></div>"#,
+        html_escape(&authorization_request(state, request).to_url())
+    )
+}
+
+/// Authorization response posted by the wallet (OpenID4VP §8.2)
+#[derive(Debug, Deserialize)]
+pub struct DirectPostParams {
+    pub vp_token: Option<String>,
+    pub state: Option<String>,
+    /// Set instead of `vp_token` when the wallet could not answer
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+fn invalid_request(description: &str) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "error": "invalid_request",
+            "error_description": description
+        })),
+    )
+}
+
+/// `POST /openid4vp/response`
+///
+/// A response that cannot be matched to a request is answered with 400.
+/// Otherwise the authorization is decided: the wallet is sent to the
+/// client's redirect URI with either a code or an error.
+pub async fn direct_post(
+    State(state): State<AppState>,
+    axum::Form(params): axum::Form<DirectPostParams>,
+) -> Result<Json<DirectPostResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let request_id = params
+        .state
+        .ok_or_else(|| invalid_request("state is required"))?;
+    let pending = state
+        .take_proof_request(&request_id)
+        .await
+        .ok_or_else(|| invalid_request("unknown or expired state"))?;
+    let authorize: AuthorizeParams = serde_json::from_value(pending.params)
+        .map_err(|_| invalid_request("proof request has no authorization request"))?;
+
+    let deny = |error: &str, description: &str| {
+        Ok(Json(DirectPostResponse {
+            redirect_uri: authorization_error_url(
+                &authorize.redirect_uri,
+                error,
+                description,
+                authorize.state.as_deref(),
+            ),
+        }))
+    };
+
+    if let Some(error) = params.error {
+        tracing::info!("Wallet declined the presentation: {}", error);
+        return deny(
+            "access_denied",
+            params
+                .error_description
+                .as_deref()
+                .unwrap_or("the wallet did not present the requested proofs"),
+        );
+    }
+    let vp_token = params
+        .vp_token
+        .ok_or_else(|| invalid_request("vp_token is required"))?;
+    let query = DcqlQuery::from_claim_requests(&pending.request.requested_claims);
+    let (subject_id, proofs) =
+        parse_vp_token(&vp_token, &query).map_err(|e| invalid_request(&e.to_string()))?;
+
+    // The authorization request was validated before the consent page was
+    // shown; only what the code needs is derived again here
+    let client = state
+        .get_client(&authorize.client_id)
+        .await
+        .ok_or_else(|| invalid_request("unknown client"))?;
+    let Some(sector) = client.sector_identifier(&authorize.redirect_uri) else {
+        return deny("invalid_request", "no sector identifier for redirect_uri");
+    };
+    let requested_claims = match claim_requests(authorize.claims.as_deref()) {
+        Ok(requests) => requests,
+        Err(e) => return deny("invalid_request", &e),
+    };
+    let pkce = match PkceChallenge::from_request(
+        authorize.code_challenge.as_deref(),
+        authorize.code_challenge_method.as_deref(),
+        client.pkce_policy(),
+    ) {
+        Ok(pkce) => pkce,
+        Err(e) => return deny("invalid_request", &e.to_string()),
+    };
+
+    let zk_claims = match verify_proofs(
+        &state,
+        &pending.request,
+        &sector,
+        &authorize.scope,
+        &subject_id,
+        &proofs,
+    )
+    .await
+    {
+        Ok(zk_claims) => zk_claims,
+        Err(e) => {
+            tracing::warn!("Rejected OpenID4VP presentation: {}", e);
+            return deny(e.error_code(), &e.to_string());
+        }
+    };
+
+    if let Err(e) = check_acr_values(authorize.acr_values.as_deref(), &zk_claims) {
+        return deny("access_denied", &e);
+    }
+
+    state
+        .record_consent(
+            &subject_id,
+            &authorize.client_id,
+            &sector,
+            granted_scopes(&authorize.scope, &requested_claims),
+            zk_claims.clone(),
+        )
+        .await;
+
+    let scopes = parse_scopes(&authorize.scope)
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let code = state
+        .create_auth_code(
+            authorize.client_id,
+            authorize.redirect_uri.clone(),
+            subject_id,
+            scopes,
+            authorize.nonce,
+            pkce,
+            None,
+            requested_claims,
+            Some(zk_claims),
+        )
+        .await;
+
+    tracing::info!(
+        "Authorization granted on an OpenID4VP presentation with scopes: {:?}",
+        authorize.scope
+    );
+
+    let mut redirect_uri = format!("{}?code={}", authorize.redirect_uri, code);
+    if let Some(s) = authorize.state {
+        redirect_uri.push_str(&format!("&state={}", s));
+    }
+    Ok(Json(DirectPostResponse { redirect_uri }))
+}
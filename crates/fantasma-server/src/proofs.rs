@@ -0,0 +1,372 @@
+//! Wallet proofs presented at consent
+//!
+//! The consent page carries a [`ProofRequest`] for the requested ZK claims,
+//! with a fresh nonce and the client's sector as verifier domain. The wallet
+//! answers it with a [`ProofResponse`], or an OpenID4VP wallet with a
+//! `vp_token`. Each proof must be bound to the request and to the wallet's
+//! subject, carry the requested claim values as public inputs, verify with
+//! the configured prover backend, which checks them against the proof's
+//! public inputs, and carry an unused nullifier. A subject backed by no
+//! proof is refused. The claims the proofs establish are then stored on the
+//! authorization code, each with the provenance of its proof.
+
+use fantasma_core::claim::{ClaimRequest, ClaimType};
+use fantasma_core::proof::{GeneratedProof, ProofRef, ProofRequest, ProofResponse};
+use fantasma_oidc::claims::ZkClaims;
+use fantasma_oidc::provenance::{ClaimProvenance, VerificationMethod};
+use fantasma_proof_store::StoredProof;
+use fantasma_stark::backend::ProverBackendTrait;
+use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::state::{AppState, PendingProofRequest};
+
+/// Why a proof response was not accepted
+#[derive(Debug, Error)]
+pub enum ProofVerificationError {
+    #[error("unknown or expired proof request")]
+    UnknownRequest,
+
+    #[error("proof request was issued for another sector")]
+    SectorMismatch,
+
+    #[error("proof for a claim that was not requested: {0}")]
+    Unrequested(String),
+
+    #[error("proof for {0} uses the wrong circuit")]
+    CircuitMismatch(String),
+
+    #[error("proof for {0} is for other claim values than requested")]
+    ClaimMismatch(String),
+
+    #[error("proof for {0} is not bound to the proof request")]
+    NotBound(String),
+
+    #[error("subject is not a pairwise subject identifier")]
+    MalformedSubject,
+
+    #[error("proof for {0} is not bound to the subject")]
+    SubjectNotBound(String),
+
+    #[error("subject is not bound to any proof")]
+    UnprovenSubject,
+
+    #[error("essential claim could not be proven: {0}")]
+    EssentialClaimMissing(String),
+
+    #[error("proof for {0} is invalid")]
+    Invalid(String),
+
+    #[error("proof for {0} was already used")]
+    NullifierReused(String),
+
+    #[error("proof verification failed: {0}")]
+    Backend(String),
+}
+
+impl ProofVerificationError {
+    /// OAuth error code for the authorization error redirect
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::UnknownRequest
+            | Self::SectorMismatch
+            | Self::Unrequested(_)
+            | Self::CircuitMismatch(_)
+            | Self::ClaimMismatch(_)
+            | Self::NotBound(_)
+            | Self::MalformedSubject
+            | Self::SubjectNotBound(_)
+            | Self::UnprovenSubject => "invalid_request",
+            Self::EssentialClaimMissing(_) | Self::Invalid(_) | Self::NullifierReused(_) => {
+                "access_denied"
+            }
+            Self::Backend(_) => "server_error",
+        }
+    }
+}
+
+/// The claims the wallet is asked to prove for an authorization request
+///
+/// Claims requested by scope are voluntary; those requested with the
+/// `claims` parameter keep their essential flag.
+pub fn proof_request_claims(scope: &str, claim_requests: &[ClaimRequest]) -> Vec<ClaimRequest> {
+    scope
+        .split_whitespace()
+        .filter_map(ClaimType::from_scope)
+        .map(|claim_type| ClaimRequest {
+            claim_type,
+            required: false,
+            reason: None,
+        })
+        .chain(claim_requests.iter().cloned())
+        .collect()
+}
+
+/// Verify the wallet's answer to the proof request of a consent page and
+/// return the proven claims
+pub async fn verify_proof_response(
+    state: &AppState,
+    pending: &PendingProofRequest,
+    sector: &str,
+    scope: &str,
+    response: &ProofResponse,
+) -> Result<ZkClaims, ProofVerificationError> {
+    if response.request_id != pending.request.request_id {
+        return Err(ProofVerificationError::UnknownRequest);
+    }
+
+    verify_proofs(
+        state,
+        &pending.request,
+        sector,
+        scope,
+        &response.subject_id,
+        &response.proofs,
+    )
+    .await
+}
+
+/// Verify proofs answering `request` for `subject` and return the proven
+/// claims
+///
+/// The proofs are what ties the subject to the wallet, so at least one is
+/// needed.
+/// Claims requested by scope that were not proven are included as
+/// unverified, as without a wallet; voluntary claims from the `claims`
+/// parameter are left out.
+pub async fn verify_proofs(
+    state: &AppState,
+    request: &ProofRequest,
+    sector: &str,
+    scope: &str,
+    subject: &str,
+    proofs: &[GeneratedProof],
+) -> Result<ZkClaims, ProofVerificationError> {
+    if request.verifier_domain != sector {
+        return Err(ProofVerificationError::SectorMismatch);
+    }
+    if !fantasma_crypto::is_pairwise_subject(subject) {
+        return Err(ProofVerificationError::MalformedSubject);
+    }
+
+    // Match every proof to a requested claim before doing any real work
+    for proof in proofs {
+        let claim = proof.claim_type.to_scope();
+        let Some(requested) = request
+            .requested_claims
+            .iter()
+            .find(|r| r.claim_type.to_scope() == claim)
+        else {
+            return Err(ProofVerificationError::Unrequested(claim));
+        };
+        if proof.circuit_id != proof.claim_type.circuit_id() {
+            return Err(ProofVerificationError::CircuitMismatch(claim));
+        }
+        // The claim goes into the token as requested, so the proof must be
+        // for exactly those values
+        if !proof.proves(&requested.claim_type) {
+            return Err(ProofVerificationError::ClaimMismatch(claim));
+        }
+        if !proof.is_bound_to(request) {
+            return Err(ProofVerificationError::NotBound(claim));
+        }
+        if !proof.is_bound_to_subject(subject) {
+            return Err(ProofVerificationError::SubjectNotBound(claim));
+        }
+    }
+    let proven: Vec<String> = proofs.iter().map(|p| p.claim_type.to_scope()).collect();
+    if let Some(missing) = request
+        .requested_claims
+        .iter()
+        .find(|r| r.required && !proven.contains(&r.claim_type.to_scope()))
+    {
+        return Err(ProofVerificationError::EssentialClaimMissing(
+            missing.claim_type.to_scope(),
+        ));
+    }
+    if proofs.is_empty() {
+        return Err(ProofVerificationError::UnprovenSubject);
+    }
+
+    let mut verified_by = Vec::with_capacity(proofs.len());
+    for proof in proofs {
+        verified_by.push(verify_proof(state, proof).await?);
+    }
+
+    // Only record nullifiers once every proof has verified
+    for proof in proofs {
+        if !state
+            .record_nullifier(&proof.nullifier, sector, &proof.circuit_id)
+            .await
+        {
+            return Err(ProofVerificationError::NullifierReused(
+                proof.claim_type.to_scope(),
+            ));
+        }
+    }
+
+    let mut proof_refs = HashMap::new();
+    for (proof, backend) in proofs.iter().zip(verified_by) {
+        proof_refs.insert(
+            proof.claim_type.to_scope(),
+            (
+                store_proof(state, proof).await,
+                proof_provenance(state, proof, backend).await,
+            ),
+        );
+    }
+
+    let scope_claims: Vec<String> = scope
+        .split_whitespace()
+        .filter_map(ClaimType::from_scope)
+        .map(|claim_type| claim_type.to_scope())
+        .collect();
+    let mut zk_claims = ZkClaims::new();
+    for requested in &request.requested_claims {
+        let claim = requested.claim_type.to_scope();
+        zk_claims = match proof_refs.get(&claim) {
+            Some((proof_ref, provenance)) => {
+                let mut zk_claims = with_claim(
+                    zk_claims,
+                    &requested.claim_type,
+                    true,
+                    Some(proof_ref.clone()),
+                );
+                zk_claims.set_provenance(&requested.claim_type, provenance.clone());
+                zk_claims
+            }
+            None if scope_claims.contains(&claim) => {
+                with_claim(zk_claims, &requested.claim_type, false, None)
+            }
+            None => zk_claims,
+        };
+    }
+
+    Ok(zk_claims)
+}
+
+/// Verify a proof with the configured prover backend and return the backend
+/// that checked it
+async fn verify_proof(
+    state: &AppState,
+    proof: &GeneratedProof,
+) -> Result<String, ProofVerificationError> {
+    let backend = state.prover_backend.clone();
+    let circuit_id = proof.circuit_id.clone();
+    let proof_bytes = proof.proof_bytes.clone();
+    let public_inputs = proof.public_inputs.clone();
+
+    // External provers run as subprocesses
+    let result = tokio::task::spawn_blocking(move || {
+        backend.verify(&circuit_id, &proof_bytes, &public_inputs)
+    })
+    .await
+    .map_err(|e| ProofVerificationError::Backend(e.to_string()))?
+    .map_err(|e| ProofVerificationError::Backend(e.to_string()))?;
+
+    if !result.valid {
+        tracing::warn!(
+            "Rejected proof for {}: {}",
+            proof.claim_type.to_scope(),
+            result.error.as_deref().unwrap_or("invalid")
+        );
+        return Err(ProofVerificationError::Invalid(proof.claim_type.to_scope()));
+    }
+    Ok(result.verified_by)
+}
+
+/// How a proof verified by `backend` established its claim
+async fn proof_provenance(
+    state: &AppState,
+    proof: &GeneratedProof,
+    backend: String,
+) -> ClaimProvenance {
+    ClaimProvenance {
+        method: VerificationMethod::of_backend(&backend),
+        verifier_backend: backend,
+        circuit_hash: state
+            .zk_circuits
+            .get(&proof.circuit_id)
+            .and_then(|circuit| circuit.bytecode_hash.clone()),
+        proof_hash: Some(format!("0x{}", hex::encode(proof.hash()))),
+        verified_at: chrono::Utc::now().timestamp(),
+        issuer_trust_level: state.issuer_trust_level(proof).await,
+    }
+}
+
+/// Keep a verified proof so that relying parties can fetch it
+///
+/// The proof stays referenced in the ID token even if storing it fails.
+async fn store_proof(state: &AppState, proof: &GeneratedProof) -> ProofRef {
+    let stored = StoredProof::new(
+        proof.proof_bytes.clone(),
+        proof.circuit_id.clone(),
+        state.config.zk_claims_max_age_seconds as i64,
+    );
+    let hash = stored.hash;
+    let size_bytes = stored.proof_bytes.len() as u64;
+
+    match state.proof_store.store(stored).await {
+        Ok(id) => ProofRef {
+            url: Some(format!(
+                "{}{}/{}",
+                state.config.issuer, state.config.proof_storage_endpoint, id
+            )),
+            id,
+            hash,
+            size_bytes,
+        },
+        Err(e) => {
+            tracing::error!("Failed to store verified proof: {}", e);
+            proof.to_ref(None)
+        }
+    }
+}
+
+fn with_claim(
+    zk_claims: ZkClaims,
+    claim_type: &ClaimType,
+    verified: bool,
+    proof_ref: Option<ProofRef>,
+) -> ZkClaims {
+    match claim_type {
+        ClaimType::AgeAtLeast { threshold } => {
+            zk_claims.with_age_claim_verified(*threshold, verified, proof_ref)
+        }
+        ClaimType::HoldsCredential {
+            credential_type, ..
+        } => zk_claims.with_credential_claim_verified(credential_type.clone(), verified, proof_ref),
+        ClaimType::KycStatus {
+            level,
+            max_age_seconds,
+            ..
+        } => zk_claims.with_kyc_claim_verified(*level, verified, proof_ref, *max_age_seconds),
+        ClaimType::SetMembership { .. } => zk_claims,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fantasma_core::claim::KycLevel;
+
+    #[test]
+    fn test_proof_request_claims() {
+        let essential = ClaimRequest {
+            claim_type: ClaimType::KycStatus {
+                provider: "*".to_string(),
+                level: KycLevel::Enhanced,
+                max_age_seconds: None,
+            },
+            required: true,
+            reason: None,
+        };
+
+        let claims = proof_request_claims("openid zk:age:21+", &[essential]);
+        assert_eq!(claims.len(), 2);
+        assert_eq!(claims[0].claim_type.to_scope(), "zk:age:21+");
+        assert!(!claims[0].required);
+        assert_eq!(claims[1].claim_type.to_scope(), "zk:kyc:enhanced");
+        assert!(claims[1].required);
+    }
+}
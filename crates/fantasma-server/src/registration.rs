@@ -0,0 +1,485 @@
+//! Dynamic client registration (RFC 7591) and management (RFC 7592)
+//!
+//! Relying parties register themselves at `/register` and receive a
+//! registration access token for reading, updating and deleting their
+//! registration at `/register/:client_id`. When an initial access token is
+//! configured, registration requires it as a Bearer token. Open registration
+//! approves no ZK scopes; an administrator grants them through the admin API.
+//!
+//! Registrations are persisted through [`fantasma_db::repos::ClientRepo`], so
+//! the endpoints need database storage.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use fantasma_db::{Client, NewClient};
+use fantasma_oidc::{
+    ClientMetadata, ClientRegistrationResponse, RegistrationError, TokenEndpointAuthMethod, ZkScope,
+};
+
+use crate::routes::{bearer_error, bearer_token};
+use crate::state::{hash_token, random_token, AppState};
+
+type ErrorResponse = (StatusCode, Json<serde_json::Value>);
+
+fn registration_error(status: StatusCode, error: &str, description: &str) -> ErrorResponse {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": error,
+            "error_description": description
+        })),
+    )
+}
+
+fn storage_unavailable() -> Response {
+    registration_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "temporarily_unavailable",
+        "client registration requires database storage",
+    )
+    .into_response()
+}
+
+fn invalid_token() -> Response {
+    bearer_error(
+        StatusCode::UNAUTHORIZED,
+        Some(("invalid_token", "registration access token is invalid")),
+    )
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Build the database row for validated metadata
+fn new_client(
+    client_id: String,
+    metadata: &ClientMetadata,
+    supported_scopes: &[String],
+    client_secret_hash: Option<Vec<u8>>,
+    registration_access_token_hash: Option<Vec<u8>>,
+) -> NewClient {
+    let auth_method = metadata.auth_method();
+
+    NewClient {
+        client_name: metadata
+            .client_name
+            .clone()
+            .unwrap_or_else(|| client_id.clone()),
+        client_id,
+        client_secret_hash,
+        redirect_uris: metadata.redirect_uris.clone(),
+        allowed_scopes: metadata.scopes(supported_scopes),
+        client_type: if auth_method == TokenEndpointAuthMethod::None {
+            "public".to_string()
+        } else {
+            "confidential".to_string()
+        },
+        id_token_signed_response_alg: metadata.id_token_signed_response_alg.clone(),
+        require_pkce_s256: false,
+        token_endpoint_auth_method: Some(auth_method.as_str().to_string()),
+        jwks: metadata
+            .jwks
+            .as_ref()
+            .and_then(|jwks| serde_json::to_value(jwks).ok()),
+        jwks_uri: metadata.jwks_uri.clone(),
+        logo_uri: metadata.logo_uri.clone(),
+        registration_access_token_hash,
+        sector_identifier_uri: metadata.sector_identifier_uri.clone(),
+        require_pushed_authorization_requests: metadata
+            .require_pushed_authorization_requests
+            .unwrap_or(false),
+        require_signed_request_object: metadata.require_signed_request_object.unwrap_or(false),
+        post_logout_redirect_uris: metadata.post_logout_redirect_uris.clone(),
+        backchannel_logout_uri: metadata.backchannel_logout_uri.clone(),
+        backchannel_logout_session_required: metadata
+            .backchannel_logout_session_required
+            .unwrap_or(false),
+        downscope_unapproved_scopes: false,
+        id_token_encrypted_response_alg: metadata.id_token_encrypted_response_alg.clone(),
+        id_token_encrypted_response_enc: metadata.id_token_encrypted_response_enc.clone(),
+        userinfo_encrypted_response_alg: metadata.userinfo_encrypted_response_alg.clone(),
+        userinfo_encrypted_response_enc: metadata.userinfo_encrypted_response_enc.clone(),
+    }
+}
+
+/// Build the database row replacing `existing` with updated metadata
+///
+/// Policy an administrator sets outlives the update: approved scopes can be
+/// given up but not added to, PKCE and downscoping stay as they are, and
+/// PAR and signed request object requirements can be raised but not lowered.
+fn updated_client(
+    existing: &Client,
+    metadata: &ClientMetadata,
+    client_secret_hash: Option<Vec<u8>>,
+) -> NewClient {
+    let mut updated = new_client(
+        existing.client_id.clone(),
+        metadata,
+        &existing.allowed_scopes,
+        client_secret_hash,
+        existing.registration_access_token_hash.clone(),
+    );
+    updated
+        .allowed_scopes
+        .retain(|scope| existing.allowed_scopes.contains(scope));
+    updated.require_pkce_s256 = existing.require_pkce_s256;
+    updated.downscope_unapproved_scopes = existing.downscope_unapproved_scopes;
+    updated.require_pushed_authorization_requests |= existing.require_pushed_authorization_requests;
+    updated.require_signed_request_object |= existing.require_signed_request_object;
+    updated
+}
+
+/// Whether a client may approve `scope` for itself by registering it
+fn self_approvable(scope: &str) -> bool {
+    !ZkScope::parse(scope).is_some_and(|scope| scope.requires_proof())
+}
+
+/// Read the registered metadata back out of a client row
+fn client_metadata(client: &Client) -> ClientMetadata {
+    ClientMetadata {
+        redirect_uris: client.redirect_uris.clone(),
+        token_endpoint_auth_method: client.token_endpoint_auth_method.clone(),
+        id_token_signed_response_alg: client.id_token_signed_response_alg.clone(),
+        id_token_encrypted_response_alg: client.id_token_encrypted_response_alg.clone(),
+        id_token_encrypted_response_enc: client.id_token_encrypted_response_enc.clone(),
+        userinfo_encrypted_response_alg: client.userinfo_encrypted_response_alg.clone(),
+        userinfo_encrypted_response_enc: client.userinfo_encrypted_response_enc.clone(),
+        jwks: client
+            .jwks
+            .clone()
+            .and_then(|jwks| serde_json::from_value(jwks).ok()),
+        jwks_uri: client.jwks_uri.clone(),
+        client_name: Some(client.client_name.clone()),
+        logo_uri: client.logo_uri.clone(),
+        scope: Some(client.allowed_scopes.join(" ")),
+        subject_type: Some("pairwise".to_string()),
+        sector_identifier_uri: client.sector_identifier_uri.clone(),
+        require_pushed_authorization_requests: Some(client.require_pushed_authorization_requests),
+        require_signed_request_object: Some(client.require_signed_request_object),
+        post_logout_redirect_uris: client.post_logout_redirect_uris.clone(),
+        backchannel_logout_uri: client.backchannel_logout_uri.clone(),
+        backchannel_logout_session_required: Some(client.backchannel_logout_session_required),
+    }
+}
+
+fn registration_response(
+    state: &AppState,
+    client: &Client,
+    client_secret: Option<String>,
+    registration_access_token: Option<String>,
+) -> ClientRegistrationResponse {
+    let has_secret = client.client_secret_hash.is_some();
+
+    ClientRegistrationResponse {
+        client_id: client.client_id.clone(),
+        client_secret,
+        client_id_issued_at: client.created_at.timestamp(),
+        client_secret_expires_at: has_secret.then_some(0),
+        registration_access_token,
+        registration_client_uri: format!(
+            "{}/{}",
+            state
+                .config
+                .endpoint_url(&state.config.registration_endpoint),
+            client.client_id
+        ),
+        metadata: client_metadata(client),
+    }
+}
+
+/// Validate metadata, including the contents of its `sector_identifier_uri`
+async fn validate(state: &AppState, metadata: &ClientMetadata) -> Result<(), ErrorResponse> {
+    let invalid = |e: RegistrationError| {
+        registration_error(StatusCode::BAD_REQUEST, e.error_code(), &e.to_string())
+    };
+
+    metadata
+        .validate(&state.config.supported_scopes)
+        .map_err(invalid)?;
+
+    if let Some(ref uri) = metadata.sector_identifier_uri {
+        let listed = fetch_sector_redirect_uris(uri).await.ok_or_else(|| {
+            invalid(RegistrationError::InvalidClientMetadata(
+                "could not fetch sector_identifier_uri".to_string(),
+            ))
+        })?;
+        metadata
+            .check_sector_redirect_uris(&listed)
+            .map_err(invalid)?;
+    }
+
+    Ok(())
+}
+
+/// Fetch the JSON array of redirect URIs published at a `sector_identifier_uri`
+async fn fetch_sector_redirect_uris(uri: &str) -> Option<Vec<String>> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .ok()?;
+    let response = client.get(uri).send().await.ok()?;
+    if !response.status().is_success() {
+        tracing::warn!(
+            "sector_identifier_uri {} returned {}",
+            uri,
+            response.status()
+        );
+        return None;
+    }
+    response.json().await.ok()
+}
+
+/// Generate a client secret and its stored hash for secret-based clients
+fn generate_secret(
+    auth_method: TokenEndpointAuthMethod,
+) -> Result<(Option<String>, Option<Vec<u8>>), StatusCode> {
+    let client_secret = auth_method
+        .uses_secret()
+        .then(fantasma_crypto::generate_secret);
+    let client_secret_hash = client_secret
+        .as_deref()
+        .map(fantasma_crypto::hash_secret)
+        .transpose()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(String::into_bytes);
+
+    Ok((client_secret, client_secret_hash))
+}
+
+/// Look up a registration and check its registration access token
+async fn authorized_client(
+    state: &AppState,
+    headers: &HeaderMap,
+    client_id: &str,
+) -> Result<Client, Response> {
+    let token = match bearer_token(headers) {
+        Ok(Some(token)) => token,
+        Ok(None) => return Err(bearer_error(StatusCode::UNAUTHORIZED, None)),
+        Err(()) => return Err(invalid_token()),
+    };
+    let repos = state.repos().ok_or_else(storage_unavailable)?;
+
+    // Unknown clients get the same answer as a wrong token
+    let client = repos
+        .clients()
+        .find_by_client_id(client_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+        .ok_or_else(invalid_token)?;
+
+    let expected = client
+        .registration_access_token_hash
+        .as_deref()
+        .ok_or_else(invalid_token)?;
+    if !constant_time_eq(&hash_token(token), expected) {
+        return Err(invalid_token());
+    }
+
+    Ok(client)
+}
+
+/// `POST /register`
+pub async fn register(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(metadata): Json<ClientMetadata>,
+) -> Result<Response, Response> {
+    if let Some(ref expected) = state.config.registration_initial_access_token {
+        match bearer_token(&headers) {
+            Ok(Some(token)) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => {}
+            Ok(Some(_)) => {
+                return Err(bearer_error(
+                    StatusCode::UNAUTHORIZED,
+                    Some(("invalid_token", "initial access token is invalid")),
+                ))
+            }
+            Ok(None) => return Err(bearer_error(StatusCode::UNAUTHORIZED, None)),
+            Err(()) => {
+                return Err(bearer_error(
+                    StatusCode::BAD_REQUEST,
+                    Some(("invalid_request", "malformed Authorization header")),
+                ))
+            }
+        }
+    }
+    validate(&state, &metadata)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    let repos = state.repos().ok_or_else(storage_unavailable)?;
+
+    let client_id = format!("client_{}", uuid::Uuid::new_v4().simple());
+    let (client_secret, client_secret_hash) =
+        generate_secret(metadata.auth_method()).map_err(IntoResponse::into_response)?;
+    let registration_access_token = random_token();
+
+    let mut new_client = new_client(
+        client_id,
+        &metadata,
+        &state.config.supported_scopes,
+        client_secret_hash,
+        Some(hash_token(&registration_access_token).to_vec()),
+    );
+    // Without an initial access token anyone can register, so ZK scopes wait
+    // for an administrator to approve them
+    if state.config.registration_initial_access_token.is_none() {
+        new_client
+            .allowed_scopes
+            .retain(|scope| self_approvable(scope));
+    }
+    let client = repos.clients().create(new_client).await.map_err(|e| {
+        tracing::error!("Failed to register client: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    tracing::info!("Registered client {}", client.client_id);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(registration_response(
+            &state,
+            &client,
+            client_secret,
+            Some(registration_access_token),
+        )),
+    )
+        .into_response())
+}
+
+/// `GET /register/:client_id`
+pub async fn read_registration(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(client_id): Path<String>,
+) -> Result<Json<ClientRegistrationResponse>, Response> {
+    let client = authorized_client(&state, &headers, &client_id).await?;
+
+    Ok(Json(registration_response(&state, &client, None, None)))
+}
+
+/// `PUT /register/:client_id`
+///
+/// Replaces the registered metadata, keeping the policy an administrator
+/// set. The existing secret is kept while the client stays on a
+/// secret-based method; a new one is issued when it moves to one.
+pub async fn update_registration(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(client_id): Path<String>,
+    Json(metadata): Json<ClientMetadata>,
+) -> Result<Json<ClientRegistrationResponse>, Response> {
+    let existing = authorized_client(&state, &headers, &client_id).await?;
+    validate(&state, &metadata)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let auth_method = metadata.auth_method();
+    let (client_secret, client_secret_hash) = match existing.client_secret_hash.clone() {
+        Some(hash) if auth_method.uses_secret() => (None, Some(hash)),
+        _ => generate_secret(auth_method).map_err(IntoResponse::into_response)?,
+    };
+
+    let updated = updated_client(&existing, &metadata, client_secret_hash);
+    let repos = state.repos().ok_or_else(storage_unavailable)?;
+    let client = repos.clients().update(updated).await.map_err(|e| {
+        tracing::error!("Failed to update client: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    Ok(Json(registration_response(
+        &state,
+        &client,
+        client_secret,
+        None,
+    )))
+}
+
+/// `DELETE /register/:client_id`
+pub async fn delete_registration(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(client_id): Path<String>,
+) -> Result<StatusCode, Response> {
+    authorized_client(&state, &headers, &client_id).await?;
+
+    let repos = state.repos().ok_or_else(storage_unavailable)?;
+    repos.clients().delete(&client_id).await.map_err(|e| {
+        tracing::error!("Failed to delete client: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    tracing::info!("Deleted client {}", client_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn existing_client() -> Client {
+        serde_json::from_value(json!({
+            "id": uuid::Uuid::new_v4(),
+            "client_id": "client_1",
+            "client_secret_hash": null,
+            "client_name": "RP",
+            "redirect_uris": ["https://rp.example/callback"],
+            "allowed_scopes": ["openid", "zk:age:18+"],
+            "client_type": "public",
+            "created_at": chrono::Utc::now(),
+            "updated_at": chrono::Utc::now(),
+            "id_token_signed_response_alg": null,
+            "require_pkce_s256": true,
+            "token_endpoint_auth_method": "none",
+            "jwks": null,
+            "jwks_uri": null,
+            "logo_uri": null,
+            "registration_access_token_hash": [1, 2, 3],
+            "sector_identifier_uri": null,
+            "require_pushed_authorization_requests": true,
+            "require_signed_request_object": false,
+            "post_logout_redirect_uris": [],
+            "backchannel_logout_uri": null,
+            "backchannel_logout_session_required": false,
+            "downscope_unapproved_scopes": true,
+            "id_token_encrypted_response_alg": null,
+            "id_token_encrypted_response_enc": null,
+            "userinfo_encrypted_response_alg": null,
+            "userinfo_encrypted_response_enc": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_update_keeps_admin_policy() {
+        let existing = existing_client();
+        let metadata: ClientMetadata = serde_json::from_value(json!({
+            "redirect_uris": ["https://rp.example/new-callback"],
+            "token_endpoint_auth_method": "none",
+            "scope": "openid zk:age:18+ zk:age:21+",
+            "require_pushed_authorization_requests": false,
+            "require_signed_request_object": true
+        }))
+        .unwrap();
+
+        let updated = updated_client(&existing, &metadata, None);
+        assert_eq!(updated.redirect_uris, ["https://rp.example/new-callback"]);
+        assert_eq!(updated.allowed_scopes, ["openid", "zk:age:18+"]);
+        assert!(updated.require_pkce_s256);
+        assert!(updated.downscope_unapproved_scopes);
+        assert!(updated.require_pushed_authorization_requests);
+        assert!(updated.require_signed_request_object);
+        assert_eq!(updated.registration_access_token_hash, Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_zk_scopes_are_not_self_approvable() {
+        assert!(self_approvable("openid"));
+        assert!(!self_approvable("zk:age:18+"));
+        assert!(!self_approvable("zk:kyc:basic"));
+    }
+}
@@ -0,0 +1,59 @@
+//! One-time-use identifier tracking
+//!
+//! Remembers identifiers (e.g. client assertion `jti` values) until they
+//! expire so that a replayed token is rejected. Kept in memory: a restart
+//! forgets seen identifiers, which is acceptable for short-lived tokens.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// In-memory replay cache
+#[derive(Clone, Default)]
+pub struct ReplayCache {
+    seen: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+}
+
+impl ReplayCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `key` as used until `expires_at`
+    ///
+    /// Returns `false` if the key was already recorded and has not expired.
+    pub async fn check_and_insert(&self, key: String, expires_at: DateTime<Utc>) -> bool {
+        let now = Utc::now();
+        let mut seen = self.seen.write().await;
+
+        // Drop entries whose tokens could no longer be accepted anyway
+        seen.retain(|_, exp| *exp > now);
+
+        if seen.contains_key(&key) {
+            return false;
+        }
+        seen.insert(key, expires_at);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_replay_detected_until_expiry() {
+        let cache = ReplayCache::new();
+        let later = Utc::now() + chrono::Duration::seconds(60);
+
+        assert!(cache.check_and_insert("jti-1".into(), later).await);
+        assert!(!cache.check_and_insert("jti-1".into(), later).await);
+        assert!(cache.check_and_insert("jti-2".into(), later).await);
+
+        // Expired entries are forgotten
+        let past = Utc::now() - chrono::Duration::seconds(1);
+        assert!(cache.check_and_insert("jti-3".into(), past).await);
+        assert!(cache.check_and_insert("jti-3".into(), later).await);
+    }
+}
@@ -2,39 +2,57 @@
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::{Html, IntoResponse, Json, Redirect},
+    http::{header, HeaderMap, Method, StatusCode},
+    response::{Html, IntoResponse, Json, Redirect, Response},
 };
-use fantasma_core::proof::ProofId;
+use fantasma_core::claim::{ClaimRequest, ClaimType, KycLevel};
+use fantasma_core::proof::{ProofId, ProofRequest, ProofResponse};
 use fantasma_oidc::{
     claims::ZkClaims,
+    claims_request::ClaimsRequest,
+    device::DEVICE_CODE_GRANT_TYPE,
     discovery::DiscoveryDocument,
+    dpop::{Confirmation, DPOP_TOKEN_TYPE},
+    jwe::{self, JweParams},
+    jwk::JwkSet,
+    jws,
+    openid4vci::PRE_AUTHORIZED_CODE_GRANT_TYPE,
+    pkce::PkceChallenge,
+    provenance::{parse_acr_values, ClaimProvenance, IssuerTrust},
+    request_object::verify_request_object,
     scopes::{parse_scopes, ZkScope},
-    token::{IdToken, IdTokenClaims, TokenResponse},
+    signing::{JwsAlgorithm, SigningKey},
+    token::{IdToken, IdTokenClaims, IntrospectionResponse, TokenResponse, UserInfoResponse},
 };
 use fantasma_proof_store::StoredProof;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-use crate::state::AppState;
+use crate::client_auth::{authenticate_client, client_jwks, ClientCredentials};
+use crate::proofs::{proof_request_claims, verify_proof_response};
+use crate::session::{session_cookie, session_id};
+use crate::state::{
+    AccessTokenRecord, AppState, ClientInfo, DevicePollError, RefreshTokenError, RefreshTokenRecord,
+};
+use crate::{dpop, openid4vci, openid4vp};
 
 /// HTML template for authorization consent page
-const AUTHORIZE_TEMPLATE: &str = include_str!("../templates/authorize.html");
+pub(crate) const AUTHORIZE_TEMPLATE: &str = include_str!("../templates/authorize.html");
 
 /// Discovery endpoint
 pub async fn discovery(State(state): State<AppState>) -> Json<DiscoveryDocument> {
-    Json(DiscoveryDocument::from_config(&state.config))
+    let mut document = DiscoveryDocument::from_config(&state.config);
+    document.zk_circuits = (*state.zk_circuits).clone();
+    Json(document)
 }
 
-/// JWKS endpoint (placeholder)
-pub async fn jwks() -> Json<serde_json::Value> {
-    // In production, this would return actual public keys
-    Json(serde_json::json!({
-        "keys": []
-    }))
+/// JWKS endpoint - public keys for verifying ID tokens
+pub async fn jwks(State(state): State<AppState>) -> Json<JwkSet> {
+    Json(state.signing_keys.jwks())
 }
 
 /// Authorization request parameters
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthorizeParams {
     pub response_type: String,
     pub client_id: String,
@@ -44,12 +62,102 @@ pub struct AuthorizeParams {
     pub nonce: Option<String>,
     pub code_challenge: Option<String>,
     pub code_challenge_method: Option<String>,
+    /// JSON `claims` request parameter
+    pub claims: Option<String>,
+    /// `consent` shows the consent page even when a grant covers the request
+    pub prompt: Option<String>,
+    /// Authentication context classes the ZK claims must attain, any one
+    pub acr_values: Option<String>,
+}
+
+/// Authorization endpoint query
+///
+/// Carries the request parameters themselves, a signed `request` object
+/// holding them (RFC 9101), or a `request_uri` returned by the PAR endpoint.
+/// With either of the latter, only `client_id` is read from the query.
+#[derive(Debug, Deserialize)]
+pub struct AuthorizeQuery {
+    pub client_id: String,
+    pub request_uri: Option<String>,
+    pub request: Option<String>,
+    pub response_type: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub claims: Option<String>,
+    pub prompt: Option<String>,
+    pub acr_values: Option<String>,
+}
+
+impl AuthorizeQuery {
+    /// The request parameters, if all required ones were sent
+    fn into_params(self) -> Option<AuthorizeParams> {
+        Some(AuthorizeParams {
+            response_type: self.response_type?,
+            client_id: self.client_id,
+            redirect_uri: self.redirect_uri?,
+            scope: self.scope?,
+            state: self.state,
+            nonce: self.nonce,
+            code_challenge: self.code_challenge,
+            code_challenge_method: self.code_challenge_method,
+            claims: self.claims,
+            prompt: self.prompt,
+            acr_values: self.acr_values,
+        })
+    }
+}
+
+/// Parse the `claims` request parameter into the ZK claims it requests
+pub(crate) fn claim_requests(claims: Option<&str>) -> Result<Vec<ClaimRequest>, String> {
+    match claims {
+        Some(claims) => ClaimsRequest::parse(claims)
+            .and_then(|request| request.claim_requests())
+            .map_err(|e| e.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Read the authorization parameters from a signed request object (RFC 9101)
+///
+/// Parameters sent alongside the request object are ignored; only the signed
+/// claims are authoritative.
+async fn request_object_params(
+    state: &AppState,
+    client: &ClientInfo,
+    request: &str,
+) -> Result<AuthorizeParams, String> {
+    let jwks = client_jwks(client)
+        .await
+        .ok_or("client has no registered keys to verify the request object")?;
+    let claims = verify_request_object(request, &client.client_id, &jwks, &state.config.issuer)
+        .map_err(|e| format!("invalid request object: {}", e))?;
+
+    let missing = |name: &str| format!("request object is missing {}", name);
+    Ok(AuthorizeParams {
+        response_type: claims
+            .response_type
+            .ok_or_else(|| missing("response_type"))?,
+        client_id: claims.client_id,
+        redirect_uri: claims.redirect_uri.ok_or_else(|| missing("redirect_uri"))?,
+        scope: claims.scope.ok_or_else(|| missing("scope"))?,
+        state: claims.state,
+        nonce: claims.nonce,
+        code_challenge: claims.code_challenge,
+        code_challenge_method: claims.code_challenge_method,
+        claims: claims.claims.map(|claims| claims.to_string()),
+        prompt: claims.prompt,
+        acr_values: claims.acr_values,
+    })
 }
 
 /// Demo user data for authorization flow
-struct DemoUser {
-    id: &'static str,
-    name: &'static str,
+pub(crate) struct DemoUser {
+    pub(crate) id: &'static str,
+    pub(crate) name: &'static str,
     dob: &'static str,
     age_18: bool,
     age_21: bool,
@@ -58,7 +166,16 @@ struct DemoUser {
     has_degree: bool,
 }
 
-const DEMO_USERS: &[DemoUser] = &[
+impl DemoUser {
+    /// Demo users have a fixed stand-in for the wallet secret
+    pub(crate) fn pairwise_subject(&self, sector_identifier: &str) -> String {
+        let secret =
+            fantasma_crypto::sha3_256(format!("fantasma-demo-user:{}", self.id).as_bytes());
+        fantasma_crypto::pairwise_subject(&secret, sector_identifier)
+    }
+}
+
+pub(crate) const DEMO_USERS: &[DemoUser] = &[
     DemoUser {
         id: "alice",
         name: "Alice",
@@ -101,11 +218,144 @@ const DEMO_USERS: &[DemoUser] = &[
     },
 ];
 
+impl DemoUser {
+    /// The demo user selected on the consent page (Alice by default)
+    pub(crate) fn find(id: Option<&str>) -> &'static DemoUser {
+        let id = id.unwrap_or("alice");
+        DEMO_USERS
+            .iter()
+            .find(|u| u.id == id)
+            .unwrap_or(&DEMO_USERS[0])
+    }
+
+    /// Whether the demo user can prove a claim
+    fn proves(&self, claim_type: &ClaimType) -> bool {
+        match claim_type {
+            // Thresholds above 21 are treated as 21+ in the demo
+            ClaimType::AgeAtLeast { threshold } => {
+                if *threshold <= 18 {
+                    self.age_18
+                } else {
+                    self.age_21
+                }
+            }
+            ClaimType::KycStatus { level, .. } => match level {
+                KycLevel::Basic => self.kyc_basic,
+                KycLevel::Enhanced | KycLevel::Accredited => self.kyc_enhanced,
+            },
+            ClaimType::HoldsCredential {
+                credential_type, ..
+            } => !credential_type.contains("degree") || self.has_degree,
+            ClaimType::SetMembership { .. } => false,
+        }
+    }
+
+    /// Add the claims requested with the `claims` parameter that the user
+    /// proved
+    ///
+    /// Unproven voluntary claims are left out silently; an unproven essential
+    /// claim fails the authorization.
+    pub(crate) fn add_requested_claims(
+        &self,
+        mut zk_claims: ZkClaims,
+        requests: &[ClaimRequest],
+    ) -> Result<ZkClaims, String> {
+        for request in requests {
+            if !self.proves(&request.claim_type) {
+                if request.required {
+                    return Err(format!(
+                        "essential claim could not be proven: {}",
+                        request.claim_type.to_scope()
+                    ));
+                }
+                continue;
+            }
+
+            zk_claims = match &request.claim_type {
+                ClaimType::AgeAtLeast { threshold } => zk_claims.with_age_claim(*threshold, None),
+                ClaimType::HoldsCredential {
+                    credential_type, ..
+                } => zk_claims.with_credential_claim(credential_type.clone(), None),
+                ClaimType::KycStatus {
+                    level,
+                    max_age_seconds,
+                    ..
+                } => zk_claims.with_kyc_claim(*level, None, *max_age_seconds),
+                ClaimType::SetMembership { .. } => zk_claims,
+            };
+        }
+        Ok(zk_claims)
+    }
+}
+
 /// Authorization endpoint - shows consent page
 pub async fn authorize(
     State(state): State<AppState>,
-    Query(params): Query<AuthorizeParams>,
+    headers: HeaderMap,
+    Query(query): Query<AuthorizeQuery>,
 ) -> impl IntoResponse {
+    let client = state.get_client(&query.client_id).await;
+    let par_required = state.config.require_pushed_authorization_requests
+        || client
+            .as_ref()
+            .is_some_and(|c| c.require_pushed_authorization_requests);
+    let signed_request_required = state.config.require_signed_request_object
+        || client
+            .as_ref()
+            .is_some_and(|c| c.require_signed_request_object);
+
+    // Pushed requests were authenticated, and checked for a signed request
+    // object where one is required, when they were pushed
+    let mut params = match (query.request_uri.as_deref(), query.request.as_deref()) {
+        (Some(request_uri), _) => {
+            let Some(params) = state
+                .take_pushed_request(request_uri, &query.client_id)
+                .await
+                .and_then(|params| serde_json::from_value::<AuthorizeParams>(params).ok())
+            else {
+                return Html("<h1>Error</h1><p>Invalid or expired request_uri</p>".to_string())
+                    .into_response();
+            };
+            params
+        }
+        (None, _) if par_required => {
+            return Html(
+                "<h1>Error</h1><p>This client must use pushed authorization requests</p>"
+                    .to_string(),
+            )
+            .into_response();
+        }
+        (None, Some(request)) => {
+            let result = match client {
+                Some(ref client) => request_object_params(&state, client, request).await,
+                None => Err("unknown client".to_string()),
+            };
+            match result {
+                Ok(params) => params,
+                Err(e) => {
+                    return Html(format!("<h1>Error</h1><p>{}</p>", html_escape(&e)))
+                        .into_response();
+                }
+            }
+        }
+        (None, None) if signed_request_required => {
+            return Html(
+                "<h1>Error</h1><p>This client must send a signed request object</p>".to_string(),
+            )
+            .into_response();
+        }
+        (None, None) => match query.into_params() {
+            Some(params) => params,
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Html("<h1>Error</h1><p>Missing required parameter</p>".to_string()),
+                )
+                    .into_response();
+            }
+        },
+    };
+
     // Validate response type
     if params.response_type != "code" {
         return Html(format!(
@@ -124,22 +374,134 @@ pub async fn authorize(
             .into_response();
     }
 
+    // Hold the request to the ZK scopes the client was approved for
+    if let Some(ref client) = client {
+        if let Err(e) = client.restrict_scopes(&mut params.scope, &mut params.claims) {
+            return Redirect::temporary(&authorization_error_url(
+                &params.redirect_uri,
+                "invalid_scope",
+                &e,
+                params.state.as_deref(),
+            ))
+            .into_response();
+        }
+    }
+
+    // Reject requests that don't meet the client's PKCE policy before consent
+    let pkce_policy = client.as_ref().map(|c| c.pkce_policy()).unwrap_or_default();
+    let pkce = match PkceChallenge::from_request(
+        params.code_challenge.as_deref(),
+        params.code_challenge_method.as_deref(),
+        pkce_policy,
+    ) {
+        Ok(pkce) => pkce,
+        Err(e) => {
+            return Redirect::temporary(&authorization_error_url(
+                &params.redirect_uri,
+                "invalid_request",
+                &e.to_string(),
+                params.state.as_deref(),
+            ))
+            .into_response();
+        }
+    };
+
+    let requested_claims = match claim_requests(params.claims.as_deref()) {
+        Ok(requests) => requests,
+        Err(e) => {
+            return Redirect::temporary(&authorization_error_url(
+                &params.redirect_uri,
+                "invalid_request",
+                &e,
+                params.state.as_deref(),
+            ))
+            .into_response();
+        }
+    };
+
+    if let Some(Err(e)) = params.acr_values.as_deref().map(parse_acr_values) {
+        return Redirect::temporary(&authorization_error_url(
+            &params.redirect_uri,
+            "invalid_request",
+            &e,
+            params.state.as_deref(),
+        ))
+        .into_response();
+    }
+
+    // The wallet derives the user's pairwise subject for this sector
+    let Some(sector) = client
+        .as_ref()
+        .and_then(|c| c.sector_identifier(&params.redirect_uri))
+    else {
+        return Redirect::temporary(&authorization_error_url(
+            &params.redirect_uri,
+            "invalid_request",
+            "no sector identifier for redirect_uri",
+            params.state.as_deref(),
+        ))
+        .into_response();
+    };
+
+    // Users who already consented to these scopes are not asked again,
+    // unless the client asks for the consent page
+    let prompt_consent = params
+        .prompt
+        .as_deref()
+        .is_some_and(|prompt| prompt.split_whitespace().any(|p| p == "consent"));
+    if !prompt_consent {
+        if let Some(response) = authorize_from_grant(
+            &state,
+            &headers,
+            &params,
+            &sector,
+            pkce.clone(),
+            &requested_claims,
+        )
+        .await
+        {
+            return response;
+        }
+    }
+
+    // ...and answers the proof request with proofs of the requested claims.
+    // The validated request is kept with it; the consent form only carries
+    // the request ID, so the browser cannot alter what is consented to
+    let proof_request = ProofRequest::new(
+        sector.clone(),
+        proof_request_claims(&params.scope, &requested_claims),
+        state.config.proof_request_expiration_seconds as i64,
+    );
+    state
+        .create_proof_request(
+            &params.client_id,
+            &proof_request,
+            serde_json::to_value(&params).unwrap_or_default(),
+        )
+        .await;
+
     // Get client name
-    let client_name = state
-        .clients
-        .get(&params.client_id)
-        .map(|c| c.name.clone())
+    let client_name = client
+        .map(|c| c.name)
         .unwrap_or_else(|| params.client_id.clone());
 
-    // Parse scopes and build permissions HTML
-    let scopes = parse_scopes(&params.scope);
+    // Parse scopes, plus the claims requested individually, and build permissions HTML
+    let mut scopes = parse_scopes(&params.scope);
+    scopes.extend(
+        requested_claims
+            .iter()
+            .filter_map(|request| ZkScope::from_claim_type(&request.claim_type)),
+    );
     let permissions_html = build_permissions_html(&scopes);
 
     // Build user options HTML
-    let user_options_html = build_user_options_html(&scopes);
+    let user_options_html = build_demo_users_html(&state, &scopes);
 
     // Build hidden fields for form
-    let hidden_fields_html = build_hidden_fields(&params);
+    let hidden_fields_html = build_hidden_fields(&sector, &proof_request);
+
+    // Other wallets can answer the proof request over OpenID4VP
+    let wallet_link_html = openid4vp::wallet_link_html(&state, &proof_request);
 
     // Build deny URL
     let deny_url = format!(
@@ -154,64 +516,340 @@ pub async fn authorize(
 
     // Render the template
     let html = AUTHORIZE_TEMPLATE
+        .replace("{{FORM_ACTION}}", "/authorize/consent")
         .replace("{{CLIENT_NAME}}", &client_name)
         .replace("{{PERMISSIONS}}", &permissions_html)
         .replace("{{USER_OPTIONS}}", &user_options_html)
         .replace("{{HIDDEN_FIELDS}}", &hidden_fields_html)
-        .replace("{{DENY_URL}}", &deny_url);
+        .replace("{{DENY_URL}}", &deny_url)
+        .replace("{{WALLET_LINK}}", &wallet_link_html);
 
     Html(html).into_response()
 }
 
-/// Build hidden fields for the authorization form
-fn build_hidden_fields(params: &AuthorizeParams) -> String {
-    let mut html = String::new();
+/// Check ZK claims against the `acr_values` of an authorization request
+///
+/// The claims must attain at least one of the requested classes; no token
+/// is issued for claims below all of them.
+pub(crate) fn check_acr_values(
+    acr_values: Option<&str>,
+    zk_claims: &ZkClaims,
+) -> Result<(), String> {
+    let Some(acr_values) = acr_values.filter(|v| !v.trim().is_empty()) else {
+        return Ok(());
+    };
+    let required = parse_acr_values(acr_values)?;
+    let attained = zk_claims.auth_context();
+    if attained.is_some_and(|attained| required.iter().any(|r| attained.satisfies(r))) {
+        return Ok(());
+    }
+    Err(format!(
+        "the claims attain {}, below the requested acr_values",
+        attained.map_or_else(|| "no verified claim".to_string(), |a| a.to_string())
+    ))
+}
 
-    html.push_str(&format!(
-        r#"<input type="hidden" name="response_type" value="{}">"#,
-        html_escape(&params.response_type)
-    ));
-    html.push_str(&format!(
-        r#"<input type="hidden" name="client_id" value="{}">"#,
-        html_escape(&params.client_id)
-    ));
-    html.push_str(&format!(
-        r#"<input type="hidden" name="redirect_uri" value="{}">"#,
-        html_escape(&params.redirect_uri)
-    ));
-    html.push_str(&format!(
-        r#"<input type="hidden" name="scope" value="{}">"#,
-        html_escape(&params.scope)
-    ));
+/// The scopes a consent grants: the requested scopes, plus those of the
+/// claims requested individually
+pub(crate) fn granted_scopes(scope: &str, claim_requests: &[ClaimRequest]) -> Vec<String> {
+    let mut scopes: Vec<String> = parse_scopes(scope).iter().map(|s| s.to_string()).collect();
+    for scope in claim_requests
+        .iter()
+        .filter_map(|request| ZkScope::from_claim_type(&request.claim_type))
+    {
+        let scope = scope.to_string();
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    scopes
+}
 
-    if let Some(ref state) = params.state {
-        html.push_str(&format!(
-            r#"<input type="hidden" name="state" value="{}">"#,
-            html_escape(state)
-        ));
+/// Issue a code without the consent page when the user consented before
+///
+/// Applies when the browser session signed in to the client before, and the
+/// subject's grant to it was made in `sector` and covers the requested scopes
+/// with claims that are still fresh; the code carries the granted claims for
+/// those scopes.
+async fn authorize_from_grant(
+    state: &AppState,
+    headers: &HeaderMap,
+    params: &AuthorizeParams,
+    sector: &str,
+    pkce: Option<PkceChallenge>,
+    requested_claims: &[ClaimRequest],
+) -> Option<Response> {
+    let sid = session_id(headers)?;
+    let (_, subject_id) = state
+        .session_clients(sid)
+        .await
+        .into_iter()
+        .find(|(client_id, _)| *client_id == params.client_id)?;
+    let grant = state.find_consent(&subject_id, &params.client_id).await?;
+    // The subject is pairwise for the sector it was consented in; a redirect
+    // URI in another sector takes a subject of its own
+    if grant.sector_identifier.as_deref() != Some(sector) {
+        return None;
     }
-    if let Some(ref nonce) = params.nonce {
-        html.push_str(&format!(
-            r#"<input type="hidden" name="nonce" value="{}">"#,
-            html_escape(nonce)
+    let scopes = granted_scopes(&params.scope, requested_claims);
+    if !grant.covers(
+        &scopes,
+        state.config.zk_claims_max_age_seconds,
+        chrono::Utc::now(),
+    ) {
+        return None;
+    }
+    // Claims too weak for the requested acr_values take fresh proofs
+    let zk_claims = grant.claims_for(&scopes);
+    if check_acr_values(params.acr_values.as_deref(), &zk_claims).is_err() {
+        return None;
+    }
+
+    let sid = state
+        .join_session(Some(sid), &params.client_id, &subject_id)
+        .await;
+    let code = state
+        .create_auth_code(
+            params.client_id.clone(),
+            params.redirect_uri.clone(),
+            subject_id,
+            parse_scopes(&params.scope)
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            params.nonce.clone(),
+            pkce,
+            Some(sid.clone()),
+            requested_claims.to_vec(),
+            Some(zk_claims),
+        )
+        .await;
+
+    tracing::info!(
+        "Authorization granted on an earlier consent with scopes: {:?}",
+        params.scope
+    );
+
+    let mut redirect_url = format!("{}?code={}", params.redirect_uri, code);
+    if let Some(ref s) = params.state {
+        redirect_url.push_str(&format!("&state={}", s));
+    }
+    Some(
+        (
+            [(header::SET_COOKIE, session_cookie(state, &sid))],
+            Redirect::temporary(&redirect_url),
+        )
+            .into_response(),
+    )
+}
+
+/// Pushed authorization request parameters (RFC 9126 §2.1)
+///
+/// The authorization request parameters, minus `client_id`, which comes
+/// from the client authentication. They may also be pushed as a signed
+/// `request` object.
+#[derive(Debug, Deserialize)]
+pub struct PushedAuthorizationParams {
+    pub response_type: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub claims: Option<String>,
+    pub prompt: Option<String>,
+    pub acr_values: Option<String>,
+    /// Not allowed: a pushed request cannot refer to another one
+    pub request_uri: Option<String>,
+    pub request: Option<String>,
+    #[serde(flatten)]
+    pub client: ClientCredentials,
+}
+
+/// Pushed authorization response (RFC 9126 §2.2)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PushedAuthorizationResponse {
+    pub request_uri: String,
+    pub expires_in: u64,
+}
+
+/// Pushed authorization request endpoint (RFC 9126)
+///
+/// Authenticates the client and validates the request up front, so that
+/// `/authorize` only needs the returned `request_uri` and the client_id.
+pub async fn pushed_authorization_request(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::Form(params): axum::Form<PushedAuthorizationParams>,
+) -> Result<(StatusCode, Json<PushedAuthorizationResponse>), (StatusCode, Json<serde_json::Value>)>
+{
+    let client = authenticate_client(&state, &headers, &params.client).await?;
+
+    let invalid = |error: &str, description: &str| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": error,
+                "error_description": description
+            })),
+        )
+    };
+
+    if params.request_uri.is_some() {
+        return Err(invalid(
+            "invalid_request",
+            "request_uri is not allowed in a pushed authorization request",
         ));
     }
-    if let Some(ref challenge) = params.code_challenge {
-        html.push_str(&format!(
-            r#"<input type="hidden" name="code_challenge" value="{}">"#,
-            html_escape(challenge)
+
+    let signed_request_required =
+        state.config.require_signed_request_object || client.require_signed_request_object;
+    let mut authorize_params = match params.request {
+        Some(ref request) => request_object_params(&state, &client, request)
+            .await
+            .map_err(|e| invalid("invalid_request_object", &e))?,
+        None if signed_request_required => {
+            return Err(invalid(
+                "invalid_request",
+                "this client must send a signed request object",
+            ));
+        }
+        None => {
+            let missing = |name: &str| invalid("invalid_request", &format!("{} is required", name));
+            AuthorizeParams {
+                response_type: params
+                    .response_type
+                    .ok_or_else(|| missing("response_type"))?,
+                client_id: client.client_id.clone(),
+                redirect_uri: params.redirect_uri.ok_or_else(|| missing("redirect_uri"))?,
+                scope: params.scope.ok_or_else(|| missing("scope"))?,
+                state: params.state,
+                nonce: params.nonce,
+                code_challenge: params.code_challenge,
+                code_challenge_method: params.code_challenge_method,
+                claims: params.claims,
+                prompt: params.prompt,
+                acr_values: params.acr_values,
+            }
+        }
+    };
+
+    if authorize_params.response_type != "code" {
+        return Err(invalid(
+            "unsupported_response_type",
+            "only the code response type is supported",
         ));
     }
-    if let Some(ref method) = params.code_challenge_method {
+    if !state
+        .validate_redirect_uri(&client.client_id, &authorize_params.redirect_uri)
+        .await
+    {
+        return Err(invalid("invalid_request", "invalid redirect_uri"));
+    }
+    PkceChallenge::from_request(
+        authorize_params.code_challenge.as_deref(),
+        authorize_params.code_challenge_method.as_deref(),
+        client.pkce_policy(),
+    )
+    .map_err(|e| invalid("invalid_request", &e.to_string()))?;
+    client
+        .restrict_scopes(&mut authorize_params.scope, &mut authorize_params.claims)
+        .map_err(|e| invalid("invalid_scope", &e))?;
+    claim_requests(authorize_params.claims.as_deref())
+        .map_err(|e| invalid("invalid_request", &e))?;
+    if let Some(ref acr_values) = authorize_params.acr_values {
+        parse_acr_values(acr_values).map_err(|e| invalid("invalid_request", &e))?;
+    }
+
+    let stored = serde_json::to_value(&authorize_params).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": "server_error",
+                "error_description": e.to_string()
+            })),
+        )
+    })?;
+    let request_uri = state
+        .push_authorization_request(client.client_id, stored)
+        .await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(PushedAuthorizationResponse {
+            request_uri,
+            expires_in: state.config.request_uri_expiration_seconds,
+        }),
+    ))
+}
+
+/// Build an error redirect back to the client (RFC 6749 §4.1.2.1)
+pub(crate) fn authorization_error_url(
+    redirect_uri: &str,
+    error: &str,
+    description: &str,
+    state: Option<&str>,
+) -> String {
+    let mut url = format!(
+        "{}?error={}&error_description={}",
+        redirect_uri,
+        error,
+        urlencoding::encode(description)
+    );
+    if let Some(s) = state {
+        url.push_str(&format!("&state={}", s));
+    }
+    url
+}
+
+/// Build hidden fields for the authorization form
+///
+/// The form identifies the authorization request by its proof request ID;
+/// the request itself stays on the server.
+pub(crate) fn build_hidden_fields(sector_identifier: &str, proof_request: &ProofRequest) -> String {
+    let mut html = String::new();
+
+    html.push_str(&format!(
+        r#"<input type="hidden" name="request_id" value="{}">"#,
+        html_escape(&proof_request.request_id)
+    ));
+    // Informational for the wallet; the consent handler derives it again
+    html.push_str(&format!(
+        r#"<input type="hidden" name="sector_identifier" value="{}">"#,
+        html_escape(sector_identifier)
+    ));
+    // For the wallet, which posts its answer back as `proof_response`
+    if let Ok(request) = serde_json::to_string(proof_request) {
         html.push_str(&format!(
-            r#"<input type="hidden" name="code_challenge_method" value="{}">"#,
-            html_escape(method)
+            r#"<input type="hidden" name="proof_request" value="{}">"#,
+            html_escape(&request)
         ));
     }
 
     html
 }
 
+/// Build the demo user selection for the consent page, if demo users are
+/// enabled
+pub(crate) fn build_demo_users_html(state: &AppState, scopes: &[ZkScope]) -> String {
+    if !state.config.demo_users {
+        return String::new();
+    }
+    format!(
+        r#"<div class="demo-notice">
+                    <strong>Demo Mode:</strong> Select a test user below to simulate different verification scenarios.
+                </div>
+                <div class="user-selector">
+                    <div class="section-title">Select Test User</div>
+                    <div class="user-grid">
+                        {}
+                    </div>
+                </div>"#,
+        build_user_options_html(scopes)
+    )
+}
+
 /// Build user options HTML with pass/fail badges based on requested scopes
 fn build_user_options_html(scopes: &[ZkScope]) -> String {
     let mut html = String::new();
@@ -225,12 +863,11 @@ fn build_user_options_html(scopes: &[ZkScope]) -> String {
         for scope in scopes {
             match scope {
                 ZkScope::Age { threshold } => {
+                    // Thresholds above 21 are treated as 21+ in the demo
                     let passes = if *threshold <= 18 {
                         user.age_18
-                    } else if *threshold <= 21 {
-                        user.age_21
                     } else {
-                        user.age_21 // assume 21+ for higher thresholds in demo
+                        user.age_21
                     };
                     badges.push((format!("{}+", threshold), passes));
                 }
@@ -285,7 +922,7 @@ fn build_user_options_html(scopes: &[ZkScope]) -> String {
 }
 
 /// Simple HTML escape
-fn html_escape(s: &str) -> String {
+pub(crate) fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -294,7 +931,7 @@ fn html_escape(s: &str) -> String {
 }
 
 /// Build permissions HTML from scopes
-fn build_permissions_html(scopes: &[ZkScope]) -> String {
+pub(crate) fn build_permissions_html(scopes: &[ZkScope]) -> String {
     let mut html = String::new();
 
     for scope in scopes {
@@ -385,12 +1022,14 @@ fn build_query_string(params: &AuthorizeParams) -> String {
             urlencoding::encode(method)
         ));
     }
+    if let Some(ref claims) = params.claims {
+        parts.push(format!("claims={}", urlencoding::encode(claims)));
+    }
 
     parts.join("&")
 }
 
 /// URL encoding helper
-#[allow(dead_code)]
 mod urlencoding {
     pub fn encode(s: &str) -> String {
         url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
@@ -401,70 +1040,234 @@ mod urlencoding {
 #[derive(Debug, Deserialize)]
 pub struct ConsentParams {
     pub action: String,
-    pub response_type: String,
-    pub client_id: String,
-    pub redirect_uri: String,
-    pub scope: String,
-    pub state: Option<String>,
-    pub nonce: Option<String>,
-    pub code_challenge: Option<String>,
-    pub code_challenge_method: Option<String>,
+    /// ID of the proof request issued with the consent page, which holds
+    /// the authorization request
+    pub request_id: String,
     /// Selected demo user for testing
     pub demo_user: Option<String>,
+    /// The wallet's JSON `ProofResponse` to the page's proof request
+    pub proof_response: Option<String>,
 }
 
 /// Consent confirmation endpoint (accepts form data via POST)
+///
+/// The authorization request is the one validated when the consent page was
+/// shown, looked up by its proof request ID; each can be consented to once.
+/// When the wallet answered the proof request, its proofs are verified here
+/// and the claims they prove stored on the code; otherwise the selected demo
+/// user stands in for a wallet, when demo users are enabled. Approval joins
+/// the browser's session, or starts one and sets the session cookie, so the
+/// client is notified when the user logs out.
 pub async fn authorize_consent(
     State(state): State<AppState>,
-    axum::Form(params): axum::Form<ConsentParams>,
+    headers: HeaderMap,
+    axum::Form(form): axum::Form<ConsentParams>,
 ) -> impl IntoResponse {
-    if params.action != "approve" && params.action != "allow" {
-        let mut redirect_url = format!(
-            "{}?error=access_denied&error_description=User%20denied%20access",
-            params.redirect_uri
-        );
-        if let Some(s) = params.state {
-            redirect_url.push_str(&format!("&state={}", s));
-        }
-        return Redirect::temporary(&redirect_url);
+    let Some(pending) = state.take_proof_request(&form.request_id).await else {
+        return Html("<h1>Error</h1><p>Invalid or expired authorization request</p>".to_string())
+            .into_response();
+    };
+    let Ok(params) = serde_json::from_value::<AuthorizeParams>(pending.params.clone()) else {
+        return Html("<h1>Error</h1><p>Invalid or expired authorization request</p>".to_string())
+            .into_response();
+    };
+
+    if form.action != "approve" && form.action != "allow" {
+        return Redirect::temporary(&authorization_error_url(
+            &params.redirect_uri,
+            "access_denied",
+            "User denied access",
+            params.state.as_deref(),
+        ))
+        .into_response();
     }
 
+    // The request was validated before the consent page was shown; only
+    // what the code needs is derived again here
+    let client = state.get_client(&params.client_id).await;
+    let pkce_policy = client.as_ref().map(|c| c.pkce_policy()).unwrap_or_default();
+    let pkce = match PkceChallenge::from_request(
+        params.code_challenge.as_deref(),
+        params.code_challenge_method.as_deref(),
+        pkce_policy,
+    ) {
+        Ok(pkce) => pkce,
+        Err(e) => {
+            return Redirect::temporary(&authorization_error_url(
+                &params.redirect_uri,
+                "invalid_request",
+                &e.to_string(),
+                params.state.as_deref(),
+            ))
+            .into_response();
+        }
+    };
+
+    let Some(sector) = client
+        .as_ref()
+        .and_then(|c| c.sector_identifier(&params.redirect_uri))
+    else {
+        return Redirect::temporary(&authorization_error_url(
+            &params.redirect_uri,
+            "invalid_request",
+            "no sector identifier for redirect_uri",
+            params.state.as_deref(),
+        ))
+        .into_response();
+    };
+    let requested_claims = match claim_requests(params.claims.as_deref()) {
+        Ok(requests) => requests,
+        Err(e) => {
+            return Redirect::temporary(&authorization_error_url(
+                &params.redirect_uri,
+                "invalid_request",
+                &e,
+                params.state.as_deref(),
+            ))
+            .into_response();
+        }
+    };
+
+    let proof_response = match form
+        .proof_response
+        .as_deref()
+        .map(serde_json::from_str::<ProofResponse>)
+        .transpose()
+    {
+        Ok(response) => response,
+        Err(e) => {
+            return Redirect::temporary(&authorization_error_url(
+                &params.redirect_uri,
+                "invalid_request",
+                &format!("malformed proof_response: {}", e),
+                params.state.as_deref(),
+            ))
+            .into_response();
+        }
+    };
+    let verified_claims = match proof_response {
+        Some(ref response) => {
+            match verify_proof_response(&state, &pending, &sector, &params.scope, response).await {
+                Ok(zk_claims) => Some(zk_claims),
+                Err(e) => {
+                    tracing::warn!("Rejected proof response: {}", e);
+                    return Redirect::temporary(&authorization_error_url(
+                        &params.redirect_uri,
+                        e.error_code(),
+                        &e.to_string(),
+                        params.state.as_deref(),
+                    ))
+                    .into_response();
+                }
+            }
+        }
+        None if state.config.demo_users => None,
+        None => {
+            return Redirect::temporary(&authorization_error_url(
+                &params.redirect_uri,
+                "access_denied",
+                "the wallet did not answer the proof request",
+                params.state.as_deref(),
+            ))
+            .into_response();
+        }
+    };
+
     // Get demo user (default to alice)
-    let demo_user_id = params.demo_user.as_deref().unwrap_or("alice");
-    let demo_user = DEMO_USERS
-        .iter()
-        .find(|u| u.id == demo_user_id)
-        .unwrap_or(&DEMO_USERS[0]);
+    let demo_user = DemoUser::find(form.demo_user.as_deref());
+
+    // The wallet's proofs are bound to the subject it presents; demo users
+    // stand in for a wallet
+    let subject_id = match proof_response {
+        Some(response) => response.subject_id,
+        None => demo_user.pairwise_subject(&sector),
+    };
 
-    // Parse scopes and add demo_user prefix to track which user was selected
     let scopes = parse_scopes(&params.scope);
     let mut scope_strings: Vec<String> = scopes.iter().map(|s| s.to_string()).collect();
 
-    // Add demo user marker (will be used in token generation)
-    scope_strings.push(format!("demo_user:{}", demo_user.id));
+    if verified_claims.is_none() {
+        // Essential claims the user cannot prove end the authorization here
+        if let Err(e) = demo_user.add_requested_claims(ZkClaims::new(), &requested_claims) {
+            return Redirect::temporary(&authorization_error_url(
+                &params.redirect_uri,
+                "access_denied",
+                &e,
+                params.state.as_deref(),
+            ))
+            .into_response();
+        }
+
+        // Add demo user marker (will be used in token generation)
+        scope_strings.push(format!("demo_user:{}", demo_user.id));
+    }
+
+    // Remember the consent, so that re-authorizing skips this page
+    let granted_claims = match verified_claims {
+        Some(ref zk_claims) => zk_claims.clone(),
+        None => demo_user_claims(&scope_strings, &requested_claims).unwrap_or_default(),
+    };
+    if let Err(e) = check_acr_values(params.acr_values.as_deref(), &granted_claims) {
+        return Redirect::temporary(&authorization_error_url(
+            &params.redirect_uri,
+            "access_denied",
+            &e,
+            params.state.as_deref(),
+        ))
+        .into_response();
+    }
+    state
+        .record_consent(
+            &subject_id,
+            &params.client_id,
+            &sector,
+            granted_scopes(&params.scope, &requested_claims),
+            granted_claims,
+        )
+        .await;
+
+    let sid = state
+        .join_session(session_id(&headers), &params.client_id, &subject_id)
+        .await;
 
     // Create authorization code
     let code = state
         .create_auth_code(
             params.client_id,
             params.redirect_uri.clone(),
+            subject_id,
             scope_strings,
             params.nonce,
+            pkce,
+            Some(sid.clone()),
+            requested_claims,
+            verified_claims.clone(),
         )
         .await;
 
-    tracing::info!(
-        "Authorization granted for demo user '{}' with scopes: {:?}",
-        demo_user.name,
-        params.scope
-    );
+    if verified_claims.is_some() {
+        tracing::info!(
+            "Authorization granted on wallet proofs with scopes: {:?}",
+            params.scope
+        );
+    } else {
+        tracing::info!(
+            "Authorization granted for demo user '{}' with scopes: {:?}",
+            demo_user.name,
+            params.scope
+        );
+    }
 
     let mut redirect_url = format!("{}?code={}", params.redirect_uri, code);
     if let Some(s) = params.state {
         redirect_url.push_str(&format!("&state={}", s));
     }
 
-    Redirect::temporary(&redirect_url)
+    (
+        [(header::SET_COOKIE, session_cookie(&state, &sid))],
+        Redirect::temporary(&redirect_url),
+    )
+        .into_response()
 }
 
 /// Token request parameters
@@ -473,18 +1276,62 @@ pub struct TokenParams {
     pub grant_type: String,
     pub code: Option<String>,
     pub redirect_uri: Option<String>,
-    pub client_id: Option<String>,
-    pub client_secret: Option<String>,
     pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+    pub device_code: Option<String>,
+    #[serde(rename = "pre-authorized_code")]
+    pub pre_authorized_code: Option<String>,
+    #[serde(flatten)]
+    pub client: ClientCredentials,
 }
 
 /// Token endpoint
+///
+/// Requests with a DPoP proof get access tokens bound to the proof's key,
+/// and public clients also get bound refresh tokens (RFC 9449 §5).
 pub async fn token(
     State(state): State<AppState>,
+    headers: HeaderMap,
     axum::Form(params): axum::Form<TokenParams>,
+) -> Response {
+    // Wallets redeem credential offers without authenticating as a client
+    if params.grant_type == PRE_AUTHORIZED_CODE_GRANT_TYPE {
+        return openid4vci::pre_authorized_code_grant(&state, params.pre_authorized_code)
+            .await
+            .into_response();
+    }
+
+    let proof =
+        dpop::check_proof(&state, &headers, "POST", &state.config.token_endpoint, None).await;
+    let dpop_jkt = match proof {
+        Ok(proof) => proof.map(|proof| proof.jkt),
+        Err(e) => return dpop::token_error(&state, e).await,
+    };
+
+    if dpop_jkt.is_some() {
+        let response = client_token(state.clone(), headers, params, dpop_jkt).await;
+        dpop::with_nonce(&state, response).await
+    } else {
+        client_token(state, headers, params, None)
+            .await
+            .into_response()
+    }
+}
+
+/// Token requests from clients
+///
+/// `dpop_jkt` is the thumbprint of the key of a verified DPoP proof.
+async fn client_token(
+    state: AppState,
+    headers: HeaderMap,
+    params: TokenParams,
+    dpop_jkt: Option<String>,
 ) -> Result<Json<TokenResponse>, (StatusCode, Json<serde_json::Value>)> {
     // Validate grant type
-    if params.grant_type != "authorization_code" {
+    if params.grant_type != "authorization_code"
+        && params.grant_type != "refresh_token"
+        && params.grant_type != DEVICE_CODE_GRANT_TYPE
+    {
         return Err((
             StatusCode::BAD_REQUEST,
             Json(serde_json::json!({
@@ -493,6 +1340,16 @@ pub async fn token(
         ));
     }
 
+    // Authenticate the client before touching the code or refresh token
+    let client = authenticate_client(&state, &headers, &params.client).await?;
+
+    if params.grant_type == "refresh_token" {
+        return refresh_token_grant(&state, client, params, dpop_jkt).await;
+    }
+    if params.grant_type == DEVICE_CODE_GRANT_TYPE {
+        return device_code_grant(&state, client, params, dpop_jkt).await;
+    }
+
     // Get the authorization code
     let code = params.code.ok_or_else(|| {
         (
@@ -515,18 +1372,239 @@ pub async fn token(
         )
     })?;
 
-    // Find demo user from scopes
-    let demo_user_id = auth_code
-        .scopes
-        .iter()
-        .find(|s| s.starts_with("demo_user:"))
-        .and_then(|s| s.strip_prefix("demo_user:"))
-        .unwrap_or("alice");
+    // The code must have been issued to this client, for this redirect URI
+    if auth_code.client_id != client.client_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "invalid_grant",
+                "error_description": "code was issued to another client"
+            })),
+        ));
+    }
+    if params.redirect_uri.as_deref() != Some(auth_code.redirect_uri.as_str()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "invalid_grant",
+                "error_description": "redirect_uri does not match the authorization request"
+            })),
+        ));
+    }
+
+    // Verify the PKCE code verifier (RFC 7636 §4.6)
+    let pkce_result = match &auth_code.pkce {
+        Some(pkce) => pkce
+            .verify(params.code_verifier.as_deref())
+            .map_err(|e| e.to_string()),
+        None if params.code_verifier.is_some() => {
+            Err("code_verifier sent but no code_challenge was registered".to_string())
+        }
+        None if client.pkce_policy().required => {
+            Err("code was issued without a code_challenge".to_string())
+        }
+        None => Ok(()),
+    };
+    pkce_result.map_err(|description| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "invalid_grant",
+                "error_description": description
+            })),
+        )
+    })?;
 
-    let demo_user = DEMO_USERS
+    authorization_tokens(
+        &state,
+        &client,
+        auth_code.subject_id,
+        auth_code.scopes,
+        auth_code.nonce,
+        auth_code.sid,
+        &auth_code.claim_requests,
+        auth_code.zk_claims,
+        dpop_jkt,
+    )
+    .await
+}
+
+/// Device code grant (RFC 8628 §3.4)
+///
+/// Until the user has finished at the verification page, polls are answered
+/// with `authorization_pending`, or `slow_down` when they come too quickly.
+async fn device_code_grant(
+    state: &AppState,
+    client: ClientInfo,
+    params: TokenParams,
+    dpop_jkt: Option<String>,
+) -> Result<Json<TokenResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let device_code = params.device_code.ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "invalid_request",
+                "error_description": "device_code is required"
+            })),
+        )
+    })?;
+
+    let poll_error = |e: DevicePollError| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": e.error_code(),
+                "error_description": e.to_string()
+            })),
+        )
+    };
+    let authorization = state
+        .poll_device_authorization(&device_code, &client.client_id)
+        .await
+        .map_err(poll_error)?;
+    let subject_id = authorization
+        .subject_id
+        .ok_or_else(|| poll_error(DevicePollError::Invalid))?;
+
+    authorization_tokens(
+        state,
+        &client,
+        subject_id,
+        authorization.scopes,
+        None,
+        authorization.sid,
+        &[],
+        authorization.zk_claims,
+        dpop_jkt,
+    )
+    .await
+}
+
+/// Issue the tokens for an authorization the user consented to
+///
+/// `verified_claims` are the ZK claims proven by the wallet at consent.
+/// Without them, the claims come from the demo user named by the
+/// `demo_user:` marker in `scopes` when demo users are enabled, and there
+/// are none otherwise. With `dpop_jkt`, the access token is
+/// bound to that key, and so is the refresh token of a public client.
+#[allow(clippy::too_many_arguments)]
+async fn authorization_tokens(
+    state: &AppState,
+    client: &ClientInfo,
+    subject_id: String,
+    scopes: Vec<String>,
+    nonce: Option<String>,
+    sid: Option<String>,
+    claim_requests: &[ClaimRequest],
+    verified_claims: Option<ZkClaims>,
+    dpop_jkt: Option<String>,
+) -> Result<Json<TokenResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let zk_claims = match verified_claims {
+        Some(zk_claims) => zk_claims,
+        None if state.config.demo_users => demo_user_claims(&scopes, claim_requests)?,
+        None => ZkClaims::new(),
+    };
+
+    // Create ID token
+    let now = chrono::Utc::now();
+    let claims = IdTokenClaims::new(
+        &state.config.issuer,
+        &subject_id,
+        &client.client_id,
+        state.config.token_expiration_seconds,
+    )
+    .with_auth_time(now.timestamp() as u64)
+    .with_zk_claims(zk_claims.clone());
+
+    let claims = if let Some(nonce) = nonce {
+        claims.with_nonce(nonce)
+    } else {
+        claims
+    };
+    let claims = match sid {
+        Some(ref sid) => claims.with_session_id(sid),
+        None => claims,
+    };
+
+    let id_token = sign_id_token(state, client, claims).await?;
+
+    // Start a new refresh token family for this authorization
+    let refresh_record = RefreshTokenRecord {
+        family_id: uuid::Uuid::new_v4(),
+        client_id: client.client_id.clone(),
+        subject_id,
+        // The demo user selection rides along as a pseudo-scope; it was never granted
+        scopes: scopes
+            .into_iter()
+            .filter(|s| !s.starts_with("demo_user:"))
+            .collect(),
+        zk_claims,
+        zk_claims_verified_at: now,
+        expires_at: now
+            + chrono::Duration::seconds(state.config.refresh_token_expiration_seconds as i64),
+        used: false,
+        revoked: false,
+        sid,
+        // Confidential clients authenticate on refresh; public ones prove the key
+        dpop_jkt: dpop_jkt.clone().filter(|_| client.is_public()),
+    };
+    let refresh_token = state.issue_refresh_token(refresh_record.clone()).await;
+
+    let access_token = state
+        .issue_access_token(AccessTokenRecord {
+            client_id: refresh_record.client_id.clone(),
+            subject_id: refresh_record.subject_id.clone(),
+            scopes: refresh_record.scopes.clone(),
+            zk_claims: refresh_record.zk_claims.clone(),
+            expires_at: now
+                + chrono::Duration::seconds(state.config.token_expiration_seconds as i64),
+            family_id: Some(refresh_record.family_id),
+            dpop_jkt: dpop_jkt.clone(),
+        })
+        .await;
+
+    Ok(Json(token_response(
+        state,
+        access_token,
+        id_token,
+        refresh_token,
+        dpop_jkt.is_some(),
+    )))
+}
+
+fn token_response(
+    state: &AppState,
+    access_token: String,
+    id_token: String,
+    refresh_token: String,
+    dpop_bound: bool,
+) -> TokenResponse {
+    let response = TokenResponse::new(
+        access_token,
+        id_token,
+        state.config.token_expiration_seconds,
+    )
+    .with_refresh_token(refresh_token);
+    if dpop_bound {
+        response.dpop_bound()
+    } else {
+        response
+    }
+}
+
+/// The ZK claims of the demo user selected at consent
+///
+/// `scopes` carry the `demo_user:` marker, and `claim_requests` are the ZK
+/// claims requested with the `claims` parameter.
+pub(crate) fn demo_user_claims(
+    scopes: &[String],
+    claim_requests: &[ClaimRequest],
+) -> Result<ZkClaims, (StatusCode, Json<serde_json::Value>)> {
+    let demo_user_id = scopes
         .iter()
-        .find(|u| u.id == demo_user_id)
-        .unwrap_or(&DEMO_USERS[0]);
+        .find(|s| s.starts_with("demo_user:"))
+        .and_then(|s| s.strip_prefix("demo_user:"));
+    let demo_user = DemoUser::find(demo_user_id);
 
     tracing::info!(
         "Generating token for demo user '{}' (born {})",
@@ -537,7 +1615,7 @@ pub async fn token(
     // Build ZK claims based on scopes and demo user's actual credentials
     let mut zk_claims = ZkClaims::new();
 
-    for scope in &auth_code.scopes {
+    for scope in scopes {
         if scope.starts_with("zk:age:") {
             let threshold: u8 = scope
                 .strip_prefix("zk:age:")
@@ -545,12 +1623,11 @@ pub async fn token(
                 .unwrap_or(18);
 
             // Check if demo user passes the age requirement
+            // Thresholds above 21 are treated as 21+ in the demo
             let verified = if threshold <= 18 {
                 demo_user.age_18
-            } else if threshold <= 21 {
-                demo_user.age_21
             } else {
-                demo_user.age_21 // Assume 21+ covers higher thresholds
+                demo_user.age_21
             };
 
             zk_claims = zk_claims.with_age_claim_verified(threshold, verified, None);
@@ -586,48 +1663,461 @@ pub async fn token(
             zk_claims = zk_claims.with_credential_claim_verified(cred_type, verified, None);
         }
     }
+    demo_user
+        .add_requested_claims(zk_claims, claim_requests)
+        .map(with_demo_provenance)
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "invalid_grant",
+                    "error_description": e
+                })),
+            )
+        })
+}
 
-    // Create ID token
-    let claims = IdTokenClaims::new(
-        &state.config.issuer,
-        &auth_code.subject_id,
-        &auth_code.client_id,
-        state.config.token_expiration_seconds,
-    )
-    .with_zk_claims(zk_claims);
-
-    let claims = if let Some(nonce) = auth_code.nonce {
-        claims.with_nonce(nonce)
-    } else {
-        claims
-    };
+/// Mark the demo user's verified claims as asserted without a proof
+///
+/// The demo credentials stand in for those of the seed issuers: a government
+/// identity authority, an accredited KYC provider and a self-declared
+/// university.
+fn with_demo_provenance(mut zk_claims: ZkClaims) -> ZkClaims {
+    if let Some(claim) = zk_claims.zk_age_claim.as_mut().filter(|c| c.verified) {
+        claim.provenance = Some(ClaimProvenance::demo(IssuerTrust::Government.min_level()));
+    }
+    if let Some(claim) = zk_claims.zk_kyc_claim.as_mut().filter(|c| c.verified) {
+        claim.provenance = Some(ClaimProvenance::demo(IssuerTrust::Accredited.min_level()));
+    }
+    if let Some(claim) = zk_claims
+        .zk_credential_claim
+        .as_mut()
+        .filter(|c| c.verified)
+    {
+        claim.provenance = Some(ClaimProvenance::demo(IssuerTrust::SelfDeclared.min_level()));
+    }
+    zk_claims
+}
 
-    let id_token = IdToken::create(claims, state.signing_key.as_ref()).map_err(|e| {
+/// Refresh token grant (RFC 6749 §6)
+///
+/// The presented token is rotated. The new ID token re-asserts the ZK claims
+/// from the original authorization only while they are within their freshness
+/// window; after that the user has to authorize again with fresh proofs.
+/// DPoP-bound refresh tokens need a proof from the same key.
+async fn refresh_token_grant(
+    state: &AppState,
+    client: ClientInfo,
+    params: TokenParams,
+    dpop_jkt: Option<String>,
+) -> Result<Json<TokenResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let refresh_token = params.refresh_token.ok_or_else(|| {
         (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_REQUEST,
             Json(serde_json::json!({
-                "error": "server_error",
-                "error_description": e.to_string()
+                "error": "invalid_request",
+                "error_description": "refresh_token is required"
             })),
         )
     })?;
 
-    // Generate access token
-    let access_token = uuid::Uuid::new_v4().to_string();
+    let record = state
+        .redeem_refresh_token(&refresh_token, &client.client_id, dpop_jkt.as_deref())
+        .await
+        .map_err(|e| {
+            match e {
+                RefreshTokenError::Storage => return server_error(&e.to_string()),
+                RefreshTokenError::Reused => tracing::warn!(
+                    "Refresh token reuse detected for client '{}'",
+                    client.client_id
+                ),
+                _ => {}
+            }
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "invalid_grant",
+                    "error_description": e.to_string()
+                })),
+            )
+        })?;
+
+    let now = chrono::Utc::now();
+    let max_age = record
+        .zk_claims
+        .max_age_seconds(state.config.zk_claims_max_age_seconds);
+    if !record.zk_claims.is_empty()
+        && (now - record.zk_claims_verified_at).num_seconds() >= max_age as i64
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "invalid_grant",
+                "error_description": "ZK claims are no longer fresh; re-authorize with new proofs"
+            })),
+        ));
+    }
 
-    Ok(Json(TokenResponse::new(
-        access_token,
-        id_token.token,
+    let claims = IdTokenClaims::new(
+        &state.config.issuer,
+        &record.subject_id,
+        &record.client_id,
         state.config.token_expiration_seconds,
+    )
+    .with_auth_time(record.zk_claims_verified_at.timestamp() as u64)
+    .with_zk_claims(record.zk_claims.clone());
+    let claims = match record.sid {
+        Some(ref sid) => claims.with_session_id(sid),
+        None => claims,
+    };
+
+    let id_token = sign_id_token(state, &client, claims).await?;
+
+    let access_token = state
+        .issue_access_token(AccessTokenRecord {
+            client_id: record.client_id.clone(),
+            subject_id: record.subject_id.clone(),
+            scopes: record.scopes.clone(),
+            zk_claims: record.zk_claims.clone(),
+            expires_at: now
+                + chrono::Duration::seconds(state.config.token_expiration_seconds as i64),
+            family_id: Some(record.family_id),
+            dpop_jkt: dpop_jkt.clone(),
+        })
+        .await;
+
+    let refresh_token = state
+        .issue_refresh_token(RefreshTokenRecord {
+            expires_at: now
+                + chrono::Duration::seconds(state.config.refresh_token_expiration_seconds as i64),
+            used: false,
+            ..record
+        })
+        .await;
+
+    Ok(Json(token_response(
+        state,
+        access_token,
+        id_token,
+        refresh_token,
+        dpop_jkt.is_some(),
     )))
 }
 
-/// UserInfo endpoint (minimal implementation)
-pub async fn userinfo() -> Json<serde_json::Value> {
-    // In production, validate the access token and return user info
-    Json(serde_json::json!({
-        "sub": "zkid:anonymous"
-    }))
+fn server_error(description: &str) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({
+            "error": "server_error",
+            "error_description": description
+        })),
+    )
+}
+
+/// The key the client signs its ID tokens and userinfo responses with
+fn client_signing_key(
+    state: &AppState,
+    client: &ClientInfo,
+) -> Result<Arc<SigningKey>, (StatusCode, Json<serde_json::Value>)> {
+    state
+        .signing_keys
+        .select(client.id_token_signed_response_alg)
+        .ok_or_else(|| server_error("no signing key for the client's algorithm"))
+}
+
+/// The ML-DSA-65 key that co-signs EdDSA tokens in hybrid mode
+fn hybrid_signing_key(state: &AppState, signing_key: &SigningKey) -> Option<Arc<SigningKey>> {
+    if !state.config.hybrid_signatures || signing_key.algorithm() != JwsAlgorithm::EdDSA {
+        return None;
+    }
+    state.signing_keys.select(Some(JwsAlgorithm::MlDsa65))
+}
+
+/// Sign an ID token with the algorithm the client registered (EdDSA by
+/// default), then encrypt it if the client registered an encryption algorithm
+async fn sign_id_token(
+    state: &AppState,
+    client: &ClientInfo,
+    claims: IdTokenClaims,
+) -> Result<String, (StatusCode, Json<serde_json::Value>)> {
+    let signing_key = client_signing_key(state, client)?;
+    let id_token = match hybrid_signing_key(state, &signing_key) {
+        Some(pq_key) => IdToken::create_hybrid(claims, &signing_key, &pq_key),
+        None => IdToken::create(claims, &signing_key),
+    }
+    .map(|id_token| id_token.token)
+    .map_err(|e| server_error(&e.to_string()))?;
+
+    match client.id_token_encryption {
+        Some(params) => encrypt_for_client(client, params, &id_token).await,
+        None => Ok(id_token),
+    }
+}
+
+/// Encrypt a signed JWT to the client's key for `params.alg` (a nested JWT,
+/// OIDC Core §10.2)
+async fn encrypt_for_client(
+    client: &ClientInfo,
+    params: JweParams,
+    jwt: &str,
+) -> Result<String, (StatusCode, Json<serde_json::Value>)> {
+    let jwks = client_jwks(client)
+        .await
+        .ok_or_else(|| server_error("the client's keys could not be retrieved"))?;
+    let key = params
+        .alg
+        .select_key(&jwks)
+        .ok_or_else(|| server_error("the client has no key for its encryption algorithm"))?;
+
+    jwe::encrypt(jwt.as_bytes(), key, params, Some("JWT")).map_err(|e| {
+        tracing::warn!("Encrypting for client {} failed: {}", client.client_id, e);
+        server_error("the response could not be encrypted")
+    })
+}
+
+/// Bearer token error response with a `WWW-Authenticate` challenge (RFC 6750 §3)
+///
+/// Requests without any credentials get a bare challenge and no error code.
+pub(crate) fn bearer_error(status: StatusCode, error: Option<(&str, &str)>) -> Response {
+    let challenge = match error {
+        Some((code, description)) => format!(
+            r#"Bearer realm="fantasma", error="{}", error_description="{}""#,
+            code, description
+        ),
+        None => r#"Bearer realm="fantasma""#.to_string(),
+    };
+    let body = match error {
+        Some((code, description)) => serde_json::json!({
+            "error": code,
+            "error_description": description
+        }),
+        None => serde_json::json!({ "error": "invalid_token" }),
+    };
+
+    (status, [(header::WWW_AUTHENTICATE, challenge)], Json(body)).into_response()
+}
+
+/// Extract the access token from an `Authorization: Bearer` header
+///
+/// `Err` means a header is present but is not a Bearer credential.
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Result<Option<&str>, ()> {
+    let Some(value) = headers.get(header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|v| {
+            v.strip_prefix("Bearer ")
+                .or_else(|| v.strip_prefix("bearer "))
+        })
+        .map(|token| Some(token.trim()))
+        .ok_or(())
+}
+
+/// UserInfo endpoint (OIDC Core §5.3)
+///
+/// Returns the subject and the ZK claims bound to the access token, i.e. the
+/// same claim set that went into the ID token. DPoP-bound tokens must be
+/// presented with the DPoP scheme and a proof from their key.
+pub async fn userinfo(
+    State(state): State<AppState>,
+    method: Method,
+    headers: HeaderMap,
+) -> Response {
+    let dpop_token = dpop::dpop_token(&headers);
+    let presented = match dpop_token {
+        Some(token) => Ok(Some(token)),
+        None => bearer_token(&headers),
+    };
+    let token = match presented {
+        Ok(Some(token)) => token,
+        Ok(None) => return bearer_error(StatusCode::UNAUTHORIZED, None),
+        Err(()) => {
+            return bearer_error(
+                StatusCode::BAD_REQUEST,
+                Some(("invalid_request", "malformed Authorization header")),
+            )
+        }
+    };
+
+    let Some(record) = state.validate_access_token(token).await else {
+        return bearer_error(
+            StatusCode::UNAUTHORIZED,
+            Some(("invalid_token", "access token is invalid or expired")),
+        );
+    };
+
+    if let Err(response) = dpop::check_binding(
+        &state,
+        &headers,
+        method.as_str(),
+        &state.config.userinfo_endpoint,
+        token,
+        dpop_token.is_some(),
+        record.dpop_jkt.as_deref(),
+    )
+    .await
+    {
+        return response;
+    }
+
+    if !record.scopes.iter().any(|s| s == "openid") {
+        return bearer_error(
+            StatusCode::FORBIDDEN,
+            Some(("insufficient_scope", "access token lacks the openid scope")),
+        );
+    }
+
+    let userinfo = UserInfoResponse {
+        sub: record.subject_id,
+        zk_claims: record.zk_claims,
+    };
+    let encryption = match state.get_client(&record.client_id).await {
+        Some(client) => client.userinfo_encryption.map(|params| (client, params)),
+        None => None,
+    };
+    let Some((client, params)) = encryption else {
+        return Json(userinfo).into_response();
+    };
+
+    // A signed JWT, encrypted to the client (OIDC Core §5.3.2)
+    match signed_userinfo(&state, &client, &userinfo) {
+        Ok(jwt) => match encrypt_for_client(&client, params, &jwt).await {
+            Ok(jwe) => ([(header::CONTENT_TYPE, "application/jwt")], jwe).into_response(),
+            Err(e) => e.into_response(),
+        },
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Sign userinfo claims as a JWT with `iss` and `aud` (OIDC Core §5.3.2)
+fn signed_userinfo(
+    state: &AppState,
+    client: &ClientInfo,
+    userinfo: &UserInfoResponse,
+) -> Result<String, (StatusCode, Json<serde_json::Value>)> {
+    let mut payload = serde_json::to_value(userinfo).map_err(|e| server_error(&e.to_string()))?;
+    payload["iss"] = serde_json::Value::from(state.config.issuer.clone());
+    payload["aud"] = serde_json::Value::from(client.client_id.clone());
+
+    let signing_key = client_signing_key(state, client)?;
+    jws::encode(&payload, "JWT", &signing_key).map_err(|e| server_error(&e.to_string()))
+}
+
+/// Token introspection/revocation request parameters
+#[derive(Debug, Deserialize)]
+pub struct TokenManagementParams {
+    pub token: String,
+    pub token_type_hint: Option<String>,
+    #[serde(flatten)]
+    pub client: ClientCredentials,
+}
+
+/// A token found by value, either an access or a refresh token
+enum FoundToken {
+    Access(AccessTokenRecord),
+    Refresh(RefreshTokenRecord),
+}
+
+/// Look up a token, trying the hinted type first (RFC 7009 §2.1)
+///
+/// Unknown hints are ignored; both token types are always searched.
+async fn find_token(state: &AppState, token: &str, hint: Option<&str>) -> Option<FoundToken> {
+    if hint == Some("refresh_token") {
+        if let Some(record) = state.find_refresh_token(token).await {
+            return Some(FoundToken::Refresh(record));
+        }
+    }
+    if let Some(record) = state.validate_access_token(token).await {
+        return Some(FoundToken::Access(record));
+    }
+    state
+        .find_refresh_token(token)
+        .await
+        .map(FoundToken::Refresh)
+}
+
+/// Token introspection endpoint (RFC 7662)
+///
+/// Clients may only introspect tokens issued to themselves; anything else is
+/// reported inactive so the endpoint cannot be used to probe for tokens.
+pub async fn introspect(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::Form(params): axum::Form<TokenManagementParams>,
+) -> Result<Json<IntrospectionResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let client = authenticate_client(&state, &headers, &params.client).await?;
+
+    let response = match find_token(&state, &params.token, params.token_type_hint.as_deref()).await
+    {
+        Some(FoundToken::Access(record)) if record.client_id == client.client_id => {
+            IntrospectionResponse {
+                active: true,
+                scope: Some(record.scopes.join(" ")),
+                client_id: Some(record.client_id),
+                sub: Some(record.subject_id),
+                token_type: Some(
+                    match record.dpop_jkt {
+                        Some(_) => DPOP_TOKEN_TYPE,
+                        None => "Bearer",
+                    }
+                    .to_string(),
+                ),
+                exp: Some(record.expires_at.timestamp()),
+                iss: Some(state.config.issuer.clone()),
+                cnf: record.dpop_jkt.map(|jkt| Confirmation { jkt }),
+                zk_claims: record.zk_claims,
+            }
+        }
+        Some(FoundToken::Refresh(record)) if record.client_id == client.client_id => {
+            IntrospectionResponse {
+                active: true,
+                scope: Some(record.scopes.join(" ")),
+                client_id: Some(record.client_id),
+                sub: Some(record.subject_id),
+                token_type: Some("refresh_token".to_string()),
+                exp: Some(record.expires_at.timestamp()),
+                iss: Some(state.config.issuer.clone()),
+                cnf: record.dpop_jkt.map(|jkt| Confirmation { jkt }),
+                zk_claims: record.zk_claims,
+            }
+        }
+        _ => IntrospectionResponse::inactive(),
+    };
+
+    Ok(Json(response))
+}
+
+/// Token revocation endpoint (RFC 7009)
+///
+/// Revoking a refresh token also revokes every access and refresh token from
+/// the same grant. Unknown tokens, and tokens belonging to other clients, are
+/// answered with 200 as well, without revoking anything.
+pub async fn revoke(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::Form(params): axum::Form<TokenManagementParams>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    let client = authenticate_client(&state, &headers, &params.client).await?;
+
+    match find_token(&state, &params.token, params.token_type_hint.as_deref()).await {
+        Some(FoundToken::Access(record)) if record.client_id == client.client_id => {
+            state.revoke_access_token(&params.token).await;
+        }
+        Some(FoundToken::Refresh(record)) if record.client_id == client.client_id => {
+            state.revoke_token_family(record.family_id).await;
+        }
+        Some(_) => {
+            tracing::warn!(
+                "Client {} tried to revoke a token issued to another client",
+                client.client_id
+            );
+        }
+        None => {}
+    }
+
+    Ok(StatusCode::OK)
 }
 
 /// Submit proof request
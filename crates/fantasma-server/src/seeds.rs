@@ -170,6 +170,7 @@ impl SeedData {
             signature: placeholder_signature(),
             issued_at: Utc::now(),
             expires_at: Some(Utc::now() + chrono::Duration::days(365)),
+            holder: None,
         }
     }
 
@@ -193,6 +194,7 @@ impl SeedData {
             signature: placeholder_signature(),
             issued_at: Utc::now(),
             expires_at: Some(Utc::now() + chrono::Duration::days(365)),
+            holder: None,
         }
     }
 
@@ -222,6 +224,7 @@ impl SeedData {
             signature: placeholder_signature(),
             issued_at: Utc::now(),
             expires_at: None, // Degrees don't expire
+            holder: None,
         }
     }
 }
@@ -0,0 +1,304 @@
+//! Browser sessions and logout
+//!
+//! A session starts the first time a user consents in a browser and is
+//! remembered in a cookie; every client that obtains a code during it is
+//! recorded. ID tokens carry the session's `sid`.
+//!
+//! RP-initiated logout (`/end_session`) ends the session, revokes the
+//! refresh and access tokens issued during it and POSTs a signed logout
+//! token to the back-channel logout URI of every client that took part.
+//! Without an `id_token_hint` the user confirms the logout first, and only a
+//! POST from the provider's own origin ends the session.
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use fantasma_oidc::logout::{LogoutToken, LogoutTokenClaims};
+use fantasma_oidc::token::IdToken;
+use serde::Deserialize;
+
+use crate::routes::html_escape;
+use crate::state::AppState;
+
+/// Name of the session cookie
+pub const SESSION_COOKIE: &str = "fantasma_session";
+
+/// Lifetime of logout tokens in seconds
+const LOGOUT_TOKEN_EXPIRATION_SECONDS: i64 = 120;
+
+/// The session ID from the request's session cookie
+pub fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value)
+}
+
+/// `Set-Cookie` value remembering the session in the browser
+pub fn session_cookie(state: &AppState, sid: &str) -> String {
+    let secure = if state.config.issuer.starts_with("https://") {
+        "; Secure"
+    } else {
+        ""
+    };
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Lax{}",
+        SESSION_COOKIE, sid, secure
+    )
+}
+
+/// `Set-Cookie` value removing the session cookie
+fn clear_session_cookie() -> String {
+    format!(
+        "{}=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0",
+        SESSION_COOKIE
+    )
+}
+
+/// RP-initiated logout request parameters (RP-Initiated Logout §2)
+#[derive(Debug, Default, Deserialize)]
+pub struct EndSessionParams {
+    pub id_token_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub post_logout_redirect_uri: Option<String>,
+    pub state: Option<String>,
+}
+
+fn logout_error(description: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Html(format!("<h1>Error</h1><p>{}</p>", description)),
+    )
+        .into_response()
+}
+
+/// `GET /end_session`
+pub async fn end_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<EndSessionParams>,
+) -> Response {
+    logout(&state, &headers, params, false).await
+}
+
+/// `POST /end_session`
+pub async fn end_session_form(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::Form(params): axum::Form<EndSessionParams>,
+) -> Response {
+    let confirmed = is_same_origin(&state, &headers);
+    logout(&state, &headers, params, confirmed).await
+}
+
+/// Whether the request was sent from a page of this provider
+///
+/// Browsers send `Origin` with form POSTs; `Referer` is the fallback.
+fn is_same_origin(state: &AppState, headers: &HeaderMap) -> bool {
+    let Ok(issuer) = url::Url::parse(&state.config.issuer) else {
+        return false;
+    };
+    [header::ORIGIN, header::REFERER]
+        .iter()
+        .find_map(|name| headers.get(name))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| url::Url::parse(value).ok())
+        .is_some_and(|url| url.origin() == issuer.origin())
+}
+
+/// Page asking the user to confirm a logout no client vouched for
+fn confirm_logout_page(params: &EndSessionParams) -> Response {
+    let hidden_fields: String = [
+        ("client_id", &params.client_id),
+        ("post_logout_redirect_uri", &params.post_logout_redirect_uri),
+        ("state", &params.state),
+    ]
+    .iter()
+    .filter_map(|(name, value)| {
+        value.as_deref().map(|value| {
+            format!(
+                r#"<input type="hidden" name="{}" value="{}">"#,
+                name,
+                html_escape(value)
+            )
+        })
+    })
+    .collect();
+
+    Html(format!(
+        r#"<h1>Sign out</h1><p>Do you want to sign out?</p>
+<form action="/end_session" method="post">{}<button type="submit">Sign out</button></form>"#,
+        hidden_fields
+    ))
+    .into_response()
+}
+
+/// End the browser's session
+///
+/// `confirmed` says whether the user confirmed the logout on this
+/// provider's own page; without it, a request lacking an `id_token_hint` is
+/// answered with the confirmation page, since any site could send it.
+async fn logout(
+    state: &AppState,
+    headers: &HeaderMap,
+    params: EndSessionParams,
+    confirmed: bool,
+) -> Response {
+    let hint = match params.id_token_hint.as_deref() {
+        Some(token) => {
+            match IdToken::verify_hint(token, &state.signing_keys.jwks(), &state.config.issuer) {
+                Ok(claims) => Some(claims),
+                Err(_) => return logout_error("Invalid id_token_hint"),
+            }
+        }
+        None => None,
+    };
+
+    // The client is named by the hint's audience or by client_id
+    let client_id = match (
+        hint.as_ref().map(|c| c.aud.as_str()),
+        params.client_id.as_deref(),
+    ) {
+        (Some(aud), Some(id)) if aud != id => {
+            return logout_error("client_id does not match the id_token_hint")
+        }
+        (Some(aud), _) => Some(aud.to_string()),
+        (None, id) => id.map(str::to_string),
+    };
+
+    // Only registered post-logout redirect URIs are honoured
+    let redirect = match params.post_logout_redirect_uri.clone() {
+        Some(uri) => {
+            let client = match client_id.as_deref() {
+                Some(id) => state.get_client(id).await,
+                None => None,
+            };
+            if !client.is_some_and(|c| c.post_logout_redirect_uris.contains(&uri)) {
+                return logout_error("Invalid post_logout_redirect_uri");
+            }
+            Some(uri)
+        }
+        None => None,
+    };
+
+    if hint.is_none() && !confirmed {
+        return confirm_logout_page(&params);
+    }
+
+    let sid = hint
+        .and_then(|claims| claims.sid)
+        .or_else(|| session_id(headers).map(str::to_string));
+    if let Some(ref sid) = sid {
+        let clients = state.end_session(sid).await;
+        tracing::info!("Ended session with {} client(s)", clients.len());
+        // Slow or unreachable clients must not hold up the browser
+        tokio::spawn(notify_clients(state.clone(), sid.clone(), clients));
+    }
+
+    let cookie = [(header::SET_COOKIE, clear_session_cookie())];
+    match redirect {
+        Some(uri) => {
+            let mut url = uri;
+            if let Some(s) = params.state {
+                url.push_str(if url.contains('?') { "&" } else { "?" });
+                url.push_str(&format!(
+                    "state={}",
+                    url::form_urlencoded::byte_serialize(s.as_bytes()).collect::<String>()
+                ));
+            }
+            (cookie, Redirect::to(&url)).into_response()
+        }
+        None => (
+            cookie,
+            Html("<h1>Signed out</h1><p>You have been signed out.</p>".to_string()),
+        )
+            .into_response(),
+    }
+}
+
+/// Send logout tokens to the back-channel logout URIs of a session's clients
+///
+/// Delivery failures are logged and otherwise ignored: the session has
+/// already ended and its tokens are revoked.
+async fn notify_clients(state: AppState, sid: String, clients: Vec<(String, String)>) {
+    let http = match reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+    {
+        Ok(http) => http,
+        Err(e) => {
+            tracing::error!("Failed to build HTTP client for back-channel logout: {}", e);
+            return;
+        }
+    };
+
+    for (client_id, subject_id) in clients {
+        let Some(client) = state.get_client(&client_id).await else {
+            continue;
+        };
+        let Some(ref uri) = client.backchannel_logout_uri else {
+            continue;
+        };
+        let Some(key) = state
+            .signing_keys
+            .select(client.id_token_signed_response_alg)
+        else {
+            continue;
+        };
+
+        let claims = LogoutTokenClaims::new(
+            &state.config.issuer,
+            &client_id,
+            Some(subject_id),
+            Some(sid.clone()),
+            LOGOUT_TOKEN_EXPIRATION_SECONDS,
+        );
+        let token = match LogoutToken::create(&claims, &key) {
+            Ok(token) => token,
+            Err(e) => {
+                tracing::error!("Failed to sign logout token: {}", e);
+                continue;
+            }
+        };
+
+        match http
+            .post(uri)
+            .form(&[("logout_token", token.as_str())])
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => tracing::warn!(
+                "Back-channel logout to {} returned {}",
+                client_id,
+                response.status()
+            ),
+            Err(e) => tracing::warn!("Back-channel logout to {} failed: {}", client_id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_id_from_cookies() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            "theme=dark; fantasma_session=abc123".parse().unwrap(),
+        );
+        assert_eq!(session_id(&headers), Some("abc123"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, "theme=dark".parse().unwrap());
+        assert_eq!(session_id(&headers), None);
+    }
+}
@@ -1,18 +1,48 @@
 //! Application state
 
+use fantasma_core::claim::ClaimRequest;
+use fantasma_core::credential::CredentialType;
+use fantasma_core::issuer::{public_key_hash, TrustAnchor};
+use fantasma_core::proof::{GeneratedProof, ProofRequest};
+use fantasma_crypto::HybridKeypair;
 use fantasma_db::{
-    models::{NewAuthCode, NewClient},
+    models::{
+        NewAccessToken, NewAuthCode, NewClient, NewConsentGrant, NewCredentialOffer,
+        NewDeviceAuthorization, NewNullifier, NewProofRequest, NewPushedAuthorizationRequest,
+        NewRefreshToken,
+    },
     pool::{DatabasePool, Repositories},
     PostgresProofStore,
 };
+use fantasma_issuer::Issuer;
+use fantasma_oidc::claims::ZkClaims;
+use fantasma_oidc::claims_request::ClaimsRequest;
+use fantasma_oidc::client_auth::TokenEndpointAuthMethod;
 use fantasma_oidc::config::OidcConfig;
+use fantasma_oidc::device::{
+    normalize_user_code, SLOW_DOWN_INCREMENT_SECONDS, USER_CODE_CHARSET, USER_CODE_LENGTH,
+};
+use fantasma_oidc::discovery::ZkCircuitInfo;
+use fantasma_oidc::jwe::JweParams;
+use fantasma_oidc::jwk::JwkSet;
+use fantasma_oidc::pkce::{PkceChallenge, PkceMethod, PkcePolicy};
+use fantasma_oidc::provenance::IssuerTrust;
+use fantasma_oidc::scopes::ZkScope;
+use fantasma_oidc::signing::{JwsAlgorithm, SigningKeys};
 use fantasma_proof_store::{InMemoryProofStore, ProofStore};
-use fantasma_stark::circuit::CircuitType;
+use fantasma_stark::backend::{ProverBackend, ProverConfig};
+use fantasma_stark::circuit::{CircuitLoader, CircuitType};
 use fantasma_stark::verifier::Verifier;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use thiserror::Error;
 use tokio::sync::RwLock;
 
+use crate::dpop::DpopNonces;
+use crate::keys::KeyRotation;
+use crate::replay::ReplayCache;
+
 /// Stored authorization code (in-memory version)
 #[derive(Debug, Clone)]
 pub struct AuthCode {
@@ -21,17 +51,431 @@ pub struct AuthCode {
     pub redirect_uri: String,
     pub scopes: Vec<String>,
     pub nonce: Option<String>,
+    pub pkce: Option<PkceChallenge>,
+    pub subject_id: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// Browser session the code was issued in
+    pub sid: Option<String>,
+    /// ZK claims requested with the `claims` parameter
+    pub claim_requests: Vec<ClaimRequest>,
+    /// ZK claims verified from the wallet's proofs at consent
+    pub zk_claims: Option<ZkClaims>,
+}
+
+/// Browser session (in-memory version)
+#[derive(Debug, Clone, Default)]
+pub struct BrowserSession {
+    /// Clients that obtained a code during the session, with the subject each saw
+    pub clients: HashMap<String, String>,
+}
+
+/// What a user approved sharing with a client
+///
+/// One per pairwise subject and client, holding the most recently approved
+/// scope set and the ZK claims shared for it.
+#[derive(Debug, Clone)]
+pub struct ConsentGrant {
+    pub id: uuid::Uuid,
+    pub client_id: String,
+    pub subject_id: String,
+    /// Sector of the pairwise subject; unknown for grants recorded before
+    /// it was kept
+    pub sector_identifier: Option<String>,
+    pub scopes: Vec<String>,
+    pub zk_claims: ZkClaims,
+    /// When the proofs behind `zk_claims` were verified
+    pub zk_claims_verified_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ConsentGrant {
+    /// The granted ZK claims shared for `scopes`
+    pub fn claims_for(&self, scopes: &[String]) -> ZkClaims {
+        let scopes: Vec<ZkScope> = scopes.iter().filter_map(|s| ZkScope::parse(s)).collect();
+        self.zk_claims.for_scopes(&scopes)
+    }
+
+    /// Whether re-authorizing for `scopes` at `now` can skip the consent
+    /// page: all of them were granted, and the claims shared for them are
+    /// still within `max_age_seconds` of their proofs
+    pub fn covers(
+        &self,
+        scopes: &[String],
+        max_age_seconds: u64,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> bool {
+        let claims = self.claims_for(scopes);
+        let fresh = claims.is_empty()
+            || (now - self.zk_claims_verified_at).num_seconds()
+                < claims.max_age_seconds(max_age_seconds) as i64;
+        fresh && scopes.iter().all(|scope| self.scopes.contains(scope))
+    }
+}
+
+/// Stored pushed authorization request (in-memory version)
+#[derive(Debug, Clone)]
+pub struct PushedRequest {
+    pub client_id: String,
+    /// The validated authorization request parameters
+    pub params: serde_json::Value,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Proof request shown to the wallet at consent (in-memory version)
+#[derive(Debug, Clone)]
+pub struct PendingProofRequest {
+    pub client_id: String,
+    pub request: ProofRequest,
+    /// The authorization request parameters the proofs are requested for
+    pub params: serde_json::Value,
+}
+
+/// OpenID4VCI credential offer (in-memory version)
+#[derive(Debug, Clone)]
+pub struct CredentialOffer {
+    pub credential_configuration_id: String,
+    /// The credential to issue
+    pub credential_type: CredentialType,
+    /// Set once the pre-authorized code was redeemed
+    pub access_token_hash: Option<[u8; 32]>,
+    pub access_token_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Nonce the wallet's key proof must carry
+    pub c_nonce: Option<String>,
+    pub c_nonce_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub issued: bool,
+    /// Until when the pre-authorized code can be redeemed
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl CredentialOffer {
+    /// Whether the access token may still fetch the credential at `now`
+    fn is_redeemable(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        !self.issued && self.access_token_expires_at.is_some_and(|exp| exp > now)
+    }
+}
+
+/// Progress of a device authorization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceAuthorizationStatus {
+    /// Waiting for the user at the verification page
+    Pending,
+    Approved,
+    Denied,
+}
+
+impl DeviceAuthorizationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Denied => "denied",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(Self::Pending),
+            "approved" => Some(Self::Approved),
+            "denied" => Some(Self::Denied),
+            _ => None,
+        }
+    }
+}
+
+/// Stored device authorization (in-memory version)
+#[derive(Debug, Clone)]
+pub struct DeviceAuthorization {
+    pub user_code: String,
+    pub client_id: String,
+    pub scopes: Vec<String>,
+    pub status: DeviceAuthorizationStatus,
+    /// Pairwise subject the user presented when approving
+    pub subject_id: Option<String>,
+    /// Browser session the user approved in
+    pub sid: Option<String>,
+    /// ZK claims verified from the wallet's proofs when approving
+    pub zk_claims: Option<ZkClaims>,
+    /// Minimum seconds between polls; raised on `slow_down`
+    pub interval: u64,
+    pub last_polled_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// The user's approval of a device authorization
+#[derive(Debug, Clone)]
+pub struct DeviceApproval {
+    /// Pairwise subject the user presented
+    pub subject_id: String,
+    pub scopes: Vec<String>,
+    /// Browser session the user approved in
+    pub sid: Option<String>,
+    /// ZK claims verified from the wallet's proofs, if it sent any
+    pub zk_claims: Option<ZkClaims>,
+}
+
+impl DeviceAuthorization {
+    /// Record a poll of the token endpoint at `now` (RFC 8628 §3.5)
+    ///
+    /// Succeeds once the user has approved. Polling sooner than the
+    /// interval allows is answered with `slow_down` and raises the interval.
+    fn poll(&mut self, now: chrono::DateTime<chrono::Utc>) -> Result<(), DevicePollError> {
+        if self.expires_at <= now {
+            return Err(DevicePollError::Expired);
+        }
+        match self.status {
+            DeviceAuthorizationStatus::Approved => Ok(()),
+            DeviceAuthorizationStatus::Denied => Err(DevicePollError::Denied),
+            DeviceAuthorizationStatus::Pending => {
+                let too_soon = self
+                    .last_polled_at
+                    .is_some_and(|last| (now - last).num_seconds() < self.interval as i64);
+                self.last_polled_at = Some(now);
+                if too_soon {
+                    self.interval += SLOW_DOWN_INCREMENT_SECONDS;
+                    Err(DevicePollError::SlowDown)
+                } else {
+                    Err(DevicePollError::Pending)
+                }
+            }
+        }
+    }
+}
+
+/// Device code polling errors (RFC 8628 §3.5)
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DevicePollError {
+    #[error("invalid device code")]
+    Invalid,
+
+    #[error("the user has not yet completed the authorization")]
+    Pending,
+
+    #[error("polling too frequently; increase the interval by 5 seconds")]
+    SlowDown,
+
+    #[error("the user denied the authorization")]
+    Denied,
+
+    #[error("the device code has expired")]
+    Expired,
+}
+
+impl DevicePollError {
+    /// Token endpoint error code
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::Invalid => "invalid_grant",
+            Self::Pending => "authorization_pending",
+            Self::SlowDown => "slow_down",
+            Self::Denied => "access_denied",
+            Self::Expired => "expired_token",
+        }
+    }
+}
+
+/// Stored refresh token (in-memory version)
+#[derive(Debug, Clone)]
+pub struct RefreshTokenRecord {
+    /// Shared by every token rotated from the same authorization
+    pub family_id: uuid::Uuid,
+    pub client_id: String,
+    pub subject_id: String,
+    pub scopes: Vec<String>,
+    /// ZK claims re-asserted in refreshed ID tokens
+    pub zk_claims: ZkClaims,
+    /// When the proofs behind `zk_claims` were verified
+    pub zk_claims_verified_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub used: bool,
+    pub revoked: bool,
+    /// Browser session the grant was made in; logging out revokes it
+    pub sid: Option<String>,
+    /// Thumbprint of the DPoP key the token is bound to (public clients only)
+    pub dpop_jkt: Option<String>,
+}
+
+/// Stored access token (in-memory version)
+#[derive(Debug, Clone)]
+pub struct AccessTokenRecord {
+    pub client_id: String,
     pub subject_id: String,
+    pub scopes: Vec<String>,
+    /// The ZK claims that went into the ID token issued alongside
+    pub zk_claims: ZkClaims,
     pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// Refresh token family issued alongside, revoked together with it
+    pub family_id: Option<uuid::Uuid>,
+    /// Thumbprint of the DPoP key the token is bound to
+    pub dpop_jkt: Option<String>,
+}
+
+/// Refresh token redemption errors
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RefreshTokenError {
+    #[error("invalid or expired refresh token")]
+    Invalid,
+
+    #[error("refresh token was issued to another client")]
+    WrongClient,
+
+    #[error("refresh token is bound to another DPoP key")]
+    WrongKey,
+
+    #[error("refresh token reuse detected; all tokens from this grant were revoked")]
+    Reused,
+
+    /// The token store failed; nothing about the token is known
+    #[error("refresh token storage is unavailable")]
+    Storage,
+}
+
+/// A credential issuer trusted without the database's trust list
+#[derive(Debug, Clone)]
+pub struct TrustedIssuer {
+    pub public_key: Vec<u8>,
+    pub trust_tier: IssuerTrust,
 }
 
 /// Registered client information
 #[derive(Debug, Clone)]
 pub struct ClientInfo {
     pub client_id: String,
-    pub client_secret: Option<String>,
+    /// Argon2 hash (PHC string) of the client secret
+    pub client_secret_hash: Option<String>,
     pub redirect_uris: Vec<String>,
     pub name: String,
+    /// Registered ID token signing algorithm (`None` = provider default)
+    pub id_token_signed_response_alg: Option<JwsAlgorithm>,
+    /// How the client authenticates at the token endpoint
+    pub token_endpoint_auth_method: TokenEndpointAuthMethod,
+    /// Registered keys for `private_key_jwt` client assertions
+    pub jwks: Option<JwkSet>,
+    /// Where to fetch the keys when none are registered inline
+    pub jwks_uri: Option<String>,
+    /// Its host is the client's sector for pairwise subjects
+    pub sector_identifier_uri: Option<String>,
+    /// Reject the `plain` PKCE method for this client
+    pub require_pkce_s256: bool,
+    /// Only accept authorization requests pushed to the PAR endpoint
+    pub require_pushed_authorization_requests: bool,
+    /// Only accept authorization requests carrying a signed request object
+    pub require_signed_request_object: bool,
+    /// Where the user agent may be sent after logging out
+    pub post_logout_redirect_uris: Vec<String>,
+    /// Where logout tokens are POSTed when a session ends
+    pub backchannel_logout_uri: Option<String>,
+    /// Whether logout tokens must carry `sid`; they always do
+    pub backchannel_logout_session_required: bool,
+    /// The ZK scopes the client was approved for (`None` = any)
+    pub allowed_scopes: Option<Vec<String>>,
+    /// Drop requested ZK scopes the client was not approved for instead of
+    /// failing the request with `invalid_scope`
+    pub downscope_unapproved_scopes: bool,
+    /// Encrypt ID tokens to one of the client's keys (`None` = signed only)
+    pub id_token_encryption: Option<JweParams>,
+    /// Return userinfo as an encrypted JWT (`None` = plain JSON)
+    pub userinfo_encryption: Option<JweParams>,
+}
+
+impl ClientInfo {
+    /// Public clients cannot authenticate and must use PKCE
+    pub fn is_public(&self) -> bool {
+        self.token_endpoint_auth_method == TokenEndpointAuthMethod::None
+    }
+
+    /// The sector whose pairwise subjects this client sees
+    pub fn sector_identifier(&self, redirect_uri: &str) -> Option<String> {
+        fantasma_oidc::subject::sector_identifier(
+            redirect_uri,
+            self.sector_identifier_uri.as_deref(),
+        )
+    }
+
+    /// The sector of authorizations made without a redirect URI, such as
+    /// device authorizations
+    pub fn default_sector_identifier(&self) -> Option<String> {
+        match self.sector_identifier_uri {
+            Some(ref uri) => fantasma_oidc::subject::sector_identifier(uri, None),
+            None => fantasma_oidc::subject::common_sector(&self.redirect_uris),
+        }
+    }
+
+    /// Whether the client was approved for a scope; only ZK scopes need approval
+    pub fn allows_scope(&self, scope: &ZkScope) -> bool {
+        match self.allowed_scopes {
+            Some(ref allowed) if scope.requires_proof() => {
+                let scope = scope.to_string();
+                allowed
+                    .iter()
+                    .filter_map(|s| ZkScope::parse(s))
+                    .any(|s| s.to_string() == scope)
+            }
+            _ => true,
+        }
+    }
+
+    /// Hold the requested `scope`, and the ZK claims requested individually
+    /// in `claims`, to the ZK scopes the client was approved for
+    ///
+    /// Unapproved ones are dropped when the client downscopes; otherwise
+    /// the request fails, naming the first of them.
+    pub fn restrict_scopes(
+        &self,
+        scope: &mut String,
+        claims: &mut Option<String>,
+    ) -> Result<(), String> {
+        let mut unapproved = Vec::new();
+        let approved: Vec<&str> = scope
+            .split_whitespace()
+            .filter(|s| match ZkScope::parse(s) {
+                Some(zk_scope) if !self.allows_scope(&zk_scope) => {
+                    unapproved.push(zk_scope.to_string());
+                    false
+                }
+                _ => true,
+            })
+            .collect();
+        let approved = approved.join(" ");
+
+        // A malformed claims parameter is reported when it is used
+        let mut claims_request = claims
+            .as_deref()
+            .and_then(|claims| ClaimsRequest::parse(claims).ok());
+        if let Some(ref mut request) = claims_request {
+            let dropped = request.retain_zk_claims(|claim_type| {
+                !matches!(ZkScope::from_claim_type(claim_type), Some(s) if !self.allows_scope(&s))
+            });
+            unapproved.extend(
+                dropped
+                    .iter()
+                    .filter_map(ZkScope::from_claim_type)
+                    .map(|s| s.to_string()),
+            );
+        }
+
+        match unapproved.first() {
+            None => Ok(()),
+            Some(scope) if !self.downscope_unapproved_scopes => {
+                Err(format!("client is not approved for {}", scope))
+            }
+            Some(_) => {
+                *scope = approved;
+                if let Some(request) = claims_request {
+                    *claims = serde_json::to_string(&request).ok();
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// PKCE requirements for authorization requests from this client
+    pub fn pkce_policy(&self) -> PkcePolicy {
+        PkcePolicy {
+            required: self.is_public() || self.require_pkce_s256,
+            s256_only: self.require_pkce_s256,
+        }
+    }
 }
 
 /// Storage backend abstraction
@@ -40,6 +484,23 @@ pub enum StorageBackend {
     /// In-memory storage (for development)
     InMemory {
         auth_codes: Arc<RwLock<HashMap<String, AuthCode>>>,
+        /// Keyed by `request_uri`
+        pushed_requests: Arc<RwLock<HashMap<String, PushedRequest>>>,
+        /// Keyed by `request_id`
+        proof_requests: Arc<RwLock<HashMap<String, PendingProofRequest>>>,
+        /// Nullifiers of accepted proofs
+        nullifiers: Arc<RwLock<HashSet<[u8; 32]>>>,
+        /// Keyed by hex-encoded SHA-256 of the pre-authorized code
+        credential_offers: Arc<RwLock<HashMap<String, CredentialOffer>>>,
+        /// Keyed by hex-encoded SHA-256 of the device code
+        device_authorizations: Arc<RwLock<HashMap<String, DeviceAuthorization>>>,
+        /// Keyed by `sid`
+        sessions: Arc<RwLock<HashMap<String, BrowserSession>>>,
+        consent_grants: Arc<RwLock<HashMap<uuid::Uuid, ConsentGrant>>>,
+        /// Keyed by hex-encoded SHA-256 of the token
+        refresh_tokens: Arc<RwLock<HashMap<String, RefreshTokenRecord>>>,
+        /// Keyed by hex-encoded SHA-256 of the token
+        access_tokens: Arc<RwLock<HashMap<String, AccessTokenRecord>>>,
     },
     /// PostgreSQL storage (for production)
     Database { pool: DatabasePool },
@@ -51,20 +512,42 @@ pub struct AppState {
     /// OIDC configuration
     pub config: Arc<OidcConfig>,
 
-    /// Signing key (in production, use proper key management)
-    pub signing_key: Arc<[u8; 32]>,
+    /// ID token signing keys (published at the JWKS endpoint)
+    pub signing_keys: Arc<SigningKeys>,
+
+    /// Lifecycle of the signing keys: scheduled rotation and revocation
+    pub key_rotation: Arc<KeyRotation>,
 
     /// Proof verifier
     pub verifier: Arc<Verifier>,
 
+    /// The loaded circuits and the hashes published in discovery
+    pub zk_circuits: Arc<ZkCircuitInfo>,
+
+    /// Backend verifying the proofs wallets present at consent
+    /// (`FANTASMA_PROVER_BACKEND`; the mock backend needs `mock_proofs`)
+    pub prover_backend: Arc<ProverBackend>,
+
+    /// Credential issuer behind the OpenID4VCI endpoints
+    pub issuer: Arc<Issuer>,
+
     /// Proof storage (InMemoryProofStore or PostgresProofStore)
     pub proof_store: Arc<dyn ProofStore>,
 
+    /// Seen one-time identifiers (client assertion and DPoP proof `jti`s)
+    pub replay_cache: ReplayCache,
+
+    /// Nonces issued for DPoP proofs
+    pub dpop_nonces: DpopNonces,
+
     /// Storage backend
     storage: StorageBackend,
 
     /// Demo clients (always available for testing)
     pub clients: Arc<HashMap<String, ClientInfo>>,
+
+    /// Issuers trusted next to those in the database (none by default)
+    pub trusted_issuers: Arc<Vec<TrustedIssuer>>,
 }
 
 impl AppState {
@@ -75,43 +558,24 @@ impl AppState {
 
     /// Create new state with optional database
     pub fn with_storage(config: OidcConfig, db: Option<DatabasePool>) -> Self {
-        // Load or generate signing key
-        let signing_key = match std::env::var("FANTASMA_KEY_DIR") {
-            Ok(dir) if !dir.is_empty() => {
-                let passphrase = std::env::var("FANTASMA_KEY_PASSPHRASE")
-                    .unwrap_or_else(|_| "fantasma-dev-passphrase".into());
-                match fantasma_crypto::KeyStore::new(&dir) {
-                    Ok(store) => match store.load_or_generate(&passphrase) {
-                        Ok(kp) => {
-                            tracing::info!(
-                                "Loaded Dilithium signing key from {} (pubkey hash: {})",
-                                dir,
-                                hex::encode(&kp.public_key.hash()[..8])
-                            );
-                            kp.public_key.hash()
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to load key store: {}. Using random key.", e);
-                            random_signing_key()
-                        }
-                    },
-                    Err(e) => {
-                        tracing::warn!("Failed to open key store dir: {}. Using random key.", e);
-                        random_signing_key()
-                    }
-                }
-            }
-            _ => {
-                tracing::info!("FANTASMA_KEY_DIR not set, using ephemeral signing key");
-                random_signing_key()
-            }
-        };
+        // Load or generate the ID token signing keys. A key directory that
+        // cannot be read stops startup: tokens signed with stand-in keys
+        // would not verify against the keys relying parties have cached.
+        let key_rotation = KeyRotation::load(&config)
+            .unwrap_or_else(|e| panic!("Failed to load signing keys: {}", e));
 
         // Create verifier and load circuit verification keys
-        let mut verifier = Verifier::new();
-        let _ = verifier.load_verification_key(CircuitType::AgeVerification);
-        let _ = verifier.load_verification_key(CircuitType::CredentialVerification);
-        let _ = verifier.load_verification_key(CircuitType::KycVerification);
+        let (verifier, zk_circuits) = load_circuits();
+
+        // A prover backend that cannot be set up stops startup too: falling
+        // back to the mock backend would accept forged proofs
+        let prover_backend = ProverBackend::from_config(ProverConfig {
+            allow_mock: config.mock_proofs,
+            ..ProverConfig::from_env()
+        })
+        .unwrap_or_else(|e| panic!("Failed to set up the prover backend: {}", e));
+
+        let issuer = load_issuer(&config.issuer, config.hybrid_signatures);
 
         // Register demo clients
         let demo_clients = create_demo_clients();
@@ -128,6 +592,15 @@ impl AppState {
                 (
                     StorageBackend::InMemory {
                         auth_codes: Arc::new(RwLock::new(HashMap::new())),
+                        pushed_requests: Arc::new(RwLock::new(HashMap::new())),
+                        proof_requests: Arc::new(RwLock::new(HashMap::new())),
+                        nullifiers: Arc::new(RwLock::new(HashSet::new())),
+                        credential_offers: Arc::new(RwLock::new(HashMap::new())),
+                        device_authorizations: Arc::new(RwLock::new(HashMap::new())),
+                        sessions: Arc::new(RwLock::new(HashMap::new())),
+                        consent_grants: Arc::new(RwLock::new(HashMap::new())),
+                        refresh_tokens: Arc::new(RwLock::new(HashMap::new())),
+                        access_tokens: Arc::new(RwLock::new(HashMap::new())),
                     },
                     ps,
                 )
@@ -136,11 +609,18 @@ impl AppState {
 
         Self {
             config: Arc::new(config),
-            signing_key: Arc::new(signing_key),
+            signing_keys: key_rotation.keys(),
+            key_rotation: Arc::new(key_rotation),
             verifier: Arc::new(verifier),
+            zk_circuits: Arc::new(zk_circuits),
+            prover_backend: Arc::new(prover_backend),
+            issuer: Arc::new(issuer),
             proof_store,
+            replay_cache: ReplayCache::new(),
+            dpop_nonces: DpopNonces::new(),
             storage,
             clients: Arc::new(demo_clients),
+            trusted_issuers: Arc::new(Vec::new()),
         }
     }
 
@@ -157,6 +637,42 @@ impl AppState {
         }
     }
 
+    /// Trust level of the issuer a verified proof names in its issuer slot
+    ///
+    /// The provider's own credential issuer ranks by its trust anchor;
+    /// trusted issuers rank by the tier an administrator gave them. A proof
+    /// naming no known issuer gets 0. The public inputs are only those the
+    /// prover backend checked against the proof, so the wallet cannot pick
+    /// an issuer it holds no credential from.
+    pub async fn issuer_trust_level(&self, proof: &GeneratedProof) -> u8 {
+        let Some(key_hash) = proof.issuer_key_hash() else {
+            return 0;
+        };
+        let names = |public_key: &[u8]| hex::encode(public_key_hash(public_key)) == key_hash;
+        if hex::encode(self.issuer.info.public_key_hash()) == key_hash {
+            return self.issuer.info.trust_anchor.trust_level();
+        }
+
+        let mut tiers: Vec<IssuerTrust> = self
+            .trusted_issuers
+            .iter()
+            .filter(|issuer| names(&issuer.public_key))
+            .map(|issuer| issuer.trust_tier)
+            .collect();
+        if let Some(repos) = self.repos() {
+            match repos.issuers().list_trusted().await {
+                Ok(issuers) => tiers.extend(
+                    issuers
+                        .iter()
+                        .filter(|issuer| names(&issuer.public_key))
+                        .filter_map(|issuer| IssuerTrust::parse(&issuer.trust_tier)),
+                ),
+                Err(e) => tracing::error!("Failed to list trusted issuers: {}", e),
+            }
+        }
+        tiers.into_iter().max().map_or(0, |tier| tier.min_level())
+    }
+
     /// Validate a redirect URI for a client
     pub async fn validate_redirect_uri(&self, client_id: &str, redirect_uri: &str) -> bool {
         let normalized_input = redirect_uri.trim_end_matches('/');
@@ -196,11 +712,54 @@ impl AppState {
         // Check database
         if let Some(repos) = self.repos() {
             if let Ok(Some(client)) = repos.clients().find_by_client_id(client_id).await {
+                let token_endpoint_auth_method = client
+                    .token_endpoint_auth_method
+                    .as_deref()
+                    .and_then(TokenEndpointAuthMethod::parse)
+                    .unwrap_or(if client.client_type == "public" {
+                        TokenEndpointAuthMethod::None
+                    } else {
+                        TokenEndpointAuthMethod::ClientSecretBasic
+                    });
+
                 return Some(ClientInfo {
                     client_id: client.client_id,
-                    client_secret: None, // Don't expose hash
+                    client_secret_hash: client
+                        .client_secret_hash
+                        .and_then(|hash| String::from_utf8(hash).ok()),
                     redirect_uris: client.redirect_uris,
                     name: client.client_name,
+                    id_token_signed_response_alg: client
+                        .id_token_signed_response_alg
+                        .as_deref()
+                        .and_then(JwsAlgorithm::parse),
+                    token_endpoint_auth_method,
+                    jwks: client
+                        .jwks
+                        .and_then(|jwks| serde_json::from_value(jwks).ok()),
+                    jwks_uri: client.jwks_uri,
+                    sector_identifier_uri: client.sector_identifier_uri,
+                    require_pkce_s256: client.require_pkce_s256,
+                    require_pushed_authorization_requests: client
+                        .require_pushed_authorization_requests,
+                    require_signed_request_object: client.require_signed_request_object,
+                    post_logout_redirect_uris: client.post_logout_redirect_uris,
+                    backchannel_logout_uri: client.backchannel_logout_uri,
+                    backchannel_logout_session_required: client.backchannel_logout_session_required,
+                    allowed_scopes: Some(client.allowed_scopes),
+                    downscope_unapproved_scopes: client.downscope_unapproved_scopes,
+                    id_token_encryption: JweParams::parse(
+                        client.id_token_encrypted_response_alg.as_deref(),
+                        client.id_token_encrypted_response_enc.as_deref(),
+                    )
+                    .ok()
+                    .flatten(),
+                    userinfo_encryption: JweParams::parse(
+                        client.userinfo_encrypted_response_alg.as_deref(),
+                        client.userinfo_encrypted_response_enc.as_deref(),
+                    )
+                    .ok()
+                    .flatten(),
                 });
             }
         }
@@ -209,12 +768,23 @@ impl AppState {
     }
 
     /// Generate a new authorization code
+    ///
+    /// `subject_id` is the pairwise subject the user presented to the client;
+    /// `sid` the browser session the user consented in.
+    /// `claim_requests` are the ZK claims requested with the `claims` parameter;
+    /// `zk_claims` those verified from the wallet's proofs, if it sent any.
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_auth_code(
         &self,
         client_id: String,
         redirect_uri: String,
+        subject_id: String,
         scopes: Vec<String>,
         nonce: Option<String>,
+        pkce: Option<PkceChallenge>,
+        sid: Option<String>,
+        claim_requests: Vec<ClaimRequest>,
+        zk_claims: Option<ZkClaims>,
     ) -> String {
         use rand::distributions::Alphanumeric;
         use rand::Rng;
@@ -225,20 +795,23 @@ impl AppState {
             .map(char::from)
             .collect();
 
-        let subject_id = format!("zkid:{}", uuid::Uuid::new_v4().simple());
         let expires_at = chrono::Utc::now()
             + chrono::Duration::seconds(self.config.auth_code_expiration_seconds as i64);
 
         match &self.storage {
-            StorageBackend::InMemory { auth_codes } => {
+            StorageBackend::InMemory { auth_codes, .. } => {
                 let auth_code = AuthCode {
                     code: code.clone(),
                     client_id,
                     redirect_uri,
                     scopes,
                     nonce,
+                    pkce,
                     subject_id,
                     expires_at,
+                    sid,
+                    claim_requests,
+                    zk_claims,
                 };
 
                 let mut codes = auth_codes.write().await;
@@ -254,10 +827,14 @@ impl AppState {
                     scopes,
                     nonce,
                     state: None,
-                    code_challenge: None,
-                    code_challenge_method: None,
-                    zk_claims: None,
+                    code_challenge: pkce.as_ref().map(|p| p.challenge.clone()),
+                    code_challenge_method: pkce.as_ref().map(|p| p.method.as_str().to_string()),
+                    zk_claims: zk_claims.and_then(|claims| serde_json::to_value(claims).ok()),
                     expires_at,
+                    sid,
+                    claims_request: (!claim_requests.is_empty())
+                        .then(|| serde_json::to_value(&claim_requests).ok())
+                        .flatten(),
                 };
 
                 if let Err(e) = repos.auth_codes().create(new_code).await {
@@ -269,90 +846,1529 @@ impl AppState {
         code
     }
 
-    /// Exchange an authorization code for tokens
-    pub async fn exchange_code(&self, code: &str) -> Option<AuthCode> {
+    /// Store a pushed authorization request and return its `request_uri`
+    pub async fn push_authorization_request(
+        &self,
+        client_id: String,
+        params: serde_json::Value,
+    ) -> String {
+        let request_uri = format!("{}{}", REQUEST_URI_PREFIX, random_token());
+        let now = chrono::Utc::now();
+        let expires_at =
+            now + chrono::Duration::seconds(self.config.request_uri_expiration_seconds as i64);
+
         match &self.storage {
-            StorageBackend::InMemory { auth_codes } => {
-                let mut codes = auth_codes.write().await;
-                codes
-                    .remove(code)
-                    .filter(|c| c.expires_at > chrono::Utc::now())
+            StorageBackend::InMemory {
+                pushed_requests, ..
+            } => {
+                let mut requests = pushed_requests.write().await;
+                requests.retain(|_, r| r.expires_at > now);
+                requests.insert(
+                    request_uri.clone(),
+                    PushedRequest {
+                        client_id,
+                        params,
+                        expires_at,
+                    },
+                );
             }
             StorageBackend::Database { pool } => {
                 let repos = Repositories::new(pool);
+                let new_request = NewPushedAuthorizationRequest {
+                    request_uri: request_uri.clone(),
+                    client_id,
+                    params,
+                    expires_at,
+                };
 
-                // Find and mark as used
-                if let Ok(Some(db_code)) = repos.auth_codes().find_by_code(code).await {
-                    let _ = repos.auth_codes().mark_used(code).await;
-
-                    return Some(AuthCode {
-                        code: db_code.code,
-                        client_id: db_code.client_id,
-                        redirect_uri: db_code.redirect_uri,
-                        scopes: db_code.scopes,
-                        nonce: db_code.nonce,
-                        subject_id: db_code.user_id,
-                        expires_at: db_code.expires_at,
-                    });
+                if let Err(e) = repos.pushed_requests().create(new_request).await {
+                    tracing::error!("Failed to store pushed authorization request: {}", e);
                 }
-                None
             }
         }
+
+        request_uri
     }
 
-    /// Register a new client (database only)
-    pub async fn register_client(&self, client: NewClient) -> Result<(), String> {
+    /// Issue a proof request for the wallet to answer
+    ///
+    /// `params` are the authorization request the proofs are requested for.
+    pub async fn create_proof_request(
+        &self,
+        client_id: &str,
+        request: &ProofRequest,
+        params: serde_json::Value,
+    ) {
         match &self.storage {
-            StorageBackend::InMemory { .. } => {
-                Err("Cannot register clients in in-memory mode".to_string())
+            StorageBackend::InMemory { proof_requests, .. } => {
+                let now = chrono::Utc::now();
+                let mut requests = proof_requests.write().await;
+                requests.retain(|_, r| r.request.expires_at > now);
+                requests.insert(
+                    request.request_id.clone(),
+                    PendingProofRequest {
+                        client_id: client_id.to_string(),
+                        request: request.clone(),
+                        params,
+                    },
+                );
             }
             StorageBackend::Database { pool } => {
                 let repos = Repositories::new(pool);
-                repos
-                    .clients()
-                    .create(client)
+                let Ok(value) = serde_json::to_value(request) else {
+                    return;
+                };
+                let new_request = NewProofRequest {
+                    request_id: request.request_id.clone(),
+                    client_id: client_id.to_string(),
+                    request: value,
+                    expires_at: request.expires_at,
+                    authorization_params: params,
+                };
+
+                if let Err(e) = repos.proof_requests().create(new_request).await {
+                    tracing::error!("Failed to store proof request: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Redeem a proof request
+    ///
+    /// Each request can be answered once, so its nonce is never accepted twice.
+    pub async fn take_proof_request(&self, request_id: &str) -> Option<PendingProofRequest> {
+        match &self.storage {
+            StorageBackend::InMemory { proof_requests, .. } => proof_requests
+                .write()
+                .await
+                .remove(request_id)
+                .filter(|r| !r.request.is_expired()),
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let record = repos
+                    .proof_requests()
+                    .take(request_id)
                     .await
-                    .map(|_| ())
-                    .map_err(|e| e.to_string())
+                    .ok()
+                    .flatten()?;
+
+                Some(PendingProofRequest {
+                    client_id: record.client_id,
+                    request: serde_json::from_value(record.request).ok()?,
+                    params: record.authorization_params,
+                })
             }
         }
     }
-}
 
-/// Generate a random 32-byte signing key (ephemeral, lost on restart)
-fn random_signing_key() -> [u8; 32] {
-    use rand::RngCore;
-    let mut key = [0u8; 32];
-    rand::thread_rng().fill_bytes(&mut key);
-    key
-}
+    /// Record the nullifier of an accepted proof
+    ///
+    /// Returns `false` if the nullifier was already used.
+    pub async fn record_nullifier(
+        &self,
+        nullifier: &[u8; 32],
+        domain: &str,
+        circuit_id: &str,
+    ) -> bool {
+        match &self.storage {
+            StorageBackend::InMemory { nullifiers, .. } => {
+                nullifiers.write().await.insert(*nullifier)
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let new_nullifier = NewNullifier {
+                    nullifier_hash: nullifier.to_vec(),
+                    domain: domain.to_string(),
+                    circuit_type: circuit_id.to_string(),
+                };
 
-/// Create demo clients for testing
-fn create_demo_clients() -> HashMap<String, ClientInfo> {
-    let mut clients = HashMap::new();
+                match repos.nullifiers().create(new_nullifier).await {
+                    Ok(_) => true,
+                    Err(fantasma_db::DbError::Duplicate(_)) => false,
+                    Err(e) => {
+                        tracing::error!("Failed to record nullifier: {}", e);
+                        false
+                    }
+                }
+            }
+        }
+    }
 
-    // Generic demo client
-    clients.insert(
-        "demo-client".to_string(),
-        ClientInfo {
-            client_id: "demo-client".to_string(),
-            client_secret: Some("demo-secret".to_string()),
-            redirect_uris: vec![
-                "http://localhost:8080/callback".to_string(),
-                "https://oauth.pstmn.io/v1/callback".to_string(),
-            ],
-            name: "Demo Client".to_string(),
-        },
-    );
+    /// Redeem a `request_uri` for the parameters pushed by `client_id`
+    ///
+    /// Each `request_uri` can be used once, and only by the client that
+    /// pushed it (RFC 9126 §4).
+    pub async fn take_pushed_request(
+        &self,
+        request_uri: &str,
+        client_id: &str,
+    ) -> Option<serde_json::Value> {
+        let request = match &self.storage {
+            StorageBackend::InMemory {
+                pushed_requests, ..
+            } => pushed_requests
+                .write()
+                .await
+                .remove(request_uri)
+                .filter(|r| r.expires_at > chrono::Utc::now())?,
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let db_request = repos
+                    .pushed_requests()
+                    .take(request_uri)
+                    .await
+                    .ok()
+                    .flatten()?;
 
-    // Demo relying party (examples/relying-party)
-    clients.insert(
-        "demo-rp".to_string(),
-        ClientInfo {
-            client_id: "demo-rp".to_string(),
-            client_secret: Some("demo-secret".to_string()),
-            redirect_uris: vec!["http://localhost:8080/callback".to_string()],
-            name: "Demo Relying Party".to_string(),
+                PushedRequest {
+                    client_id: db_request.client_id,
+                    params: db_request.params,
+                    expires_at: db_request.expires_at,
+                }
+            }
+        };
+
+        (request.client_id == client_id).then_some(request.params)
+    }
+
+    /// Create an offer of `credential_type`; returns the pre-authorized code
+    pub async fn create_credential_offer(
+        &self,
+        credential_configuration_id: String,
+        credential_type: CredentialType,
+    ) -> String {
+        let code = random_token();
+        let code_hash = hash_token(&code);
+        let now = chrono::Utc::now();
+        let expires_at =
+            now + chrono::Duration::seconds(self.config.credential_offer_expiration_seconds as i64);
+
+        match &self.storage {
+            StorageBackend::InMemory {
+                credential_offers, ..
+            } => {
+                let mut offers = credential_offers.write().await;
+                offers.retain(|_, o| {
+                    o.expires_at > now || o.access_token_expires_at.is_some_and(|exp| exp > now)
+                });
+                offers.insert(
+                    hex::encode(code_hash),
+                    CredentialOffer {
+                        credential_configuration_id,
+                        credential_type,
+                        access_token_hash: None,
+                        access_token_expires_at: None,
+                        c_nonce: None,
+                        c_nonce_expires_at: None,
+                        issued: false,
+                        expires_at,
+                    },
+                );
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let new_offer = NewCredentialOffer {
+                    pre_authorized_code_hash: code_hash.to_vec(),
+                    credential_configuration_id,
+                    credential_type: serde_json::to_value(&credential_type).unwrap_or_default(),
+                    expires_at,
+                };
+
+                if let Err(e) = repos.credential_offers().create(new_offer).await {
+                    tracing::error!("Failed to store credential offer: {}", e);
+                }
+            }
+        }
+
+        code
+    }
+
+    /// Redeem a pre-authorized code; returns the access token and `c_nonce`
+    ///
+    /// Each code can be redeemed once.
+    pub async fn redeem_pre_authorized_code(&self, code: &str) -> Option<(String, String)> {
+        let code_hash = hash_token(code);
+        let access_token = random_token();
+        let access_token_hash = hash_token(&access_token);
+        let c_nonce = random_token();
+        let now = chrono::Utc::now();
+        let access_token_expires_at =
+            now + chrono::Duration::seconds(self.config.token_expiration_seconds as i64);
+        let c_nonce_expires_at =
+            now + chrono::Duration::seconds(self.config.c_nonce_expiration_seconds as i64);
+
+        match &self.storage {
+            StorageBackend::InMemory {
+                credential_offers, ..
+            } => {
+                let mut offers = credential_offers.write().await;
+                let offer = offers
+                    .get_mut(&hex::encode(code_hash))
+                    .filter(|o| o.access_token_hash.is_none() && o.expires_at > now)?;
+                offer.access_token_hash = Some(access_token_hash);
+                offer.access_token_expires_at = Some(access_token_expires_at);
+                offer.c_nonce = Some(c_nonce.clone());
+                offer.c_nonce_expires_at = Some(c_nonce_expires_at);
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                repos
+                    .credential_offers()
+                    .redeem(
+                        &code_hash,
+                        &access_token_hash,
+                        access_token_expires_at,
+                        &c_nonce,
+                        c_nonce_expires_at,
+                    )
+                    .await
+                    .ok()
+                    .flatten()?;
+            }
+        }
+
+        Some((access_token, c_nonce))
+    }
+
+    /// Find the offer `access_token` may still fetch the credential for
+    pub async fn find_credential_offer(&self, access_token: &str) -> Option<CredentialOffer> {
+        let access_token_hash = hash_token(access_token);
+
+        match &self.storage {
+            StorageBackend::InMemory {
+                credential_offers, ..
+            } => credential_offers
+                .read()
+                .await
+                .values()
+                .find(|o| {
+                    o.access_token_hash == Some(access_token_hash)
+                        && o.is_redeemable(chrono::Utc::now())
+                })
+                .cloned(),
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let db_offer = repos
+                    .credential_offers()
+                    .find_by_access_token(&access_token_hash)
+                    .await
+                    .ok()
+                    .flatten()?;
+
+                Some(CredentialOffer {
+                    credential_configuration_id: db_offer.credential_configuration_id,
+                    credential_type: serde_json::from_value(db_offer.credential_type).ok()?,
+                    access_token_hash: Some(access_token_hash),
+                    access_token_expires_at: db_offer.access_token_expires_at,
+                    c_nonce: db_offer.c_nonce,
+                    c_nonce_expires_at: db_offer.c_nonce_expires_at,
+                    issued: db_offer.issued_at.is_some(),
+                    expires_at: db_offer.expires_at,
+                })
+            }
+        }
+    }
+
+    /// Hand out a fresh `c_nonce` for the offer `access_token` was issued for
+    pub async fn renew_c_nonce(&self, access_token: &str) -> String {
+        let access_token_hash = hash_token(access_token);
+        let c_nonce = random_token();
+        let c_nonce_expires_at = chrono::Utc::now()
+            + chrono::Duration::seconds(self.config.c_nonce_expiration_seconds as i64);
+
+        match &self.storage {
+            StorageBackend::InMemory {
+                credential_offers, ..
+            } => {
+                let mut offers = credential_offers.write().await;
+                if let Some(offer) = offers
+                    .values_mut()
+                    .find(|o| o.access_token_hash == Some(access_token_hash))
+                {
+                    offer.c_nonce = Some(c_nonce.clone());
+                    offer.c_nonce_expires_at = Some(c_nonce_expires_at);
+                }
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                if let Err(e) = repos
+                    .credential_offers()
+                    .set_c_nonce(&access_token_hash, &c_nonce, c_nonce_expires_at)
+                    .await
+                {
+                    tracing::error!("Failed to store c_nonce: {}", e);
+                }
+            }
+        }
+
+        c_nonce
+    }
+
+    /// Record that the credential for `access_token` was issued
+    ///
+    /// Returns false if it already was, so each offer yields one credential.
+    pub async fn mark_credential_issued(&self, access_token: &str) -> bool {
+        let access_token_hash = hash_token(access_token);
+
+        match &self.storage {
+            StorageBackend::InMemory {
+                credential_offers, ..
+            } => {
+                let mut offers = credential_offers.write().await;
+                match offers.values_mut().find(|o| {
+                    o.access_token_hash == Some(access_token_hash)
+                        && o.is_redeemable(chrono::Utc::now())
+                }) {
+                    Some(offer) => {
+                        offer.issued = true;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                repos
+                    .credential_offers()
+                    .mark_issued(&access_token_hash)
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::error!("Failed to mark credential as issued: {}", e);
+                        false
+                    })
+            }
+        }
+    }
+
+    /// Start a device authorization; returns the device code and user code
+    pub async fn create_device_authorization(
+        &self,
+        client_id: String,
+        scopes: Vec<String>,
+    ) -> (String, String) {
+        let device_code = random_token();
+        let device_code_hash = hash_token(&device_code);
+        let user_code = generate_user_code();
+        let interval = self.config.device_code_interval_seconds;
+        let now = chrono::Utc::now();
+        let expires_at =
+            now + chrono::Duration::seconds(self.config.device_code_expiration_seconds as i64);
+
+        match &self.storage {
+            StorageBackend::InMemory {
+                device_authorizations,
+                ..
+            } => {
+                let mut authorizations = device_authorizations.write().await;
+                authorizations.retain(|_, a| a.expires_at > now);
+                authorizations.insert(
+                    hex::encode(device_code_hash),
+                    DeviceAuthorization {
+                        user_code: user_code.clone(),
+                        client_id,
+                        scopes,
+                        status: DeviceAuthorizationStatus::Pending,
+                        subject_id: None,
+                        sid: None,
+                        zk_claims: None,
+                        interval,
+                        last_polled_at: None,
+                        expires_at,
+                    },
+                );
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let new_authorization = NewDeviceAuthorization {
+                    device_code_hash: device_code_hash.to_vec(),
+                    user_code: user_code.clone(),
+                    client_id,
+                    scopes,
+                    interval_seconds: interval as i32,
+                    expires_at,
+                };
+
+                if let Err(e) = repos
+                    .device_authorizations()
+                    .create(new_authorization)
+                    .await
+                {
+                    tracing::error!("Failed to store device authorization: {}", e);
+                }
+            }
+        }
+
+        (device_code, user_code)
+    }
+
+    /// Find the unexpired device authorization awaiting `user_code`
+    pub async fn find_device_authorization(&self, user_code: &str) -> Option<DeviceAuthorization> {
+        let user_code = normalize_user_code(user_code)?;
+
+        match &self.storage {
+            StorageBackend::InMemory {
+                device_authorizations,
+                ..
+            } => device_authorizations
+                .read()
+                .await
+                .values()
+                .find(|a| {
+                    a.user_code == user_code
+                        && a.status == DeviceAuthorizationStatus::Pending
+                        && a.expires_at > chrono::Utc::now()
+                })
+                .cloned(),
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let db_authorization = repos
+                    .device_authorizations()
+                    .find_pending_by_user_code(&user_code)
+                    .await
+                    .ok()
+                    .flatten()?;
+
+                Some(device_authorization_from_db(db_authorization))
+            }
+        }
+    }
+
+    /// Record the user's decision on a pending device authorization
+    ///
+    /// `None` denies the request. Returns false if no authorization is
+    /// awaiting `user_code`.
+    pub async fn complete_device_authorization(
+        &self,
+        user_code: &str,
+        approval: Option<DeviceApproval>,
+    ) -> bool {
+        let Some(user_code) = normalize_user_code(user_code) else {
+            return false;
+        };
+
+        match &self.storage {
+            StorageBackend::InMemory {
+                device_authorizations,
+                ..
+            } => {
+                let mut authorizations = device_authorizations.write().await;
+                let Some(authorization) = authorizations.values_mut().find(|a| {
+                    a.user_code == user_code
+                        && a.status == DeviceAuthorizationStatus::Pending
+                        && a.expires_at > chrono::Utc::now()
+                }) else {
+                    return false;
+                };
+
+                match approval {
+                    Some(approval) => {
+                        authorization.status = DeviceAuthorizationStatus::Approved;
+                        authorization.subject_id = Some(approval.subject_id);
+                        authorization.scopes = approval.scopes;
+                        authorization.sid = approval.sid;
+                        authorization.zk_claims = approval.zk_claims;
+                    }
+                    None => authorization.status = DeviceAuthorizationStatus::Denied,
+                }
+                true
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let result = match approval {
+                    Some(approval) => {
+                        let zk_claims = approval
+                            .zk_claims
+                            .and_then(|claims| serde_json::to_value(claims).ok());
+                        repos
+                            .device_authorizations()
+                            .approve(
+                                &user_code,
+                                &approval.subject_id,
+                                &approval.scopes,
+                                approval.sid.as_deref(),
+                                zk_claims.as_ref(),
+                            )
+                            .await
+                    }
+                    None => repos.device_authorizations().deny(&user_code).await,
+                };
+
+                result.unwrap_or_else(|e| {
+                    tracing::error!("Failed to update device authorization: {}", e);
+                    false
+                })
+            }
+        }
+    }
+
+    /// Poll a device authorization on behalf of `client_id`
+    ///
+    /// Returns the authorization once the user has approved it; it can then
+    /// not be redeemed again.
+    pub async fn poll_device_authorization(
+        &self,
+        device_code: &str,
+        client_id: &str,
+    ) -> Result<DeviceAuthorization, DevicePollError> {
+        let device_code_hash = hash_token(device_code);
+        let now = chrono::Utc::now();
+
+        match &self.storage {
+            StorageBackend::InMemory {
+                device_authorizations,
+                ..
+            } => {
+                let key = hex::encode(device_code_hash);
+                let mut authorizations = device_authorizations.write().await;
+                let authorization = authorizations
+                    .get_mut(&key)
+                    .filter(|a| a.client_id == client_id)
+                    .ok_or(DevicePollError::Invalid)?;
+
+                authorization.poll(now)?;
+                authorizations.remove(&key).ok_or(DevicePollError::Invalid)
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let mut authorization = repos
+                    .device_authorizations()
+                    .find_by_device_code(&device_code_hash)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(device_authorization_from_db)
+                    .filter(|a| a.client_id == client_id)
+                    .ok_or(DevicePollError::Invalid)?;
+
+                if let Err(e) = authorization.poll(now) {
+                    if matches!(e, DevicePollError::Pending | DevicePollError::SlowDown) {
+                        let _ = repos
+                            .device_authorizations()
+                            .record_poll(&device_code_hash, authorization.interval as i32)
+                            .await;
+                    }
+                    return Err(e);
+                }
+
+                // Deleting the row redeems it, so concurrent polls cannot both succeed
+                repos
+                    .device_authorizations()
+                    .take_approved(&device_code_hash)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(device_authorization_from_db)
+                    .ok_or(DevicePollError::Invalid)
+            }
+        }
+    }
+
+    /// Exchange an authorization code for tokens
+    pub async fn exchange_code(&self, code: &str) -> Option<AuthCode> {
+        match &self.storage {
+            StorageBackend::InMemory { auth_codes, .. } => {
+                let mut codes = auth_codes.write().await;
+                codes
+                    .remove(code)
+                    .filter(|c| c.expires_at > chrono::Utc::now())
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+
+                // Find and mark as used
+                if let Ok(Some(db_code)) = repos.auth_codes().find_by_code(code).await {
+                    let _ = repos.auth_codes().mark_used(code).await;
+
+                    let pkce = db_code.code_challenge.map(|challenge| PkceChallenge {
+                        challenge,
+                        method: db_code
+                            .code_challenge_method
+                            .as_deref()
+                            .and_then(PkceMethod::parse)
+                            .unwrap_or(PkceMethod::Plain),
+                    });
+
+                    return Some(AuthCode {
+                        code: db_code.code,
+                        client_id: db_code.client_id,
+                        redirect_uri: db_code.redirect_uri,
+                        scopes: db_code.scopes,
+                        nonce: db_code.nonce,
+                        pkce,
+                        subject_id: db_code.user_id,
+                        expires_at: db_code.expires_at,
+                        sid: db_code.sid,
+                        claim_requests: db_code
+                            .claims_request
+                            .and_then(|requests| serde_json::from_value(requests).ok())
+                            .unwrap_or_default(),
+                        zk_claims: db_code
+                            .zk_claims
+                            .and_then(|claims| serde_json::from_value(claims).ok()),
+                    });
+                }
+                None
+            }
+        }
+    }
+
+    /// Store a new access token and return it
+    pub async fn issue_access_token(&self, record: AccessTokenRecord) -> String {
+        let token = random_token();
+        let token_hash = hash_token(&token);
+
+        match &self.storage {
+            StorageBackend::InMemory { access_tokens, .. } => {
+                let mut tokens = access_tokens.write().await;
+                tokens.insert(hex::encode(token_hash), record);
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let new_token = NewAccessToken {
+                    token_hash: token_hash.to_vec(),
+                    client_id: record.client_id,
+                    user_id: record.subject_id,
+                    scopes: record.scopes,
+                    zk_claims: serde_json::to_value(&record.zk_claims).ok(),
+                    expires_at: record.expires_at,
+                    family_id: record.family_id,
+                    dpop_jkt: record.dpop_jkt,
+                };
+
+                if let Err(e) = repos.access_tokens().create(new_token).await {
+                    tracing::error!("Failed to store access token: {}", e);
+                }
+            }
+        }
+
+        token
+    }
+
+    /// Look up an unexpired, unrevoked access token
+    pub async fn validate_access_token(&self, token: &str) -> Option<AccessTokenRecord> {
+        let token_hash = hash_token(token);
+
+        match &self.storage {
+            StorageBackend::InMemory { access_tokens, .. } => {
+                let tokens = access_tokens.read().await;
+                tokens
+                    .get(&hex::encode(token_hash))
+                    .filter(|t| t.expires_at > chrono::Utc::now())
+                    .cloned()
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let db_token = repos
+                    .access_tokens()
+                    .find_by_hash(&token_hash)
+                    .await
+                    .ok()
+                    .flatten()?;
+
+                Some(AccessTokenRecord {
+                    client_id: db_token.client_id,
+                    subject_id: db_token.user_id,
+                    scopes: db_token.scopes,
+                    zk_claims: db_token
+                        .zk_claims
+                        .and_then(|claims| serde_json::from_value(claims).ok())
+                        .unwrap_or_default(),
+                    expires_at: db_token.expires_at,
+                    family_id: db_token.family_id,
+                    dpop_jkt: db_token.dpop_jkt,
+                })
+            }
+        }
+    }
+
+    /// Revoke a single access token
+    pub async fn revoke_access_token(&self, token: &str) {
+        let token_hash = hash_token(token);
+
+        match &self.storage {
+            StorageBackend::InMemory { access_tokens, .. } => {
+                access_tokens.write().await.remove(&hex::encode(token_hash));
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                if let Err(e) = repos.access_tokens().revoke(&token_hash).await {
+                    tracing::error!("Failed to revoke access token: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Revoke a refresh token family and every access token issued with it
+    pub async fn revoke_token_family(&self, family_id: uuid::Uuid) {
+        match &self.storage {
+            StorageBackend::InMemory {
+                refresh_tokens,
+                access_tokens,
+                ..
+            } => {
+                for t in refresh_tokens.write().await.values_mut() {
+                    if t.family_id == family_id {
+                        t.revoked = true;
+                    }
+                }
+                access_tokens
+                    .write()
+                    .await
+                    .retain(|_, t| t.family_id != Some(family_id));
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                if let Err(e) = repos.refresh_tokens().revoke_family(family_id).await {
+                    tracing::error!("Failed to revoke refresh token family: {}", e);
+                }
+                if let Err(e) = repos.access_tokens().revoke_family(family_id).await {
+                    tracing::error!("Failed to revoke access tokens: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Look up a refresh token that can still be redeemed, without redeeming it
+    pub async fn find_refresh_token(&self, token: &str) -> Option<RefreshTokenRecord> {
+        let token_hash = hash_token(token);
+        let now = chrono::Utc::now();
+
+        let record = match &self.storage {
+            StorageBackend::InMemory { refresh_tokens, .. } => refresh_tokens
+                .read()
+                .await
+                .get(&hex::encode(token_hash))
+                .cloned()?,
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let db_token = repos
+                    .refresh_tokens()
+                    .find_any_by_hash(&token_hash)
+                    .await
+                    .ok()
+                    .flatten()?;
+
+                RefreshTokenRecord {
+                    family_id: db_token.family_id,
+                    client_id: db_token.client_id,
+                    subject_id: db_token.user_id,
+                    scopes: db_token.scopes,
+                    zk_claims: db_token
+                        .zk_claims
+                        .and_then(|claims| serde_json::from_value(claims).ok())
+                        .unwrap_or_default(),
+                    zk_claims_verified_at: db_token.zk_claims_verified_at,
+                    expires_at: db_token.expires_at,
+                    used: db_token.used_at.is_some(),
+                    revoked: db_token.revoked_at.is_some(),
+                    sid: db_token.sid,
+                    dpop_jkt: db_token.dpop_jkt,
+                }
+            }
+        };
+
+        (!record.used && !record.revoked && record.expires_at > now).then_some(record)
+    }
+
+    /// Store a new refresh token and return it
+    pub async fn issue_refresh_token(&self, record: RefreshTokenRecord) -> String {
+        let token = random_token();
+        let token_hash = hash_token(&token);
+
+        match &self.storage {
+            StorageBackend::InMemory { refresh_tokens, .. } => {
+                let mut tokens = refresh_tokens.write().await;
+                tokens.insert(hex::encode(token_hash), record);
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let new_token = NewRefreshToken {
+                    token_hash: token_hash.to_vec(),
+                    client_id: record.client_id,
+                    user_id: record.subject_id,
+                    scopes: record.scopes,
+                    expires_at: record.expires_at,
+                    family_id: record.family_id,
+                    zk_claims: serde_json::to_value(&record.zk_claims).ok(),
+                    zk_claims_verified_at: record.zk_claims_verified_at,
+                    sid: record.sid,
+                    dpop_jkt: record.dpop_jkt,
+                };
+
+                if let Err(e) = repos.refresh_tokens().create(new_token).await {
+                    tracing::error!("Failed to store refresh token: {}", e);
+                }
+            }
+        }
+
+        token
+    }
+
+    /// Redeem a refresh token for rotation
+    ///
+    /// The token is marked used and cannot be presented again. Presenting a
+    /// token that was already rotated revokes its whole family, since either
+    /// the legitimate client or an attacker holds a stolen copy.
+    ///
+    /// A DPoP-bound token is only redeemed with a proof from its key, whose
+    /// thumbprint is `dpop_jkt`.
+    pub async fn redeem_refresh_token(
+        &self,
+        token: &str,
+        client_id: &str,
+        dpop_jkt: Option<&str>,
+    ) -> Result<RefreshTokenRecord, RefreshTokenError> {
+        let token_hash = hash_token(token);
+        let now = chrono::Utc::now();
+
+        match &self.storage {
+            StorageBackend::InMemory { refresh_tokens, .. } => {
+                let mut tokens = refresh_tokens.write().await;
+                let record = tokens
+                    .get(&hex::encode(token_hash))
+                    .cloned()
+                    .ok_or(RefreshTokenError::Invalid)?;
+
+                if record.revoked || record.expires_at <= now {
+                    return Err(RefreshTokenError::Invalid);
+                }
+                if record.client_id != client_id {
+                    return Err(RefreshTokenError::WrongClient);
+                }
+                if record.dpop_jkt.is_some() && record.dpop_jkt.as_deref() != dpop_jkt {
+                    return Err(RefreshTokenError::WrongKey);
+                }
+                if record.used {
+                    drop(tokens);
+                    self.revoke_token_family(record.family_id).await;
+                    return Err(RefreshTokenError::Reused);
+                }
+
+                if let Some(t) = tokens.get_mut(&hex::encode(token_hash)) {
+                    t.used = true;
+                }
+                Ok(record)
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let db_token = repos
+                    .refresh_tokens()
+                    .find_any_by_hash(&token_hash)
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("Failed to look up refresh token: {}", e);
+                        RefreshTokenError::Storage
+                    })?
+                    .ok_or(RefreshTokenError::Invalid)?;
+
+                if db_token.revoked_at.is_some() || db_token.expires_at <= now {
+                    return Err(RefreshTokenError::Invalid);
+                }
+                if db_token.client_id != client_id {
+                    return Err(RefreshTokenError::WrongClient);
+                }
+                if db_token.dpop_jkt.is_some() && db_token.dpop_jkt.as_deref() != dpop_jkt {
+                    return Err(RefreshTokenError::WrongKey);
+                }
+
+                // mark_used is atomic, so concurrent redemptions cannot both
+                // succeed; a failed update says nothing about reuse
+                let first_use = db_token.used_at.is_none()
+                    && repos
+                        .refresh_tokens()
+                        .mark_used(&token_hash)
+                        .await
+                        .map_err(|e| {
+                            tracing::error!("Failed to mark refresh token used: {}", e);
+                            RefreshTokenError::Storage
+                        })?;
+                if !first_use {
+                    self.revoke_token_family(db_token.family_id).await;
+                    return Err(RefreshTokenError::Reused);
+                }
+
+                Ok(RefreshTokenRecord {
+                    family_id: db_token.family_id,
+                    client_id: db_token.client_id,
+                    subject_id: db_token.user_id,
+                    scopes: db_token.scopes,
+                    zk_claims: db_token
+                        .zk_claims
+                        .and_then(|claims| serde_json::from_value(claims).ok())
+                        .unwrap_or_default(),
+                    zk_claims_verified_at: db_token.zk_claims_verified_at,
+                    expires_at: db_token.expires_at,
+                    used: true,
+                    revoked: false,
+                    sid: db_token.sid,
+                    dpop_jkt: db_token.dpop_jkt,
+                })
+            }
+        }
+    }
+
+    /// Record a client in a browser session, starting a new session when
+    /// `sid` is absent or no longer active; returns the session's `sid`
+    pub async fn join_session(
+        &self,
+        sid: Option<&str>,
+        client_id: &str,
+        subject_id: &str,
+    ) -> String {
+        match &self.storage {
+            StorageBackend::InMemory { sessions, .. } => {
+                let mut sessions = sessions.write().await;
+                let sid = match sid {
+                    Some(sid) if sessions.contains_key(sid) => sid.to_string(),
+                    _ => random_token(),
+                };
+                sessions
+                    .entry(sid.clone())
+                    .or_default()
+                    .clients
+                    .insert(client_id.to_string(), subject_id.to_string());
+                sid
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let active = match sid {
+                    Some(sid) => repos.sessions().find_active(sid).await.ok().flatten(),
+                    None => None,
+                };
+                let sid = match active {
+                    Some(session) => session.sid,
+                    None => {
+                        let sid = random_token();
+                        if let Err(e) = repos.sessions().create(&sid).await {
+                            tracing::error!("Failed to store session: {}", e);
+                        }
+                        sid
+                    }
+                };
+                if let Err(e) = repos
+                    .sessions()
+                    .add_client(&sid, client_id, subject_id)
+                    .await
+                {
+                    tracing::error!("Failed to record session client: {}", e);
+                }
+                sid
+            }
+        }
+    }
+
+    /// End a browser session and revoke every token issued during it
+    ///
+    /// Returns the clients that took part, with the subject each saw; empty
+    /// if the session was unknown or had already ended.
+    pub async fn end_session(&self, sid: &str) -> Vec<(String, String)> {
+        match &self.storage {
+            StorageBackend::InMemory {
+                sessions,
+                refresh_tokens,
+                access_tokens,
+                ..
+            } => {
+                let Some(session) = sessions.write().await.remove(sid) else {
+                    return Vec::new();
+                };
+
+                let mut families = Vec::new();
+                for t in refresh_tokens.write().await.values_mut() {
+                    if t.sid.as_deref() == Some(sid) {
+                        t.revoked = true;
+                        families.push(t.family_id);
+                    }
+                }
+                access_tokens
+                    .write()
+                    .await
+                    .retain(|_, t| !t.family_id.is_some_and(|f| families.contains(&f)));
+
+                session.clients.into_iter().collect()
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                if !repos.sessions().end(sid).await.unwrap_or(false) {
+                    return Vec::new();
+                }
+
+                if let Err(e) = repos.access_tokens().revoke_session(sid).await {
+                    tracing::error!("Failed to revoke session access tokens: {}", e);
+                }
+                if let Err(e) = repos.refresh_tokens().revoke_session(sid).await {
+                    tracing::error!("Failed to revoke session refresh tokens: {}", e);
+                }
+
+                repos
+                    .sessions()
+                    .clients(sid)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|c| (c.client_id, c.user_id))
+                    .collect()
+            }
+        }
+    }
+
+    /// The clients that took part in an active browser session, with the
+    /// subject each saw
+    pub async fn session_clients(&self, sid: &str) -> Vec<(String, String)> {
+        match &self.storage {
+            StorageBackend::InMemory { sessions, .. } => sessions
+                .read()
+                .await
+                .get(sid)
+                .map(|session| session.clients.clone().into_iter().collect())
+                .unwrap_or_default(),
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                if !matches!(repos.sessions().find_active(sid).await, Ok(Some(_))) {
+                    return Vec::new();
+                }
+                repos
+                    .sessions()
+                    .clients(sid)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|c| (c.client_id, c.user_id))
+                    .collect()
+            }
+        }
+    }
+
+    /// Record the user's consent to share `scopes`, and the ZK claims proven
+    /// for them, with a client; replaces the subject's earlier grant to it
+    ///
+    /// `sector` is the sector the pairwise subject was presented for.
+    pub async fn record_consent(
+        &self,
+        subject_id: &str,
+        client_id: &str,
+        sector: &str,
+        scopes: Vec<String>,
+        zk_claims: ZkClaims,
+    ) -> Option<ConsentGrant> {
+        let now = chrono::Utc::now();
+        match &self.storage {
+            StorageBackend::InMemory { consent_grants, .. } => {
+                let mut grants = consent_grants.write().await;
+                let existing = grants
+                    .values()
+                    .find(|g| g.subject_id == subject_id && g.client_id == client_id)
+                    .map(|g| (g.id, g.created_at));
+                let (id, created_at) = existing.unwrap_or_else(|| (uuid::Uuid::new_v4(), now));
+                let grant = ConsentGrant {
+                    id,
+                    client_id: client_id.to_string(),
+                    subject_id: subject_id.to_string(),
+                    sector_identifier: Some(sector.to_string()),
+                    scopes,
+                    zk_claims,
+                    zk_claims_verified_at: now,
+                    created_at,
+                    updated_at: now,
+                };
+                grants.insert(id, grant.clone());
+                Some(grant)
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let result = repos
+                    .consent_grants()
+                    .upsert(NewConsentGrant {
+                        user_id: subject_id.to_string(),
+                        client_id: client_id.to_string(),
+                        sector_identifier: sector.to_string(),
+                        scopes,
+                        zk_claims: serde_json::to_value(&zk_claims).unwrap_or_default(),
+                        zk_claims_verified_at: now,
+                    })
+                    .await;
+                match result {
+                    Ok(grant) => Some(consent_grant_from_db(grant)),
+                    Err(e) => {
+                        tracing::error!("Failed to store consent grant: {}", e);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// The subject's grant to a client, if it consented before
+    pub async fn find_consent(&self, subject_id: &str, client_id: &str) -> Option<ConsentGrant> {
+        match &self.storage {
+            StorageBackend::InMemory { consent_grants, .. } => consent_grants
+                .read()
+                .await
+                .values()
+                .find(|g| g.subject_id == subject_id && g.client_id == client_id)
+                .cloned(),
+            StorageBackend::Database { pool } => Repositories::new(pool)
+                .consent_grants()
+                .find(subject_id, client_id)
+                .await
+                .ok()
+                .flatten()
+                .map(consent_grant_from_db),
+        }
+    }
+
+    pub async fn find_consent_grant(&self, id: uuid::Uuid) -> Option<ConsentGrant> {
+        match &self.storage {
+            StorageBackend::InMemory { consent_grants, .. } => {
+                consent_grants.read().await.get(&id).cloned()
+            }
+            StorageBackend::Database { pool } => Repositories::new(pool)
+                .consent_grants()
+                .find_by_id(id)
+                .await
+                .ok()
+                .flatten()
+                .map(consent_grant_from_db),
+        }
+    }
+
+    /// Narrow a grant to `scopes`, dropping the claims shared for the others
+    pub async fn update_consent_grant(
+        &self,
+        id: uuid::Uuid,
+        scopes: Vec<String>,
+    ) -> Option<ConsentGrant> {
+        match &self.storage {
+            StorageBackend::InMemory { consent_grants, .. } => {
+                let mut grants = consent_grants.write().await;
+                let grant = grants.get_mut(&id)?;
+                grant.zk_claims = grant.claims_for(&scopes);
+                grant.scopes = scopes;
+                grant.updated_at = chrono::Utc::now();
+                Some(grant.clone())
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                let grant =
+                    consent_grant_from_db(repos.consent_grants().find_by_id(id).await.ok()??);
+                let zk_claims = serde_json::to_value(grant.claims_for(&scopes)).unwrap_or_default();
+                repos
+                    .consent_grants()
+                    .update(id, &scopes, &zk_claims)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(consent_grant_from_db)
+            }
+        }
+    }
+
+    /// Delete a grant; returns `false` if it was unknown
+    pub async fn delete_consent_grant(&self, id: uuid::Uuid) -> bool {
+        match &self.storage {
+            StorageBackend::InMemory { consent_grants, .. } => {
+                consent_grants.write().await.remove(&id).is_some()
+            }
+            StorageBackend::Database { pool } => Repositories::new(pool)
+                .consent_grants()
+                .delete(id)
+                .await
+                .unwrap_or(false),
+        }
+    }
+
+    /// Revoke every refresh and access token a client holds for a subject
+    pub async fn revoke_client_tokens(&self, client_id: &str, subject_id: &str) {
+        match &self.storage {
+            StorageBackend::InMemory {
+                refresh_tokens,
+                access_tokens,
+                ..
+            } => {
+                for t in refresh_tokens.write().await.values_mut() {
+                    if t.client_id == client_id && t.subject_id == subject_id {
+                        t.revoked = true;
+                    }
+                }
+                access_tokens
+                    .write()
+                    .await
+                    .retain(|_, t| !(t.client_id == client_id && t.subject_id == subject_id));
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                if let Err(e) = repos
+                    .refresh_tokens()
+                    .revoke_for_client(client_id, subject_id)
+                    .await
+                {
+                    tracing::error!("Failed to revoke refresh tokens: {}", e);
+                }
+                if let Err(e) = repos
+                    .access_tokens()
+                    .revoke_for_client(client_id, subject_id)
+                    .await
+                {
+                    tracing::error!("Failed to revoke access tokens: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Register a new client (database only)
+    pub async fn register_client(&self, client: NewClient) -> Result<(), String> {
+        match &self.storage {
+            StorageBackend::InMemory { .. } => {
+                Err("Cannot register clients in in-memory mode".to_string())
+            }
+            StorageBackend::Database { pool } => {
+                let repos = Repositories::new(pool);
+                repos
+                    .clients()
+                    .create(client)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/// `request_uri` values for pushed authorization requests (RFC 9126 §2.2)
+pub const REQUEST_URI_PREFIX: &str = "urn:ietf:params:oauth:request_uri:";
+
+/// Generate an opaque bearer token (256 bits, base64url)
+pub(crate) fn random_token() -> String {
+    use base64::Engine;
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Tokens are stored only as SHA-256 hashes
+pub(crate) fn hash_token(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+/// Generate a user code for a device authorization, e.g. `WDJB-MJHT`
+fn generate_user_code() -> String {
+    use rand::seq::SliceRandom;
+
+    let mut rng = rand::thread_rng();
+    let code: String = (0..USER_CODE_LENGTH)
+        .map(|_| *USER_CODE_CHARSET.choose(&mut rng).unwrap_or(&b'B') as char)
+        .collect();
+    normalize_user_code(&code).unwrap_or(code)
+}
+
+fn device_authorization_from_db(
+    db_authorization: fantasma_db::models::DeviceAuthorization,
+) -> DeviceAuthorization {
+    DeviceAuthorization {
+        user_code: db_authorization.user_code,
+        client_id: db_authorization.client_id,
+        scopes: db_authorization.scopes,
+        status: DeviceAuthorizationStatus::parse(&db_authorization.status)
+            .unwrap_or(DeviceAuthorizationStatus::Denied),
+        subject_id: db_authorization.user_id,
+        sid: db_authorization.sid,
+        zk_claims: db_authorization
+            .zk_claims
+            .and_then(|v| serde_json::from_value(v).ok()),
+        interval: db_authorization.interval_seconds.max(0) as u64,
+        last_polled_at: db_authorization.last_polled_at,
+        expires_at: db_authorization.expires_at,
+    }
+}
+
+fn consent_grant_from_db(db_grant: fantasma_db::models::ConsentGrant) -> ConsentGrant {
+    ConsentGrant {
+        id: db_grant.id,
+        client_id: db_grant.client_id,
+        subject_id: db_grant.user_id,
+        sector_identifier: db_grant.sector_identifier,
+        scopes: db_grant.scopes,
+        zk_claims: serde_json::from_value(db_grant.zk_claims).unwrap_or_default(),
+        zk_claims_verified_at: db_grant.zk_claims_verified_at,
+        created_at: db_grant.created_at,
+        updated_at: db_grant.updated_at,
+    }
+}
+
+/// Load the circuit verification keys and hash the compiled circuits.
+///
+/// Bytecode hashes come from the Scarb artifacts under
+/// `FANTASMA_CIRCUITS_PATH` (default `circuits`), verification keys from
+/// `FANTASMA_VERIFICATION_KEYS_PATH` when set. Hashes that can't be computed
+/// are left out of discovery rather than published as placeholders.
+fn load_circuits() -> (Verifier, ZkCircuitInfo) {
+    let circuits_path =
+        std::env::var("FANTASMA_CIRCUITS_PATH").unwrap_or_else(|_| "circuits".into());
+    let loader =
+        CircuitLoader::load_from_dir(std::path::Path::new(&circuits_path)).unwrap_or_else(|e| {
+            tracing::warn!("Failed to load circuits: {}. Using placeholder hashes.", e);
+            CircuitLoader::with_defaults()
+        });
+
+    let mut verifier = Verifier::new();
+    match std::env::var("FANTASMA_VERIFICATION_KEYS_PATH") {
+        Ok(dir) if !dir.is_empty() => {
+            if let Err(e) = verifier.load_verification_keys_from_dir(std::path::Path::new(&dir)) {
+                tracing::warn!("Failed to load verification keys: {}", e);
+            }
+        }
+        _ => {
+            for circuit_type in CircuitType::ALL {
+                let _ = verifier.load_verification_key(circuit_type);
+            }
+        }
+    }
+
+    let zk_circuits = fantasma_oidc::discovery::zk_circuits(&loader, &verifier);
+    (verifier, zk_circuits)
+}
+
+/// Load the credential issuer, signing with the Dilithium key in
+/// `FANTASMA_KEY_DIR/issuer`, or with its Ed25519 and ML-DSA-65 keys when
+/// `hybrid` is set
+///
+/// Falls back to an ephemeral key when no key directory is configured or the
+/// key cannot be loaded.
+fn load_issuer(issuer: &str, hybrid: bool) -> Issuer {
+    let domain = url::Url::parse(issuer)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| issuer.to_string());
+    let trust_anchor = TrustAnchor::SelfDeclared { domain };
+
+    let loaded = match std::env::var("FANTASMA_KEY_DIR") {
+        Ok(dir) if !dir.is_empty() => {
+            let passphrase = std::env::var("FANTASMA_KEY_PASSPHRASE")
+                .unwrap_or_else(|_| "fantasma-dev-passphrase".into());
+            fantasma_crypto::KeyStore::new(std::path::Path::new(&dir).join("issuer"))
+                .and_then(|store| {
+                    let issuer = if hybrid {
+                        let keypair = HybridKeypair::from_parts(
+                            store.load_or_generate_ed25519(&passphrase)?,
+                            store.load_or_generate_ml_dsa_65(&passphrase)?,
+                        );
+                        Issuer::with_hybrid_keypair(
+                            issuer,
+                            "Fantasma",
+                            trust_anchor.clone(),
+                            keypair,
+                        )
+                    } else {
+                        let keypair = store.load_or_generate(&passphrase)?;
+                        Issuer::with_keypair(issuer, "Fantasma", trust_anchor.clone(), keypair)
+                    };
+                    Ok(issuer)
+                })
+                .map_err(|e| {
+                    tracing::warn!("Failed to load issuer key: {}. Using random key.", e);
+                })
+                .ok()
+        }
+        _ => None,
+    };
+
+    loaded.unwrap_or_else(|| {
+        if hybrid {
+            Issuer::with_hybrid_keypair(issuer, "Fantasma", trust_anchor, HybridKeypair::generate())
+        } else {
+            Issuer::new(issuer, "Fantasma", trust_anchor)
+        }
+    })
+}
+
+/// Create demo clients for testing
+fn create_demo_clients() -> HashMap<String, ClientInfo> {
+    let mut clients = HashMap::new();
+
+    // All confidential demo clients share the secret "demo-secret"
+    let demo_secret_hash =
+        fantasma_crypto::hash_secret("demo-secret").expect("failed to hash demo secret");
+
+    // Generic demo client
+    clients.insert(
+        "demo-client".to_string(),
+        ClientInfo {
+            client_id: "demo-client".to_string(),
+            client_secret_hash: Some(demo_secret_hash.clone()),
+            redirect_uris: vec![
+                "http://localhost:8080/callback".to_string(),
+                "https://oauth.pstmn.io/v1/callback".to_string(),
+            ],
+            name: "Demo Client".to_string(),
+            id_token_signed_response_alg: None,
+            token_endpoint_auth_method: TokenEndpointAuthMethod::ClientSecretBasic,
+            jwks: None,
+            jwks_uri: None,
+            sector_identifier_uri: None,
+            require_pkce_s256: false,
+            require_pushed_authorization_requests: false,
+            require_signed_request_object: false,
+            post_logout_redirect_uris: vec!["http://localhost:8080/".to_string()],
+            backchannel_logout_uri: None,
+            backchannel_logout_session_required: false,
+            allowed_scopes: None,
+            downscope_unapproved_scopes: false,
+            id_token_encryption: None,
+            userinfo_encryption: None,
+        },
+    );
+
+    // Demo relying party (examples/relying-party)
+    clients.insert(
+        "demo-rp".to_string(),
+        ClientInfo {
+            client_id: "demo-rp".to_string(),
+            client_secret_hash: Some(demo_secret_hash.clone()),
+            redirect_uris: vec!["http://localhost:8080/callback".to_string()],
+            name: "Demo Relying Party".to_string(),
+            id_token_signed_response_alg: None,
+            token_endpoint_auth_method: TokenEndpointAuthMethod::ClientSecretBasic,
+            jwks: None,
+            jwks_uri: None,
+            sector_identifier_uri: None,
+            require_pkce_s256: false,
+            require_pushed_authorization_requests: false,
+            require_signed_request_object: false,
+            post_logout_redirect_uris: vec!["http://localhost:8080/".to_string()],
+            backchannel_logout_uri: None,
+            backchannel_logout_session_required: false,
+            allowed_scopes: None,
+            downscope_unapproved_scopes: false,
+            id_token_encryption: None,
+            userinfo_encryption: None,
+        },
+    );
+
+    // Demo relying party that requires post-quantum ID tokens
+    clients.insert(
+        "demo-pq-rp".to_string(),
+        ClientInfo {
+            client_id: "demo-pq-rp".to_string(),
+            client_secret_hash: Some(demo_secret_hash.clone()),
+            redirect_uris: vec!["http://localhost:8080/callback".to_string()],
+            name: "Demo Post-Quantum Relying Party".to_string(),
+            id_token_signed_response_alg: Some(JwsAlgorithm::MlDsa65),
+            token_endpoint_auth_method: TokenEndpointAuthMethod::ClientSecretBasic,
+            jwks: None,
+            jwks_uri: None,
+            sector_identifier_uri: None,
+            require_pkce_s256: false,
+            require_pushed_authorization_requests: false,
+            require_signed_request_object: false,
+            post_logout_redirect_uris: Vec::new(),
+            backchannel_logout_uri: None,
+            backchannel_logout_session_required: false,
+            allowed_scopes: None,
+            downscope_unapproved_scopes: false,
+            id_token_encryption: None,
+            userinfo_encryption: None,
         },
     );
 
@@ -361,12 +2377,27 @@ fn create_demo_clients() -> HashMap<String, ClientInfo> {
         "fantasma-wallet".to_string(),
         ClientInfo {
             client_id: "fantasma-wallet".to_string(),
-            client_secret: None, // Public client
+            client_secret_hash: None, // Public client
             redirect_uris: vec![
                 "chrome-extension://*/callback".to_string(),
                 "moz-extension://*/callback".to_string(),
             ],
             name: "Fantasma Wallet".to_string(),
+            id_token_signed_response_alg: None,
+            token_endpoint_auth_method: TokenEndpointAuthMethod::None,
+            jwks: None,
+            jwks_uri: None,
+            sector_identifier_uri: None,
+            require_pkce_s256: true,
+            require_pushed_authorization_requests: false,
+            require_signed_request_object: false,
+            post_logout_redirect_uris: Vec::new(),
+            backchannel_logout_uri: None,
+            backchannel_logout_session_required: false,
+            allowed_scopes: None,
+            downscope_unapproved_scopes: false,
+            id_token_encryption: None,
+            userinfo_encryption: None,
         },
     );
 
@@ -0,0 +1,310 @@
+//! Integration tests for claim provenance and the `acr_values` parameter
+
+use axum::http::StatusCode;
+use fantasma_core::claim::ClaimType;
+use fantasma_core::issuer::public_key_hash;
+use fantasma_core::proof::{subject_hash, GeneratedProof, ProofRequest, ProofResponse};
+use fantasma_oidc::provenance::IssuerTrust;
+use fantasma_server::state::TrustedIssuer;
+use fantasma_stark::backend::{ProverBackend, StoneBackend};
+use serde_json::Value;
+use std::sync::Arc;
+
+mod common;
+use common::{body_json, body_text, consent_proof_request, location, query_param, TestApp};
+
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+
+fn encode(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+fn consent_form(scope: &str, acr_values: Option<&str>) -> String {
+    let mut form = format!(
+        "response_type=code&client_id=demo-client&redirect_uri={}&scope={}&demo_user=alice&action=approve",
+        REDIRECT_URI,
+        encode(scope)
+    );
+    if let Some(acr_values) = acr_values {
+        form.push_str(&format!("&acr_values={}", encode(acr_values)));
+    }
+    form
+}
+
+async fn id_token_claims(app: &TestApp, code: &str) -> Value {
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id=demo-client&client_secret=demo-secret",
+                code, REDIRECT_URI
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let tokens = body_json(response).await;
+    let payload = tokens["id_token"]
+        .as_str()
+        .unwrap()
+        .split('.')
+        .nth(1)
+        .unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_demo_claims_carry_provenance() {
+    let app = TestApp::new().await;
+    let code = app
+        .authorization_code(&consent_form("openid zk:age:21+ zk:kyc:basic", None))
+        .await;
+    let id_token = id_token_claims(&app, &code).await;
+
+    let provenance = &id_token["zk_age_claim"]["provenance"];
+    assert_eq!(provenance["method"], "demo");
+    assert_eq!(provenance["verifier_backend"], "demo");
+    assert_eq!(provenance["issuer_trust_level"], 100);
+    assert!(provenance["verified_at"].as_i64().is_some());
+    assert!(provenance.get("proof_hash").is_none());
+    assert_eq!(
+        id_token["zk_kyc_claim"]["provenance"]["issuer_trust_level"],
+        80
+    );
+
+    // The token attains the weakest of its claims
+    assert_eq!(id_token["acr"], "urn:fantasma:acr:demo:accredited");
+    assert_eq!(id_token["amr"], serde_json::json!(["demo"]));
+}
+
+#[tokio::test]
+async fn test_claims_below_acr_values_are_refused() {
+    let app = TestApp::new().await;
+
+    // Demo claims are not STARK-verified
+    let response = app
+        .consent(&consent_form(
+            "openid zk:age:21+",
+            Some("urn:fantasma:acr:stark:government"),
+        ))
+        .await;
+    let redirect = location(&response);
+    assert_eq!(
+        query_param(&redirect, "error").as_deref(),
+        Some("access_denied")
+    );
+    assert!(query_param(&redirect, "code").is_none());
+
+    // Any one of the requested classes will do
+    let code = app
+        .authorization_code(&consent_form(
+            "openid zk:age:21+",
+            Some("urn:fantasma:acr:stark:government urn:fantasma:acr:demo:government"),
+        ))
+        .await;
+    let id_token = id_token_claims(&app, &code).await;
+    assert_eq!(id_token["acr"], "urn:fantasma:acr:demo:government");
+
+    // Without a verified claim there is nothing to attain a class with
+    let response = app
+        .consent(&consent_form("openid", Some("urn:fantasma:acr:demo")))
+        .await;
+    assert_eq!(
+        query_param(&location(&response), "error").as_deref(),
+        Some("access_denied")
+    );
+}
+
+/// Issuer keys trusted at each tier
+const GOVERNMENT_KEY: &[u8] = &[1u8; 32];
+const FRAMEWORK_KEY: &[u8] = &[2u8; 32];
+
+/// An application verifying proofs with the stone backend, whose verifier
+/// accepts every proof with the expected public inputs, and trusting an
+/// issuer at the government and one at the trust framework tier
+async fn stark_app() -> TestApp {
+    // `true` stands in for the stone verifier binary
+    let backend = StoneBackend::new("/usr/bin/true".into())
+        .unwrap()
+        .with_verifier("/usr/bin/true".into());
+    TestApp::with_app_state(|state| {
+        state.prover_backend = Arc::new(ProverBackend::Stone(backend));
+        state.trusted_issuers = Arc::new(vec![
+            TrustedIssuer {
+                public_key: GOVERNMENT_KEY.to_vec(),
+                trust_tier: IssuerTrust::Government,
+            },
+            TrustedIssuer {
+                public_key: FRAMEWORK_KEY.to_vec(),
+                trust_tier: IssuerTrust::TrustFramework,
+            },
+        ]);
+    })
+    .await
+}
+
+/// Prove 21+ from a credential of `issuer_key`, as a stone wallet would
+async fn prove_age(
+    app: &TestApp,
+    acr_values: &str,
+    issuer_key: &[u8],
+    nullifier: [u8; 32],
+) -> String {
+    let response = app
+        .get(&format!(
+            "/authorize?response_type=code&client_id=demo-client&redirect_uri={}&scope={}&acr_values={}",
+            REDIRECT_URI,
+            encode("openid zk:age:21+"),
+            encode(acr_values)
+        ))
+        .await;
+    let request: ProofRequest = consent_proof_request(&body_text(response).await);
+    let subject = fantasma_crypto::pairwise_subject(&[42u8; 32], &request.verifier_domain);
+
+    let claim_type = ClaimType::AgeAtLeast { threshold: 21 };
+    let public_inputs: Vec<String> = claim_type
+        .public_inputs()
+        .iter()
+        .chain([
+            &request.verifier_domain_hash(),
+            &request.nonce_hash(),
+            &nullifier,
+            &subject_hash(&subject),
+            &public_key_hash(issuer_key),
+        ])
+        .map(hex::encode)
+        .collect();
+    let proof = GeneratedProof {
+        claim_type: claim_type.clone(),
+        proof_bytes: serde_json::to_vec(&serde_json::json!({
+            "public_input": { "inputs": public_inputs }
+        }))
+        .unwrap(),
+        circuit_id: claim_type.circuit_id().to_string(),
+        public_inputs,
+        nullifier,
+        generated_at: chrono::Utc::now(),
+    };
+    let response = ProofResponse {
+        request_id: request.request_id.clone(),
+        proofs: vec![proof],
+        proof_refs: vec![],
+        subject_id: subject,
+        generated_at: chrono::Utc::now(),
+    };
+
+    let form = format!(
+        "request_id={}&action=approve&proof_response={}",
+        encode(&request.request_id),
+        encode(&serde_json::to_string(&response).unwrap())
+    );
+    location(&app.post_form("/authorize/consent", &form).await)
+}
+
+#[tokio::test]
+async fn test_proofs_attain_their_issuer_tier() {
+    let app = stark_app().await;
+
+    let redirect = prove_age(
+        &app,
+        "urn:fantasma:acr:stark:government",
+        GOVERNMENT_KEY,
+        [1u8; 32],
+    )
+    .await;
+    let code = query_param(&redirect, "code").expect("no code issued");
+    let id_token = id_token_claims(&app, &code).await;
+    assert_eq!(id_token["acr"], "urn:fantasma:acr:stark:government");
+    let provenance = &id_token["zk_age_claim"]["provenance"];
+    assert_eq!(provenance["method"], "stark");
+    assert_eq!(provenance["verifier_backend"], "stone");
+    assert_eq!(provenance["issuer_trust_level"], 100);
+
+    let redirect = prove_age(
+        &app,
+        "urn:fantasma:acr:stark:trust_framework",
+        FRAMEWORK_KEY,
+        [2u8; 32],
+    )
+    .await;
+    let code = query_param(&redirect, "code").expect("no code issued");
+    let id_token = id_token_claims(&app, &code).await;
+    assert_eq!(id_token["acr"], "urn:fantasma:acr:stark:trust_framework");
+
+    // An issuer nobody trusts attains no tier
+    let redirect = prove_age(&app, "urn:fantasma:acr:stark", &[3u8; 32], [3u8; 32]).await;
+    let code = query_param(&redirect, "code").expect("no code issued");
+    let id_token = id_token_claims(&app, &code).await;
+    assert_eq!(id_token["acr"], "urn:fantasma:acr:stark");
+}
+
+#[tokio::test]
+async fn test_issuer_tiers_are_ordered() {
+    let app = stark_app().await;
+
+    // A government issuer satisfies lower tiers
+    let redirect = prove_age(
+        &app,
+        "urn:fantasma:acr:stark:accredited",
+        GOVERNMENT_KEY,
+        [4u8; 32],
+    )
+    .await;
+    assert!(query_param(&redirect, "code").is_some());
+
+    // A trust framework member falls below accredited and government
+    for (acr_values, nullifier) in [
+        ("urn:fantasma:acr:stark:accredited", [5u8; 32]),
+        ("urn:fantasma:acr:stark:government", [6u8; 32]),
+    ] {
+        let redirect = prove_age(&app, acr_values, FRAMEWORK_KEY, nullifier).await;
+        assert_eq!(
+            query_param(&redirect, "error").as_deref(),
+            Some("access_denied")
+        );
+    }
+
+    // Without a trusted issuer, the tier cannot be met at all
+    let redirect = prove_age(
+        &app,
+        "urn:fantasma:acr:stark:self_declared",
+        &[3u8; 32],
+        [7u8; 32],
+    )
+    .await;
+    assert_eq!(
+        query_param(&redirect, "error").as_deref(),
+        Some("access_denied")
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_acr_values_are_rejected() {
+    let app = TestApp::new().await;
+    let response = app
+        .get(&format!(
+            "/authorize?response_type=code&client_id=demo-client&redirect_uri={}&scope=openid&acr_values={}",
+            REDIRECT_URI,
+            encode("urn:mace:incommon:iap:silver")
+        ))
+        .await;
+    assert_eq!(
+        query_param(&location(&response), "error").as_deref(),
+        Some("invalid_request")
+    );
+}
+
+#[tokio::test]
+async fn test_discovery_advertises_acr_values() {
+    let app = TestApp::new().await;
+
+    let json = body_json(app.get("/.well-known/openid-configuration").await).await;
+    let acr_values = json["acr_values_supported"].as_array().unwrap();
+    assert!(acr_values.contains(&Value::from("urn:fantasma:acr:stark:government")));
+    assert!(json["claims_supported"]
+        .as_array()
+        .unwrap()
+        .contains(&Value::from("acr")));
+}
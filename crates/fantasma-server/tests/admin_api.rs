@@ -120,6 +120,25 @@ async fn test_admin_api() {
         "create client without DB"
     );
 
+    // ── Clients: scope policy → 503 ─────────────────────────
+    let body = serde_json::json!({
+        "allowed_scopes": ["openid", "zk:age:18+"],
+        "downscope_unapproved_scopes": true
+    });
+    let res = app
+        .request_with_headers(
+            "PUT",
+            "/admin/clients/test-new-client/scopes",
+            &[("X-Admin-Key", ADMIN_KEY)],
+            Some(&body),
+        )
+        .await;
+    assert_eq!(
+        res.status(),
+        StatusCode::SERVICE_UNAVAILABLE,
+        "update scope policy without DB"
+    );
+
     // ── Proofs: list → 503 ──────────────────────────────────
     let res = app
         .router()
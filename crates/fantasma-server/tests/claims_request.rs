@@ -0,0 +1,136 @@
+//! Integration tests for the OIDC `claims` request parameter
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+mod common;
+use common::{body_json, location, query_param, TestApp};
+
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+
+fn encode(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+fn consent_form(demo_user: &str, claims: &Value) -> String {
+    format!(
+        "response_type=code&client_id=demo-client&redirect_uri={}&scope=openid&demo_user={}&claims={}&action=approve",
+        REDIRECT_URI,
+        demo_user,
+        encode(&claims.to_string())
+    )
+}
+
+/// Consent and exchange the code, returning the ID token claims
+async fn id_token_claims(app: &TestApp, demo_user: &str, claims: &Value) -> Value {
+    let code = app
+        .authorization_code(&consent_form(demo_user, claims))
+        .await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id=demo-client&client_secret=demo-secret",
+                code, REDIRECT_URI
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let tokens = body_json(response).await;
+    let payload = tokens["id_token"]
+        .as_str()
+        .unwrap()
+        .split('.')
+        .nth(1)
+        .unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_requested_claims_with_parameters() {
+    let app = TestApp::new().await;
+    let claims = json!({
+        "id_token": {
+            "zk_age_claim": {"essential": true, "value": 25},
+            "zk_kyc_claim": {"value": "enhanced", "max_age_seconds": 3600}
+        }
+    });
+
+    // Alice is over 21 and has enhanced KYC
+    let id_token = id_token_claims(&app, "alice", &claims).await;
+    assert_eq!(id_token["zk_age_claim"]["threshold"], 25);
+    assert_eq!(id_token["zk_age_claim"]["verified"], true);
+    assert_eq!(id_token["zk_kyc_claim"]["level"], "Enhanced");
+    assert_eq!(id_token["zk_kyc_claim"]["max_age_seconds"], 3600);
+}
+
+#[tokio::test]
+async fn test_unproven_essential_claim_denies_authorization() {
+    let app = TestApp::new().await;
+    let claims = json!({"id_token": {"zk_age_claim": {"essential": true, "value": 21}}});
+
+    // Bob is under 21
+    let response = app.consent(&consent_form("bob", &claims)).await;
+    let location = location(&response);
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("access_denied")
+    );
+    assert!(query_param(&location, "code").is_none());
+}
+
+#[tokio::test]
+async fn test_unproven_voluntary_claim_is_omitted() {
+    let app = TestApp::new().await;
+    let claims = json!({
+        "id_token": {"zk_age_claim": {"value": 21}},
+        "userinfo": {"zk_kyc_claim": {"value": "basic"}}
+    });
+
+    // Bob is under 21 but has basic KYC
+    let id_token = id_token_claims(&app, "bob", &claims).await;
+    assert!(id_token.get("zk_age_claim").is_none());
+    assert_eq!(id_token["zk_kyc_claim"]["verified"], true);
+}
+
+#[tokio::test]
+async fn test_claims_shown_on_consent_page_and_validated() {
+    let app = TestApp::new().await;
+    let authorize = |claims: &str| {
+        format!(
+            "/authorize?response_type=code&client_id=demo-client&redirect_uri={}&scope=openid&state=xyz&claims={}",
+            REDIRECT_URI,
+            encode(claims)
+        )
+    };
+
+    let response = app
+        .get(&authorize(
+            r#"{"id_token":{"zk_age_claim":{"essential":true,"value":25}}}"#,
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(String::from_utf8_lossy(&body).contains("Age 25 or older"));
+
+    let response = app.get(&authorize("{not json")).await;
+    let location = location(&response);
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("invalid_request")
+    );
+    assert_eq!(query_param(&location, "state").as_deref(), Some("xyz"));
+}
+
+#[tokio::test]
+async fn test_discovery_advertises_claims_parameter() {
+    let app = TestApp::new().await;
+
+    let json = body_json(app.get("/.well-known/openid-configuration").await).await;
+    assert_eq!(json["claims_parameter_supported"], true);
+}
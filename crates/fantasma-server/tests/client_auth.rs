@@ -0,0 +1,177 @@
+//! Integration tests for client authentication at the token endpoint
+
+use axum::http::StatusCode;
+use base64::Engine;
+use fantasma_oidc::client_auth::{
+    Audience, ClientAssertionClaims, TokenEndpointAuthMethod, CLIENT_ASSERTION_TYPE_JWT_BEARER,
+};
+use fantasma_oidc::{jws, JwkSet, SigningKey};
+use fantasma_server::state::ClientInfo;
+
+mod common;
+use common::{body_json, TestApp};
+
+const CONSENT: &str = "response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid&demo_user=alice&action=approve";
+
+fn code_exchange(code: &str, client_id: &str) -> String {
+    format!(
+        "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id={}",
+        code, client_id
+    )
+}
+
+#[tokio::test]
+async fn test_token_requires_client_authentication() {
+    let app = TestApp::new().await;
+
+    // No secret
+    let code = app.authorization_code(CONSENT).await;
+    let response = app
+        .post_form("/token", &code_exchange(&code, "demo-client"))
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(body_json(response).await["error"], "invalid_client");
+
+    // Wrong secret
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "{}&client_secret=wrong",
+                code_exchange(&code, "demo-client")
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Failed authentication does not burn the code
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "{}&client_secret=demo-secret",
+                code_exchange(&code, "demo-client")
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_client_secret_basic() {
+    let app = TestApp::new().await;
+    let code = app.authorization_code(CONSENT).await;
+
+    let credentials = base64::engine::general_purpose::STANDARD.encode("demo-client:demo-secret");
+    let response = app
+        .post_form_with_headers(
+            "/token",
+            &[("authorization", &format!("Basic {}", credentials))],
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback",
+                code
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_code_bound_to_client() {
+    let app = TestApp::new().await;
+    let code = app.authorization_code(CONSENT).await;
+
+    // demo-rp authenticates correctly but the code belongs to demo-client
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "{}&client_secret=demo-secret",
+                code_exchange(&code, "demo-rp")
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_grant");
+}
+
+fn jwt_client(key: &SigningKey) -> ClientInfo {
+    ClientInfo {
+        client_id: "jwt-client".to_string(),
+        client_secret_hash: None,
+        redirect_uris: vec!["http://localhost:8080/callback".to_string()],
+        name: "JWT Client".to_string(),
+        id_token_signed_response_alg: None,
+        token_endpoint_auth_method: TokenEndpointAuthMethod::PrivateKeyJwt,
+        jwks: Some(JwkSet::new(vec![key.public_jwk()])),
+        jwks_uri: None,
+        sector_identifier_uri: None,
+        require_pkce_s256: false,
+        require_pushed_authorization_requests: false,
+        require_signed_request_object: false,
+        post_logout_redirect_uris: Vec::new(),
+        backchannel_logout_uri: None,
+        backchannel_logout_session_required: false,
+        allowed_scopes: None,
+        downscope_unapproved_scopes: false,
+        id_token_encryption: None,
+        userinfo_encryption: None,
+    }
+}
+
+fn client_assertion(key: &SigningKey, aud: &str) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = ClientAssertionClaims {
+        iss: "jwt-client".to_string(),
+        sub: "jwt-client".to_string(),
+        aud: Audience::Single(aud.to_string()),
+        exp: now + 60,
+        iat: Some(now),
+        jti: uuid::Uuid::new_v4().to_string(),
+    };
+    jws::encode(&claims, "JWT", key).unwrap()
+}
+
+#[tokio::test]
+async fn test_private_key_jwt() {
+    let key = SigningKey::generate_ml_dsa_65();
+    let app = TestApp::with_clients(vec![jwt_client(&key)]).await;
+    let consent = CONSENT.replace("demo-client", "jwt-client");
+
+    let assertion = client_assertion(&key, "http://localhost:8080/token");
+    let body = |code: &str, assertion: &str| {
+        format!(
+            "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_assertion_type={}&client_assertion={}",
+            code, CLIENT_ASSERTION_TYPE_JWT_BEARER, assertion
+        )
+    };
+
+    let code = app.authorization_code(&consent).await;
+    let response = app.post_form("/token", &body(&code, &assertion)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The same assertion cannot be replayed
+    let code = app.authorization_code(&consent).await;
+    let response = app.post_form("/token", &body(&code, &assertion)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Assertions signed by another key are rejected
+    let other = client_assertion(
+        &SigningKey::generate_ml_dsa_65(),
+        "http://localhost:8080/token",
+    );
+    let response = app.post_form("/token", &body(&code, &other)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // A secret cannot stand in for the assertion
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "{}&client_secret=demo-secret",
+                code_exchange(&code, "jwt-client")
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
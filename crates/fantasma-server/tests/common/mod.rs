@@ -1,7 +1,18 @@
 //! Test utilities for integration tests
 
-use axum::Router;
+#![allow(dead_code)]
+
+use axum::{
+    body::Body,
+    http::{Request, Response, StatusCode},
+    Router,
+};
+use fantasma_core::proof::ProofRequest;
 use fantasma_oidc::config::OidcConfig;
+use fantasma_server::state::{AppState, ClientInfo};
+use serde_json::Value;
+use std::sync::Arc;
+use tower::ServiceExt;
 
 /// Test application wrapper
 pub struct TestApp {
@@ -11,11 +22,39 @@ pub struct TestApp {
 impl TestApp {
     /// Create a new test application with in-memory storage
     pub async fn new() -> Self {
-        let config = OidcConfig::with_issuer("http://localhost:8080".to_string());
+        Self::with_clients(Vec::new()).await
+    }
+
+    /// Create a test application with extra clients next to the demo clients
+    pub async fn with_clients(clients: Vec<ClientInfo>) -> Self {
+        let mut state = AppState::with_storage(test_config(), None);
+        let registered = Arc::get_mut(&mut state.clients).unwrap();
+        for client in clients {
+            registered.insert(client.client_id.clone(), client);
+        }
+
+        Self::with_state(state)
+    }
+
+    /// Create a test application with a customised configuration
+    pub async fn with_config(configure: impl FnOnce(&mut OidcConfig)) -> Self {
+        let mut config = test_config();
+        configure(&mut config);
 
         // Create state with in-memory storage (no database)
-        let state = fantasma_server::state::AppState::with_storage(config, None);
+        Self::with_state(AppState::with_storage(config, None))
+    }
+
+    /// Create a test application with customised state, e.g. another
+    /// prover backend or trusted issuers
+    pub async fn with_app_state(configure: impl FnOnce(&mut AppState)) -> Self {
+        let mut state = AppState::with_storage(test_config(), None);
+        configure(&mut state);
+
+        Self::with_state(state)
+    }
 
+    fn with_state(state: AppState) -> Self {
         // Build router using the library function
         let router = fantasma_server::create_router(state);
 
@@ -26,4 +65,231 @@ impl TestApp {
     pub fn router(&self) -> Router {
         self.router.clone()
     }
+
+    /// Send a GET request
+    pub async fn get(&self, uri: &str) -> Response<Body> {
+        self.router()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    /// Send a GET request with extra headers
+    pub async fn get_with_headers(&self, uri: &str, headers: &[(&str, &str)]) -> Response<Body> {
+        let mut request = Request::builder().uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        self.router()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    /// Send a form-encoded POST request
+    pub async fn post_form(&self, uri: &str, body: &str) -> Response<Body> {
+        self.router()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    /// Send a form-encoded POST request with extra headers
+    pub async fn post_form_with_headers(
+        &self,
+        uri: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Response<Body> {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/x-www-form-urlencoded");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        self.router()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap()
+    }
+
+    /// Send a JSON POST request with extra headers
+    pub async fn post_json_with_headers(
+        &self,
+        uri: &str,
+        headers: &[(&str, &str)],
+        body: &Value,
+    ) -> Response<Body> {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        self.router()
+            .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap()
+    }
+
+    /// Send a request with any method, extra headers and an optional JSON body
+    pub async fn request_with_headers(
+        &self,
+        method: &str,
+        uri: &str,
+        headers: &[(&str, &str)],
+        body: Option<&Value>,
+    ) -> Response<Body> {
+        let mut request = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let body = match body {
+            Some(body) => {
+                request = request.header("content-type", "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+        self.router()
+            .oneshot(request.body(body).unwrap())
+            .await
+            .unwrap()
+    }
+
+    /// Go through the consent page as a browser would
+    ///
+    /// `form` holds the authorization request parameters together with the
+    /// consent fields (`action`, `demo_user`, `proof_response`).
+    /// The request is sent to `/authorize`, and the consent fields are
+    /// posted with the request ID of the page it shows. Responses other than
+    /// a consent page, such as error redirects, are returned as they are.
+    pub async fn consent(&self, form: &str) -> Response<Body> {
+        self.consent_with_headers(&[], form).await
+    }
+
+    /// Go through the consent page with extra headers on both requests
+    pub async fn consent_with_headers(
+        &self,
+        headers: &[(&str, &str)],
+        form: &str,
+    ) -> Response<Body> {
+        let (consent, query): (Vec<&str>, Vec<&str>) = form.split('&').partition(|pair| {
+            let name = pair.split('=').next().unwrap_or_default();
+            CONSENT_FIELDS.contains(&name)
+        });
+
+        let response = self
+            .get_with_headers(&format!("/authorize?{}", query.join("&")), headers)
+            .await;
+        if response.status() != StatusCode::OK {
+            return response;
+        }
+        let request_id = consent_request_id(&body_text(response).await);
+
+        let mut body = format!("request_id={}", request_id);
+        for pair in consent {
+            body.push('&');
+            body.push_str(pair);
+        }
+        self.post_form_with_headers("/authorize/consent", headers, &body)
+            .await
+    }
+
+    /// Approve consent with the given form body and return the issued code
+    pub async fn authorization_code(&self, consent_form: &str) -> String {
+        let response = self.consent(consent_form).await;
+        let location = location(&response);
+        query_param(&location, "code")
+            .unwrap_or_else(|| panic!("no code in redirect: {}", location))
+    }
+}
+
+/// Fields of the consent form, as opposed to the authorization request
+const CONSENT_FIELDS: &[&str] = &["action", "demo_user", "proof_response"];
+
+/// The request ID a consent page posts back
+pub fn consent_request_id(html: &str) -> String {
+    let marker = r#"name="request_id" value=""#;
+    let start = html
+        .find(marker)
+        .expect("no request_id on the consent page")
+        + marker.len();
+    let end = start + html[start..].find('"').unwrap();
+    html[start..end].to_string()
+}
+
+/// The proof request meant for the wallet on a consent page
+pub fn consent_proof_request(html: &str) -> ProofRequest {
+    let marker = r#"name="proof_request" value=""#;
+    let start = html
+        .find(marker)
+        .expect("no proof request on the consent page")
+        + marker.len();
+    let end = start + html[start..].find('"').unwrap();
+    let json = html[start..end]
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&");
+    serde_json::from_str(&json).unwrap()
+}
+
+/// Configuration shared by all test applications
+///
+/// Demo users are enabled, so that tests can consent without a wallet, and
+/// wallets prove with the mock backend.
+pub fn test_config() -> OidcConfig {
+    let mut config = OidcConfig::with_issuer("http://localhost:8080".to_string());
+    config.demo_users = true;
+    config.mock_proofs = true;
+    config
+}
+
+/// The `Location` header of a redirect
+pub fn location(response: &Response<Body>) -> String {
+    response
+        .headers()
+        .get("location")
+        .expect("missing location header")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Extract a query parameter from a redirect location
+///
+/// Parses the query by hand since redirect URIs such as
+/// `chrome-extension://*/callback` are not valid `url::Url`s.
+pub fn query_param(location: &str, name: &str) -> Option<String> {
+    let query = location.split_once('?')?.1;
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.into_owned())
+}
+
+/// Read a response body as text
+pub async fn body_text(response: Response<Body>) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8_lossy(&body).into_owned()
+}
+
+/// Read a response body as JSON
+pub async fn body_json(response: Response<Body>) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
 }
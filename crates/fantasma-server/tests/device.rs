@@ -0,0 +1,298 @@
+//! Integration tests for the device authorization grant (RFC 8628)
+
+use axum::http::StatusCode;
+use fantasma_core::claim::ClaimType;
+use fantasma_core::proof::{subject_hash, GeneratedProof, ProofRequest, ProofResponse};
+use fantasma_oidc::DEVICE_CODE_GRANT_TYPE;
+use fantasma_stark::backend::{MockBackend, ProverBackendTrait};
+use serde_json::Value;
+
+mod common;
+use common::{body_json, body_text, consent_proof_request, TestApp};
+
+fn encode(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+async fn start(app: &TestApp, client_id: &str, scope: &str) -> (StatusCode, Value) {
+    let response = app
+        .post_form(
+            "/device_authorization",
+            &format!(
+                "client_id={}&client_secret=demo-secret&scope={}",
+                client_id, scope
+            ),
+        )
+        .await;
+    let status = response.status();
+    (status, body_json(response).await)
+}
+
+async fn poll(app: &TestApp, client_id: &str, device_code: &str) -> (StatusCode, Value) {
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type={}&device_code={}&client_id={}&client_secret=demo-secret",
+                DEVICE_CODE_GRANT_TYPE, device_code, client_id
+            ),
+        )
+        .await;
+    let status = response.status();
+    (status, body_json(response).await)
+}
+
+/// Open the verification page for `user_code` and read its proof request
+///
+/// Without a consent form on the page, the request is empty.
+async fn verification_page(app: &TestApp, user_code: &str) -> Option<ProofRequest> {
+    let page = body_text(app.get(&format!("/device?user_code={}", user_code)).await).await;
+    page.contains(r#"name="proof_request""#)
+        .then(|| consent_proof_request(&page))
+}
+
+/// Consent on the verification page as a demo user
+async fn consent(app: &TestApp, user_code: &str, action: &str) -> StatusCode {
+    let request_id = verification_page(app, user_code)
+        .await
+        .map(|request| request.request_id)
+        .unwrap_or_default();
+    app.post_form(
+        "/device/consent",
+        &format!(
+            "action={}&user_code={}&request_id={}&demo_user=alice",
+            action, user_code, request_id
+        ),
+    )
+    .await
+    .status()
+}
+
+/// Answer the page's proof request with a mock age proof, as a wallet would
+fn wallet_response(request: &ProofRequest, threshold: u8) -> ProofResponse {
+    let subject_id = fantasma_crypto::pairwise_subject(&[42u8; 32], &request.verifier_domain);
+    let claim_type = ClaimType::AgeAtLeast { threshold };
+    let circuit_id = claim_type.circuit_id().to_string();
+    let nullifier = [7u8; 32];
+    let public_inputs = claim_type
+        .public_inputs()
+        .iter()
+        .chain([
+            &request.verifier_domain_hash(),
+            &request.nonce_hash(),
+            &nullifier,
+            &subject_hash(&subject_id),
+        ])
+        .map(hex::encode)
+        .collect::<Vec<_>>();
+    let result = MockBackend::new()
+        .prove(&circuit_id, &[], &public_inputs)
+        .unwrap();
+
+    ProofResponse {
+        request_id: request.request_id.clone(),
+        proofs: vec![GeneratedProof {
+            claim_type,
+            proof_bytes: result.proof_bytes,
+            public_inputs: result.public_inputs,
+            circuit_id,
+            nullifier,
+            generated_at: chrono::Utc::now(),
+        }],
+        proof_refs: vec![],
+        subject_id,
+        generated_at: chrono::Utc::now(),
+    }
+}
+
+/// Post the wallet's answer on the verification page
+async fn wallet_consent(app: &TestApp, user_code: &str, response: &ProofResponse) -> StatusCode {
+    app.post_form(
+        "/device/consent",
+        &format!(
+            "action=approve&user_code={}&request_id={}&proof_response={}",
+            user_code,
+            encode(&response.request_id),
+            encode(&serde_json::to_string(response).unwrap())
+        ),
+    )
+    .await
+    .status()
+}
+
+fn claims(token: &str) -> Value {
+    let payload = token.split('.').nth(1).unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_device_flow() {
+    let app = TestApp::new().await;
+
+    let (status, device) = start(&app, "demo-rp", "openid%20zk:age:18%2B").await;
+    assert_eq!(status, StatusCode::OK);
+    let device_code = device["device_code"].as_str().unwrap();
+    let user_code = device["user_code"].as_str().unwrap();
+    assert_eq!(device["verification_uri"], "http://localhost:8080/device");
+    assert_eq!(device["interval"], 5);
+
+    // The user has not been to the verification page yet
+    let (status, error) = poll(&app, "demo-rp", device_code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "authorization_pending");
+
+    // Polling again straight away is too fast
+    let (_, error) = poll(&app, "demo-rp", device_code).await;
+    assert_eq!(error["error"], "slow_down");
+
+    // The verification page shows the consent form for the device
+    let complete = device["verification_uri_complete"].as_str().unwrap();
+    let response = app
+        .get(complete.strip_prefix("http://localhost:8080").unwrap())
+        .await;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let page = String::from_utf8_lossy(&body);
+    assert!(page.contains("/device/consent"));
+    assert!(page.contains("Demo Relying Party"));
+
+    // User codes are accepted however the user typed them
+    let typed = user_code.to_lowercase().replace('-', "%20");
+    assert_eq!(consent(&app, &typed, "allow").await, StatusCode::OK);
+
+    let (status, tokens) = poll(&app, "demo-rp", device_code).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(tokens["refresh_token"].is_string());
+    let id_token = claims(tokens["id_token"].as_str().unwrap());
+    assert_eq!(id_token["aud"], "demo-rp");
+    assert!(id_token["sid"].is_string());
+
+    // The device code is redeemed once
+    let (_, error) = poll(&app, "demo-rp", device_code).await;
+    assert_eq!(error["error"], "invalid_grant");
+}
+
+#[tokio::test]
+async fn test_denied_device_authorization() {
+    let app = TestApp::new().await;
+
+    let (_, device) = start(&app, "demo-rp", "openid").await;
+    let device_code = device["device_code"].as_str().unwrap();
+    let user_code = device["user_code"].as_str().unwrap();
+
+    assert_eq!(consent(&app, user_code, "deny").await, StatusCode::OK);
+    let (_, error) = poll(&app, "demo-rp", device_code).await;
+    assert_eq!(error["error"], "access_denied");
+
+    // The user code cannot be used again
+    assert_eq!(
+        consent(&app, user_code, "allow").await,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn test_demo_users_are_off_by_default() {
+    let app = TestApp::with_config(|config| config.demo_users = false).await;
+
+    let (_, device) = start(&app, "demo-rp", "openid%20zk:age:18%2B").await;
+    let device_code = device["device_code"].as_str().unwrap();
+    let user_code = device["user_code"].as_str().unwrap();
+
+    // Nobody can stand in for a wallet
+    let response = app.get(&format!("/device?user_code={}", user_code)).await;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&body).contains("Select Test User"));
+    assert_eq!(
+        consent(&app, user_code, "allow").await,
+        StatusCode::BAD_REQUEST
+    );
+    let (_, error) = poll(&app, "demo-rp", device_code).await;
+    assert_eq!(error["error"], "authorization_pending");
+}
+
+#[tokio::test]
+async fn test_wallet_connects_device() {
+    let app = TestApp::with_config(|config| config.demo_users = false).await;
+
+    let (_, device) = start(&app, "demo-rp", "openid%20zk:age:18%2B").await;
+    let device_code = device["device_code"].as_str().unwrap();
+    let user_code = device["user_code"].as_str().unwrap();
+
+    let request = verification_page(&app, user_code)
+        .await
+        .expect("no proof request on the verification page");
+    assert_eq!(request.requested_claims.len(), 1);
+
+    // Proofs for other claims than requested are refused
+    let response = wallet_response(&request, 21);
+    assert_eq!(
+        wallet_consent(&app, user_code, &response).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    let request = verification_page(&app, user_code).await.unwrap();
+    let response = wallet_response(&request, 18);
+    assert_eq!(
+        wallet_consent(&app, user_code, &response).await,
+        StatusCode::OK
+    );
+
+    let (status, tokens) = poll(&app, "demo-rp", device_code).await;
+    assert_eq!(status, StatusCode::OK);
+    let id_token = claims(tokens["id_token"].as_str().unwrap());
+    assert_eq!(id_token["sub"], response.subject_id);
+    assert_eq!(id_token["zk_age_claim"]["verified"], true);
+    assert!(id_token["zk_age_claim"]["proof_ref"].is_object());
+}
+
+#[tokio::test]
+async fn test_device_code_is_bound_to_client() {
+    let app = TestApp::new().await;
+
+    let (_, device) = start(&app, "demo-rp", "openid").await;
+    let device_code = device["device_code"].as_str().unwrap();
+
+    let (status, error) = poll(&app, "demo-pq-rp", device_code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+}
+
+#[tokio::test]
+async fn test_expired_device_code() {
+    let app = TestApp::with_config(|config| config.device_code_expiration_seconds = 0).await;
+
+    let (_, device) = start(&app, "demo-rp", "openid").await;
+    let (_, error) = poll(&app, "demo-rp", device["device_code"].as_str().unwrap()).await;
+    assert_eq!(error["error"], "expired_token");
+}
+
+#[tokio::test]
+async fn test_client_without_single_sector_is_rejected() {
+    let app = TestApp::new().await;
+
+    // demo-client's redirect URIs span two hosts
+    let (status, error) = start(&app, "demo-client", "openid").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "unauthorized_client");
+}
+
+#[tokio::test]
+async fn test_discovery_advertises_device_flow() {
+    let app = TestApp::new().await;
+
+    let json = body_json(app.get("/.well-known/openid-configuration").await).await;
+    assert_eq!(
+        json["device_authorization_endpoint"],
+        "http://localhost:8080/device_authorization"
+    );
+    assert!(json["grant_types_supported"]
+        .as_array()
+        .unwrap()
+        .contains(&Value::from(DEVICE_CODE_GRANT_TYPE)));
+}
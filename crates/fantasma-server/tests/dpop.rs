@@ -0,0 +1,281 @@
+//! Integration tests for DPoP sender-constrained tokens (RFC 9449)
+
+use axum::body::Body;
+use axum::http::{header, Response, StatusCode};
+use fantasma_oidc::dpop::{create_proof, DPOP_NONCE_HEADER};
+use fantasma_oidc::SigningKey;
+use serde_json::Value;
+
+mod common;
+use common::{body_json, TestApp};
+
+const TOKEN_URL: &str = "http://localhost:8080/token";
+const USERINFO_URL: &str = "http://localhost:8080/userinfo";
+
+// RFC 7636 Appendix B
+const PKCE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+const PKCE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+fn dpop_nonce(response: &Response<Body>) -> String {
+    response.headers()[DPOP_NONCE_HEADER]
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Get a nonce the way a client does: a first request answered with
+/// `use_dpop_nonce`
+async fn first_nonce(app: &TestApp, key: &SigningKey) -> String {
+    let proof = create_proof(key, "POST", TOKEN_URL, None, None).unwrap();
+    let response = app
+        .post_form_with_headers(
+            "/token",
+            &[("DPoP", &proof)],
+            "grant_type=refresh_token&refresh_token=none&client_id=fantasma-wallet",
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let nonce = dpop_nonce(&response);
+    assert_eq!(body_json(response).await["error"], "use_dpop_nonce");
+    nonce
+}
+
+async fn wallet_code(app: &TestApp) -> String {
+    app.authorization_code(&format!(
+        "response_type=code&client_id=fantasma-wallet&redirect_uri=chrome-extension://*/callback&scope=openid&demo_user=alice&action=approve&code_challenge={}&code_challenge_method=S256",
+        PKCE_CHALLENGE
+    ))
+    .await
+}
+
+async fn dpop_token_request(
+    app: &TestApp,
+    key: &SigningKey,
+    nonce: &str,
+    form: &str,
+) -> Response<Body> {
+    let proof = create_proof(key, "POST", TOKEN_URL, None, Some(nonce)).unwrap();
+    app.post_form_with_headers("/token", &[("DPoP", &proof)], form)
+        .await
+}
+
+/// Wallet tokens bound to `key`, with the nonce for the next proof
+async fn wallet_tokens(app: &TestApp, key: &SigningKey) -> (Value, String) {
+    let nonce = first_nonce(app, key).await;
+    let code = wallet_code(app).await;
+    let response = dpop_token_request(
+        app,
+        key,
+        &nonce,
+        &format!(
+            "grant_type=authorization_code&code={}&redirect_uri=chrome-extension://*/callback&client_id=fantasma-wallet&code_verifier={}",
+            code, PKCE_VERIFIER
+        ),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let nonce = dpop_nonce(&response);
+    (body_json(response).await, nonce)
+}
+
+async fn userinfo(app: &TestApp, access_token: &str, proof: Option<&str>) -> Response<Body> {
+    let authorization = format!("DPoP {}", access_token);
+    let mut headers = vec![("Authorization", authorization.as_str())];
+    if let Some(proof) = proof {
+        headers.push(("DPoP", proof));
+    }
+    app.get_with_headers("/userinfo", &headers).await
+}
+
+#[tokio::test]
+async fn test_tokens_bound_to_proof_key() {
+    let app = TestApp::new().await;
+    let key = SigningKey::generate_ed25519();
+    let (tokens, nonce) = wallet_tokens(&app, &key).await;
+    assert_eq!(tokens["token_type"], "DPoP");
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    // The key holder can use the token
+    let proof = create_proof(&key, "GET", USERINFO_URL, Some(access_token), Some(&nonce)).unwrap();
+    let response = userinfo(&app, access_token, Some(&proof)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_json(response).await["sub"].is_string());
+
+    // The same proof cannot be replayed
+    let response = userinfo(&app, access_token, Some(&proof)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(body_json(response).await["error"], "invalid_dpop_proof");
+
+    // A stolen token is useless without the key
+    let thief = SigningKey::generate_ed25519();
+    let proof = create_proof(
+        &thief,
+        "GET",
+        USERINFO_URL,
+        Some(access_token),
+        Some(&nonce),
+    )
+    .unwrap();
+    let response = userinfo(&app, access_token, Some(&proof)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers()[header::WWW_AUTHENTICATE]
+        .to_str()
+        .unwrap()
+        .starts_with(r#"DPoP algs="EdDSA ML-DSA-65", "#));
+    assert_eq!(body_json(response).await["error"], "invalid_dpop_proof");
+
+    let response = app
+        .get_with_headers(
+            "/userinfo",
+            &[("Authorization", &format!("Bearer {}", access_token))],
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(body_json(response).await["error"], "invalid_token");
+}
+
+#[tokio::test]
+async fn test_userinfo_requires_server_nonce() {
+    let app = TestApp::new().await;
+    let key = SigningKey::generate_ml_dsa_65();
+    let (tokens, _) = wallet_tokens(&app, &key).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    let proof = create_proof(
+        &key,
+        "GET",
+        USERINFO_URL,
+        Some(access_token),
+        Some("made-up"),
+    )
+    .unwrap();
+    let response = userinfo(&app, access_token, Some(&proof)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let nonce = dpop_nonce(&response);
+    assert_eq!(body_json(response).await["error"], "use_dpop_nonce");
+
+    let proof = create_proof(&key, "GET", USERINFO_URL, Some(access_token), Some(&nonce)).unwrap();
+    assert_eq!(
+        userinfo(&app, access_token, Some(&proof)).await.status(),
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn test_public_client_refresh_token_bound() {
+    let app = TestApp::new().await;
+    let key = SigningKey::generate_ed25519();
+    let (tokens, nonce) = wallet_tokens(&app, &key).await;
+    let refresh_form = format!(
+        "grant_type=refresh_token&refresh_token={}&client_id=fantasma-wallet",
+        tokens["refresh_token"].as_str().unwrap()
+    );
+
+    // Without a proof, or with another key, the token is not redeemed
+    let response = app.post_form("/token", &refresh_form).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_grant");
+
+    let thief = SigningKey::generate_ed25519();
+    let response = dpop_token_request(&app, &thief, &nonce, &refresh_form).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_grant");
+
+    // ... and is still good for its key holder
+    let response = dpop_token_request(&app, &key, &nonce, &refresh_form).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let refreshed = body_json(response).await;
+    assert_eq!(refreshed["token_type"], "DPoP");
+    assert_ne!(refreshed["refresh_token"], tokens["refresh_token"]);
+}
+
+#[tokio::test]
+async fn test_confidential_client_binding() {
+    let app = TestApp::new().await;
+    let key = SigningKey::generate_ed25519();
+    let nonce = first_nonce(&app, &key).await;
+
+    let code = app
+        .authorization_code(
+            "response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid&demo_user=alice&action=approve",
+        )
+        .await;
+    let response = dpop_token_request(
+        &app,
+        &key,
+        &nonce,
+        &format!(
+            "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-client&client_secret=demo-secret",
+            code
+        ),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = body_json(response).await;
+    assert_eq!(tokens["token_type"], "DPoP");
+
+    // Introspection reports the access token's key; the refresh token of a
+    // confidential client stays unbound
+    let introspect = |token: &str| {
+        format!(
+            "token={}&client_id=demo-client&client_secret=demo-secret",
+            token
+        )
+    };
+    let response = app
+        .post_form(
+            "/introspect",
+            &introspect(tokens["access_token"].as_str().unwrap()),
+        )
+        .await;
+    let json = body_json(response).await;
+    assert_eq!(json["token_type"], "DPoP");
+    assert_eq!(json["cnf"]["jkt"], key.public_jwk().thumbprint());
+
+    let response = app
+        .post_form(
+            "/introspect",
+            &introspect(tokens["refresh_token"].as_str().unwrap()),
+        )
+        .await;
+    assert!(body_json(response).await.get("cnf").is_none());
+}
+
+#[tokio::test]
+async fn test_bearer_tokens_without_proof() {
+    let app = TestApp::new().await;
+    let code = app
+        .authorization_code(
+            "response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid&demo_user=alice&action=approve",
+        )
+        .await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-client&client_secret=demo-secret",
+                code
+            ),
+        )
+        .await;
+    assert!(response.headers().get(DPOP_NONCE_HEADER).is_none());
+    let tokens = body_json(response).await;
+    assert_eq!(tokens["token_type"], "Bearer");
+
+    // An unbound token cannot be passed off as a DPoP token
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let response = userinfo(&app, access_token, None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(body_json(response).await["error"], "invalid_token");
+}
+
+#[tokio::test]
+async fn test_discovery_advertises_dpop() {
+    let app = TestApp::new().await;
+    let json = body_json(app.get("/.well-known/openid-configuration").await).await;
+    let algs = json["dpop_signing_alg_values_supported"]
+        .as_array()
+        .unwrap();
+    assert!(algs.contains(&Value::from("EdDSA")));
+    assert!(algs.contains(&Value::from("ML-DSA-65")));
+}
@@ -0,0 +1,192 @@
+//! Integration tests for encrypted ID tokens and userinfo responses
+
+use axum::http::{header, StatusCode};
+use fantasma_oidc::jwe::{self, DecryptionKey, JweAlgorithm, JweEncryption, JweParams};
+use fantasma_oidc::{jws, IdToken, JwkSet, TokenEndpointAuthMethod};
+use fantasma_server::state::ClientInfo;
+use serde_json::Value;
+
+mod common;
+use common::{body_json, TestApp};
+
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+const ISSUER: &str = "http://localhost:8080";
+
+fn encrypting_client(
+    client_id: &str,
+    key: &DecryptionKey,
+    id_token_encryption: Option<JweParams>,
+    userinfo_encryption: Option<JweParams>,
+) -> ClientInfo {
+    ClientInfo {
+        client_id: client_id.to_string(),
+        client_secret_hash: Some(fantasma_crypto::hash_secret("rp-secret").unwrap()),
+        redirect_uris: vec![REDIRECT_URI.to_string()],
+        name: "Encrypting RP".to_string(),
+        id_token_signed_response_alg: None,
+        token_endpoint_auth_method: TokenEndpointAuthMethod::ClientSecretPost,
+        jwks: Some(JwkSet::new(vec![key.public_jwk()])),
+        jwks_uri: None,
+        sector_identifier_uri: None,
+        require_pkce_s256: false,
+        require_pushed_authorization_requests: false,
+        require_signed_request_object: false,
+        post_logout_redirect_uris: Vec::new(),
+        backchannel_logout_uri: None,
+        backchannel_logout_session_required: false,
+        allowed_scopes: None,
+        downscope_unapproved_scopes: false,
+        id_token_encryption,
+        userinfo_encryption,
+    }
+}
+
+async fn tokens(app: &TestApp, client_id: &str) -> Value {
+    let code = app
+        .authorization_code(&format!(
+            "response_type=code&client_id={}&redirect_uri={}&scope=openid%20zk:age:21%2B&demo_user=alice&action=approve",
+            client_id, REDIRECT_URI
+        ))
+        .await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id={}&client_secret=rp-secret",
+                code, REDIRECT_URI, client_id
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await
+}
+
+async fn jwks(app: &TestApp) -> JwkSet {
+    serde_json::from_value(body_json(app.get("/.well-known/jwks.json").await).await).unwrap()
+}
+
+#[tokio::test]
+async fn test_id_token_encrypted_with_ml_kem() {
+    let key = DecryptionKey::generate_ml_kem_768();
+    let params = JweParams {
+        alg: JweAlgorithm::MlKem768,
+        enc: JweEncryption::A256Gcm,
+    };
+    let app =
+        TestApp::with_clients(vec![encrypting_client("pq-rp", &key, Some(params), None)]).await;
+
+    let tokens = tokens(&app, "pq-rp").await;
+    let id_token = tokens["id_token"].as_str().unwrap();
+    assert_eq!(id_token.split('.').count(), 5);
+
+    let header = jwe::decode_header(id_token).unwrap();
+    assert_eq!(header.alg, JweAlgorithm::MlKem768);
+    assert_eq!(header.enc, JweEncryption::A256Gcm);
+    assert_eq!(header.cty.as_deref(), Some("JWT"));
+    assert_eq!(header.kid, key.public_jwk().kid);
+
+    // The plaintext is the signed ID token
+    let inner = String::from_utf8(key.decrypt(id_token).unwrap()).unwrap();
+    let claims = IdToken::verify(&inner, &jwks(&app).await, ISSUER, "pq-rp").unwrap();
+    assert!(claims.zk_claims.zk_age_claim.is_some());
+
+    // Only the registered key can decrypt it
+    assert!(DecryptionKey::generate_ml_kem_768()
+        .decrypt(id_token)
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_userinfo_encrypted_with_ecdh_es() {
+    let key = DecryptionKey::generate_x25519();
+    let params = JweParams {
+        alg: JweAlgorithm::EcdhEs,
+        enc: JweEncryption::A128CbcHs256,
+    };
+    let app = TestApp::with_clients(vec![encrypting_client(
+        "ecdh-rp",
+        &key,
+        Some(params),
+        Some(params),
+    )])
+    .await;
+
+    let tokens = tokens(&app, "ecdh-rp").await;
+    let inner =
+        String::from_utf8(key.decrypt(tokens["id_token"].as_str().unwrap()).unwrap()).unwrap();
+    let id_claims = IdToken::verify(&inner, &jwks(&app).await, ISSUER, "ecdh-rp").unwrap();
+
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let response = app
+        .get_with_headers(
+            "/userinfo",
+            &[("Authorization", &format!("Bearer {}", access_token))],
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/jwt");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let jwe = String::from_utf8(body.to_vec()).unwrap();
+    assert_eq!(jwe::decode_header(&jwe).unwrap().alg, JweAlgorithm::EcdhEs);
+
+    let inner = String::from_utf8(key.decrypt(&jwe).unwrap()).unwrap();
+    let (_, userinfo): (_, Value) = jws::verify(&inner, &jwks(&app).await).unwrap();
+    assert_eq!(userinfo["iss"], ISSUER);
+    assert_eq!(userinfo["aud"], "ecdh-rp");
+    assert_eq!(userinfo["sub"], id_claims.sub.as_str());
+    assert!(userinfo["zk_age_claim"].is_object());
+}
+
+#[tokio::test]
+async fn test_unencrypted_clients_get_plain_responses() {
+    let app = TestApp::new().await;
+    let code = app
+        .authorization_code(&format!(
+            "response_type=code&client_id=demo-client&redirect_uri={}&scope=openid&demo_user=alice&action=approve",
+            REDIRECT_URI
+        ))
+        .await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id=demo-client&client_secret=demo-secret",
+                code, REDIRECT_URI
+            ),
+        )
+        .await;
+    let tokens = body_json(response).await;
+    assert_eq!(tokens["id_token"].as_str().unwrap().split('.').count(), 3);
+
+    let response = app
+        .get_with_headers(
+            "/userinfo",
+            &[(
+                "Authorization",
+                &format!("Bearer {}", tokens["access_token"].as_str().unwrap()),
+            )],
+        )
+        .await;
+    assert!(body_json(response).await["sub"].is_string());
+}
+
+#[tokio::test]
+async fn test_discovery_advertises_encryption() {
+    let app = TestApp::new().await;
+    let json = body_json(app.get("/.well-known/openid-configuration").await).await;
+
+    for name in [
+        "id_token_encryption_alg_values_supported",
+        "userinfo_encryption_alg_values_supported",
+    ] {
+        let algs = json[name].as_array().unwrap();
+        assert!(algs.contains(&Value::from("ECDH-ES")));
+        assert!(algs.contains(&Value::from("ML-KEM-768")));
+    }
+    assert!(json["id_token_encryption_enc_values_supported"]
+        .as_array()
+        .unwrap()
+        .contains(&Value::from("A128CBC-HS256")));
+}
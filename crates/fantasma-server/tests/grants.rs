@@ -0,0 +1,247 @@
+//! Integration tests for persisted consent and grant management
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+mod common;
+use common::{body_json, location, query_param, TestApp};
+
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+const AGE_SCOPE: &str = "openid+zk%3Aage%3A18%2B";
+
+/// Consent to `scope` for the demo client as a demo user, returning the
+/// code and the session cookie
+async fn consent(app: &TestApp, scope: &str, demo_user: &str) -> (String, String) {
+    let body = format!(
+        "response_type=code&client_id=demo-client&redirect_uri={}&scope={}&demo_user={}&action=approve",
+        REDIRECT_URI, scope, demo_user
+    );
+    let response = app.consent(&body).await;
+
+    let set_cookie = response.headers()["set-cookie"].to_str().unwrap();
+    let session = set_cookie.split(';').next().unwrap().to_string();
+    let code = query_param(&location(&response), "code").unwrap();
+    (code, session)
+}
+
+async fn authorize(app: &TestApp, query: &str, cookie: &str) -> axum::response::Response {
+    app.get_with_headers(
+        &format!(
+            "/authorize?response_type=code&client_id=demo-client&redirect_uri={}&{}",
+            REDIRECT_URI, query
+        ),
+        &[("cookie", cookie)],
+    )
+    .await
+}
+
+async fn exchange(app: &TestApp, code: &str) -> Value {
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id=demo-client&client_secret=demo-secret",
+                code, REDIRECT_URI
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await
+}
+
+async fn refresh_status(app: &TestApp, tokens: &Value) -> StatusCode {
+    app.post_form(
+        "/token",
+        &format!(
+            "grant_type=refresh_token&refresh_token={}&client_id=demo-client&client_secret=demo-secret",
+            tokens["refresh_token"].as_str().unwrap()
+        ),
+    )
+    .await
+    .status()
+}
+
+fn claims(token: &str) -> Value {
+    let payload = token.split('.').nth(1).unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn grants(app: &TestApp, cookie: &str) -> Value {
+    let response = app.get_with_headers("/grants", &[("cookie", cookie)]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await
+}
+
+#[tokio::test]
+async fn test_reauthorization_within_grant_skips_consent() {
+    let app = TestApp::new().await;
+    let (_, session) = consent(&app, AGE_SCOPE, "alice").await;
+
+    // The same scopes, and a subset of them, are granted straight away
+    for scope in [AGE_SCOPE, "openid"] {
+        let response = authorize(&app, &format!("scope={}&state=again", scope), &session).await;
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        let location = location(&response);
+        assert_eq!(query_param(&location, "state").as_deref(), Some("again"));
+
+        // The ID token carries the granted claims for the requested scopes only
+        let tokens = exchange(&app, &query_param(&location, "code").unwrap()).await;
+        let id_token = claims(tokens["id_token"].as_str().unwrap());
+        assert_eq!(
+            id_token["zk_age_claim"].is_object(),
+            scope == AGE_SCOPE,
+            "{}",
+            id_token
+        );
+    }
+
+    // New scopes, a forced prompt and another browser all show the page
+    let response = authorize(&app, "scope=openid+zk%3Akyc%3Abasic", &session).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = authorize(&app, "scope=openid&prompt=consent", &session).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = authorize(&app, "scope=openid", "fantasma_session=other").await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_grant_is_limited_to_its_sector() {
+    let app = TestApp::new().await;
+    let (_, session) = consent(&app, AGE_SCOPE, "alice").await;
+
+    // demo-client's other redirect URI is in another sector, where the
+    // granted subject is not the user's
+    let response = app
+        .get_with_headers(
+            &format!(
+                "/authorize?response_type=code&client_id=demo-client&redirect_uri={}&scope=openid",
+                "https://oauth.pstmn.io/v1/callback"
+            ),
+            &[("cookie", &session)],
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_grants_list_the_claims_clients_hold() {
+    let app = TestApp::new().await;
+    let (_, session) = consent(&app, AGE_SCOPE, "alice").await;
+
+    let grants = grants(&app, &session).await;
+    let grants = grants.as_array().unwrap();
+    assert_eq!(grants.len(), 1);
+    assert_eq!(grants[0]["client_id"], "demo-client");
+    assert_eq!(grants[0]["scopes"], json!(["openid", "zk:age:18+"]));
+    assert_eq!(grants[0]["zk_claims"]["zk_age_claim"]["threshold"], 18);
+
+    let grant_id = grants[0]["grant_id"].as_str().unwrap();
+    let response = app
+        .get_with_headers(&format!("/grants/{}", grant_id), &[("cookie", &session)])
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Grants are only shown to the session that made them
+    let response = app.get("/grants").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let (_, other_session) = consent(&app, "openid", "bob").await;
+    let response = app
+        .get_with_headers(
+            &format!("/grants/{}", grant_id),
+            &[("cookie", &other_session)],
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_narrowing_a_grant_revokes_tokens() {
+    let app = TestApp::new().await;
+    let (code, session) = consent(&app, AGE_SCOPE, "alice").await;
+    let tokens = exchange(&app, &code).await;
+    let grant_id = grants(&app, &session).await[0]["grant_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let uri = format!("/grants/{}", grant_id);
+
+    // Scopes can be removed but not added
+    let response = app
+        .request_with_headers(
+            "PATCH",
+            &uri,
+            &[("cookie", &session)],
+            Some(&json!({ "scopes": ["openid", "zk:kyc:basic"] })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_scope");
+
+    let response = app
+        .request_with_headers(
+            "PATCH",
+            &uri,
+            &[("cookie", &session)],
+            Some(&json!({ "scopes": ["openid"] })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let grant = body_json(response).await;
+    assert_eq!(grant["scopes"], json!(["openid"]));
+    assert!(grant["zk_claims"]["zk_age_claim"].is_null());
+
+    // The client's tokens carried the age claim
+    assert_eq!(refresh_status(&app, &tokens).await, StatusCode::BAD_REQUEST);
+
+    // Asking for the age claim again takes a new consent
+    let response = authorize(&app, &format!("scope={}", AGE_SCOPE), &session).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_revoking_a_grant_revokes_tokens() {
+    let app = TestApp::new().await;
+    let (code, session) = consent(&app, AGE_SCOPE, "alice").await;
+    let tokens = exchange(&app, &code).await;
+    assert_eq!(refresh_status(&app, &tokens).await, StatusCode::OK);
+
+    // A later authorization within the grant
+    let response = authorize(&app, "scope=openid", &session).await;
+    let code = query_param(&location(&response), "code").unwrap();
+    let later_tokens = exchange(&app, &code).await;
+
+    let grant_id = grants(&app, &session).await[0]["grant_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let response = app
+        .request_with_headers(
+            "DELETE",
+            &format!("/grants/{}", grant_id),
+            &[("cookie", &session)],
+            None,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    assert_eq!(
+        refresh_status(&app, &later_tokens).await,
+        StatusCode::BAD_REQUEST
+    );
+    let response = app
+        .get_with_headers(
+            "/userinfo",
+            &[(
+                "authorization",
+                &format!("Bearer {}", later_tokens["access_token"].as_str().unwrap()),
+            )],
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    assert!(grants(&app, &session).await.as_array().unwrap().is_empty());
+    let response = authorize(&app, "scope=openid", &session).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,210 @@
+//! Integration tests for hybrid Ed25519 + ML-DSA-65 signatures
+
+use axum::http::StatusCode;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use fantasma_core::credential::{Credential, SignatureAlgorithm};
+use fantasma_core::issuer::{IssuerId, IssuerInfo, TrustAnchor};
+use fantasma_oidc::client_auth::Audience;
+use fantasma_oidc::jws::{self, JwsHeader};
+use fantasma_oidc::openid4vci::{ProofJwtClaims, PROOF_JWT_TYPE};
+use fantasma_oidc::{HybridPolicy, IdToken, JwkSet, SigningKey};
+use serde_json::json;
+
+mod common;
+use common::{body_json, TestApp};
+
+const ADMIN_KEY: &str = "test-admin-key-for-hybrid-signatures";
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+const ISSUER: &str = "http://localhost:8080";
+
+async fn hybrid_app() -> TestApp {
+    TestApp::with_config(|config| config.hybrid_signatures = true).await
+}
+
+async fn id_token(app: &TestApp, client_id: &str) -> String {
+    let code = app
+        .authorization_code(&format!(
+            "response_type=code&client_id={}&redirect_uri={}&scope=openid&demo_user=alice&action=approve",
+            client_id, REDIRECT_URI
+        ))
+        .await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id={}&client_secret=demo-secret",
+                code, REDIRECT_URI, client_id
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await["id_token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn jwks(app: &TestApp) -> JwkSet {
+    serde_json::from_value(body_json(app.get("/.well-known/jwks.json").await).await).unwrap()
+}
+
+#[tokio::test]
+async fn test_id_tokens_carry_both_signatures() {
+    let app = hybrid_app().await;
+    let token = id_token(&app, "demo-client").await;
+    let jwks = jwks(&app).await;
+
+    let header = jws::decode_header(&token).unwrap();
+    assert_eq!(header.alg, "EdDSA");
+    let pq_signature = header.pq_signature.unwrap();
+    assert_eq!(pq_signature.alg, "ML-DSA-65");
+    assert!(jwks.find(&pq_signature.kid).is_some());
+
+    // EdDSA-only relying parties verify it as before
+    assert!(IdToken::verify(&token, &jwks, ISSUER, "demo-client").is_ok());
+    for policy in [HybridPolicy::RequireBoth, HybridPolicy::AcceptEither] {
+        assert!(IdToken::verify_hybrid(&token, &jwks, ISSUER, "demo-client", policy).is_ok());
+    }
+
+    // Clients signing with ML-DSA-65 alone get no second signature
+    let token = id_token(&app, "demo-pq-rp").await;
+    let header = jws::decode_header(&token).unwrap();
+    assert_eq!(header.alg, "ML-DSA-65");
+    assert!(header.pq_signature.is_none());
+}
+
+#[tokio::test]
+async fn test_hybrid_mode_is_off_by_default() {
+    let app = TestApp::new().await;
+    let token = id_token(&app, "demo-client").await;
+    let jwks = jwks(&app).await;
+
+    assert!(jws::decode_header(&token).unwrap().pq_signature.is_none());
+    assert!(matches!(
+        IdToken::verify_hybrid(
+            &token,
+            &jwks,
+            ISSUER,
+            "demo-client",
+            HybridPolicy::RequireBoth
+        ),
+        Err(fantasma_oidc::token::TokenError::InvalidSignature(_))
+    ));
+    assert!(IdToken::verify_hybrid(
+        &token,
+        &jwks,
+        ISSUER,
+        "demo-client",
+        HybridPolicy::AcceptEither
+    )
+    .is_ok());
+}
+
+#[tokio::test]
+async fn test_credentials_carry_composite_signatures() {
+    std::env::set_var("FANTASMA_ADMIN_KEY", ADMIN_KEY);
+    let app = hybrid_app().await;
+
+    let metadata = body_json(app.get("/.well-known/openid-credential-issuer").await).await;
+    assert_eq!(
+        metadata["credential_configurations_supported"]["fantasma_identity_v1"]
+            ["credential_signing_alg_values_supported"][0],
+        "HybridEd25519MlDsa65"
+    );
+
+    // Offer, redeem and request a credential as a wallet would
+    let offer = body_json(
+        app.post_json_with_headers(
+            "/admin/credential-offers",
+            &[("X-Admin-Key", ADMIN_KEY)],
+            &json!({
+                "credential": {
+                    "Identity": {
+                        "birthdate": "2000-01-01",
+                        "identity_hash": vec![0u8; 32]
+                    }
+                }
+            }),
+        )
+        .await,
+    )
+    .await;
+    let code = offer["credential_offer"]["grants"]
+        ["urn:ietf:params:oauth:grant-type:pre-authorized_code"]["pre-authorized_code"]
+        .as_str()
+        .unwrap();
+    let tokens = body_json(
+        app.post_form(
+            "/token",
+            &format!(
+                "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Apre-authorized_code&pre-authorized_code={}",
+                code
+            ),
+        )
+        .await,
+    )
+    .await;
+
+    let holder = SigningKey::generate_ed25519();
+    let header = JwsHeader {
+        alg: holder.algorithm().as_str().to_string(),
+        typ: Some(PROOF_JWT_TYPE.to_string()),
+        kid: None,
+        jwk: Some(holder.public_jwk()),
+        pq_signature: None,
+    };
+    let claims = ProofJwtClaims {
+        iss: None,
+        aud: Audience::Single(ISSUER.to_string()),
+        iat: chrono::Utc::now().timestamp(),
+        nonce: tokens["c_nonce"].as_str().map(str::to_string),
+    };
+    let response = app
+        .post_json_with_headers(
+            "/credential",
+            &[(
+                "Authorization",
+                &format!("Bearer {}", tokens["access_token"].as_str().unwrap()),
+            )],
+            &json!({
+                "credential_configuration_id": "fantasma_identity_v1",
+                "proof": {
+                    "proof_type": "jwt",
+                    "jwt": jws::encode_with_header(&claims, &header, &holder).unwrap()
+                }
+            }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let credential: Credential =
+        serde_json::from_value(body_json(response).await["credential"].clone()).unwrap();
+    assert_eq!(
+        credential.signature.algorithm,
+        SignatureAlgorithm::HybridEd25519MlDsa65
+    );
+
+    let issuer = IssuerInfo {
+        id: IssuerId::new(metadata["fantasma_issuer_id"].as_str().unwrap()),
+        name: "Fantasma".to_string(),
+        public_key: URL_SAFE_NO_PAD
+            .decode(metadata["fantasma_issuer_public_key"].as_str().unwrap())
+            .unwrap(),
+        trust_anchor: TrustAnchor::SelfDeclared {
+            domain: "localhost".to_string(),
+        },
+        metadata_url: None,
+        status_url: None,
+        supported_schemas: vec![],
+    };
+    assert!(fantasma_issuer::verify_credential(&issuer, &credential).is_ok());
+
+    // Verifiers that accept either signature survive a broken Ed25519 half
+    let mut broken = credential;
+    broken.signature.bytes[0] ^= 1;
+    assert!(fantasma_issuer::verify_credential(&issuer, &broken).is_err());
+    assert!(
+        fantasma_issuer::verify_credential_with(&issuer, &broken, HybridPolicy::AcceptEither)
+            .is_ok()
+    );
+}
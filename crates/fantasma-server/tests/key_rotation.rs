@@ -0,0 +1,151 @@
+//! Integration tests for signing key rotation through the admin API
+//!
+//! The admin API reads `FANTASMA_ADMIN_KEY` from the environment, so the
+//! steps run in a single test function.
+
+use axum::http::StatusCode;
+use fantasma_oidc::{IdToken, JwkSet};
+use serde_json::{json, Value};
+
+mod common;
+use common::{body_json, TestApp};
+
+const ADMIN_KEY: &str = "test-admin-key-for-key-rotation";
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+const ISSUER: &str = "http://localhost:8080";
+
+async fn id_token(app: &TestApp) -> String {
+    let code = app
+        .authorization_code(&format!(
+            "response_type=code&client_id=demo-client&redirect_uri={}&scope=openid&demo_user=alice&action=approve",
+            REDIRECT_URI
+        ))
+        .await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id=demo-client&client_secret=demo-secret",
+                code, REDIRECT_URI
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await["id_token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+fn token_kid(token: &str) -> String {
+    let header = token.split('.').next().unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, header).unwrap();
+    let header: Value = serde_json::from_slice(&bytes).unwrap();
+    header["kid"].as_str().unwrap().to_string()
+}
+
+async fn jwks(app: &TestApp) -> JwkSet {
+    serde_json::from_value(body_json(app.get("/.well-known/jwks.json").await).await).unwrap()
+}
+
+async fn admin(
+    app: &TestApp,
+    method: &str,
+    uri: &str,
+    body: Option<&Value>,
+) -> (StatusCode, Value) {
+    let response = app
+        .request_with_headers(method, uri, &[("X-Admin-Key", ADMIN_KEY)], body)
+        .await;
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_key_rotation() {
+    std::env::set_var("FANTASMA_ADMIN_KEY", ADMIN_KEY);
+    let app = TestApp::new().await;
+
+    let (status, keys) = admin(&app, "GET", "/admin/keys", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let keys = keys.as_array().unwrap();
+    assert_eq!(keys.len(), 2);
+    assert!(keys.iter().all(|k| k["status"] == "active"));
+
+    // ── Scheduled rotation: the next key is published before it signs ──
+    let old_token = id_token(&app).await;
+    let old_kid = token_kid(&old_token);
+    let (status, keys) = admin(
+        &app,
+        "POST",
+        "/admin/keys/rotate",
+        Some(&json!({
+            "algorithm": "EdDSA",
+            "activates_at": "2999-01-01T00:00:00Z"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let next = keys
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|k| k["status"] == "next")
+        .unwrap();
+    let next_kid = next["kid"].as_str().unwrap().to_string();
+    assert_eq!(next["algorithm"], "EdDSA");
+    assert!(jwks(&app).await.find(&next_kid).is_some());
+    assert_eq!(token_kid(&id_token(&app).await), old_kid);
+
+    // ── Immediate rotation: tokens from the retired key still verify ──
+    let (status, _) = admin(
+        &app,
+        "POST",
+        "/admin/keys/rotate",
+        Some(&json!({ "algorithm": "EdDSA" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let new_token = id_token(&app).await;
+    assert_eq!(token_kid(&new_token), next_kid);
+
+    let published = jwks(&app).await;
+    assert!(IdToken::verify(&old_token, &published, ISSUER, "demo-client").is_ok());
+    assert!(IdToken::verify(&new_token, &published, ISSUER, "demo-client").is_ok());
+    let (_, keys) = admin(&app, "GET", "/admin/keys", None).await;
+    let retired = keys
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|k| k["kid"] == old_kid.as_str())
+        .unwrap();
+    assert_eq!(retired["status"], "retired");
+    assert_eq!(retired["published"], true);
+
+    // ── Emergency revoke: the key leaves the JWKS at once ──
+    let (status, _) = admin(&app, "DELETE", &format!("/admin/keys/{}", old_kid), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let published = jwks(&app).await;
+    assert!(published.find(&old_kid).is_none());
+    assert!(IdToken::verify(&old_token, &published, ISSUER, "demo-client").is_err());
+
+    // Revoking the active key puts a fresh one in its place
+    let (status, _) = admin(&app, "DELETE", &format!("/admin/keys/{}", next_kid), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let replacement = token_kid(&id_token(&app).await);
+    assert_ne!(replacement, next_kid);
+    assert!(jwks(&app).await.find(&next_kid).is_none());
+
+    let (status, _) = admin(&app, "DELETE", "/admin/keys/unknown-kid", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Without the admin key nothing changes
+    let response = app
+        .request_with_headers("DELETE", &format!("/admin/keys/{}", replacement), &[], None)
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,305 @@
+//! Integration tests for RP-initiated and back-channel logout
+
+use axum::{extract::State, http::StatusCode, routing::post, Router};
+use fantasma_oidc::logout::LogoutToken;
+use fantasma_oidc::{JwkSet, TokenEndpointAuthMethod};
+use fantasma_server::state::ClientInfo;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::{body_json, body_text, location, query_param, TestApp};
+
+const ISSUER: &str = "http://localhost:8080";
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+
+type Received = Arc<Mutex<Vec<String>>>;
+
+/// Serve a back-channel logout endpoint that records the tokens it receives
+async fn logout_receiver() -> (String, Received) {
+    let received = Received::default();
+    let router = Router::new()
+        .route(
+            "/backchannel",
+            post(
+                |State(received): State<Received>,
+                 axum::Form(form): axum::Form<HashMap<String, String>>| async move {
+                    received.lock().unwrap().push(form["logout_token"].clone());
+                    StatusCode::OK
+                },
+            ),
+        )
+        .with_state(received.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let uri = format!("http://{}/backchannel", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    (uri, received)
+}
+
+/// Wait for the logout tokens delivered in the background
+async fn received_tokens(received: &Received, count: usize) -> Vec<String> {
+    for _ in 0..50 {
+        let tokens = received.lock().unwrap().clone();
+        if tokens.len() >= count {
+            return tokens;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    received.lock().unwrap().clone()
+}
+
+fn backchannel_client(backchannel_logout_uri: String) -> ClientInfo {
+    ClientInfo {
+        client_id: "logout-rp".to_string(),
+        client_secret_hash: Some(fantasma_crypto::hash_secret("demo-secret").unwrap()),
+        redirect_uris: vec![REDIRECT_URI.to_string()],
+        name: "Logout RP".to_string(),
+        id_token_signed_response_alg: None,
+        token_endpoint_auth_method: TokenEndpointAuthMethod::ClientSecretPost,
+        jwks: None,
+        jwks_uri: None,
+        sector_identifier_uri: None,
+        require_pkce_s256: false,
+        require_pushed_authorization_requests: false,
+        require_signed_request_object: false,
+        post_logout_redirect_uris: vec!["http://localhost:8080/bye".to_string()],
+        backchannel_logout_uri: Some(backchannel_logout_uri),
+        backchannel_logout_session_required: true,
+        allowed_scopes: None,
+        downscope_unapproved_scopes: false,
+        id_token_encryption: None,
+        userinfo_encryption: None,
+    }
+}
+
+/// Consent for `client_id`, returning the code and the session cookie
+async fn consent(app: &TestApp, client_id: &str, cookie: Option<&str>) -> (String, String) {
+    let body = format!(
+        "response_type=code&client_id={}&redirect_uri={}&scope=openid&action=approve",
+        client_id, REDIRECT_URI
+    );
+    let headers: Vec<(&str, &str)> = cookie.map(|c| ("cookie", c)).into_iter().collect();
+    let response = app.consent_with_headers(&headers, &body).await;
+
+    let set_cookie = response.headers()["set-cookie"].to_str().unwrap();
+    let session = set_cookie.split(';').next().unwrap().to_string();
+    let code = query_param(&location(&response), "code").unwrap();
+    (code, session)
+}
+
+async fn exchange(app: &TestApp, client_id: &str, code: &str) -> Value {
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id={}&client_secret=demo-secret",
+                code, REDIRECT_URI, client_id
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await
+}
+
+async fn refresh_status(app: &TestApp, client_id: &str, tokens: &Value) -> StatusCode {
+    app.post_form(
+        "/token",
+        &format!(
+            "grant_type=refresh_token&refresh_token={}&client_id={}&client_secret=demo-secret",
+            tokens["refresh_token"].as_str().unwrap(),
+            client_id
+        ),
+    )
+    .await
+    .status()
+}
+
+fn claims(token: &str) -> Value {
+    let payload = token.split('.').nth(1).unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_logout_ends_session_across_clients() {
+    let (backchannel_uri, received) = logout_receiver().await;
+    let app = TestApp::with_clients(vec![backchannel_client(backchannel_uri)]).await;
+
+    // Two clients sign in during the same browser session
+    let (code, session) = consent(&app, "demo-client", None).await;
+    let demo_tokens = exchange(&app, "demo-client", &code).await;
+    let (code, same_session) = consent(&app, "logout-rp", Some(&session)).await;
+    assert_eq!(session, same_session);
+    let rp_tokens = exchange(&app, "logout-rp", &code).await;
+
+    let id_token = rp_tokens["id_token"].as_str().unwrap();
+    let sid = claims(id_token)["sid"].as_str().unwrap().to_string();
+    assert_eq!(
+        claims(demo_tokens["id_token"].as_str().unwrap())["sid"],
+        sid.as_str()
+    );
+
+    let response = app
+        .get(&format!(
+            "/end_session?id_token_hint={}&post_logout_redirect_uri=http://localhost:8080/bye&state=s1",
+            id_token
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&response), "http://localhost:8080/bye?state=s1");
+    assert!(response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .contains("Max-Age=0"));
+
+    // Only the client with a back-channel logout URI is notified
+    let jwks: JwkSet =
+        serde_json::from_value(body_json(app.get("/.well-known/jwks.json").await).await).unwrap();
+    let tokens = received_tokens(&received, 1).await;
+    assert_eq!(tokens.len(), 1);
+    let logout = LogoutToken::verify(&tokens[0], &jwks, ISSUER, "logout-rp").unwrap();
+    assert_eq!(logout.sid.as_deref(), Some(sid.as_str()));
+    assert_eq!(
+        logout.sub,
+        claims(id_token)["sub"].as_str().map(str::to_string)
+    );
+
+    // Every grant made during the session is revoked
+    assert_eq!(
+        refresh_status(&app, "demo-client", &demo_tokens).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        refresh_status(&app, "logout-rp", &rp_tokens).await,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn test_post_logout_redirect_must_be_registered() {
+    let app = TestApp::new().await;
+    let (code, session) = consent(&app, "demo-client", None).await;
+    let tokens = exchange(&app, "demo-client", &code).await;
+    let id_token = tokens["id_token"].as_str().unwrap();
+
+    // Not registered for the client
+    let response = app
+        .get(&format!(
+            "/end_session?id_token_hint={}&post_logout_redirect_uri=https://evil.example/",
+            id_token
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // No client to check it against
+    let response = app
+        .get("/end_session?post_logout_redirect_uri=http://localhost:8080/")
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Nothing was logged out by the rejected requests
+    assert_eq!(
+        refresh_status(&app, "demo-client", &tokens).await,
+        StatusCode::OK
+    );
+
+    // Logging out with just the session cookie
+    let response = app
+        .post_form_with_headers(
+            "/end_session",
+            &[("cookie", &session), ("origin", ISSUER)],
+            "",
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response).await.contains("Signed out"));
+}
+
+#[tokio::test]
+async fn test_logout_without_hint_is_confirmed() {
+    let app = TestApp::new().await;
+    let (code, session) = consent(&app, "demo-client", None).await;
+    let tokens = exchange(&app, "demo-client", &code).await;
+
+    // Any site can send the browser here; the user is asked first
+    let response = app
+        .get_with_headers(
+            "/end_session?client_id=demo-client&state=s1",
+            &[("cookie", &session)],
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("set-cookie").is_none());
+    let page = body_text(response).await;
+    assert!(page.contains(r#"action="/end_session" method="post""#));
+    assert!(page.contains(r#"name="state" value="s1""#));
+
+    // A cross-site POST only gets the confirmation page too
+    for headers in [
+        vec![
+            ("cookie", session.as_str()),
+            ("origin", "https://evil.example"),
+        ],
+        vec![("cookie", session.as_str())],
+    ] {
+        let response = app
+            .post_form_with_headers("/end_session", &headers, "client_id=demo-client")
+            .await;
+        assert!(response.headers().get("set-cookie").is_none());
+        assert!(!body_text(response).await.contains("Signed out"));
+    }
+
+    // Nothing was logged out so far; refreshing rotates the refresh token
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=refresh_token&refresh_token={}&client_id=demo-client&client_secret=demo-secret",
+                tokens["refresh_token"].as_str().unwrap()
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = body_json(response).await;
+
+    // Confirming on the provider's page ends the session
+    let response = app
+        .post_form_with_headers(
+            "/end_session",
+            &[
+                ("cookie", &session),
+                (
+                    "referer",
+                    "http://localhost:8080/end_session?client_id=demo-client",
+                ),
+            ],
+            "client_id=demo-client",
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .contains("Max-Age=0"));
+    assert_eq!(
+        refresh_status(&app, "demo-client", &tokens).await,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn test_discovery_advertises_logout() {
+    let app = TestApp::new().await;
+
+    let json = body_json(app.get("/.well-known/openid-configuration").await).await;
+    assert_eq!(
+        json["end_session_endpoint"],
+        "http://localhost:8080/end_session"
+    );
+    assert_eq!(json["backchannel_logout_supported"], true);
+    assert_eq!(json["backchannel_logout_session_supported"], true);
+}
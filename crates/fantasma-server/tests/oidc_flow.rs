@@ -43,6 +43,12 @@ async fn test_discovery_endpoint() {
     assert!(scope_strings.contains(&"openid"));
     assert!(scope_strings.contains(&"zk:age:18+"));
     assert!(scope_strings.contains(&"zk:age:21+"));
+
+    // Circuits are listed, but without compiled artifacts no hash is pinned
+    let age_circuit = &json["zk_circuits"]["age_verification_v1"];
+    assert!(age_circuit["description"].as_str().is_some());
+    assert!(age_circuit.get("bytecode_hash").is_none());
+    assert!(json["zk_circuits"]["kyc_verification_v1"].is_object());
 }
 
 #[tokio::test]
@@ -67,8 +73,18 @@ async fn test_jwks_endpoint() {
         .unwrap();
     let json: Value = serde_json::from_slice(&body).unwrap();
 
-    // Check that keys array exists (may be empty in development mode)
-    assert!(json["keys"].as_array().is_some());
+    // The Ed25519 and ML-DSA-65 signing keys are published with a kid
+    let keys = json["keys"].as_array().unwrap();
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0]["kty"].as_str(), Some("OKP"));
+    assert_eq!(keys[0]["crv"].as_str(), Some("Ed25519"));
+    assert_eq!(keys[0]["alg"].as_str(), Some("EdDSA"));
+    assert!(keys[0]["kid"].as_str().is_some());
+    assert!(keys[0].get("d").is_none());
+    assert_eq!(keys[1]["kty"].as_str(), Some("AKP"));
+    assert_eq!(keys[1]["alg"].as_str(), Some("ML-DSA-65"));
+    assert!(keys[1]["pub"].as_str().is_some());
+    assert!(keys[1]["kid"].as_str().is_some());
 }
 
 #[tokio::test]
@@ -129,7 +145,7 @@ async fn test_token_endpoint_invalid_grant() {
                 .method("POST")
                 .uri("/token")
                 .header("content-type", "application/x-www-form-urlencoded")
-                .body(Body::from("grant_type=authorization_code&code=invalid&redirect_uri=http://localhost:8080/callback&client_id=demo-client"))
+                .body(Body::from("grant_type=authorization_code&code=invalid&redirect_uri=http://localhost:8080/callback&client_id=demo-client&client_secret=demo-secret"))
                 .unwrap(),
         )
         .await
@@ -182,6 +198,7 @@ async fn test_full_oidc_flow() {
         .unwrap();
 
     assert_eq!(auth_response.status(), StatusCode::OK);
+    let request_id = common::consent_request_id(&common::body_text(auth_response).await);
 
     // Step 2: Submit consent (simulating user approval)
     let consent_response = app
@@ -192,7 +209,10 @@ async fn test_full_oidc_flow() {
                 .method("POST")
                 .uri("/authorize/consent")
                 .header("content-type", "application/x-www-form-urlencoded")
-                .body(Body::from("response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid%20zk:age:21+&state=test123&demo_user=alice&action=approve"))
+                .body(Body::from(format!(
+                    "request_id={}&demo_user=alice&action=approve",
+                    request_id
+                )))
                 .unwrap(),
         )
         .await
@@ -230,7 +250,7 @@ async fn test_full_oidc_flow() {
                 .uri("/token")
                 .header("content-type", "application/x-www-form-urlencoded")
                 .body(Body::from(format!(
-                    "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-client",
+                    "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-client&client_secret=demo-secret",
                     code
                 )))
                 .unwrap(),
@@ -255,6 +275,26 @@ async fn test_full_oidc_flow() {
     let parts: Vec<&str> = id_token.split('.').collect();
     assert_eq!(parts.len(), 3); // Header.Payload.Signature
 
+    // Verify the signature against the published JWKS
+    let jwks_response = app
+        .router()
+        .oneshot(
+            Request::builder()
+                .uri("/.well-known/jwks.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(jwks_response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let jwks: fantasma_oidc::JwkSet = serde_json::from_slice(&body).unwrap();
+    let verified =
+        fantasma_oidc::IdToken::verify(id_token, &jwks, "http://localhost:8080", "demo-client")
+            .unwrap();
+    assert_eq!(verified.aud, "demo-client");
+
     // Decode payload
     let payload =
         base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, parts[1])
@@ -277,18 +317,7 @@ async fn test_denied_consent() {
     let app = TestApp::new().await;
 
     // User denies consent
-    let response = app
-        .router()
-        .oneshot(
-            Request::builder()
-                .method("POST")
-                .uri("/authorize/consent")
-                .header("content-type", "application/x-www-form-urlencoded")
-                .body(Body::from("response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid&state=test123&demo_user=alice&action=deny"))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
+    let response = app.consent("response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid&state=test123&demo_user=alice&action=deny").await;
 
     assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
 
@@ -308,19 +337,7 @@ async fn test_underage_user_fails_21_check() {
     let app = TestApp::new().await;
 
     // Bob is under 21 in demo users
-    let consent_response = app
-        .router()
-        .clone()
-        .oneshot(
-            Request::builder()
-                .method("POST")
-                .uri("/authorize/consent")
-                .header("content-type", "application/x-www-form-urlencoded")
-                .body(Body::from("response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid%20zk:age:21+&state=test123&demo_user=bob&action=approve"))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
+    let consent_response = app.consent("response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid%20zk:age:21+&state=test123&demo_user=bob&action=approve").await;
 
     assert_eq!(consent_response.status(), StatusCode::TEMPORARY_REDIRECT);
 
@@ -348,7 +365,7 @@ async fn test_underage_user_fails_21_check() {
                 .uri("/token")
                 .header("content-type", "application/x-www-form-urlencoded")
                 .body(Body::from(format!(
-                    "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-client",
+                    "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-client&client_secret=demo-secret",
                     code
                 )))
                 .unwrap(),
@@ -373,3 +390,192 @@ async fn test_underage_user_fails_21_check() {
         assert_eq!(age_claim["verified"].as_bool(), Some(false));
     }
 }
+
+#[tokio::test]
+async fn test_ml_dsa_signed_id_token_for_pq_client() {
+    let app = TestApp::new().await;
+
+    let consent_response = app.consent("response_type=code&client_id=demo-pq-rp&redirect_uri=http://localhost:8080/callback&scope=openid%20zk:age:18+&demo_user=alice&action=approve").await;
+
+    let location = consent_response
+        .headers()
+        .get("location")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    let code = url::Url::parse(location)
+        .unwrap()
+        .query_pairs()
+        .find(|(k, _)| k == "code")
+        .map(|(_, v)| v.to_string())
+        .unwrap();
+
+    let token_response = app
+        .router()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/token")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(format!(
+                    "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-pq-rp&client_secret=demo-secret",
+                    code
+                )))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(token_response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(token_response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let id_token = json["id_token"].as_str().unwrap();
+
+    let header = fantasma_oidc::jws::decode_header(id_token).unwrap();
+    assert_eq!(header.alg, "ML-DSA-65");
+
+    let jwks_response = app
+        .router()
+        .oneshot(
+            Request::builder()
+                .uri("/.well-known/jwks.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(jwks_response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let jwks: fantasma_oidc::JwkSet = serde_json::from_slice(&body).unwrap();
+
+    let claims =
+        fantasma_oidc::IdToken::verify(id_token, &jwks, "http://localhost:8080", "demo-pq-rp")
+            .unwrap();
+    assert_eq!(claims.aud, "demo-pq-rp");
+}
+
+// RFC 7636 Appendix B
+const PKCE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+const PKCE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+const WALLET_CONSENT: &str = "response_type=code&client_id=fantasma-wallet&redirect_uri=chrome-extension://*/callback&scope=openid&demo_user=alice&action=approve";
+
+#[tokio::test]
+async fn test_public_client_requires_pkce() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .get("/authorize?client_id=fantasma-wallet&redirect_uri=chrome-extension://*/callback&response_type=code&scope=openid&state=xyz")
+        .await;
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+
+    let location = common::location(&response);
+    assert_eq!(
+        common::query_param(&location, "error").as_deref(),
+        Some("invalid_request")
+    );
+    assert_eq!(
+        common::query_param(&location, "state").as_deref(),
+        Some("xyz")
+    );
+
+    // A consent form without a consent page cannot be used to skip the check
+    let response = app.post_form("/authorize/consent", WALLET_CONSENT).await;
+    assert!(response.headers().get("location").is_none());
+}
+
+#[tokio::test]
+async fn test_public_client_rejects_plain_pkce() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .get(&format!(
+            "/authorize?client_id=fantasma-wallet&redirect_uri=chrome-extension://*/callback&response_type=code&scope=openid&code_challenge={}&code_challenge_method=plain",
+            PKCE_VERIFIER
+        ))
+        .await;
+
+    let location = common::location(&response);
+    assert_eq!(
+        common::query_param(&location, "error").as_deref(),
+        Some("invalid_request")
+    );
+}
+
+#[tokio::test]
+async fn test_pkce_s256_code_exchange() {
+    let app = TestApp::new().await;
+    let consent = format!(
+        "{}&code_challenge={}&code_challenge_method=S256",
+        WALLET_CONSENT, PKCE_CHALLENGE
+    );
+
+    // Missing verifier
+    let code = app.authorization_code(&consent).await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri=chrome-extension://*/callback&client_id=fantasma-wallet",
+                code
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(common::body_json(response).await["error"], "invalid_grant");
+
+    // Wrong verifier
+    let code = app.authorization_code(&consent).await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri=chrome-extension://*/callback&client_id=fantasma-wallet&code_verifier={}",
+                code,
+                "x".repeat(43)
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(common::body_json(response).await["error"], "invalid_grant");
+
+    // Correct verifier
+    let code = app.authorization_code(&consent).await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri=chrome-extension://*/callback&client_id=fantasma-wallet&code_verifier={}",
+                code, PKCE_VERIFIER
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(common::body_json(response).await["id_token"].is_string());
+}
+
+#[tokio::test]
+async fn test_pkce_plain_for_confidential_client() {
+    let app = TestApp::new().await;
+
+    let code = app
+        .authorization_code(&format!(
+            "response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid&demo_user=alice&action=approve&code_challenge={}&code_challenge_method=plain",
+            PKCE_VERIFIER
+        ))
+        .await;
+
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-client&client_secret=demo-secret&code_verifier={}",
+                code, PKCE_VERIFIER
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,267 @@
+//! Integration tests for the OpenID4VCI credential issuer
+
+use axum::http::StatusCode;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use fantasma_core::credential::Credential;
+use fantasma_core::issuer::{IssuerId, IssuerInfo, TrustAnchor};
+use fantasma_oidc::client_auth::Audience;
+use fantasma_oidc::jws::{self, JwsHeader};
+use fantasma_oidc::openid4vci::{ProofJwtClaims, PROOF_JWT_TYPE};
+use fantasma_oidc::SigningKey;
+use serde_json::{json, Value};
+
+mod common;
+use common::{body_json, TestApp};
+
+const ADMIN_KEY: &str = "test-admin-key-for-openid4vci-tests";
+const ISSUER: &str = "http://localhost:8080";
+
+/// Create an offer of an identity credential through the admin API
+async fn credential_offer(app: &TestApp) -> Value {
+    std::env::set_var("FANTASMA_ADMIN_KEY", ADMIN_KEY);
+    let response = app
+        .post_json_with_headers(
+            "/admin/credential-offers",
+            &[("X-Admin-Key", ADMIN_KEY)],
+            &json!({
+                "credential": {
+                    "Identity": {
+                        "birthdate": "2000-01-01",
+                        "identity_hash": vec![0u8; 32]
+                    }
+                }
+            }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    body_json(response).await
+}
+
+/// Redeem the offer's pre-authorized code as a wallet would
+async fn redeem(app: &TestApp, offer: &Value) -> axum::response::Response {
+    let code = offer["credential_offer"]["grants"]
+        ["urn:ietf:params:oauth:grant-type:pre-authorized_code"]["pre-authorized_code"]
+        .as_str()
+        .unwrap();
+    app.post_form(
+        "/token",
+        &format!(
+            "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Apre-authorized_code&pre-authorized_code={}",
+            code
+        ),
+    )
+    .await
+}
+
+fn key_proof(holder: &SigningKey, nonce: &str) -> Value {
+    let header = JwsHeader {
+        alg: holder.algorithm().as_str().to_string(),
+        typ: Some(PROOF_JWT_TYPE.to_string()),
+        kid: None,
+        jwk: Some(holder.public_jwk()),
+        pq_signature: None,
+    };
+    let claims = ProofJwtClaims {
+        iss: None,
+        aud: Audience::Single(ISSUER.to_string()),
+        iat: chrono::Utc::now().timestamp(),
+        nonce: Some(nonce.to_string()),
+    };
+    json!({
+        "proof_type": "jwt",
+        "jwt": jws::encode_with_header(&claims, &header, holder).unwrap()
+    })
+}
+
+async fn request_credential(
+    app: &TestApp,
+    access_token: &str,
+    request: &Value,
+) -> axum::response::Response {
+    app.post_json_with_headers(
+        "/credential",
+        &[("Authorization", &format!("Bearer {}", access_token))],
+        request,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_credential_issuer_metadata() {
+    let app = TestApp::new().await;
+    let response = app.get("/.well-known/openid-credential-issuer").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let metadata = body_json(response).await;
+
+    assert_eq!(metadata["credential_issuer"], ISSUER);
+    assert_eq!(
+        metadata["credential_endpoint"],
+        "http://localhost:8080/credential"
+    );
+    let identity = &metadata["credential_configurations_supported"]["fantasma_identity_v1"];
+    assert_eq!(identity["format"], "fantasma_credential");
+    assert_eq!(identity["schema"], "fantasma:identity:v1");
+    assert_eq!(
+        identity["cryptographic_binding_methods_supported"][0],
+        "jwk"
+    );
+
+    // The provider advertises the grant used to redeem offers
+    let discovery = body_json(app.get("/.well-known/openid-configuration").await).await;
+    assert!(discovery["grant_types_supported"]
+        .as_array()
+        .unwrap()
+        .contains(&json!(
+            "urn:ietf:params:oauth:grant-type:pre-authorized_code"
+        )));
+}
+
+#[tokio::test]
+async fn test_pre_authorized_code_flow_issues_bound_credential() {
+    let app = TestApp::new().await;
+    let offer = credential_offer(&app).await;
+    assert!(offer["credential_offer_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("openid-credential-offer://?credential_offer="));
+
+    let response = redeem(&app, &offer).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = body_json(response).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let c_nonce = tokens["c_nonce"].as_str().unwrap();
+
+    // The code is single-use
+    let response = redeem(&app, &offer).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_grant");
+
+    let holder = SigningKey::generate_ml_dsa_65();
+    let response = request_credential(
+        &app,
+        access_token,
+        &json!({
+            "credential_configuration_id": "fantasma_identity_v1",
+            "proof": key_proof(&holder, c_nonce)
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let credential: Credential =
+        serde_json::from_value(body_json(response).await["credential"].clone()).unwrap();
+    assert_eq!(
+        credential.holder.as_ref().unwrap().jkt,
+        holder.public_jwk().thumbprint()
+    );
+    assert!(credential.birthdate().is_some());
+
+    // The credential verifies against the published issuer key
+    let metadata = body_json(app.get("/.well-known/openid-credential-issuer").await).await;
+    let issuer = IssuerInfo {
+        id: IssuerId::new(metadata["fantasma_issuer_id"].as_str().unwrap()),
+        name: "Fantasma".to_string(),
+        public_key: URL_SAFE_NO_PAD
+            .decode(metadata["fantasma_issuer_public_key"].as_str().unwrap())
+            .unwrap(),
+        trust_anchor: TrustAnchor::SelfDeclared {
+            domain: "localhost".to_string(),
+        },
+        metadata_url: None,
+        status_url: None,
+        supported_schemas: vec![],
+    };
+    assert!(fantasma_issuer::verify_credential(&issuer, &credential).is_ok());
+
+    // Each offer yields one credential
+    let response = request_credential(
+        &app,
+        access_token,
+        &json!({
+            "credential_configuration_id": "fantasma_identity_v1",
+            "proof": key_proof(&holder, c_nonce)
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_key_proof_must_carry_current_c_nonce() {
+    let app = TestApp::new().await;
+    let offer = credential_offer(&app).await;
+    let tokens = body_json(redeem(&app, &offer).await).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let holder = SigningKey::generate_ed25519();
+
+    let response = request_credential(
+        &app,
+        access_token,
+        &json!({
+            "credential_configuration_id": "fantasma_identity_v1",
+            "proof": key_proof(&holder, "stale-nonce")
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let error = body_json(response).await;
+    assert_eq!(error["error"], "invalid_proof");
+    let c_nonce = error["c_nonce"].as_str().unwrap();
+    assert_ne!(c_nonce, tokens["c_nonce"].as_str().unwrap());
+
+    // Retrying with the fresh nonce succeeds
+    let response = request_credential(
+        &app,
+        access_token,
+        &json!({
+            "credential_configuration_id": "fantasma_identity_v1",
+            "proof": key_proof(&holder, c_nonce)
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_credential_request_errors() {
+    let app = TestApp::new().await;
+    let offer = credential_offer(&app).await;
+    let tokens = body_json(redeem(&app, &offer).await).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let c_nonce = tokens["c_nonce"].as_str().unwrap();
+    let holder = SigningKey::generate_ed25519();
+
+    // The offer is for another credential
+    let response = request_credential(
+        &app,
+        access_token,
+        &json!({
+            "credential_configuration_id": "fantasma_kyc_v1",
+            "proof": key_proof(&holder, c_nonce)
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body_json(response).await["error"],
+        "unsupported_credential_type"
+    );
+
+    // No key proof
+    let response = request_credential(
+        &app,
+        access_token,
+        &json!({ "credential_configuration_id": "fantasma_identity_v1" }),
+    )
+    .await;
+    assert_eq!(body_json(response).await["error"], "invalid_proof");
+
+    // Unknown access token
+    let response = request_credential(
+        &app,
+        "not-a-token",
+        &json!({ "credential_configuration_id": "fantasma_identity_v1" }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,234 @@
+//! Integration tests for the OpenID4VP verifier mode
+
+use axum::http::StatusCode;
+use fantasma_core::claim::ClaimType;
+use fantasma_core::proof::GeneratedProof;
+use fantasma_oidc::openid4vp::{DcqlQuery, StarkPresentation};
+use fantasma_stark::backend::{MockBackend, ProverBackendTrait};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+mod common;
+use common::{body_json, query_param, TestApp};
+
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+
+fn encode(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+/// Open the consent page and follow its OpenID4VP link, as a wallet would
+async fn wallet_request(app: &TestApp, scope: &str) -> HashMap<String, String> {
+    let response = app
+        .get(&format!(
+            "/authorize?response_type=code&client_id=demo-client&redirect_uri={}&scope={}&state=rp-state",
+            REDIRECT_URI,
+            encode(scope)
+        ))
+        .await;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let page = String::from_utf8_lossy(&body);
+
+    let start = page
+        .find(r#"href="openid4vp://?"#)
+        .expect("no OpenID4VP link")
+        + r#"href="openid4vp://?"#.len();
+    let end = start + page[start..].find('"').unwrap();
+    let query = page[start..end].replace("&amp;", "&");
+    url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+/// A presentation of `claim_type` bound to the wallet request
+fn presentation(request: &HashMap<String, String>, claim_type: ClaimType, nullifier: u8) -> String {
+    let circuit_id = claim_type.circuit_id().to_string();
+    let sub = fantasma_crypto::pairwise_subject(&[42u8; 32], &request["verifier_domain"]);
+    let public_inputs = claim_type
+        .public_inputs()
+        .iter()
+        .chain([
+            &fantasma_crypto::sha3_256(request["verifier_domain"].as_bytes()),
+            &fantasma_crypto::sha3_256(request["nonce"].as_bytes()),
+            &[nullifier; 32],
+            &fantasma_core::proof::subject_hash(&sub),
+        ])
+        .map(hex::encode)
+        .collect::<Vec<_>>();
+    let result = MockBackend::new()
+        .prove(&circuit_id, &[], &public_inputs)
+        .unwrap();
+
+    StarkPresentation {
+        sub,
+        proof: GeneratedProof {
+            claim_type,
+            proof_bytes: result.proof_bytes,
+            public_inputs: result.public_inputs,
+            circuit_id,
+            nullifier: [nullifier; 32],
+            generated_at: chrono::Utc::now(),
+        },
+    }
+    .encode()
+}
+
+async fn direct_post(app: &TestApp, request: &HashMap<String, String>, vp_token: &Value) -> Value {
+    let response = app
+        .post_form(
+            "/openid4vp/response",
+            &format!(
+                "vp_token={}&state={}",
+                encode(&vp_token.to_string()),
+                encode(&request["state"])
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await
+}
+
+#[tokio::test]
+async fn test_wallet_request_carries_dcql_query() {
+    let app = TestApp::new().await;
+    let request = wallet_request(&app, "openid zk:age:21+ zk:kyc:basic").await;
+
+    assert_eq!(request["response_type"], "vp_token");
+    assert_eq!(request["response_mode"], "direct_post");
+    assert_eq!(
+        request["response_uri"],
+        "http://localhost:8080/openid4vp/response"
+    );
+    assert_eq!(
+        request["client_id"],
+        "redirect_uri:http://localhost:8080/openid4vp/response"
+    );
+
+    let query: DcqlQuery = serde_json::from_str(&request["dcql_query"]).unwrap();
+    let ids: Vec<&str> = query.credentials.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, ["zk_age_21", "zk_kyc_basic"]);
+    assert!(query.credential_sets.iter().all(|set| !set.required));
+}
+
+#[tokio::test]
+async fn test_vp_token_completes_authorization() {
+    let app = TestApp::new().await;
+    let request = wallet_request(&app, "openid zk:age:21+").await;
+    let vp_token = json!({
+        "zk_age_21": [presentation(&request, ClaimType::AgeAtLeast { threshold: 21 }, 1)]
+    });
+
+    // The wallet is sent on to the client with a code
+    let response = direct_post(&app, &request, &vp_token).await;
+    let redirect_uri = response["redirect_uri"].as_str().unwrap();
+    assert!(redirect_uri.starts_with(REDIRECT_URI));
+    assert_eq!(
+        query_param(redirect_uri, "state").as_deref(),
+        Some("rp-state")
+    );
+    let code = query_param(redirect_uri, "code").unwrap();
+
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id=demo-client&client_secret=demo-secret",
+                code, REDIRECT_URI
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = body_json(response).await;
+    let payload = tokens["id_token"]
+        .as_str()
+        .unwrap()
+        .split('.')
+        .nth(1)
+        .unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    let id_token: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        id_token["sub"],
+        fantasma_crypto::pairwise_subject(&[42u8; 32], &request["verifier_domain"])
+    );
+    assert_eq!(id_token["zk_age_claim"]["verified"], true);
+
+    // The request was answered
+    let response = app
+        .post_form(
+            "/openid4vp/response",
+            &format!(
+                "vp_token={}&state={}",
+                encode(&vp_token.to_string()),
+                encode(&request["state"])
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_rejected_presentation_is_reported_to_client() {
+    let app = TestApp::new().await;
+
+    // A proof for another request's nonce
+    let first = wallet_request(&app, "openid zk:age:18+").await;
+    let second = wallet_request(&app, "openid zk:age:18+").await;
+    let vp_token = json!({
+        "zk_age_18": [presentation(&first, ClaimType::AgeAtLeast { threshold: 18 }, 2)]
+    });
+    let response = direct_post(&app, &second, &vp_token).await;
+    let redirect_uri = response["redirect_uri"].as_str().unwrap();
+    assert_eq!(
+        query_param(redirect_uri, "error").as_deref(),
+        Some("invalid_request")
+    );
+    assert_eq!(
+        query_param(redirect_uri, "state").as_deref(),
+        Some("rp-state")
+    );
+
+    // The wallet declined
+    let response = app
+        .post_form(
+            "/openid4vp/response",
+            &format!("error=access_denied&state={}", encode(&first["state"])),
+        )
+        .await;
+    let response = body_json(response).await;
+    assert_eq!(
+        query_param(response["redirect_uri"].as_str().unwrap(), "error").as_deref(),
+        Some("access_denied")
+    );
+}
+
+#[tokio::test]
+async fn test_malformed_vp_token_is_rejected() {
+    let app = TestApp::new().await;
+    let request = wallet_request(&app, "openid zk:age:18+").await;
+
+    // Presentations must answer a credential query of the request
+    let vp_token = json!({
+        "zk_age_21": [presentation(&request, ClaimType::AgeAtLeast { threshold: 21 }, 3)]
+    });
+    let response = app
+        .post_form(
+            "/openid4vp/response",
+            &format!(
+                "vp_token={}&state={}",
+                encode(&vp_token.to_string()),
+                encode(&request["state"])
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_request");
+
+    let response = app
+        .post_form("/openid4vp/response", "vp_token=%7B%7D&state=unknown")
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,128 @@
+//! Integration tests for pairwise subject identifiers
+
+use axum::http::StatusCode;
+use fantasma_oidc::TokenEndpointAuthMethod;
+use fantasma_server::state::ClientInfo;
+use serde_json::Value;
+
+mod common;
+use common::{body_json, body_text, consent_request_id, location, query_param, TestApp};
+
+fn other_sector_client() -> ClientInfo {
+    ClientInfo {
+        client_id: "other-rp".to_string(),
+        client_secret_hash: Some(fantasma_crypto::hash_secret("demo-secret").unwrap()),
+        redirect_uris: vec!["https://other-rp.example/callback".to_string()],
+        name: "Other RP".to_string(),
+        id_token_signed_response_alg: None,
+        token_endpoint_auth_method: TokenEndpointAuthMethod::ClientSecretPost,
+        jwks: None,
+        jwks_uri: None,
+        sector_identifier_uri: None,
+        require_pkce_s256: false,
+        require_pushed_authorization_requests: false,
+        require_signed_request_object: false,
+        post_logout_redirect_uris: Vec::new(),
+        backchannel_logout_uri: None,
+        backchannel_logout_session_required: false,
+        allowed_scopes: None,
+        downscope_unapproved_scopes: false,
+        id_token_encryption: None,
+        userinfo_encryption: None,
+    }
+}
+
+/// Log in through consent and return the `sub` of the issued ID token
+async fn login(app: &TestApp, client_id: &str, redirect_uri: &str, extra: &str) -> String {
+    let code = app
+        .authorization_code(&format!(
+            "response_type=code&client_id={}&redirect_uri={}&scope=openid&action=approve{}",
+            client_id, redirect_uri, extra
+        ))
+        .await;
+    issued_subject(app, client_id, redirect_uri, &code).await
+}
+
+/// Redeem `code` and return the `sub` of the issued ID token
+async fn issued_subject(app: &TestApp, client_id: &str, redirect_uri: &str, code: &str) -> String {
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id={}&client_secret=demo-secret",
+                code, redirect_uri, client_id
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = body_json(response).await;
+
+    let payload = tokens["id_token"]
+        .as_str()
+        .unwrap()
+        .split('.')
+        .nth(1)
+        .unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    let claims: Value = serde_json::from_slice(&bytes).unwrap();
+    claims["sub"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_subject_is_stable_per_sector() {
+    let app = TestApp::with_clients(vec![other_sector_client()]).await;
+    let local = "http://localhost:8080/callback";
+
+    let first = login(&app, "demo-client", local, "&demo_user=alice").await;
+    let second = login(&app, "demo-client", local, "&demo_user=alice").await;
+    assert_eq!(first, second, "returning users keep their sub");
+    assert!(fantasma_crypto::is_pairwise_subject(&first));
+
+    // Another user in the same sector gets a different sub
+    let bob = login(&app, "demo-client", local, "&demo_user=bob").await;
+    assert_ne!(first, bob);
+
+    // A client in another sector cannot correlate
+    let other = login(
+        &app,
+        "other-rp",
+        "https://other-rp.example/callback",
+        "&demo_user=alice",
+    )
+    .await;
+    assert_ne!(first, other);
+}
+
+#[tokio::test]
+async fn test_unproven_subject_is_ignored() {
+    let app = TestApp::new().await;
+    let redirect_uri = "http://localhost:8080/callback";
+    // A wallet subject posted without proofs bound to it
+    let sub = fantasma_crypto::pairwise_subject(&[42u8; 32], "localhost");
+
+    let page = app
+        .get(&format!(
+            "/authorize?response_type=code&client_id=demo-client&redirect_uri={}&scope=openid",
+            redirect_uri
+        ))
+        .await;
+    let response = app
+        .post_form(
+            "/authorize/consent",
+            &format!(
+                "request_id={}&action=approve&demo_user=alice&subject={}",
+                consent_request_id(&body_text(page).await),
+                sub
+            ),
+        )
+        .await;
+    let code = query_param(&location(&response), "code").expect("no code issued");
+
+    let issued = issued_subject(&app, "demo-client", redirect_uri, &code).await;
+    assert_ne!(issued, sub);
+    assert_eq!(
+        issued,
+        login(&app, "demo-client", redirect_uri, "&demo_user=alice").await
+    );
+}
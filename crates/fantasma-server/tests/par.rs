@@ -0,0 +1,187 @@
+//! Integration tests for pushed authorization requests (RFC 9126)
+
+use axum::http::StatusCode;
+use fantasma_oidc::TokenEndpointAuthMethod;
+use fantasma_server::state::ClientInfo;
+
+mod common;
+use common::{body_json, body_text, consent_request_id, location, query_param, TestApp};
+
+const PUSH: &str = "response_type=code&redirect_uri=http://localhost:8080/callback&scope=openid&state=xyz&client_id=demo-client&client_secret=demo-secret";
+
+fn par_only_client() -> ClientInfo {
+    ClientInfo {
+        client_id: "par-rp".to_string(),
+        client_secret_hash: Some(fantasma_crypto::hash_secret("demo-secret").unwrap()),
+        redirect_uris: vec!["http://localhost:8080/callback".to_string()],
+        name: "PAR RP".to_string(),
+        id_token_signed_response_alg: None,
+        token_endpoint_auth_method: TokenEndpointAuthMethod::ClientSecretPost,
+        jwks: None,
+        jwks_uri: None,
+        sector_identifier_uri: None,
+        require_pkce_s256: false,
+        require_pushed_authorization_requests: true,
+        require_signed_request_object: false,
+        post_logout_redirect_uris: Vec::new(),
+        backchannel_logout_uri: None,
+        backchannel_logout_session_required: false,
+        allowed_scopes: None,
+        downscope_unapproved_scopes: false,
+        id_token_encryption: None,
+        userinfo_encryption: None,
+    }
+}
+
+async fn push(app: &TestApp, body: &str) -> String {
+    let response = app.post_form("/par", body).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let json = body_json(response).await;
+    assert_eq!(json["expires_in"], 60);
+    json["request_uri"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_pushed_request_is_used_once() {
+    let app = TestApp::new().await;
+    let request_uri = push(&app, PUSH).await;
+    assert!(request_uri.starts_with("urn:ietf:params:oauth:request_uri:"));
+
+    let uri = format!(
+        "/authorize?client_id=demo-client&request_uri={}",
+        urlencoding(&request_uri)
+    );
+    let response = app.get(&uri).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let request_id = consent_request_id(&body_text(response).await);
+
+    // Consent grants the pushed request
+    let response = app
+        .post_form(
+            "/authorize/consent",
+            &format!("request_id={}&action=approve", request_id),
+        )
+        .await;
+    let redirect = location(&response);
+    assert!(query_param(&redirect, "code").is_some());
+    assert_eq!(query_param(&redirect, "state").as_deref(), Some("xyz"));
+
+    // Replayed request_uri
+    let response = app.get(&uri).await;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(String::from_utf8_lossy(&body).contains("Invalid or expired request_uri"));
+}
+
+#[tokio::test]
+async fn test_request_uri_is_bound_to_client() {
+    let app = TestApp::new().await;
+    let request_uri = push(&app, PUSH).await;
+
+    let response = app
+        .get(&format!(
+            "/authorize?client_id=demo-rp&request_uri={}",
+            urlencoding(&request_uri)
+        ))
+        .await;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(String::from_utf8_lossy(&body).contains("Invalid or expired request_uri"));
+}
+
+#[tokio::test]
+async fn test_push_is_validated() {
+    let app = TestApp::new().await;
+
+    // Unauthenticated
+    let response = app
+        .post_form(
+            "/par",
+            "response_type=code&redirect_uri=http://localhost:8080/callback&scope=openid&client_id=demo-client",
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Unregistered redirect URI
+    let response = app
+        .post_form(
+            "/par",
+            "response_type=code&redirect_uri=https://evil.example/cb&scope=openid&client_id=demo-client&client_secret=demo-secret",
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_request");
+
+    // Public clients must still send a PKCE challenge
+    let response = app
+        .post_form(
+            "/par",
+            "response_type=code&redirect_uri=chrome-extension://*/callback&scope=openid&client_id=fantasma-wallet",
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_client_requiring_par() {
+    let app = TestApp::with_clients(vec![par_only_client()]).await;
+
+    let response = app
+        .get("/authorize?client_id=par-rp&redirect_uri=http://localhost:8080/callback&response_type=code&scope=openid")
+        .await;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert!(String::from_utf8_lossy(&body).contains("must use pushed authorization requests"));
+
+    // Nor can the consent form stand in for a pushed request
+    let response = app
+        .post_form(
+            "/authorize/consent",
+            "response_type=code&client_id=par-rp&redirect_uri=http://localhost:8080/callback&scope=openid&action=approve",
+        )
+        .await;
+    assert!(response.headers().get("location").is_none());
+    let response = app
+        .post_form(
+            "/authorize/consent",
+            "request_id=made-up&client_id=par-rp&redirect_uri=http://localhost:8080/callback&scope=openid&action=approve",
+        )
+        .await;
+    assert!(response.headers().get("location").is_none());
+    assert!(body_text(response)
+        .await
+        .contains("Invalid or expired authorization request"));
+
+    let request_uri = push(
+        &app,
+        "response_type=code&redirect_uri=http://localhost:8080/callback&scope=openid&client_id=par-rp&client_secret=demo-secret",
+    )
+    .await;
+    let response = app
+        .get(&format!(
+            "/authorize?client_id=par-rp&request_uri={}",
+            urlencoding(&request_uri)
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_discovery_advertises_par() {
+    let app =
+        TestApp::with_config(|config| config.require_pushed_authorization_requests = true).await;
+
+    let json = body_json(app.get("/.well-known/openid-configuration").await).await;
+    assert_eq!(
+        json["pushed_authorization_request_endpoint"],
+        "http://localhost:8080/par"
+    );
+    assert_eq!(json["require_pushed_authorization_requests"], true);
+}
+
+fn urlencoding(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
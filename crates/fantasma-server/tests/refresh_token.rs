@@ -0,0 +1,125 @@
+//! Integration tests for the refresh token grant
+
+use axum::http::StatusCode;
+use serde_json::Value;
+
+mod common;
+use common::{body_json, TestApp};
+
+const CONSENT: &str = "response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid%20zk:age:18+&nonce=n-0S6&demo_user=alice&action=approve";
+
+/// Run the authorization code flow and return the token response
+async fn initial_tokens(app: &TestApp) -> Value {
+    let code = app.authorization_code(CONSENT).await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-client&client_secret=demo-secret",
+                code
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await
+}
+
+async fn refresh(app: &TestApp, client_id: &str, refresh_token: &str) -> (StatusCode, Value) {
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=refresh_token&refresh_token={}&client_id={}&client_secret=demo-secret",
+                refresh_token, client_id
+            ),
+        )
+        .await;
+    let status = response.status();
+    (status, body_json(response).await)
+}
+
+fn id_token_claims(token_response: &Value) -> Value {
+    let payload = token_response["id_token"]
+        .as_str()
+        .unwrap()
+        .split('.')
+        .nth(1)
+        .unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_refresh_rotates_token_and_keeps_zk_claims() {
+    let app = TestApp::new().await;
+    let tokens = initial_tokens(&app).await;
+    let first = tokens["refresh_token"].as_str().unwrap();
+
+    let (status, refreshed) = refresh(&app, "demo-client", first).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let second = refreshed["refresh_token"].as_str().unwrap();
+    assert_ne!(first, second);
+
+    let original = id_token_claims(&tokens);
+    let claims = id_token_claims(&refreshed);
+    assert_eq!(claims["sub"], original["sub"]);
+    assert_eq!(claims["auth_time"], original["auth_time"]);
+    assert_eq!(claims["zk_age_claim"]["verified"], true);
+    // Refreshed ID tokens carry no nonce (OIDC Core §12.2)
+    assert!(claims.get("nonce").is_none());
+
+    // The rotated token keeps working
+    let (status, _) = refresh(&app, "demo-client", second).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_refresh_token_reuse_revokes_family() {
+    let app = TestApp::new().await;
+    let tokens = initial_tokens(&app).await;
+    let first = tokens["refresh_token"].as_str().unwrap();
+
+    let (_, refreshed) = refresh(&app, "demo-client", first).await;
+    let second = refreshed["refresh_token"].as_str().unwrap();
+
+    // Replaying the rotated token fails...
+    let (status, error) = refresh(&app, "demo-client", first).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+
+    // ...and takes the current token down with it
+    let (status, _) = refresh(&app, "demo-client", second).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_refresh_token_bound_to_client() {
+    let app = TestApp::new().await;
+    let tokens = initial_tokens(&app).await;
+    let token = tokens["refresh_token"].as_str().unwrap();
+
+    let (status, error) = refresh(&app, "demo-rp", token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+
+    // The legitimate client can still use it
+    let (status, _) = refresh(&app, "demo-client", token).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_stale_zk_claims_require_fresh_proofs() {
+    let app = TestApp::with_config(|config| config.zk_claims_max_age_seconds = 0).await;
+    let tokens = initial_tokens(&app).await;
+
+    let (status, error) = refresh(
+        &app,
+        "demo-client",
+        tokens["refresh_token"].as_str().unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_grant");
+}
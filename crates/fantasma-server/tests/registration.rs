@@ -0,0 +1,120 @@
+//! Integration tests for dynamic client registration
+//!
+//! Registrations are persisted through the database, so without one the
+//! tests cover access control and metadata validation, which happen first.
+
+use axum::http::{header, StatusCode};
+use serde_json::json;
+
+mod common;
+use common::{body_json, TestApp};
+
+const INITIAL_TOKEN: &str = "initial-access-token";
+
+async fn app_with_initial_token() -> TestApp {
+    TestApp::with_config(|config| {
+        config.registration_initial_access_token = Some(INITIAL_TOKEN.to_string());
+    })
+    .await
+}
+
+fn authorization() -> String {
+    format!("Bearer {}", INITIAL_TOKEN)
+}
+
+#[tokio::test]
+async fn test_registration_requires_initial_access_token() {
+    let app = app_with_initial_token().await;
+    let metadata = json!({ "redirect_uris": ["https://rp.example/callback"] });
+
+    let response = app
+        .post_json_with_headers("/register", &[], &metadata)
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+
+    let response = app
+        .post_json_with_headers(
+            "/register",
+            &[("authorization", "Bearer wrong-token")],
+            &metadata,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(body_json(response).await["error"], "invalid_token");
+
+    // Accepted, but there is no database to persist the client in
+    let response = app
+        .post_json_with_headers(
+            "/register",
+            &[("authorization", &authorization())],
+            &metadata,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn test_registration_rejects_invalid_metadata() {
+    let app = app_with_initial_token().await;
+    let auth = authorization();
+    let headers = [("authorization", auth.as_str())];
+
+    let response = app
+        .post_json_with_headers(
+            "/register",
+            &headers,
+            &json!({ "redirect_uris": ["http://rp.example/callback"] }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_redirect_uri");
+
+    let response = app
+        .post_json_with_headers(
+            "/register",
+            &headers,
+            &json!({
+                "redirect_uris": ["https://rp.example/callback"],
+                "token_endpoint_auth_method": "private_key_jwt"
+            }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body_json(response).await["error"],
+        "invalid_client_metadata"
+    );
+
+    let response = app
+        .post_json_with_headers(
+            "/register",
+            &headers,
+            &json!({
+                "redirect_uris": ["https://rp.example/callback"],
+                "scope": "openid zk:age:99+"
+            }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body_json(response).await["error"],
+        "invalid_client_metadata"
+    );
+}
+
+#[tokio::test]
+async fn test_registration_management_requires_registration_token() {
+    let app = TestApp::new().await;
+
+    let response = app.get("/register/demo-client").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+
+    let response = app.get("/.well-known/openid-configuration").await;
+    let doc = body_json(response).await;
+    assert_eq!(
+        doc["registration_endpoint"],
+        "http://localhost:8080/register"
+    );
+}
@@ -0,0 +1,218 @@
+//! Integration tests for signed request objects (JAR, RFC 9101)
+
+use axum::http::StatusCode;
+use fantasma_oidc::client_auth::Audience;
+use fantasma_oidc::request_object::{RequestObjectClaims, REQUEST_OBJECT_TYPE};
+use fantasma_oidc::{jws, JwkSet, SigningKey, TokenEndpointAuthMethod};
+use fantasma_server::state::ClientInfo;
+
+mod common;
+use common::{body_json, consent_request_id, location, query_param, TestApp};
+
+const ISSUER: &str = "http://localhost:8080";
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+
+fn jar_client(key: &SigningKey, require_signed_request_object: bool) -> ClientInfo {
+    ClientInfo {
+        client_id: "jar-rp".to_string(),
+        client_secret_hash: Some(fantasma_crypto::hash_secret("demo-secret").unwrap()),
+        redirect_uris: vec![REDIRECT_URI.to_string()],
+        name: "JAR RP".to_string(),
+        id_token_signed_response_alg: None,
+        token_endpoint_auth_method: TokenEndpointAuthMethod::ClientSecretPost,
+        jwks: Some(JwkSet::new(vec![key.public_jwk()])),
+        jwks_uri: None,
+        sector_identifier_uri: None,
+        require_pkce_s256: false,
+        require_pushed_authorization_requests: false,
+        require_signed_request_object,
+        post_logout_redirect_uris: Vec::new(),
+        backchannel_logout_uri: None,
+        backchannel_logout_session_required: false,
+        allowed_scopes: None,
+        downscope_unapproved_scopes: false,
+        id_token_encryption: None,
+        userinfo_encryption: None,
+    }
+}
+
+fn request_object(key: &SigningKey) -> String {
+    let claims = RequestObjectClaims {
+        iss: "jar-rp".to_string(),
+        aud: Some(Audience::Single(ISSUER.to_string())),
+        client_id: "jar-rp".to_string(),
+        exp: Some(chrono::Utc::now().timestamp() + 60),
+        response_type: Some("code".to_string()),
+        redirect_uri: Some(REDIRECT_URI.to_string()),
+        scope: Some("openid zk:age:21+".to_string()),
+        state: Some("signed-state".to_string()),
+        nonce: Some("signed-nonce".to_string()),
+        ..Default::default()
+    };
+    jws::encode(&claims, REQUEST_OBJECT_TYPE, key).unwrap()
+}
+
+async fn authorize_page(app: &TestApp, query: &str) -> String {
+    let response = app.get(&format!("/authorize?{}", query)).await;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8_lossy(&body).into_owned()
+}
+
+/// Approve the request shown on a consent page, returning the redirect
+async fn approve(app: &TestApp, html: &str) -> String {
+    let response = app
+        .post_form(
+            "/authorize/consent",
+            &format!("request_id={}&action=approve", consent_request_id(html)),
+        )
+        .await;
+    location(&response)
+}
+
+#[tokio::test]
+async fn test_signed_request_parameters_are_authoritative() {
+    let key = SigningKey::generate_ed25519();
+    let app = TestApp::with_clients(vec![jar_client(&key, false)]).await;
+
+    // Tampered query parameters next to the request object are ignored
+    let html = authorize_page(
+        &app,
+        &format!(
+            "client_id=jar-rp&request={}&scope=openid%20zk:age:18%2B&nonce=tampered",
+            request_object(&key)
+        ),
+    )
+    .await;
+    assert!(html.contains("Age 21 or older"), "{}", html);
+    assert!(!html.contains("Age 18 or older"));
+
+    let code = query_param(&approve(&app, &html).await, "code").unwrap();
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id=jar-rp&client_secret=demo-secret",
+                code, REDIRECT_URI
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let id_token = body_json(response).await["id_token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let header = jws::decode_header(&id_token).unwrap();
+    let jwks: JwkSet =
+        serde_json::from_value(body_json(app.get("/.well-known/jwks.json").await).await).unwrap();
+    let claims = fantasma_oidc::IdToken::verify(&id_token, &jwks, ISSUER, "jar-rp").unwrap();
+    assert_eq!(header.alg, "EdDSA");
+    assert_eq!(claims.nonce.as_deref(), Some("signed-nonce"));
+}
+
+#[tokio::test]
+async fn test_request_object_must_verify() {
+    let key = SigningKey::generate_ed25519();
+    let app = TestApp::with_clients(vec![jar_client(&key, false)]).await;
+
+    let attacker = SigningKey::generate_ed25519();
+    let html = authorize_page(
+        &app,
+        &format!("client_id=jar-rp&request={}", request_object(&attacker)),
+    )
+    .await;
+    assert!(html.contains("invalid request object"), "{}", html);
+
+    // Presented under another client's id
+    let html = authorize_page(
+        &app,
+        &format!("client_id=demo-client&request={}", request_object(&key)),
+    )
+    .await;
+    assert!(html.contains("<h1>Error</h1>"), "{}", html);
+}
+
+#[tokio::test]
+async fn test_client_requiring_signed_requests() {
+    let key = SigningKey::generate_ed25519();
+    let app = TestApp::with_clients(vec![jar_client(&key, true)]).await;
+
+    let html = authorize_page(
+        &app,
+        &format!(
+            "client_id=jar-rp&response_type=code&redirect_uri={}&scope=openid",
+            REDIRECT_URI
+        ),
+    )
+    .await;
+    assert!(html.contains("must send a signed request object"));
+
+    // The consent form cannot carry an unsigned request either
+    let response = app
+        .post_form(
+            "/authorize/consent",
+            &format!(
+                "response_type=code&client_id=jar-rp&redirect_uri={}&scope=openid&action=approve",
+                REDIRECT_URI
+            ),
+        )
+        .await;
+    assert!(response.headers().get("location").is_none());
+
+    // Pushed requests are held to the same rule
+    let response = app
+        .post_form(
+            "/par",
+            &format!(
+                "response_type=code&redirect_uri={}&scope=openid&client_id=jar-rp&client_secret=demo-secret",
+                REDIRECT_URI
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post_form(
+            "/par",
+            &format!(
+                "request={}&client_id=jar-rp&client_secret=demo-secret",
+                request_object(&key)
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let request_uri = body_json(response).await["request_uri"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let html = authorize_page(
+        &app,
+        &format!(
+            "client_id=jar-rp&request_uri={}",
+            url::form_urlencoded::byte_serialize(request_uri.as_bytes()).collect::<String>()
+        ),
+    )
+    .await;
+    let redirect = approve(&app, &html).await;
+    assert!(query_param(&redirect, "code").is_some(), "{}", redirect);
+    assert_eq!(
+        query_param(&redirect, "state").as_deref(),
+        Some("signed-state")
+    );
+}
+
+#[tokio::test]
+async fn test_discovery_advertises_request_objects() {
+    let app = TestApp::new().await;
+
+    let json = body_json(app.get("/.well-known/openid-configuration").await).await;
+    assert_eq!(json["request_parameter_supported"], true);
+    assert_eq!(json["require_signed_request_object"], false);
+    assert!(json["request_object_signing_alg_values_supported"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|alg| alg == "ML-DSA-65"));
+}
@@ -0,0 +1,180 @@
+//! Integration tests for per-client allowed scopes
+
+use axum::http::StatusCode;
+use fantasma_oidc::TokenEndpointAuthMethod;
+use fantasma_server::state::ClientInfo;
+use serde_json::Value;
+
+mod common;
+use common::{body_json, location, query_param, TestApp};
+
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+const AGE_AND_KYC: &str = "openid+zk%3Aage%3A18%2B+zk%3Akyc%3Aaccredited";
+
+/// A client approved for the age scope only
+fn gaming_client(client_id: &str, downscope_unapproved_scopes: bool) -> ClientInfo {
+    ClientInfo {
+        client_id: client_id.to_string(),
+        client_secret_hash: Some(fantasma_crypto::hash_secret("demo-secret").unwrap()),
+        redirect_uris: vec![REDIRECT_URI.to_string()],
+        name: "Gaming Site".to_string(),
+        id_token_signed_response_alg: None,
+        token_endpoint_auth_method: TokenEndpointAuthMethod::ClientSecretPost,
+        jwks: None,
+        jwks_uri: None,
+        sector_identifier_uri: Some(REDIRECT_URI.to_string()),
+        require_pkce_s256: false,
+        require_pushed_authorization_requests: false,
+        require_signed_request_object: false,
+        post_logout_redirect_uris: Vec::new(),
+        backchannel_logout_uri: None,
+        backchannel_logout_session_required: false,
+        allowed_scopes: Some(vec!["openid".to_string(), "zk:age:18+".to_string()]),
+        downscope_unapproved_scopes,
+        id_token_encryption: None,
+        userinfo_encryption: None,
+    }
+}
+
+async fn app() -> TestApp {
+    TestApp::with_clients(vec![
+        gaming_client("strict-rp", false),
+        gaming_client("downscoping-rp", true),
+    ])
+    .await
+}
+
+async fn authorize(app: &TestApp, client_id: &str, query: &str) -> axum::response::Response {
+    app.get(&format!(
+        "/authorize?response_type=code&client_id={}&redirect_uri={}&state=s&{}",
+        client_id, REDIRECT_URI, query
+    ))
+    .await
+}
+
+async fn page(response: axum::response::Response) -> String {
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8_lossy(&body).into_owned()
+}
+
+fn consent_form(client_id: &str, scope: &str) -> String {
+    format!(
+        "response_type=code&client_id={}&redirect_uri={}&scope={}&state=s&action=approve",
+        client_id, REDIRECT_URI, scope
+    )
+}
+
+async fn consent(app: &TestApp, client_id: &str, scope: &str) -> axum::response::Response {
+    app.consent(&consent_form(client_id, scope)).await
+}
+
+fn id_token_claims(tokens: &Value) -> Value {
+    let payload = tokens["id_token"]
+        .as_str()
+        .unwrap()
+        .split('.')
+        .nth(1)
+        .unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_unapproved_scope_is_rejected() {
+    let app = app().await;
+
+    let response = authorize(&app, "strict-rp", &format!("scope={}", AGE_AND_KYC)).await;
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+    let redirect = location(&response);
+    assert_eq!(
+        query_param(&redirect, "error").as_deref(),
+        Some("invalid_scope")
+    );
+    assert_eq!(query_param(&redirect, "state").as_deref(), Some("s"));
+
+    // ZK claims requested individually need the same approval
+    let response = authorize(
+        &app,
+        "strict-rp",
+        "scope=openid&claims=%7B%22id_token%22%3A%7B%22zk_kyc_claim%22%3Anull%7D%7D",
+    )
+    .await;
+    assert_eq!(
+        query_param(&location(&response), "error").as_deref(),
+        Some("invalid_scope")
+    );
+
+    // The consent form cannot bring its own scopes
+    let response = app
+        .post_form(
+            "/authorize/consent",
+            &consent_form("strict-rp", AGE_AND_KYC),
+        )
+        .await;
+    assert!(response.headers().get("location").is_none());
+
+    // Approved scopes go through
+    let html = page(authorize(&app, "strict-rp", "scope=openid+zk%3Aage%3A18%2B").await).await;
+    assert!(html.contains("Age 18 or older"), "{}", html);
+
+    // Other clients are unaffected
+    page(authorize(&app, "demo-client", &format!("scope={}", AGE_AND_KYC)).await).await;
+}
+
+#[tokio::test]
+async fn test_unapproved_scope_is_downscoped() {
+    let app = app().await;
+
+    let html =
+        page(authorize(&app, "downscoping-rp", &format!("scope={}", AGE_AND_KYC)).await).await;
+    assert!(html.contains("Age 18 or older"), "{}", html);
+    assert!(!html.contains("KYC"), "{}", html);
+
+    let response = consent(&app, "downscoping-rp", AGE_AND_KYC).await;
+    let code = query_param(&location(&response), "code").unwrap();
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id=downscoping-rp&client_secret=demo-secret",
+                code, REDIRECT_URI
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let claims = id_token_claims(&body_json(response).await);
+    assert!(claims["zk_age_claim"].is_object(), "{}", claims);
+    assert!(claims["zk_kyc_claim"].is_null(), "{}", claims);
+}
+
+#[tokio::test]
+async fn test_device_authorization_checks_scopes() {
+    let app = app().await;
+
+    let response = app
+        .post_form(
+            "/device_authorization",
+            &format!(
+                "client_id=strict-rp&client_secret=demo-secret&scope={}",
+                AGE_AND_KYC
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_scope");
+
+    let response = app
+        .post_form(
+            "/device_authorization",
+            &format!(
+                "client_id=downscoping-rp&client_secret=demo-secret&scope={}",
+                AGE_AND_KYC
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
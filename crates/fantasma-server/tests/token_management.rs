@@ -0,0 +1,175 @@
+//! Integration tests for token introspection and revocation
+
+use axum::http::StatusCode;
+use serde_json::Value;
+
+mod common;
+use common::{body_json, TestApp};
+
+const CONSENT: &str = "response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid%20zk:age:18+&nonce=n-0S6&demo_user=alice&action=approve";
+
+async fn initial_tokens(app: &TestApp) -> Value {
+    let code = app.authorization_code(CONSENT).await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-client&client_secret=demo-secret",
+                code
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await
+}
+
+async fn introspect(app: &TestApp, client_id: &str, token: &str) -> (StatusCode, Value) {
+    let response = app
+        .post_form(
+            "/introspect",
+            &format!(
+                "token={}&client_id={}&client_secret=demo-secret",
+                token, client_id
+            ),
+        )
+        .await;
+    let status = response.status();
+    (status, body_json(response).await)
+}
+
+async fn revoke(app: &TestApp, token: &str, hint: &str) -> StatusCode {
+    app.post_form(
+        "/revoke",
+        &format!(
+            "token={}&token_type_hint={}&client_id=demo-client&client_secret=demo-secret",
+            token, hint
+        ),
+    )
+    .await
+    .status()
+}
+
+#[tokio::test]
+async fn test_introspect_access_token_includes_zk_claims() {
+    let app = TestApp::new().await;
+    let tokens = initial_tokens(&app).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    let (status, body) = introspect(&app, "demo-client", access_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["active"], true);
+    assert_eq!(body["client_id"], "demo-client");
+    assert_eq!(body["token_type"], "Bearer");
+    assert_eq!(body["scope"], "openid zk:age:18+");
+    assert_eq!(body["zk_age_claim"]["verified"], true);
+
+    let (_, body) = introspect(
+        &app,
+        "demo-client",
+        tokens["refresh_token"].as_str().unwrap(),
+    )
+    .await;
+    assert_eq!(body["active"], true);
+    assert_eq!(body["token_type"], "refresh_token");
+
+    // Other clients learn nothing about the token
+    let (status, body) = introspect(&app, "demo-rp", access_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, serde_json::json!({ "active": false }));
+
+    let (_, body) = introspect(&app, "demo-client", "unknown").await;
+    assert_eq!(body, serde_json::json!({ "active": false }));
+}
+
+#[tokio::test]
+async fn test_introspection_requires_client_authentication() {
+    let app = TestApp::new().await;
+    let tokens = initial_tokens(&app).await;
+
+    let response = app
+        .post_form(
+            "/introspect",
+            &format!(
+                "token={}&client_id=demo-client&client_secret=wrong",
+                tokens["access_token"].as_str().unwrap()
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(body_json(response).await["error"], "invalid_client");
+
+    let response = app
+        .post_form(
+            "/revoke",
+            &format!("token={}", tokens["access_token"].as_str().unwrap()),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_revoke_access_token() {
+    let app = TestApp::new().await;
+    let tokens = initial_tokens(&app).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    assert_eq!(
+        revoke(&app, access_token, "access_token").await,
+        StatusCode::OK
+    );
+
+    let (_, body) = introspect(&app, "demo-client", access_token).await;
+    assert_eq!(body["active"], false);
+    let response = app
+        .get_with_headers(
+            "/userinfo",
+            &[("authorization", &format!("Bearer {}", access_token))],
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Revoking again (or revoking garbage) still succeeds
+    assert_eq!(
+        revoke(&app, access_token, "access_token").await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        revoke(&app, "garbage", "refresh_token").await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn test_revoke_refresh_token_revokes_grant() {
+    let app = TestApp::new().await;
+    let tokens = initial_tokens(&app).await;
+    let refresh_token = tokens["refresh_token"].as_str().unwrap();
+
+    assert_eq!(
+        revoke(&app, refresh_token, "refresh_token").await,
+        StatusCode::OK
+    );
+
+    let (_, body) = introspect(&app, "demo-client", refresh_token).await;
+    assert_eq!(body["active"], false);
+    // Access tokens from the same grant are revoked with it
+    let (_, body) = introspect(
+        &app,
+        "demo-client",
+        tokens["access_token"].as_str().unwrap(),
+    )
+    .await;
+    assert_eq!(body["active"], false);
+
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=refresh_token&refresh_token={}&client_id=demo-client&client_secret=demo-secret",
+                refresh_token
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_grant");
+}
@@ -0,0 +1,108 @@
+//! Integration tests for the UserInfo endpoint
+
+use axum::http::{header, StatusCode};
+use serde_json::Value;
+
+mod common;
+use common::{body_json, TestApp};
+
+const CONSENT: &str = "response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid%20zk:age:18+&nonce=n-0S6&demo_user=alice&action=approve";
+
+async fn initial_tokens(app: &TestApp) -> Value {
+    let code = app.authorization_code(CONSENT).await;
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri=http://localhost:8080/callback&client_id=demo-client&client_secret=demo-secret",
+                code
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await
+}
+
+fn id_token_claims(token_response: &Value) -> Value {
+    let payload = token_response["id_token"]
+        .as_str()
+        .unwrap()
+        .split('.')
+        .nth(1)
+        .unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn userinfo(app: &TestApp, access_token: &str) -> axum::response::Response {
+    app.get_with_headers(
+        "/userinfo",
+        &[("authorization", &format!("Bearer {}", access_token))],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_userinfo_returns_id_token_claims() {
+    let app = TestApp::new().await;
+    let tokens = initial_tokens(&app).await;
+
+    let response = userinfo(&app, tokens["access_token"].as_str().unwrap()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let info = body_json(response).await;
+
+    let claims = id_token_claims(&tokens);
+    assert_eq!(info["sub"], claims["sub"]);
+    assert_eq!(info["zk_age_claim"], claims["zk_age_claim"]);
+}
+
+#[tokio::test]
+async fn test_userinfo_requires_bearer_token() {
+    let app = TestApp::new().await;
+
+    let response = app.get("/userinfo").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let challenge = response.headers()[header::WWW_AUTHENTICATE]
+        .to_str()
+        .unwrap();
+    assert!(challenge.starts_with("Bearer"));
+    assert!(!challenge.contains("error="));
+
+    let response = userinfo(&app, "not-a-real-token").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let challenge = response.headers()[header::WWW_AUTHENTICATE]
+        .to_str()
+        .unwrap();
+    assert!(challenge.contains(r#"error="invalid_token""#));
+
+    let response = app
+        .get_with_headers("/userinfo", &[("authorization", "Basic Zm9vOmJhcg==")])
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_json(response).await["error"], "invalid_request");
+}
+
+#[tokio::test]
+async fn test_refreshed_access_token_works_at_userinfo() {
+    let app = TestApp::new().await;
+    let tokens = initial_tokens(&app).await;
+
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=refresh_token&refresh_token={}&client_id=demo-client&client_secret=demo-secret",
+                tokens["refresh_token"].as_str().unwrap()
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let refreshed = body_json(response).await;
+    assert_ne!(refreshed["access_token"], tokens["access_token"]);
+
+    let response = userinfo(&app, refreshed["access_token"].as_str().unwrap()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let info = body_json(response).await;
+    assert_eq!(info["sub"], id_token_claims(&tokens)["sub"]);
+}
@@ -0,0 +1,461 @@
+//! Integration tests for wallet proofs presented at consent
+
+use axum::http::StatusCode;
+use fantasma_core::claim::ClaimType;
+use fantasma_core::proof::{subject_hash, GeneratedProof, ProofRequest, ProofResponse};
+use fantasma_stark::backend::{MockBackend, ProverBackend, ProverBackendTrait, StoneBackend};
+use serde_json::Value;
+use std::sync::Arc;
+
+mod common;
+use common::{
+    body_json, body_text, consent_proof_request, consent_request_id, location, query_param, TestApp,
+};
+
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+
+fn encode(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+async fn proof_request(app: &TestApp, scope: &str) -> ProofRequest {
+    proof_request_for(app, &format!("scope={}", encode(scope))).await
+}
+
+/// Open the consent page and read the proof request meant for the wallet
+async fn proof_request_for(app: &TestApp, query: &str) -> ProofRequest {
+    let response = app
+        .get(&format!(
+            "/authorize?response_type=code&client_id=demo-client&redirect_uri={}&{}",
+            REDIRECT_URI, query
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    consent_proof_request(&body_text(response).await)
+}
+
+/// Prove `claim_type` for `request` with the mock prover, as a wallet would
+fn prove(request: &ProofRequest, claim_type: ClaimType, nullifier: [u8; 32]) -> GeneratedProof {
+    prove_with(request, claim_type, nullifier, &[])
+}
+
+/// Like [`prove`], with `extra` public inputs after the binding values
+fn prove_with(
+    request: &ProofRequest,
+    claim_type: ClaimType,
+    nullifier: [u8; 32],
+    extra: &[[u8; 32]],
+) -> GeneratedProof {
+    let circuit_id = claim_type.circuit_id().to_string();
+    let public_inputs = claim_type
+        .public_inputs()
+        .iter()
+        .chain([
+            &request.verifier_domain_hash(),
+            &request.nonce_hash(),
+            &nullifier,
+            &subject_hash(&wallet_subject(request)),
+        ])
+        .chain(extra)
+        .map(hex::encode)
+        .collect::<Vec<_>>();
+    let result = MockBackend::new()
+        .prove(&circuit_id, &[], &public_inputs)
+        .unwrap();
+
+    GeneratedProof {
+        claim_type,
+        proof_bytes: result.proof_bytes,
+        public_inputs: result.public_inputs,
+        circuit_id,
+        nullifier,
+        generated_at: chrono::Utc::now(),
+    }
+}
+
+fn wallet_subject(request: &ProofRequest) -> String {
+    fantasma_crypto::pairwise_subject(&[42u8; 32], &request.verifier_domain)
+}
+
+/// Post the wallet's answer to the consent page's proof request
+async fn consent(app: &TestApp, response: &ProofResponse) -> String {
+    let form = format!(
+        "request_id={}&action=approve&proof_response={}",
+        encode(&response.request_id),
+        encode(&serde_json::to_string(response).unwrap())
+    );
+    location(&app.post_form("/authorize/consent", &form).await)
+}
+
+fn proof_response(request: &ProofRequest, proofs: Vec<GeneratedProof>) -> ProofResponse {
+    ProofResponse {
+        request_id: request.request_id.clone(),
+        proofs,
+        proof_refs: vec![],
+        subject_id: wallet_subject(request),
+        generated_at: chrono::Utc::now(),
+    }
+}
+
+async fn id_token_claims(app: &TestApp, code: &str) -> Value {
+    let response = app
+        .post_form(
+            "/token",
+            &format!(
+                "grant_type=authorization_code&code={}&redirect_uri={}&client_id=demo-client&client_secret=demo-secret",
+                code, REDIRECT_URI
+            ),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let tokens = body_json(response).await;
+    let payload = tokens["id_token"]
+        .as_str()
+        .unwrap()
+        .split('.')
+        .nth(1)
+        .unwrap();
+    let bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, payload).unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_verified_proofs_become_id_token_claims() {
+    let app = TestApp::new().await;
+    let scope = "openid zk:age:21+ zk:kyc:basic";
+    let request = proof_request(&app, scope).await;
+    assert_eq!(request.requested_claims.len(), 2);
+
+    // The wallet proves the age claim only
+    let proof = prove(&request, ClaimType::AgeAtLeast { threshold: 21 }, [1u8; 32]);
+    let location = consent(&app, &proof_response(&request, vec![proof])).await;
+    let code = query_param(&location, "code").expect("no code issued");
+
+    let id_token = id_token_claims(&app, &code).await;
+    assert_eq!(id_token["sub"], wallet_subject(&request));
+    assert_eq!(id_token["zk_age_claim"]["verified"], true);
+    assert_eq!(id_token["zk_kyc_claim"]["verified"], false);
+
+    // The verified proof can be fetched by the relying party
+    let url = id_token["zk_age_claim"]["proof_ref"]["url"]
+        .as_str()
+        .unwrap();
+    let path = url.strip_prefix("http://localhost:8080").unwrap();
+    assert_eq!(app.get(path).await.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_verified_proofs_carry_provenance() {
+    let app = TestApp::new().await;
+    let scope = "openid zk:age:18+";
+    let request = proof_request(&app, scope).await;
+
+    // The proof names the provider's own credential issuer
+    let metadata = body_json(app.get("/.well-known/openid-credential-issuer").await).await;
+    let issuer_key = base64::Engine::decode(
+        &base64::engine::general_purpose::URL_SAFE_NO_PAD,
+        metadata["fantasma_issuer_public_key"].as_str().unwrap(),
+    )
+    .unwrap();
+    let proof = prove_with(
+        &request,
+        ClaimType::AgeAtLeast { threshold: 18 },
+        [6u8; 32],
+        &[fantasma_core::issuer::public_key_hash(&issuer_key)],
+    );
+    let proof_hash = format!("0x{}", hex::encode(proof.hash()));
+
+    let location = consent(&app, &proof_response(&request, vec![proof])).await;
+    let code = query_param(&location, "code").expect("no code issued");
+    let id_token = id_token_claims(&app, &code).await;
+
+    let provenance = &id_token["zk_age_claim"]["provenance"];
+    assert_eq!(provenance["method"], "mock");
+    assert_eq!(provenance["verifier_backend"], "mock");
+    assert_eq!(provenance["proof_hash"], proof_hash.as_str());
+    // The provider's issuer is self-declared
+    assert_eq!(provenance["issuer_trust_level"], 20);
+    assert_eq!(id_token["acr"], "urn:fantasma:acr:mock:self_declared");
+    assert_eq!(id_token["amr"], serde_json::json!(["mock"]));
+}
+
+#[tokio::test]
+async fn test_mock_proofs_fall_below_stark_acr_values() {
+    let app = TestApp::new().await;
+    let scope = "openid zk:age:18+";
+    let acr_values = "urn:fantasma:acr:stark";
+    let request = proof_request_for(
+        &app,
+        &format!("scope={}&acr_values={}", encode(scope), encode(acr_values)),
+    )
+    .await;
+
+    let proof = prove(&request, ClaimType::AgeAtLeast { threshold: 18 }, [7u8; 32]);
+    let location = consent(&app, &proof_response(&request, vec![proof])).await;
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("access_denied")
+    );
+}
+
+#[tokio::test]
+async fn test_mock_fallback_is_not_stark_provenance() {
+    // A stone backend without its verifier, allowed to fall back to mock
+    let dir = tempfile::tempdir().unwrap();
+    let prover_path = dir.path().join("cpu_air_prover");
+    std::fs::write(&prover_path, b"").unwrap();
+    let backend = StoneBackend::new(prover_path)
+        .unwrap()
+        .with_mock_fallback(true);
+    let app = TestApp::with_app_state(|state| {
+        state.prover_backend = Arc::new(ProverBackend::Stone(backend));
+    })
+    .await;
+
+    let request = proof_request(&app, "openid zk:age:18+").await;
+    let proof = prove(
+        &request,
+        ClaimType::AgeAtLeast { threshold: 18 },
+        [14u8; 32],
+    );
+    let location = consent(&app, &proof_response(&request, vec![proof])).await;
+    let code = query_param(&location, "code").expect("no code issued");
+    let id_token = id_token_claims(&app, &code).await;
+
+    let provenance = &id_token["zk_age_claim"]["provenance"];
+    assert_eq!(provenance["method"], "mock");
+    assert_eq!(provenance["verifier_backend"], "mock");
+    assert!(id_token["acr"]
+        .as_str()
+        .unwrap()
+        .starts_with("urn:fantasma:acr:mock"));
+}
+
+#[tokio::test]
+async fn test_reused_nullifier_is_rejected() {
+    let app = TestApp::new().await;
+    let scope = "openid zk:age:18+";
+
+    let request = proof_request(&app, scope).await;
+    let proof = prove(&request, ClaimType::AgeAtLeast { threshold: 18 }, [2u8; 32]);
+    let location = consent(&app, &proof_response(&request, vec![proof])).await;
+    assert!(query_param(&location, "code").is_some());
+
+    // A fresh request, answered with a proof reusing the nullifier
+    let request = proof_request(&app, scope).await;
+    let proof = prove(&request, ClaimType::AgeAtLeast { threshold: 18 }, [2u8; 32]);
+    let location = consent(&app, &proof_response(&request, vec![proof])).await;
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("access_denied")
+    );
+}
+
+#[tokio::test]
+async fn test_proof_must_answer_its_request() {
+    let app = TestApp::new().await;
+    let scope = "openid zk:age:18+";
+
+    // A proof generated for one request cannot answer another
+    let first = proof_request(&app, scope).await;
+    let second = proof_request(&app, scope).await;
+    let proof = prove(&first, ClaimType::AgeAtLeast { threshold: 18 }, [3u8; 32]);
+    let location = consent(&app, &proof_response(&second, vec![proof])).await;
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("invalid_request")
+    );
+
+    // The answered request is used up
+    let proof = prove(&second, ClaimType::AgeAtLeast { threshold: 18 }, [4u8; 32]);
+    let form = format!(
+        "request_id={}&action=approve&proof_response={}",
+        encode(&second.request_id),
+        encode(&serde_json::to_string(&proof_response(&second, vec![proof])).unwrap())
+    );
+    let response = app.post_form("/authorize/consent", &form).await;
+    assert!(response.headers().get("location").is_none());
+
+    // The response must answer the request of the page it is posted with
+    let request = proof_request(&app, scope).await;
+    let other = proof_request(&app, scope).await;
+    let proof = prove(&other, ClaimType::AgeAtLeast { threshold: 18 }, [8u8; 32]);
+    let form = format!(
+        "request_id={}&action=approve&proof_response={}",
+        encode(&request.request_id),
+        encode(&serde_json::to_string(&proof_response(&other, vec![proof])).unwrap())
+    );
+    let redirect = common::location(&app.post_form("/authorize/consent", &form).await);
+    assert_eq!(
+        query_param(&redirect, "error").as_deref(),
+        Some("invalid_request")
+    );
+
+    // Claims that were not requested cannot be proven
+    let request = proof_request(&app, scope).await;
+    let proof = prove(&request, ClaimType::AgeAtLeast { threshold: 21 }, [5u8; 32]);
+    let location = consent(&app, &proof_response(&request, vec![proof])).await;
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("invalid_request")
+    );
+}
+
+#[tokio::test]
+async fn test_proof_must_carry_the_requested_values() {
+    let app = TestApp::new().await;
+    let scope = "openid zk:age:21+";
+
+    // A proof of 18+ relabelled as 21+
+    let request = proof_request(&app, scope).await;
+    let mut proof = prove(&request, ClaimType::AgeAtLeast { threshold: 18 }, [9u8; 32]);
+    proof.claim_type = ClaimType::AgeAtLeast { threshold: 21 };
+    let location = consent(&app, &proof_response(&request, vec![proof])).await;
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("invalid_request")
+    );
+    assert!(query_param(&location, "code").is_none());
+}
+
+#[tokio::test]
+async fn test_public_inputs_must_match_the_proof() {
+    let app = TestApp::new().await;
+    let scope = "openid zk:age:21+";
+
+    // An 18+ proof whose public inputs are rewritten to claim 21+
+    let request = proof_request(&app, scope).await;
+    let mut proof = prove(
+        &request,
+        ClaimType::AgeAtLeast { threshold: 18 },
+        [13u8; 32],
+    );
+    let claim_type = ClaimType::AgeAtLeast { threshold: 21 };
+    proof.public_inputs[0] = hex::encode(claim_type.public_inputs()[0]);
+    proof.claim_type = claim_type;
+    let location = consent(&app, &proof_response(&request, vec![proof])).await;
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("access_denied")
+    );
+    assert!(query_param(&location, "code").is_none());
+}
+
+#[tokio::test]
+async fn test_wallet_is_required_without_demo_users() {
+    let app = TestApp::with_config(|config| config.demo_users = false).await;
+    let scope = "openid zk:age:18+";
+
+    // The consent page offers no demo users, and the form cannot pick one
+    let response = app
+        .get(&format!(
+            "/authorize?response_type=code&client_id=demo-client&redirect_uri={}&scope={}",
+            REDIRECT_URI,
+            encode(scope)
+        ))
+        .await;
+    let page = body_text(response).await;
+    assert!(!page.contains("Select Test User"));
+    let form = format!(
+        "request_id={}&action=approve&demo_user=alice",
+        consent_request_id(&page)
+    );
+    let location = location(&app.post_form("/authorize/consent", &form).await);
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("access_denied")
+    );
+
+    // The wallet's proofs sign the user in
+    let request = proof_request(&app, scope).await;
+    let proof = prove(
+        &request,
+        ClaimType::AgeAtLeast { threshold: 18 },
+        [10u8; 32],
+    );
+    let location = consent(&app, &proof_response(&request, vec![proof])).await;
+    let code = query_param(&location, "code").expect("no code issued");
+    let id_token = id_token_claims(&app, &code).await;
+    assert_eq!(id_token["sub"], wallet_subject(&request));
+    assert_eq!(id_token["zk_age_claim"]["verified"], true);
+}
+
+#[tokio::test]
+async fn test_subject_must_be_bound_to_the_proofs() {
+    let app = TestApp::new().await;
+    let scope = "openid zk:age:18+";
+
+    // A valid proof presented with someone else's subject
+    let request = proof_request(&app, scope).await;
+    let proof = prove(
+        &request,
+        ClaimType::AgeAtLeast { threshold: 18 },
+        [11u8; 32],
+    );
+    let mut response = proof_response(&request, vec![proof]);
+    response.subject_id = fantasma_crypto::pairwise_subject(&[7u8; 32], &request.verifier_domain);
+    let location = consent(&app, &response).await;
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("invalid_request")
+    );
+    assert!(query_param(&location, "code").is_none());
+
+    // A subject backed by no proof at all
+    let request = proof_request(&app, scope).await;
+    let location = consent(&app, &proof_response(&request, vec![])).await;
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("invalid_request")
+    );
+
+    // Subjects must be pairwise subject identifiers
+    let request = proof_request(&app, scope).await;
+    let proof = prove(
+        &request,
+        ClaimType::AgeAtLeast { threshold: 18 },
+        [12u8; 32],
+    );
+    let mut response = proof_response(&request, vec![proof]);
+    response.subject_id = "alice@example.com".to_string();
+    let location = consent(&app, &response).await;
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("invalid_request")
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_or_missing_essential_proof_denies_authorization() {
+    let app = TestApp::new().await;
+    let scope = "openid zk:age:18+";
+
+    let request = proof_request(&app, scope).await;
+    let mut proof = prove(&request, ClaimType::AgeAtLeast { threshold: 18 }, [6u8; 32]);
+    proof.proof_bytes = vec![0u8; 128];
+    let redirect = consent(&app, &proof_response(&request, vec![proof])).await;
+    assert_eq!(
+        query_param(&redirect, "error").as_deref(),
+        Some("access_denied")
+    );
+
+    // An essential claim from the claims parameter must be proven
+    let claims = r#"{"id_token":{"zk_age_claim":{"essential":true,"value":21}}}"#;
+    let request = proof_request_for(&app, &format!("scope=openid&claims={}", encode(claims))).await;
+    assert!(request.requested_claims[0].required);
+
+    let location = consent(&app, &proof_response(&request, vec![])).await;
+    assert_eq!(
+        query_param(&location, "error").as_deref(),
+        Some("access_denied")
+    );
+}
+
+#[tokio::test]
+#[should_panic(expected = "prover backend")]
+async fn test_mock_backend_must_be_allowed() {
+    // Without mock_proofs the server does not start on the mock backend
+    TestApp::with_config(|config| config.mock_proofs = false).await;
+}
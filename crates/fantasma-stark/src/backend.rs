@@ -1,7 +1,8 @@
 //! Prover backend abstraction
 //!
 //! Supports different STARK proving backends:
-//! - Mock: For development and testing
+//! - Mock: For development and testing; proofs are hashes anyone can make,
+//!   so it must be allowed explicitly with `allow_mock`
 //! - StoneProver: StarkWare's production prover
 //! - Stwo: StarkWare's Rust-native prover (experimental)
 //!
@@ -10,8 +11,11 @@
 //! ```rust,ignore
 //! use fantasma_stark::backend::{ProverBackend, ProverConfig};
 //!
-//! // Development mode (default)
-//! let prover = ProverBackend::from_config(ProverConfig::default());
+//! // Development mode
+//! let prover = ProverBackend::from_config(ProverConfig {
+//!     allow_mock: true,
+//!     ..Default::default()
+//! });
 //!
 //! // Production mode with stone-prover
 //! let prover = ProverBackend::from_config(ProverConfig {
@@ -63,6 +67,11 @@ pub struct ProverConfig {
 
     /// Path to circuit artifacts
     pub circuits_path: Option<PathBuf>,
+
+    /// Accept the mock backend, and mock verification when the stone
+    /// verifier is missing. Mock proofs can be forged, so development only
+    #[serde(default)]
+    pub allow_mock: bool,
 }
 
 impl Default for ProverConfig {
@@ -74,6 +83,7 @@ impl Default for ProverConfig {
             num_threads: None,
             max_memory_mb: None,
             circuits_path: None,
+            allow_mock: false,
         }
     }
 }
@@ -95,6 +105,7 @@ impl ProverConfig {
             circuits_path: std::env::var("FANTASMA_CIRCUITS_PATH")
                 .ok()
                 .map(PathBuf::from),
+            allow_mock: false,
         }
     }
 
@@ -134,6 +145,10 @@ pub struct VerifyResult {
     /// Whether the proof is valid
     pub valid: bool,
 
+    /// Backend that actually checked the proof, e.g. `mock` when the stone
+    /// backend fell back to mock verification
+    pub verified_by: String,
+
     /// Verification time in milliseconds
     pub verify_time_ms: u64,
 
@@ -166,13 +181,32 @@ pub trait ProverBackendTrait: Send + Sync {
     fn is_available(&self) -> bool;
 }
 
+/// Marker at the start of mock proofs
+const MOCK_PROOF_PREFIX: &[u8] = b"STARK_PROOF_V1";
+
 /// Mock prover backend for development
+///
+/// A mock proof commits to its circuit and public inputs by hash, so they
+/// cannot be changed after proving; it proves nothing else.
 pub struct MockBackend;
 
 impl MockBackend {
     pub fn new() -> Self {
         Self
     }
+
+    /// Seed of the mock proof for a circuit and its public inputs
+    fn seed(circuit_type: &str, public_inputs: &[String]) -> [u8; 32] {
+        use sha3::{Digest, Sha3_256};
+
+        let mut hasher = Sha3_256::new();
+        hasher.update(b"mock_proof:");
+        hasher.update(circuit_type.as_bytes());
+        for input in public_inputs {
+            hasher.update(input.as_bytes());
+        }
+        hasher.finalize().into()
+    }
 }
 
 impl Default for MockBackend {
@@ -194,17 +228,11 @@ impl ProverBackendTrait for MockBackend {
         let start = Instant::now();
 
         // Generate deterministic mock proof
-        let mut hasher = Sha3_256::new();
-        hasher.update(b"mock_proof:");
-        hasher.update(circuit_type.as_bytes());
-        for input in public_inputs {
-            hasher.update(input.as_bytes());
-        }
-        let seed: [u8; 32] = hasher.finalize().into();
+        let seed = Self::seed(circuit_type, public_inputs);
 
         // Generate ~100KB proof
         let mut proof = Vec::with_capacity(100_000);
-        proof.extend_from_slice(b"STARK_PROOF_V1");
+        proof.extend_from_slice(MOCK_PROOF_PREFIX);
         proof.extend_from_slice(&seed);
 
         while proof.len() < 100_000 {
@@ -226,24 +254,30 @@ impl ProverBackendTrait for MockBackend {
 
     fn verify(
         &self,
-        _circuit_type: &str,
+        circuit_type: &str,
         proof_bytes: &[u8],
-        _public_inputs: &[String],
+        public_inputs: &[String],
     ) -> Result<VerifyResult, BackendError> {
         use std::time::Instant;
 
         let start = Instant::now();
 
-        let valid = proof_bytes.len() >= 100 && proof_bytes.starts_with(b"STARK_PROOF_V1");
+        let error = match proof_bytes.strip_prefix(MOCK_PROOF_PREFIX) {
+            Some(rest) if proof_bytes.len() >= 100 => {
+                if rest.starts_with(&Self::seed(circuit_type, public_inputs)) {
+                    None
+                } else {
+                    Some("Mock proof is for other public inputs".to_string())
+                }
+            }
+            _ => Some("Invalid mock proof".to_string()),
+        };
 
         Ok(VerifyResult {
-            valid,
+            valid: error.is_none(),
+            verified_by: self.name().to_string(),
             verify_time_ms: start.elapsed().as_millis() as u64,
-            error: if valid {
-                None
-            } else {
-                Some("Invalid mock proof".to_string())
-            },
+            error,
         })
     }
 
@@ -269,6 +303,7 @@ impl ProverBackendTrait for MockBackend {
 pub struct StoneBackend {
     prover_path: PathBuf,
     verifier_path: Option<PathBuf>,
+    mock_fallback: bool,
     _num_threads: usize,
 }
 
@@ -284,6 +319,7 @@ impl StoneBackend {
         Ok(Self {
             prover_path,
             verifier_path: None,
+            mock_fallback: false,
             _num_threads: num_cpus::get(),
         })
     }
@@ -293,6 +329,12 @@ impl StoneBackend {
         self
     }
 
+    /// Verify with the mock backend when no stone verifier is found
+    pub fn with_mock_fallback(mut self, mock_fallback: bool) -> Self {
+        self.mock_fallback = mock_fallback;
+        self
+    }
+
     /// Write Stone prover config to a temp file
     fn write_prover_config(&self, dir: &std::path::Path) -> Result<PathBuf, BackendError> {
         let config = crate::stone_config::StoneProverConfig::default();
@@ -438,6 +480,12 @@ impl ProverBackendTrait for StoneBackend {
                     .unwrap_or(std::path::Path::new("."));
                 let verifier = parent.join("cpu_air_verifier");
                 if !verifier.exists() {
+                    if !self.mock_fallback {
+                        return Err(BackendError::NotAvailable(format!(
+                            "Stone verifier not found at: {}",
+                            verifier.display()
+                        )));
+                    }
                     tracing::warn!("Stone verifier not found, falling back to mock verification");
                     let mock = MockBackend::new();
                     return mock.verify(circuit_type, proof_bytes, public_inputs);
@@ -448,6 +496,17 @@ impl ProverBackendTrait for StoneBackend {
 
         let start = Instant::now();
 
+        // The verifier checks the proof against the public input it embeds,
+        // which must be the one the caller expects
+        if stone_public_inputs(proof_bytes).as_deref() != Some(public_inputs) {
+            return Ok(VerifyResult {
+                valid: false,
+                verified_by: self.name().to_string(),
+                verify_time_ms: start.elapsed().as_millis() as u64,
+                error: Some("Proof is for other public inputs".to_string()),
+            });
+        }
+
         // Write proof to temp file
         let work_dir = tempfile::tempdir()
             .map_err(|e| BackendError::VerificationFailed(format!("Temp dir: {}", e)))?;
@@ -476,6 +535,7 @@ impl ProverBackendTrait for StoneBackend {
 
         Ok(VerifyResult {
             valid,
+            verified_by: self.name().to_string(),
             verify_time_ms,
             error,
         })
@@ -490,6 +550,12 @@ impl ProverBackendTrait for StoneBackend {
     }
 }
 
+/// Public inputs embedded in a stone proof, as written by `write_inputs`
+fn stone_public_inputs(proof_bytes: &[u8]) -> Option<Vec<String>> {
+    let proof: serde_json::Value = serde_json::from_slice(proof_bytes).ok()?;
+    serde_json::from_value(proof.get("public_input")?.get("inputs")?.clone()).ok()
+}
+
 /// Dynamic prover backend that can be configured at runtime
 pub enum ProverBackend {
     Mock(MockBackend),
@@ -499,18 +565,33 @@ pub enum ProverBackend {
 impl ProverBackend {
     /// Create from configuration
     pub fn from_config(config: ProverConfig) -> Result<Self, BackendError> {
+        let mock = || {
+            if config.allow_mock {
+                Ok(ProverBackend::Mock(MockBackend::new()))
+            } else {
+                Err(BackendError::ConfigError(
+                    "the mock backend accepts forged proofs and must be allowed explicitly".into(),
+                ))
+            }
+        };
+
         match config.backend.as_str() {
-            "mock" => Ok(ProverBackend::Mock(MockBackend::new())),
+            "mock" => mock(),
             "stone" => {
-                let prover_path = config.stone_prover_path.ok_or_else(|| {
+                let prover_path = config.stone_prover_path.clone().ok_or_else(|| {
                     BackendError::ConfigError("stone_prover_path required for stone backend".into())
                 })?;
-                Ok(ProverBackend::Stone(StoneBackend::new(prover_path)?))
+                let mut backend =
+                    StoneBackend::new(prover_path)?.with_mock_fallback(config.allow_mock);
+                if let Some(verifier_path) = config.stone_verifier_path.clone() {
+                    backend = backend.with_verifier(verifier_path);
+                }
+                Ok(ProverBackend::Stone(backend))
             }
             "stwo" => {
                 // Stwo is still experimental, fall back to mock
                 tracing::warn!("Stwo backend not yet implemented, using mock");
-                Ok(ProverBackend::Mock(MockBackend::new()))
+                mock()
             }
             other => Err(BackendError::ConfigError(format!(
                 "Unknown backend: {}",
@@ -595,10 +676,78 @@ mod tests {
         assert!(verify.valid);
     }
 
+    #[test]
+    fn test_mock_proof_binds_public_inputs() {
+        let backend = MockBackend::new();
+        let inputs = ["0x15".to_string(), "0x01".to_string()];
+        let result = backend.prove("age_verification", &[], &inputs).unwrap();
+
+        let changed = ["0x16".to_string(), "0x01".to_string()];
+        let verify = backend
+            .verify("age_verification", &result.proof_bytes, &changed)
+            .unwrap();
+        assert!(!verify.valid);
+
+        let verify = backend
+            .verify("kyc_verification", &result.proof_bytes, &inputs)
+            .unwrap();
+        assert!(!verify.valid);
+    }
+
     #[test]
     fn test_prover_backend_from_config() {
-        let backend = ProverBackend::from_config(ProverConfig::default()).unwrap();
+        // The mock backend must be asked for
+        assert!(matches!(
+            ProverBackend::from_config(ProverConfig::default()),
+            Err(BackendError::ConfigError(_))
+        ));
+
+        let backend = ProverBackend::from_config(ProverConfig {
+            allow_mock: true,
+            ..Default::default()
+        })
+        .unwrap();
         assert_eq!(backend.name(), "mock");
         assert!(backend.is_available());
     }
+
+    #[test]
+    fn test_stone_needs_its_verifier() {
+        let dir = tempfile::tempdir().unwrap();
+        let prover_path = dir.path().join("cpu_air_prover");
+        std::fs::write(&prover_path, b"").unwrap();
+        let proof = MockBackend::new()
+            .prove("age_verification", &[], &["0x15".to_string()])
+            .unwrap();
+
+        let backend = StoneBackend::new(prover_path.clone()).unwrap();
+        assert!(matches!(
+            backend.verify("age_verification", &proof.proof_bytes, &proof.public_inputs),
+            Err(BackendError::NotAvailable(_))
+        ));
+
+        // Unless mock verification was allowed, which is reported as such
+        let backend = StoneBackend::new(prover_path)
+            .unwrap()
+            .with_mock_fallback(true);
+        let verify = backend
+            .verify("age_verification", &proof.proof_bytes, &proof.public_inputs)
+            .unwrap();
+        assert!(verify.valid);
+        assert_eq!(verify.verified_by, "mock");
+    }
+
+    #[test]
+    fn test_stone_public_inputs() {
+        let proof = serde_json::json!({
+            "proof_hex": "0x00",
+            "public_input": { "inputs": ["0x15", "0x01"] }
+        });
+        let bytes = serde_json::to_vec(&proof).unwrap();
+        assert_eq!(
+            stone_public_inputs(&bytes),
+            Some(vec!["0x15".to_string(), "0x01".to_string()])
+        );
+        assert_eq!(stone_public_inputs(b"STARK_PROOF_V1"), None);
+    }
 }
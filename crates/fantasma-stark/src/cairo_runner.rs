@@ -231,6 +231,7 @@ impl AgeVerificationCircuit {
     }
 
     /// Generate a proof for age verification
+    #[allow(clippy::too_many_arguments)]
     pub fn prove(
         &self,
         birthdate: u32, // YYYYMMDD
@@ -13,6 +13,13 @@ pub enum CircuitType {
 }
 
 impl CircuitType {
+    /// Every circuit type
+    pub const ALL: [CircuitType; 3] = [
+        CircuitType::AgeVerification,
+        CircuitType::CredentialVerification,
+        CircuitType::KycVerification,
+    ];
+
     /// Get the circuit identifier string
     pub fn id(&self) -> &'static str {
         match self {
@@ -90,12 +97,16 @@ impl Circuit {
 /// Loader for compiled Cairo circuit artifacts (Sierra JSON)
 pub struct CircuitLoader {
     circuits: HashMap<CircuitType, Circuit>,
+
+    /// Whether the hashes were computed from a compiled artifact
+    compiled: bool,
 }
 
 impl CircuitLoader {
     pub fn new() -> Self {
         Self {
             circuits: HashMap::new(),
+            compiled: false,
         }
     }
 
@@ -118,11 +129,7 @@ impl CircuitLoader {
             };
 
             // Register all circuit types with the computed hash
-            for circuit_type in &[
-                CircuitType::AgeVerification,
-                CircuitType::CredentialVerification,
-                CircuitType::KycVerification,
-            ] {
+            for circuit_type in &CircuitType::ALL {
                 let mut circuit = match circuit_type {
                     CircuitType::AgeVerification => Circuit::age_verification(),
                     CircuitType::CredentialVerification => Circuit::credential_verification(),
@@ -131,6 +138,7 @@ impl CircuitLoader {
                 circuit.bytecode_hash = bytecode_hash;
                 loader.circuits.insert(*circuit_type, circuit);
             }
+            loader.compiled = true;
 
             tracing::info!(
                 "Loaded Sierra circuits from {}, hash: {}",
@@ -184,8 +192,12 @@ impl CircuitLoader {
         &self.circuits
     }
 
-    /// Get the bytecode hash for a circuit type
+    /// Get the bytecode hash for a circuit type. `None` if the circuit is
+    /// unknown or only has a placeholder hash.
     pub fn bytecode_hash(&self, circuit_type: CircuitType) -> Option<[u8; 32]> {
+        if !self.compiled {
+            return None;
+        }
         self.circuits.get(&circuit_type).map(|c| c.bytecode_hash)
     }
 }
@@ -207,15 +219,32 @@ mod tests {
         assert!(loader.get(CircuitType::KycVerification).is_some());
         assert!(loader.get(CircuitType::CredentialVerification).is_some());
         assert_eq!(loader.all().len(), 3);
+        assert!(loader.bytecode_hash(CircuitType::AgeVerification).is_none());
+    }
+
+    #[test]
+    fn test_circuit_loader_hashes_sierra_artifact() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target/dev");
+        std::fs::create_dir_all(&target).unwrap();
+        std::fs::write(
+            target.join("fantasma_circuits.sierra.json"),
+            r#"{"sierra_program":[]}"#,
+        )
+        .unwrap();
+
+        let loader = CircuitLoader::load_from_dir(dir.path()).unwrap();
+        let hash = loader.bytecode_hash(CircuitType::AgeVerification).unwrap();
+        assert_ne!(hash, [0u8; 32]);
+        assert_eq!(
+            loader.bytecode_hash(CircuitType::KycVerification),
+            Some(hash)
+        );
     }
 
     #[test]
     fn test_circuit_type_roundtrip() {
-        for ct in &[
-            CircuitType::AgeVerification,
-            CircuitType::CredentialVerification,
-            CircuitType::KycVerification,
-        ] {
+        for ct in &CircuitType::ALL {
             assert_eq!(CircuitType::from_id(ct.id()), Some(*ct));
         }
     }
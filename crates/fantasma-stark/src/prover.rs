@@ -133,10 +133,10 @@ fn generate_mock_proof(witness: &Witness) -> Vec<u8> {
     hasher.update(witness.circuit_type.id().as_bytes());
 
     for input in &witness.private_inputs {
-        hasher.update(&input.to_field_bytes());
+        hasher.update(input.to_field_bytes());
     }
     for input in &witness.public_inputs {
-        hasher.update(&input.to_field_bytes());
+        hasher.update(input.to_field_bytes());
     }
 
     let seed: [u8; 32] = hasher.finalize().into();
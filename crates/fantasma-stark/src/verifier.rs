@@ -3,6 +3,7 @@
 use crate::circuit::CircuitType;
 use crate::prover::StarkProof;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -48,7 +49,7 @@ pub struct Verifier {
 /// Verification key for a circuit (placeholder)
 struct VerificationKey {
     _circuit_type: CircuitType,
-    _key_bytes: Vec<u8>,
+    key_bytes: Vec<u8>,
 }
 
 impl Verifier {
@@ -65,14 +66,56 @@ impl Verifier {
         circuit_type: CircuitType,
     ) -> Result<(), VerifierError> {
         // In a real implementation, this would load the actual verification key
+        self.load_verification_key_bytes(circuit_type, Vec::new())
+    }
+
+    /// Load a configured verification key for a circuit
+    pub fn load_verification_key_bytes(
+        &mut self,
+        circuit_type: CircuitType,
+        key_bytes: Vec<u8>,
+    ) -> Result<(), VerifierError> {
         let vk = VerificationKey {
             _circuit_type: circuit_type,
-            _key_bytes: Vec::new(),
+            key_bytes,
         };
         self.verification_keys.insert(circuit_type, vk);
         Ok(())
     }
 
+    /// Load the verification key of every known circuit from a directory.
+    /// Keys are read from `<circuit id>.json` (e.g. `age_verification_v1.json`);
+    /// circuits without a key file keep the placeholder key.
+    pub fn load_verification_keys_from_dir(&mut self, keys_path: &Path) -> Result<(), String> {
+        for circuit_type in CircuitType::ALL {
+            let key_path = keys_path.join(format!("{}.json", circuit_type.id()));
+            if key_path.exists() {
+                let key_bytes = std::fs::read(&key_path).map_err(|e| {
+                    format!(
+                        "Failed to read verification key {}: {}",
+                        key_path.display(),
+                        e
+                    )
+                })?;
+                let _ = self.load_verification_key_bytes(circuit_type, key_bytes);
+            } else {
+                let _ = self.load_verification_key(circuit_type);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the SHA3-256 hash of a circuit's verification key. `None` if the
+    /// circuit has no key or only the placeholder key.
+    pub fn verification_key_hash(&self, circuit_type: CircuitType) -> Option<[u8; 32]> {
+        use sha3::{Digest, Sha3_256};
+        let vk = self.verification_keys.get(&circuit_type)?;
+        if vk.key_bytes.is_empty() {
+            return None;
+        }
+        Some(Sha3_256::digest(&vk.key_bytes).into())
+    }
+
     /// Verify a STARK proof
     pub fn verify(&self, proof: &StarkProof) -> Result<VerificationResult, VerifierError> {
         let _vk = self
@@ -174,4 +217,30 @@ mod tests {
         let result = verifier.verify(&proof).unwrap();
         assert!(result.valid);
     }
+
+    #[test]
+    fn test_verification_key_hash() {
+        let mut verifier = Verifier::new();
+        verifier
+            .load_verification_key(CircuitType::AgeVerification)
+            .unwrap();
+        // The placeholder key has nothing to pin
+        assert!(verifier
+            .verification_key_hash(CircuitType::AgeVerification)
+            .is_none());
+
+        verifier
+            .load_verification_key_bytes(CircuitType::KycVerification, b"{\"vk\":1}".to_vec())
+            .unwrap();
+        let hash = verifier
+            .verification_key_hash(CircuitType::KycVerification)
+            .unwrap();
+        verifier
+            .load_verification_key_bytes(CircuitType::KycVerification, b"{\"vk\":2}".to_vec())
+            .unwrap();
+        assert_ne!(
+            verifier.verification_key_hash(CircuitType::KycVerification),
+            Some(hash)
+        );
+    }
 }
@@ -450,9 +450,7 @@ pub fn generate_witness(
 
             let builder = AgeVerificationWitnessBuilder::new()
                 .birthdate(
-                    birthdate.year() as u32 * 10000
-                        + birthdate.month() as u32 * 100
-                        + birthdate.day() as u32,
+                    birthdate.year() as u32 * 10000 + birthdate.month() * 100 + birthdate.day(),
                 )
                 .salt(credential.commitment_salt)
                 .signature_hash(sha3_256(&credential.signature.bytes))
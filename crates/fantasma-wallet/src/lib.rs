@@ -59,6 +59,11 @@ impl Wallet {
         &self.user_secret
     }
 
+    /// The pairwise subject identifier to present to a relying-party sector
+    pub fn pairwise_subject(&self, sector_identifier: &str) -> String {
+        fantasma_crypto::pairwise_subject(&self.user_secret, sector_identifier)
+    }
+
     /// Import a credential into the wallet
     pub fn import_credential(&mut self, credential: Credential) -> Result<(), FantasmaError> {
         // Verify the credential is valid
@@ -152,4 +157,14 @@ mod tests {
         let wallet = Wallet::new();
         assert!(!wallet.user_secret().iter().all(|&b| b == 0));
     }
+
+    #[test]
+    fn test_pairwise_subject_per_sector() {
+        let wallet = Wallet::with_secret([1u8; 32]);
+        let sub = wallet.pairwise_subject("rp.example");
+
+        assert_eq!(sub, wallet.pairwise_subject("rp.example"));
+        assert_ne!(sub, wallet.pairwise_subject("other.example"));
+        assert_ne!(sub, Wallet::new().pairwise_subject("rp.example"));
+    }
 }
@@ -89,6 +89,7 @@ pub fn generate_age_witness(
 
 /// Generate a witness for KYC verification.
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
 pub fn generate_kyc_witness(
     user_id_hash_hex: &str,
     kyc_provider_id_hex: &str,
@@ -128,6 +129,7 @@ pub fn generate_kyc_witness(
 
 /// Generate a witness for credential verification.
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
 pub fn generate_credential_witness(
     credential_type_hex: &str,
     credential_details_hash_hex: &str,
@@ -250,7 +252,7 @@ mod hex {
     }
 
     pub fn decode(s: &str) -> Result<Vec<u8>, ()> {
-        if s.len() % 2 != 0 {
+        if !s.len().is_multiple_of(2) {
             return Err(());
         }
         (0..s.len())
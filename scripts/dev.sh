@@ -35,6 +35,8 @@ cargo build -p fantasma-server --release
 echo -e "${GREEN}Starting Fantasma server on http://localhost:3000${NC}"
 FANTASMA_ISSUER="http://localhost:3000" \
 FANTASMA_BIND="0.0.0.0:3000" \
+FANTASMA_DEMO_USERS=true \
+FANTASMA_MOCK_PROOFS=true \
 cargo run -p fantasma-server --release &
 SERVER_PID=$!
 
@@ -164,6 +164,8 @@ if [[ "${1:-}" == "--e2e" ]]; then
         export FANTASMA_ADMIN_KEY="e2e-test-key"
         export FANTASMA_ISSUER="http://localhost:3000"
         export FANTASMA_BIND="127.0.0.1:3000"
+        export FANTASMA_DEMO_USERS=true
+        export FANTASMA_MOCK_PROOFS=true
         export RUST_LOG="fantasma_server=info"
 
         echo "Building and starting server (this may take a moment)..."
@@ -242,9 +244,10 @@ if [[ "${1:-}" == "--e2e" ]]; then
             echo -e "${YELLOW}  ⚠ OIDC authorize page (couldn't verify content)${NC}"
         fi
 
+        REQUEST_ID=$(echo "$AUTH_HTML" | sed -n 's/.*name="request_id" value="\([^"]*\)".*/\1/p' | head -n1)
         REDIRECT=$(curl -sf -D- -o/dev/null -X POST \
             -H "Content-Type: application/x-www-form-urlencoded" \
-            -d "response_type=code&client_id=demo-client&redirect_uri=http://localhost:8080/callback&scope=openid%20zk:age:21%2B&state=e2e&demo_user=alice&action=approve" \
+            -d "request_id=$REQUEST_ID&demo_user=alice&action=approve" \
             http://localhost:3000/authorize/consent 2>&1 | grep -i "^location:" || true)
 
         CODE=$(echo "$REDIRECT" | sed -n 's/.*code=\([^&]*\).*/\1/p' | tr -d '\r')